@@ -9,13 +9,20 @@ fn main() {
     let mut input = String::new();
     loop {
         input.clear();
-        println!("Please enter a JSON object (or 'exit' to quit):");
+        println!("Please enter a JSON object, RION as hex or a .rion path (or 'exit' to quit):");
         std::io::stdin()
             .read_line(&mut input)
             .expect("Failed to read line");
         if input.trim().eq_ignore_ascii_case("exit") {
             break;
         }
+        if let Some(rion_bytes) = read_rion(input.trim()) {
+            match from_bytes::<serde_json::Value>(&rion_bytes) {
+                Ok(json) => println!("{}", serde_json::to_string_pretty(&json).unwrap()),
+                Err(e) => println!("Failed to decode RION bytes: {}", e),
+            }
+            continue;
+        }
         if let Some(path) = input.strip_prefix("file:") {
             let path = path.trim();
             let Ok(content) = std::fs::read_to_string(path) else {
@@ -117,3 +124,34 @@ fn main() {
         println!("  POT:  {}", zlib_pot.len());
     }
 }
+
+// RION input to decode instead of encode, either a path to a `.rion` file or
+// a hex string (that isn't also valid JSON, like `12`)
+fn read_rion(input: &str) -> Option<Vec<u8>> {
+    if input.ends_with(".rion") {
+        let path = input.strip_prefix("file:").unwrap_or(input).trim();
+        return match std::fs::read(path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                println!("Failed to read file {}: {}", path, e);
+                None
+            }
+        };
+    }
+    if serde_json::from_str::<serde_json::Value>(input).is_ok() {
+        return None;
+    }
+    let hex: String = input
+        .trim_start_matches("0x")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if hex.is_empty() || !hex.len().is_multiple_of(2) || !hex.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,309 @@
+//! Generate Rust types from a schema file at build time
+//!
+//! Schemas are JSON Schema documents, such as the ones
+//! [`Schema::to_json_schema`] writes. Every object becomes a struct deriving
+//! serde's `Serialize` and `Deserialize`, along with a
+//! [`rion_ref!`](ferion::rion_ref) struct whose typed accessors read fields
+//! straight out of encoded data.
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     ferion_codegen::compile("schemas/user.json", "User").unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/user.rs"));
+//! ```
+//!
+//! The generated code needs `ferion` with its `serde` and `chrono` features
+//! and `serde` with its `derive` feature.
+
+use std::{
+    collections::VecDeque,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use ferion::{
+    schema::{Field, Schema},
+    FerionError,
+};
+
+type Result<T> = std::result::Result<T, FerionError>;
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Generate the types for the schema file at `schema` into
+/// `$OUT_DIR/<name>.rs`, from a build script
+///
+/// `name` is the name of the top level struct, the file is named after it in
+/// snake case. Cargo is told to rerun the build script when the schema
+/// changes.
+pub fn compile(schema: impl AsRef<Path>, name: &str) -> Result<PathBuf> {
+    let schema = schema.as_ref();
+    println!("cargo:rerun-if-changed={}", schema.display());
+    let out_dir = env::var_os("OUT_DIR").ok_or_else(|| {
+        FerionError::Invalid("OUT_DIR is not set, call compile from a build script".into())
+    })?;
+    let path = Path::new(&out_dir).join(format!("{}.rs", snake_case(name)));
+    fs::write(&path, generate_file(schema, name)?)?;
+    Ok(path)
+}
+
+/// Generate the types for the schema file at `path`
+pub fn generate_file(path: impl AsRef<Path>, name: &str) -> Result<String> {
+    let schema: serde_json::Value = serde_json::from_slice(&fs::read(path)?)?;
+    generate(&Schema::from_json_schema(&schema)?, name)
+}
+
+/// Generate the types for an object schema
+///
+/// The top level struct is named `name`, nested objects and unions are named
+/// after it and the fields holding them, e.g. `UserAddress`.
+pub fn generate(schema: &Schema, name: &str) -> Result<String> {
+    let Schema::Object(fields) = schema else {
+        return Err(FerionError::Invalid(format!(
+            "Expected an object schema for {name}, found {schema:?}"
+        )));
+    };
+    let mut generator = Generator {
+        out: String::from("// Generated by ferion-codegen, do not edit\n"),
+        pending: VecDeque::new(),
+    };
+    let fields = fields
+        .iter()
+        .map(|(key, field)| (key.clone(), field.clone()));
+    generator
+        .pending
+        .push_back(Item::Struct(name.to_string(), fields.collect()));
+    while let Some(item) = generator.pending.pop_front() {
+        match item {
+            Item::Struct(name, fields) => generator.write_struct(&name, &fields)?,
+            Item::Union(name, members) => generator.write_union(&name, members)?,
+        }
+    }
+    Ok(generator.out)
+}
+
+// A type still to be written, nested types are written after their parents
+enum Item {
+    Struct(String, Vec<(String, Field)>),
+    Union(String, Vec<Schema>),
+}
+
+struct Generator {
+    out: String,
+    pending: VecDeque<Item>,
+}
+
+impl Generator {
+    fn write_struct(&mut self, name: &str, fields: &[(String, Field)]) -> Result<()> {
+        let mut body = String::new();
+        let mut accessors = String::new();
+        for (key, field) in fields {
+            let type_name = format!("{name}{}", pascal_case(key));
+            let mut ty = self.rust_type(&field.schema, &type_name)?;
+            let ident = field_ident(key);
+            let mut attributes = Vec::new();
+            if ident.strip_prefix("r#").unwrap_or(&ident) != key {
+                attributes.push(format!("rename = {key:?}"));
+            }
+            if field.optional {
+                attributes.push("default, skip_serializing_if = \"Option::is_none\"".to_string());
+                if !matches!(field.schema, Schema::Nullable(_)) {
+                    ty = format!("Option<{ty}>");
+                }
+            }
+            if !attributes.is_empty() {
+                body += &format!("    #[serde({})]\n", attributes.join(", "));
+            }
+            body += &format!("    pub {ident}: {ty},\n");
+
+            // Accessors are named after the key itself
+            if is_accessor(key) {
+                let ty = ref_type(&field.schema, &type_name);
+                accessors += &format!("        {key}: {ty},\n");
+            }
+        }
+        self.out += &format!(
+            "\n#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n\
+             pub struct {name} {{\n{body}}}\n"
+        );
+        self.out += &format!(
+            "\nferion::rion_ref! {{\n    \
+             /// Typed accessors for an encoded [`{name}`]\n    \
+             pub struct {name}Ref<'a> {{\n{accessors}    }}\n}}\n"
+        );
+        Ok(())
+    }
+
+    fn write_union(&mut self, name: &str, mut members: Vec<Schema>) -> Result<()> {
+        // Untagged variants are tried in order, the narrower types go first
+        members.sort_by_key(variant_order);
+        let mut body = String::new();
+        for member in &members {
+            let variant = variant_name(member);
+            body += &match member {
+                Schema::Null => format!("    {variant},\n"),
+                member => {
+                    let ty = self.rust_type(member, &format!("{name}{variant}"))?;
+                    format!("    {variant}({ty}),\n")
+                }
+            };
+        }
+        self.out += &format!(
+            "\n#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n\
+             #[serde(untagged)]\n\
+             pub enum {name} {{\n{body}}}\n"
+        );
+        Ok(())
+    }
+
+    // The owned type of a value, queueing the nested types it needs under
+    // `name`
+    fn rust_type(&mut self, schema: &Schema, name: &str) -> Result<String> {
+        Ok(match schema {
+            Schema::Any => {
+                return Err(FerionError::Invalid(format!(
+                    "{name} can hold any value, give its schema a type"
+                )))
+            }
+            Schema::Null => "()".to_string(),
+            Schema::Bool => "bool".to_string(),
+            Schema::Int => "i64".to_string(),
+            Schema::Float => "f64".to_string(),
+            Schema::String => "String".to_string(),
+            Schema::Bytes => "Vec<u8>".to_string(),
+            Schema::DateTime => "ferion::RionDateTime".to_string(),
+            Schema::Array(items) => format!("Vec<{}>", self.rust_type(items, name)?),
+            Schema::Object(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(key, field)| (key.clone(), field.clone()));
+                self.pending
+                    .push_back(Item::Struct(name.to_string(), fields.collect()));
+                name.to_string()
+            }
+            Schema::Table(columns) => {
+                let fields = columns
+                    .iter()
+                    .map(|(column, schema)| (column.clone(), Field::required(schema.clone())));
+                self.pending
+                    .push_back(Item::Struct(name.to_string(), fields.collect()));
+                format!("Vec<{name}>")
+            }
+            Schema::Nullable(inner) => format!("Option<{}>", self.rust_type(inner, name)?),
+            Schema::Union(members) => {
+                self.pending
+                    .push_back(Item::Union(name.to_string(), members.clone()));
+                name.to_string()
+            }
+        })
+    }
+}
+
+// The type an accessor reads a value as. Serde writes `Vec<u8>` as an array
+// of integers, so bytes are handed out as the raw field
+fn ref_type(schema: &Schema, name: &str) -> String {
+    match schema {
+        Schema::Bool => "bool".to_string(),
+        Schema::Int => "i64".to_string(),
+        Schema::Float => "f64".to_string(),
+        Schema::String => "&'a str".to_string(),
+        Schema::DateTime => "ferion::RionDateTime".to_string(),
+        Schema::Object(_) => format!("{name}Ref<'a>"),
+        Schema::Nullable(inner) => ref_type(inner, name),
+        _ => "ferion::RionField<'a>".to_string(),
+    }
+}
+
+fn variant_name(schema: &Schema) -> &'static str {
+    match schema {
+        Schema::Any => "Any",
+        Schema::Null => "Null",
+        Schema::Bool => "Bool",
+        Schema::Int => "Int",
+        Schema::Float => "Float",
+        Schema::String => "String",
+        Schema::Bytes => "Bytes",
+        Schema::DateTime => "DateTime",
+        Schema::Array(_) => "Array",
+        Schema::Object(_) => "Object",
+        Schema::Table(_) => "Table",
+        Schema::Nullable(_) => "Nullable",
+        Schema::Union(_) => "Union",
+    }
+}
+
+fn variant_order(schema: &Schema) -> u8 {
+    match schema {
+        Schema::Null => 0,
+        Schema::Bool => 1,
+        Schema::Int => 2,
+        Schema::Float => 3,
+        Schema::DateTime => 4,
+        Schema::String => 5,
+        Schema::Bytes => 6,
+        Schema::Array(_) | Schema::Table(_) => 7,
+        _ => 8,
+    }
+}
+
+// Whether `key` can name an accessor as it is, `raw` and `wrap` are taken by
+// the methods `rion_ref!` adds
+fn is_accessor(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && key != "_"
+        && snake_case(key) == key
+        && !KEYWORDS.contains(&key)
+        && !["raw", "wrap"].contains(&key)
+}
+
+fn field_ident(key: &str) -> String {
+    let ident = snake_case(key);
+    match ident.as_str() {
+        "" | "_" => "field".to_string(),
+        "crate" | "self" | "Self" | "super" => format!("{ident}_"),
+        ident if KEYWORDS.contains(&ident) => format!("r#{ident}"),
+        ident if ident.starts_with(|c: char| c.is_ascii_digit()) => format!("_{ident}"),
+        ident => ident.to_string(),
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut after_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if after_lower {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+            after_lower = false;
+        } else if c.is_ascii_alphanumeric() {
+            out.push(c);
+            after_lower = true;
+        } else {
+            out.push('_');
+            after_lower = false;
+        }
+    }
+    out
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().map(|c| c.to_ascii_uppercase());
+            first.into_iter().chain(chars)
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+// Generated by ferion-codegen, do not edit
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct User {
+    pub active: bool,
+    pub address: UserAddress,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Vec<u8>>,
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<UserExternalId>,
+    #[serde(rename = "firstName")]
+    pub first_name: String,
+    pub id: i64,
+    pub joined: ferion::RionDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    pub tags: Vec<String>,
+    pub r#type: String,
+}
+
+ferion::rion_ref! {
+    /// Typed accessors for an encoded [`User`]
+    pub struct UserRef<'a> {
+        active: bool,
+        address: UserAddressRef<'a>,
+        avatar: ferion::RionField<'a>,
+        email: &'a str,
+        external_id: ferion::RionField<'a>,
+        id: i64,
+        joined: ferion::RionDateTime,
+        score: f64,
+        tags: ferion::RionField<'a>,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UserAddress {
+    pub street: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zip: Option<String>,
+}
+
+ferion::rion_ref! {
+    /// Typed accessors for an encoded [`UserAddress`]
+    pub struct UserAddressRef<'a> {
+        street: &'a str,
+        zip: &'a str,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum UserExternalId {
+    Int(i64),
+    String(String),
+}
@@ -0,0 +1,78 @@
+use ferion::{
+    schema::{Field, Schema},
+    Precision, RionDateTime,
+};
+use ferion_codegen::{generate, generate_file};
+
+mod user {
+    include!("fixtures/user.rs");
+}
+
+use user::{User, UserAddress, UserExternalId, UserRef};
+
+#[test]
+fn test_generate_file() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/user.json");
+    let code = generate_file(path, "User").unwrap();
+    assert_eq!(code, include_str!("fixtures/user.rs"));
+}
+
+#[test]
+fn test_generated_types() {
+    let user = User {
+        active: true,
+        address: UserAddress {
+            street: "1 Main St".to_string(),
+            zip: None,
+        },
+        avatar: Some(vec![1, 2, 3]),
+        email: None,
+        external_id: Some(UserExternalId::String("A-7".to_string())),
+        first_name: "Alice".to_string(),
+        id: 42,
+        joined: RionDateTime::from_components(2024, 2, 29, 13, 45, 10, 0).unwrap(),
+        score: Some(1.5),
+        tags: vec!["admin".to_string()],
+        r#type: "person".to_string(),
+    };
+    let bytes = ferion::to_bytes(&user).unwrap();
+    assert_eq!(ferion::from_bytes::<User>(&bytes).unwrap(), user);
+
+    let user_ref = UserRef::wrap(&bytes).unwrap();
+    assert_eq!(user_ref.id().unwrap(), Some(42));
+    assert_eq!(user_ref.email().unwrap(), None);
+    assert_eq!(user_ref.score().unwrap(), Some(1.5));
+    let joined = user_ref.joined().unwrap().unwrap();
+    assert_eq!(joined.precision(), Precision::Second);
+    let address = user_ref.address().unwrap().unwrap();
+    assert_eq!(address.street().unwrap(), Some("1 Main St"));
+    assert_eq!(address.zip().unwrap(), None);
+    // Keys that aren't snake case identifiers get no accessor
+    assert_eq!(user_ref.raw().get_as("firstName").unwrap(), Some("Alice"));
+}
+
+#[test]
+fn test_generate_errors() {
+    assert!(generate(&Schema::Int, "Id").is_err());
+
+    let fields = [("extra".to_string(), Field::required(Schema::Any))];
+    assert!(generate(&Schema::Object(fields.into()), "Doc").is_err());
+}
+
+#[test]
+fn test_generate_tables() {
+    let columns = vec![
+        ("id".to_string(), Schema::Int),
+        (
+            "match".to_string(),
+            Schema::Nullable(Box::new(Schema::Bool)),
+        ),
+    ];
+    let fields = [("rows".to_string(), Field::required(Schema::Table(columns)))];
+    let code = generate(&Schema::Object(fields.into()), "Report").unwrap();
+    assert!(code.contains("pub rows: Vec<ReportRows>,"));
+    assert!(code
+        .contains("pub struct ReportRows {\n    pub id: i64,\n    pub r#match: Option<bool>,\n}"));
+    // Keywords can't name accessors
+    assert!(code.contains("pub struct ReportRowsRef<'a> {\n        id: i64,\n    }"));
+}
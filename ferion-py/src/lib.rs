@@ -0,0 +1,190 @@
+//! Python bindings for ferion
+//!
+//! Build with `maturin develop` from this directory, then `import ferion`.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use ferion::{analyze, schema, RionValue};
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyBytes, PyDateTime, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+    IntoPyObjectExt,
+};
+
+fn value_error(e: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// Encode a Python object as RION bytes
+///
+/// Supports None, bool, int, float, str, bytes, timezone aware datetimes, and
+/// lists, tuples and dicts of those. Dict keys must be str or bytes.
+#[pyfunction]
+fn dumps<'py>(py: Python<'py>, obj: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+    let encoded = to_value(obj)?.encode_to_vec().map_err(value_error)?;
+    Ok(PyBytes::new(py, &encoded))
+}
+
+/// Decode RION bytes into Python objects, tables become lists of dicts
+#[pyfunction]
+fn loads<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyAny>> {
+    let value = RionValue::from_slice(data).map_err(value_error)?;
+    to_python(py, &value)
+}
+
+/// Check RION bytes against a JSON Schema given as a JSON string
+///
+/// Returns a list of error messages, empty when the document is valid.
+#[pyfunction]
+fn validate(data: &[u8], schema: &str) -> PyResult<Vec<String>> {
+    let value = RionValue::from_slice(data).map_err(value_error)?;
+    let schema: serde_json::Value = serde_json::from_str(schema).map_err(value_error)?;
+    Ok(match schema::validate(&value, &schema) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(ToString::to_string).collect(),
+    })
+}
+
+/// Break down where the bytes of an encoded value go, like `rion stats`
+#[pyfunction]
+fn inspect<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyDict>> {
+    let analysis = analyze(data).map_err(value_error)?;
+    let usage = |usage: &ferion::Usage| -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("count", usage.count)?;
+        dict.set_item("bytes", usage.bytes)?;
+        Ok(dict)
+    };
+    let types = PyDict::new(py);
+    for (name, type_usage) in &analysis.types {
+        types.set_item(*name, usage(type_usage)?)?;
+    }
+    let out = PyDict::new(py);
+    out.set_item("total_bytes", analysis.total_bytes)?;
+    out.set_item("max_depth", analysis.max_depth)?;
+    out.set_item("types", types)?;
+    out.set_item("keys", usage(&analysis.keys)?)?;
+    Ok(out)
+}
+
+fn to_value(obj: &Bound<'_, PyAny>) -> PyResult<RionValue> {
+    if obj.is_none() {
+        return Ok(RionValue::Null);
+    }
+    // bool is a subclass of int, so it has to be checked first
+    if let Ok(b) = obj.downcast::<PyBool>() {
+        return Ok(RionValue::Bool(b.is_true()));
+    }
+    if obj.is_instance_of::<PyInt>() {
+        if let Ok(n) = obj.extract::<u128>() {
+            return Ok(RionValue::PosInt(n));
+        }
+        return Ok(RionValue::from(obj.extract::<i128>()?));
+    }
+    if let Ok(f) = obj.downcast::<PyFloat>() {
+        return Ok(RionValue::Float(f.value()));
+    }
+    if let Ok(s) = obj.downcast::<PyString>() {
+        return Ok(RionValue::from(s.to_str()?));
+    }
+    if let Ok(b) = obj.downcast::<PyBytes>() {
+        return Ok(RionValue::Bytes(b.as_bytes().to_vec()));
+    }
+    if obj.is_instance_of::<PyDateTime>() {
+        return Ok(obj.extract::<DateTime<Utc>>()?.into());
+    }
+    if let Ok(list) = obj.downcast::<PyList>() {
+        return list
+            .iter()
+            .map(|e| to_value(&e))
+            .collect::<PyResult<Vec<_>>>()
+            .map(RionValue::Array);
+    }
+    if let Ok(tuple) = obj.downcast::<PyTuple>() {
+        return tuple
+            .iter()
+            .map(|e| to_value(&e))
+            .collect::<PyResult<Vec<_>>>()
+            .map(RionValue::Array);
+    }
+    if let Ok(dict) = obj.downcast::<PyDict>() {
+        let mut fields = BTreeMap::new();
+        for (key, value) in dict.iter() {
+            let key = if let Ok(s) = key.downcast::<PyString>() {
+                s.to_str()?.as_bytes().into()
+            } else if let Ok(b) = key.downcast::<PyBytes>() {
+                b.as_bytes().into()
+            } else {
+                return Err(PyTypeError::new_err("dict keys must be str or bytes"));
+            };
+            fields.insert(key, to_value(&value)?);
+        }
+        return Ok(RionValue::Object(fields));
+    }
+    Err(PyTypeError::new_err(format!(
+        "cannot encode objects of type {}",
+        obj.get_type().name()?
+    )))
+}
+
+fn to_python<'py>(py: Python<'py>, value: &RionValue) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        RionValue::Null => Ok(py.None().into_bound(py)),
+        RionValue::Bool(b) => b.into_bound_py_any(py),
+        RionValue::PosInt(n) => n.into_bound_py_any(py),
+        RionValue::NegInt(n) => n.into_bound_py_any(py),
+        RionValue::Float(n) => n.into_bound_py_any(py),
+        RionValue::String(s) => (**s).into_bound_py_any(py),
+        RionValue::Bytes(b) => Ok(PyBytes::new(py, b).into_any()),
+        RionValue::DateTime(dt) => dt.datetime().into_bound_py_any(py),
+        RionValue::Array(elements) => {
+            let list = PyList::empty(py);
+            for element in elements {
+                list.append(to_python(py, element)?)?;
+            }
+            Ok(list.into_any())
+        }
+        RionValue::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, value) in fields {
+                dict.set_item(key_to_python(py, key)?, to_python(py, value)?)?;
+            }
+            Ok(dict.into_any())
+        }
+        RionValue::Table { columns, rows } => {
+            let list = PyList::empty(py);
+            for row in rows {
+                let dict = PyDict::new(py);
+                for (key, cell) in columns.iter().zip(row) {
+                    dict.set_item(key_to_python(py, key)?, to_python(py, cell)?)?;
+                }
+                list.append(dict)?;
+            }
+            Ok(list.into_any())
+        }
+        // Extended types Python has no equivalent for, as (type_id, data)
+        RionValue::Extended { type_id, data } => {
+            (*type_id, PyBytes::new(py, data)).into_bound_py_any(py)
+        }
+    }
+}
+
+// Keys that are not UTF-8 stay bytes
+fn key_to_python<'py>(py: Python<'py>, key: &[u8]) -> PyResult<Bound<'py, PyAny>> {
+    match std::str::from_utf8(key) {
+        Ok(key) => key.into_bound_py_any(py),
+        Err(_) => Ok(PyBytes::new(py, key).into_any()),
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "ferion")]
+fn ferion_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(dumps, m)?)?;
+    m.add_function(wrap_pyfunction!(loads, m)?)?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    m.add_function(wrap_pyfunction!(inspect, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    get_header, get_normal_header,
+    types::{NormalRionType, RionFieldType, ShortRionType},
+    FerionError, Result, RionField,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RionValue;
+
+    #[test]
+    fn test_analyze_object() {
+        let mut doc = std::collections::BTreeMap::new();
+        doc.insert(b"name"[..].into(), "Alice".into());
+        doc.insert(
+            b"tags"[..].into(),
+            RionValue::Array(vec![1u64.into(), 2u64.into()]),
+        );
+        doc.insert(b"nick"[..].into(), RionValue::Null);
+        let data = RionValue::Object(doc).encode_to_vec().unwrap();
+
+        let analysis = analyze(&data).unwrap();
+        assert_eq!(analysis.total_bytes, data.len());
+        assert_eq!(analysis.max_depth, 2);
+        assert_eq!(analysis.keys.count, 3);
+        assert_eq!(analysis.keys.bytes, 15);
+        assert_eq!(analysis.types["int"].count, 2);
+        assert_eq!(analysis.types["null"].count, 1);
+        assert_eq!(analysis.types["object"].count, 1);
+        let accounted =
+            analysis.keys.bytes + analysis.types.values().map(|u| u.bytes).sum::<usize>();
+        assert_eq!(accounted, data.len());
+    }
+
+    #[test]
+    fn test_analyze_count_hints() {
+        // [1] with a count hint (type 0x01) in front
+        let data = [0xF1, 0x02, 0x01, 0x01, 0xA1, 0x02, 0x21, 0x01];
+        let analysis = analyze(&data).unwrap();
+        assert_eq!(analysis.max_depth, 1);
+        assert_eq!(analysis.types["count hint"].bytes, 4);
+        assert_eq!(analysis.types["int"].count, 1);
+    }
+
+    #[test]
+    fn test_analyze_extended() {
+        // An extended field of an unknown type 0x7F in an array
+        let data = [0xA1, 0x04, 0xF1, 0x02, 0x7F, 0xAB];
+        let analysis = analyze(&data).unwrap();
+        assert_eq!(analysis.types["extended"].count, 1);
+        assert_eq!(analysis.types["extended"].bytes, 4);
+    }
+
+    #[test]
+    fn test_analyze_table() {
+        let table = RionValue::Table {
+            columns: vec![b"id"[..].into()],
+            rows: vec![vec![1u64.into()], vec![2u64.into()]],
+        };
+        let data = table.encode_to_vec().unwrap();
+        let analysis = analyze(&data).unwrap();
+        assert_eq!(analysis.keys.count, 1);
+        assert_eq!(analysis.types["int"].count, 2);
+        let accounted =
+            analysis.keys.bytes + analysis.types.values().map(|u| u.bytes).sum::<usize>();
+        assert_eq!(accounted, data.len());
+    }
+
+    #[test]
+    fn test_analyze_deeply_nested() {
+        // Arrays nested 200,000 deep around a null
+        let depth = 200_000;
+        let mut value = RionValue::Null;
+        for _ in 0..depth {
+            value = RionValue::Array(vec![value]);
+        }
+        let data = value.encode_to_vec().unwrap();
+        let analysis = analyze(&data).unwrap();
+        assert_eq!(analysis.max_depth, depth);
+        assert_eq!(analysis.types["array"].count, depth);
+    }
+
+    #[test]
+    fn test_analyze_trailing_data() {
+        assert!(analyze(&[0x21, 0x01, 0x00]).is_err());
+    }
+}
+
+/// A breakdown of where the bytes of an encoded value go
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Analysis {
+    pub total_bytes: usize,
+    /// Deepest container nesting, a lone scalar has a depth of 0
+    pub max_depth: usize,
+    /// Usage per value type, containers only count their own headers
+    pub types: BTreeMap<&'static str, Usage>,
+    /// Object keys and table column names
+    pub keys: Usage,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    pub count: usize,
+    /// Encoded bytes, including lead and length bytes
+    pub bytes: usize,
+}
+
+impl Usage {
+    fn add(&mut self, bytes: usize) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Walk an encoded value and record how many bytes each type accounts for
+///
+/// Nested containers are kept on a stack rather than walked recursively, so
+/// any depth [`RionValue::from_slice`](crate::RionValue::from_slice) parses
+/// can be analyzed.
+pub fn analyze(data: &[u8]) -> Result<Analysis> {
+    let mut analysis = Analysis {
+        total_bytes: data.len(),
+        ..Default::default()
+    };
+    let (rest, content) = visit(data, 0, &mut analysis)?;
+    if !rest.is_empty() {
+        return Err(FerionError::invalid(format!(
+            "{} bytes of extra data after value",
+            rest.len()
+        )));
+    }
+    // The content left to walk of each container we're in, outermost first
+    let mut stack: Vec<&[u8]> = content.into_iter().collect();
+    while let Some(content) = stack.pop() {
+        if content.is_empty() {
+            continue;
+        }
+        let (rest, inner) = visit(content, stack.len() + 1, &mut analysis)?;
+        stack.push(rest);
+        stack.extend(inner);
+    }
+    Ok(analysis)
+}
+
+// Record the field at the start of `data`, returning the data after it and
+// its content still to be walked if it's a container
+fn visit<'a>(
+    data: &'a [u8],
+    depth: usize,
+    analysis: &mut Analysis,
+) -> Result<(&'a [u8], Option<&'a [u8]>)> {
+    let hinted = crate::skip_count_hint(data)?;
+    if hinted.len() < data.len() {
+        let usage = analysis.types.entry("count hint").or_default();
+        usage.add(data.len() - hinted.len());
+    }
+    let data = hinted;
+    let (lead, _, _) = get_header(data)?;
+    let normal = match lead.field_type() {
+        RionFieldType::Normal(normal) if !lead.is_null() => normal,
+        field_type => {
+            let (field, rest) = RionField::parse(data)?;
+            let usage = if field.is_key() {
+                &mut analysis.keys
+            } else {
+                analysis
+                    .types
+                    .entry(type_name(field_type, lead.is_null()))
+                    .or_default()
+            };
+            usage.add(data.len() - rest.len());
+            return Ok((rest, None));
+        }
+    };
+
+    let (_, data_len, content) = get_normal_header(data)?;
+    let (mut content, rest) = content.split_at(data_len);
+    let mut header = data.len() - data_len - rest.len();
+    let mut inner = None;
+    match normal {
+        NormalRionType::Array | NormalRionType::Object => {
+            analysis.max_depth = analysis.max_depth.max(depth + 1);
+            inner = Some(content);
+        }
+        NormalRionType::Table => {
+            analysis.max_depth = analysis.max_depth.max(depth + 1);
+            // The row count belongs to the table itself
+            let (_, after_count) = RionField::parse(content)?;
+            header += content.len() - after_count.len();
+            content = after_count;
+            inner = Some(content);
+        }
+        NormalRionType::Key => {
+            analysis.keys.add(data.len() - rest.len());
+            return Ok((rest, None));
+        }
+        NormalRionType::Bytes | NormalRionType::UTF8 => header = data.len() - rest.len(),
+    }
+    analysis
+        .types
+        .entry(type_name(RionFieldType::Normal(normal), false))
+        .or_default()
+        .add(header);
+    Ok((rest, inner))
+}
+
+fn type_name(field_type: RionFieldType, null: bool) -> &'static str {
+    if null {
+        return "null";
+    }
+    match field_type {
+        RionFieldType::Tiny(_) => "bool",
+        RionFieldType::Short(ShortRionType::Int64Positive | ShortRionType::Int64Negative) => "int",
+        RionFieldType::Short(ShortRionType::Float) => "float",
+        RionFieldType::Short(ShortRionType::UTF8) | RionFieldType::Normal(NormalRionType::UTF8) => {
+            "string"
+        }
+        RionFieldType::Short(ShortRionType::UTCDateTime) => "datetime",
+        RionFieldType::Normal(NormalRionType::Bytes) => "bytes",
+        RionFieldType::Normal(NormalRionType::Array) => "array",
+        RionFieldType::Normal(NormalRionType::Table) => "table",
+        RionFieldType::Normal(NormalRionType::Object) => "object",
+        RionFieldType::Short(ShortRionType::Key) | RionFieldType::Normal(NormalRionType::Key) => {
+            "key"
+        }
+        RionFieldType::Extended => "extended",
+    }
+}
@@ -1,7 +1,9 @@
 use crate::{
+    error::Result,
+    field::NormalField,
     needed_bytes_usize,
     types::{LeadByte, NormalRionType, RionFieldType},
-    Result, RionField,
+    FerionError, RionField,
 };
 
 #[cfg(test)]
@@ -21,6 +23,45 @@ mod test {
         assert_eq!(array.elements.len(), 1);
     }
 
+    #[test]
+    fn test_edit_elements() {
+        let mut array = RionArray::new();
+        array.extend(["a", "c"]);
+        array.insert(1, "b");
+        array.extend([1i64, 2, 3]);
+        assert_eq!(array.len(), 6);
+        assert_eq!(array.remove(0), Some(RionField::from("a")));
+        assert_eq!(array.remove(10), None);
+        array.truncate(2);
+        assert_eq!(array.elements, [RionField::from("b"), RionField::from("c")]);
+
+        let encoded = array.encode();
+        assert_eq!(RionArray::from_slice(&encoded).unwrap(), array);
+    }
+
+    #[test]
+    fn test_sort_dedup() {
+        let mut array = RionArray::new();
+        array.extend(["b", "a", "b"]);
+        array.extend([3i64, -1, 3]);
+        array.add_element(2.5f64);
+        array.add_element(true);
+        array.sort();
+        array.dedup();
+        let expected: Vec<RionField> = vec![
+            true.into(),
+            (-1i64).into(),
+            2.5f64.into(),
+            3i64.into(),
+            "a".into(),
+            "b".into(),
+        ];
+        assert_eq!(array.elements, expected);
+
+        array.sort_by_key(|field| std::cmp::Reverse(field.clone()));
+        assert_eq!(array.elements[0], RionField::from("b"));
+    }
+
     #[test]
     fn test_encode_decode_array() {
         let mut array = RionArray::new();
@@ -43,7 +84,7 @@ mod test {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RionArray<'a> {
     pub elements: Vec<RionField<'a>>,
 }
@@ -54,6 +95,12 @@ impl<'a> Default for RionArray<'a> {
     }
 }
 
+impl<'a, T: Into<RionField<'a>>> Extend<T> for RionArray<'a> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.elements.extend(iter.into_iter().map(Into::into));
+    }
+}
+
 impl<'a> RionArray<'a> {
     pub fn new() -> Self {
         RionArray {
@@ -64,20 +111,20 @@ impl<'a> RionArray<'a> {
     pub fn from_slice(data: &'a [u8]) -> Result<Self> {
         let (array, rest) = Self::parse(data)?;
         if !rest.is_empty() {
-            return Err("Extra data after array".into());
+            return Err(FerionError::TrailingData(rest.len()));
         }
         Ok(array)
     }
 
-    fn parse(data: &'a [u8]) -> Result<(Self, &[u8])> {
-        let (lead, length, mut rest) = crate::get_normal_header(data)?;
+    fn parse(data: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        let (lead, length, mut rest) = crate::get_normal_header(crate::skip_count_hint(data)?)?;
         let RionFieldType::Normal(NormalRionType::Array) = lead.field_type() else {
-            return Err("Expected a RION array".into());
+            return Err(FerionError::unexpected("an array", lead.field_type()));
         };
         let total = rest.len();
         let mut elements = Vec::with_capacity(length);
         while total - rest.len() < length {
-            let (element, new_rest) = RionField::parse(rest)?;
+            let (element, new_rest) = RionField::parse(crate::skip_count_hint(rest)?)?;
             rest = new_rest;
             elements.push(element);
         }
@@ -89,6 +136,44 @@ impl<'a> RionArray<'a> {
         self.elements.push(element.into());
     }
 
+    /// Insert an element at `index`, shifting the ones after it
+    ///
+    /// Panics if `index > len`, like [`Vec::insert`]
+    pub fn insert(&mut self, index: usize, element: impl Into<RionField<'a>>) {
+        self.elements.insert(index, element.into());
+    }
+
+    /// Remove and return the element at `index`, or `None` if out of bounds
+    pub fn remove(&mut self, index: usize) -> Option<RionField<'a>> {
+        (index < self.elements.len()).then(|| self.elements.remove(index))
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.elements.truncate(len);
+    }
+
+    /// Sort the elements with the type-aware ordering of [`RionField`]
+    pub fn sort(&mut self) {
+        self.elements.sort();
+    }
+
+    pub fn sort_by_key<K: Ord>(&mut self, f: impl FnMut(&RionField<'a>) -> K) {
+        self.elements.sort_by_key(f);
+    }
+
+    /// Remove consecutive repeated elements, sort first to remove all duplicates
+    pub fn dedup(&mut self) {
+        self.elements.dedup();
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         if self.elements.is_empty() {
             return vec![
@@ -103,10 +188,6 @@ impl<'a> RionArray<'a> {
         let content_len = content.len();
         // number of bytes needed to encode the length
         let length_length = needed_bytes_usize(content_len);
-        if length_length > 15 {
-            println!("Warning: Object length field is too long, truncating to 15 bytes");
-        }
-        println!("Content length: {content_len} - Num Bytes {length_length}");
         let length_bytes = content_len.to_be_bytes();
         let mut encoded = Vec::with_capacity(1 + content_len + length_length);
         encoded.push(0xA0 | length_length as u8 & 0x0F);
@@ -116,3 +197,16 @@ impl<'a> RionArray<'a> {
         encoded
     }
 }
+
+impl<'a> From<Vec<RionField<'a>>> for RionField<'_> {
+    fn from(elements: Vec<RionField<'a>>) -> Self {
+        let mut content = Vec::new();
+        for element in &elements {
+            element.encode(&mut content).unwrap();
+        }
+        RionField::Normal(NormalField {
+            field_type: NormalRionType::Array,
+            data: content.into(),
+        })
+    }
+}
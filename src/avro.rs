@@ -0,0 +1,679 @@
+//! Conversion between Avro datums and [`RionValue`]s
+//!
+//! Records and maps become Objects, arrays Arrays, enums Strings, fixed
+//! Bytes and `timestamp-millis`/`timestamp-micros` longs DateTimes. Datums use
+//! the Avro binary encoding, without the object container file framing.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::{FerionError, Result, RionValue};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "Event",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "level", "type": {"type": "enum", "name": "Level", "symbols": ["DEBUG", "INFO"]}},
+            {"name": "message", "type": ["null", "string"]},
+            {"name": "tags", "type": {"type": "array", "items": "string"}},
+            {"name": "counts", "type": {"type": "map", "values": "int"}},
+            {"name": "at", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "hash", "type": {"type": "fixed", "name": "Hash", "size": 2}},
+            {"name": "ratio", "type": "double"}
+        ]
+    }"#;
+
+    fn event(id: i64, message: Option<&str>) -> RionValue {
+        let mut counts = BTreeMap::new();
+        counts.insert(b"retries"[..].into(), RionValue::PosInt(2));
+        let mut fields = BTreeMap::new();
+        fields.insert(b"id"[..].into(), RionValue::from(id));
+        fields.insert(b"level"[..].into(), "INFO".into());
+        fields.insert(
+            b"message"[..].into(),
+            message.map_or(RionValue::Null, RionValue::from),
+        );
+        fields.insert(
+            b"tags"[..].into(),
+            RionValue::Array(vec!["a".into(), "b".into()]),
+        );
+        fields.insert(b"counts"[..].into(), RionValue::Object(counts));
+        fields.insert(
+            b"at"[..].into(),
+            Utc.with_ymd_and_hms(2024, 2, 29, 12, 0, 0).unwrap().into(),
+        );
+        fields.insert(b"hash"[..].into(), RionValue::Bytes(vec![0xAB, 0xCD]));
+        fields.insert(b"ratio"[..].into(), RionValue::Float(0.5));
+        RionValue::Object(fields)
+    }
+
+    #[test]
+    fn test_primitive_encoding() {
+        // Examples from the Avro specification
+        let long = AvroSchema::parse(r#""long""#).unwrap();
+        assert_eq!(to_avro(&long, &RionValue::from(-64i64)).unwrap(), [0x7F]);
+        assert_eq!(
+            to_avro(&long, &RionValue::PosInt(64)).unwrap(),
+            [0x80, 0x01]
+        );
+        let string = AvroSchema::parse(r#"{"type": "string"}"#).unwrap();
+        let foo = to_avro(&string, &"foo".into()).unwrap();
+        assert_eq!(foo, [0x06, b'f', b'o', b'o']);
+        assert_eq!(from_avro(&string, &foo).unwrap(), RionValue::from("foo"));
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let schema = AvroSchema::parse(SCHEMA).unwrap();
+        for value in [event(-3, Some("hello")), event(7, None)] {
+            let datum = to_avro(&schema, &value).unwrap();
+            assert_eq!(from_avro(&schema, &datum).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let schema = AvroSchema::parse(SCHEMA).unwrap();
+        let mut value = event(1, None);
+        *value.get_mut("level").unwrap() = "WARN".into();
+        assert!(to_avro(&schema, &value).is_err());
+
+        let int = AvroSchema::parse(r#""int""#).unwrap();
+        assert!(to_avro(&int, &RionValue::PosInt(1 << 40)).is_err());
+        assert!(from_avro(&int, &[0x02, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_table_round_trip() {
+        let schema = AvroSchema::parse(SCHEMA).unwrap();
+        let datums = [event(1, Some("a")), event(2, None)]
+            .iter()
+            .map(|value| to_avro(&schema, value).unwrap())
+            .collect::<Vec<_>>();
+        let table = table_from_avro(&schema, datums.iter().map(Vec::as_slice)).unwrap();
+        let RionValue::Table { columns, rows } = &table else {
+            panic!("Expected a table");
+        };
+        assert_eq!(columns[0], b"id"[..].into());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(table_to_avro(&schema, &table).unwrap(), datums);
+    }
+
+    #[test]
+    fn test_named_reference() {
+        let schema = AvroSchema::parse(
+            r#"{"type": "record", "name": "Pair", "fields": [
+                {"name": "a", "type": {"type": "fixed", "name": "Id", "size": 1}},
+                {"name": "b", "type": "Id"}
+            ]}"#,
+        )
+        .unwrap();
+        let AvroSchema::Record { fields, .. } = &schema else {
+            panic!("Expected a record");
+        };
+        assert_eq!(fields[0].1, fields[1].1);
+    }
+
+    #[test]
+    fn test_empty_item_count() {
+        let nulls = AvroSchema::parse(r#"{"type": "array", "items": "null"}"#).unwrap();
+        assert_eq!(
+            from_avro(&nulls, &[0x06, 0x00]).unwrap(),
+            RionValue::Array(vec![RionValue::Null; 3])
+        );
+        // A block of about 2^62 nulls
+        let datum = [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x00];
+        assert!(from_avro(&nulls, &datum).is_err());
+    }
+}
+
+/// A parsed Avro schema
+#[derive(Debug, Clone, PartialEq)]
+pub enum AvroSchema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    /// A long holding milliseconds since the Unix epoch
+    TimestampMillis,
+    /// A long holding microseconds since the Unix epoch
+    TimestampMicros,
+    /// Field names and schemas, in encoding order
+    Record {
+        name: String,
+        fields: Vec<(String, AvroSchema)>,
+    },
+    Enum {
+        name: String,
+        symbols: Vec<String>,
+    },
+    Fixed {
+        name: String,
+        size: usize,
+    },
+    Array(Box<AvroSchema>),
+    /// A map with string keys
+    Map(Box<AvroSchema>),
+    Union(Vec<AvroSchema>),
+}
+
+impl AvroSchema {
+    /// Parse a schema from its JSON form
+    pub fn parse(json: &str) -> Result<Self> {
+        Self::from_json(&serde_json::from_str(json)?)
+    }
+
+    pub fn from_json(json: &Value) -> Result<Self> {
+        Self::resolve(json, &mut BTreeMap::new())
+    }
+
+    // `named` holds the records, enums and fixed types defined so far, which
+    // later parts of the schema may refer to by name
+    fn resolve(json: &Value, named: &mut BTreeMap<String, AvroSchema>) -> Result<Self> {
+        let object = match json {
+            Value::String(name) => return Self::primitive(name, named),
+            Value::Array(members) => {
+                let members = members
+                    .iter()
+                    .map(|member| Self::resolve(member, named))
+                    .collect::<Result<_>>()?;
+                return Ok(AvroSchema::Union(members));
+            }
+            Value::Object(object) => object,
+            _ => return Err(FerionError::invalid(format!("Invalid Avro schema {json}"))),
+        };
+        let Some(type_name) = object.get("type").and_then(Value::as_str) else {
+            return Err(FerionError::invalid(format!(
+                "Avro schema without a type {json}"
+            )));
+        };
+        let name = || -> Result<String> {
+            let name = object.get("name").and_then(Value::as_str);
+            Ok(name
+                .ok_or_else(|| FerionError::invalid(format!("Unnamed Avro {type_name}")))?
+                .to_string())
+        };
+        let schema = match type_name {
+            "record" | "error" => {
+                let Some(fields) = object.get("fields").and_then(Value::as_array) else {
+                    return Err(FerionError::invalid(format!(
+                        "Avro record without fields {json}"
+                    )));
+                };
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        let name = field.get("name").and_then(Value::as_str);
+                        let name = name.ok_or_else(|| {
+                            FerionError::invalid("Avro record field without a name")
+                        })?;
+                        let schema = field.get("type").ok_or_else(|| {
+                            FerionError::invalid("Avro record field without a type")
+                        })?;
+                        Ok((name.to_string(), Self::resolve(schema, named)?))
+                    })
+                    .collect::<Result<_>>()?;
+                AvroSchema::Record {
+                    name: name()?,
+                    fields,
+                }
+            }
+            "enum" => {
+                let Some(symbols) = object.get("symbols").and_then(Value::as_array) else {
+                    return Err(FerionError::invalid(format!(
+                        "Avro enum without symbols {json}"
+                    )));
+                };
+                let symbols = symbols
+                    .iter()
+                    .map(|symbol| {
+                        Ok(symbol
+                            .as_str()
+                            .ok_or_else(|| FerionError::invalid("Invalid enum symbol"))?
+                            .to_string())
+                    })
+                    .collect::<Result<_>>()?;
+                AvroSchema::Enum {
+                    name: name()?,
+                    symbols,
+                }
+            }
+            "fixed" => {
+                let size = object.get("size").and_then(Value::as_u64);
+                AvroSchema::Fixed {
+                    name: name()?,
+                    size: size.ok_or_else(|| FerionError::invalid("Avro fixed without a size"))?
+                        as usize,
+                }
+            }
+            "array" => {
+                let items = object
+                    .get("items")
+                    .ok_or_else(|| FerionError::invalid("Avro array without items"))?;
+                AvroSchema::Array(Box::new(Self::resolve(items, named)?))
+            }
+            "map" => {
+                let values = object
+                    .get("values")
+                    .ok_or_else(|| FerionError::invalid("Avro map without values"))?;
+                AvroSchema::Map(Box::new(Self::resolve(values, named)?))
+            }
+            "long" => match object.get("logicalType").and_then(Value::as_str) {
+                Some("timestamp-millis") => AvroSchema::TimestampMillis,
+                Some("timestamp-micros") => AvroSchema::TimestampMicros,
+                _ => AvroSchema::Long,
+            },
+            // Other logical types fall back to their underlying type
+            _ => return Self::primitive(type_name, named),
+        };
+        if let AvroSchema::Record { name, .. }
+        | AvroSchema::Enum { name, .. }
+        | AvroSchema::Fixed { name, .. } = &schema
+        {
+            named.insert(name.clone(), schema.clone());
+        }
+        Ok(schema)
+    }
+
+    fn primitive(name: &str, named: &BTreeMap<String, AvroSchema>) -> Result<Self> {
+        let schema = match name {
+            "null" => AvroSchema::Null,
+            "boolean" => AvroSchema::Boolean,
+            "int" => AvroSchema::Int,
+            "long" => AvroSchema::Long,
+            "float" => AvroSchema::Float,
+            "double" => AvroSchema::Double,
+            "bytes" => AvroSchema::Bytes,
+            "string" => AvroSchema::String,
+            // Recursive types are not supported, a name must be defined before it is used
+            _ => match named.get(name) {
+                Some(schema) => schema.clone(),
+                None => return Err(FerionError::invalid(format!("Unknown Avro type {name:?}"))),
+            },
+        };
+        Ok(schema)
+    }
+
+    // Whether a datum of this schema can be written in no bytes at all
+    fn can_be_empty(&self) -> bool {
+        match self {
+            AvroSchema::Null => true,
+            AvroSchema::Fixed { size, .. } => *size == 0,
+            AvroSchema::Record { fields, .. } => {
+                fields.iter().all(|(_, field)| field.can_be_empty())
+            }
+            _ => false,
+        }
+    }
+
+    // Whether `value` can be written with this schema, used to pick a union branch
+    fn accepts(&self, value: &RionValue) -> bool {
+        match (self, value) {
+            (AvroSchema::Null, RionValue::Null)
+            | (AvroSchema::Boolean, RionValue::Bool(_))
+            | (AvroSchema::Float | AvroSchema::Double, RionValue::Float(_))
+            | (AvroSchema::Bytes, RionValue::Bytes(_))
+            | (AvroSchema::String, RionValue::String(_))
+            | (AvroSchema::TimestampMillis | AvroSchema::TimestampMicros, RionValue::DateTime(_))
+            | (AvroSchema::Array(_), RionValue::Array(_))
+            | (AvroSchema::Record { .. } | AvroSchema::Map(_), RionValue::Object(_)) => true,
+            (AvroSchema::Int, RionValue::PosInt(n)) => i32::try_from(*n).is_ok(),
+            (AvroSchema::Int, RionValue::NegInt(n)) => i32::try_from(*n).is_ok(),
+            (AvroSchema::Long, RionValue::PosInt(n)) => i64::try_from(*n).is_ok(),
+            (AvroSchema::Long, RionValue::NegInt(n)) => i64::try_from(*n).is_ok(),
+            (AvroSchema::Enum { symbols, .. }, RionValue::String(s)) => {
+                symbols.iter().any(|symbol| **symbol == **s)
+            }
+            (AvroSchema::Fixed { size, .. }, RionValue::Bytes(bytes)) => bytes.len() == *size,
+            _ => false,
+        }
+    }
+}
+
+/// The most items an array or map whose items can take no bytes, such as
+/// nulls, is decoded with
+pub const MAX_EMPTY_ITEMS: usize = 1 << 20;
+
+/// Decode a single datum written with `schema`
+pub fn from_avro(schema: &AvroSchema, datum: &[u8]) -> Result<RionValue> {
+    let mut data = datum;
+    let value = decode(schema, &mut data)?;
+    if !data.is_empty() {
+        return Err(FerionError::invalid("Extra data after Avro datum"));
+    }
+    Ok(value)
+}
+
+/// Encode `value` as a datum of `schema`
+pub fn to_avro(schema: &AvroSchema, value: &RionValue) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    encode(schema, value, &mut out)?;
+    Ok(out)
+}
+
+/// Decode datums of a record schema into a Table value, one row per datum
+pub fn table_from_avro<'a>(
+    schema: &AvroSchema,
+    datums: impl IntoIterator<Item = &'a [u8]>,
+) -> Result<RionValue> {
+    let AvroSchema::Record { fields, .. } = schema else {
+        return Err(FerionError::invalid(
+            "Tables can only be read from Avro records",
+        ));
+    };
+    let mut rows = Vec::new();
+    for mut datum in datums {
+        let row = fields
+            .iter()
+            .map(|(_, field)| decode(field, &mut datum))
+            .collect::<Result<_>>()?;
+        if !datum.is_empty() {
+            return Err(FerionError::invalid("Extra data after Avro datum"));
+        }
+        rows.push(row);
+    }
+    let columns = fields
+        .iter()
+        .map(|(name, _)| name.as_bytes().into())
+        .collect();
+    Ok(RionValue::Table { columns, rows })
+}
+
+/// Encode every row of a Table value as a datum of a record schema
+///
+/// Columns are matched to the record fields by name, a field without a column
+/// is written as null.
+pub fn table_to_avro(schema: &AvroSchema, table: &RionValue) -> Result<Vec<Vec<u8>>> {
+    let (AvroSchema::Record { fields, .. }, RionValue::Table { columns, rows }) = (schema, table)
+    else {
+        return Err(FerionError::invalid(
+            "Expected a Table value and an Avro record schema",
+        ));
+    };
+    let indices = fields
+        .iter()
+        .map(|(name, _)| {
+            columns
+                .iter()
+                .position(|column| **column == *name.as_bytes())
+        })
+        .collect::<Vec<_>>();
+    rows.iter()
+        .map(|row| {
+            let mut out = Vec::new();
+            for ((name, field), index) in fields.iter().zip(&indices) {
+                let cell = index.and_then(|i| row.get(i)).unwrap_or(&NULL);
+                encode(field, cell, &mut out)
+                    .map_err(|e| FerionError::invalid(format!("Field {name:?}: {e}")))?;
+            }
+            Ok(out)
+        })
+        .collect()
+}
+
+// Read for a missing field or cell, RionValue has a destructor so `&Null`
+// isn't promoted to a constant
+static NULL: RionValue = RionValue::Null;
+
+fn decode(schema: &AvroSchema, data: &mut &[u8]) -> Result<RionValue> {
+    let value = match schema {
+        AvroSchema::Null => RionValue::Null,
+        AvroSchema::Boolean => match take(data, 1)? {
+            [0] => RionValue::Bool(false),
+            [1] => RionValue::Bool(true),
+            [byte] => return Err(FerionError::invalid(format!("Invalid Avro boolean {byte}"))),
+            _ => unreachable!(),
+        },
+        AvroSchema::Int => {
+            let n = read_long(data)?;
+            i32::try_from(n)
+                .map_err(|_| FerionError::invalid(format!("Avro int {n} out of range")))?;
+            RionValue::from(n)
+        }
+        AvroSchema::Long => RionValue::from(read_long(data)?),
+        AvroSchema::Float => RionValue::Float(f32::from_le_bytes(take_array(data)?) as f64),
+        AvroSchema::Double => RionValue::Float(f64::from_le_bytes(take_array(data)?)),
+        AvroSchema::Bytes => RionValue::Bytes(read_bytes(data)?.to_vec()),
+        AvroSchema::String => RionValue::from(std::str::from_utf8(read_bytes(data)?)?),
+        AvroSchema::TimestampMillis => {
+            let millis = read_long(data)?;
+            let timestamp = DateTime::<Utc>::from_timestamp_millis(millis);
+            RionValue::DateTime(
+                timestamp
+                    .ok_or_else(|| FerionError::invalid("Avro timestamp out of range"))?
+                    .into(),
+            )
+        }
+        AvroSchema::TimestampMicros => {
+            let micros = read_long(data)?;
+            let timestamp = DateTime::<Utc>::from_timestamp_micros(micros);
+            RionValue::DateTime(
+                timestamp
+                    .ok_or_else(|| FerionError::invalid("Avro timestamp out of range"))?
+                    .into(),
+            )
+        }
+        AvroSchema::Record { fields, .. } => {
+            let mut object = BTreeMap::new();
+            for (name, field) in fields {
+                object.insert(name.as_bytes().into(), decode(field, data)?);
+            }
+            RionValue::Object(object)
+        }
+        AvroSchema::Enum { symbols, .. } => {
+            let index = read_long(data)?;
+            let symbol = usize::try_from(index).ok().and_then(|i| symbols.get(i));
+            RionValue::from(
+                symbol
+                    .ok_or_else(|| {
+                        FerionError::invalid(format!("Invalid Avro enum index {index}"))
+                    })?
+                    .as_str(),
+            )
+        }
+        AvroSchema::Fixed { size, .. } => RionValue::Bytes(take(data, *size)?.to_vec()),
+        AvroSchema::Array(items) => {
+            let mut elements = Vec::new();
+            read_blocks(data, items.can_be_empty(), |data| {
+                elements.push(decode(items, data)?);
+                Ok(())
+            })?;
+            RionValue::Array(elements)
+        }
+        AvroSchema::Map(values) => {
+            let mut object = BTreeMap::<Arc<[u8]>, _>::new();
+            // Keys take at least their length byte
+            read_blocks(data, false, |data| {
+                let key = read_bytes(data)?.into();
+                object.insert(key, decode(values, data)?);
+                Ok(())
+            })?;
+            RionValue::Object(object)
+        }
+        AvroSchema::Union(members) => {
+            let index = read_long(data)?;
+            let member = usize::try_from(index).ok().and_then(|i| members.get(i));
+            decode(
+                member.ok_or_else(|| {
+                    FerionError::invalid(format!("Invalid Avro union index {index}"))
+                })?,
+                data,
+            )?
+        }
+    };
+    Ok(value)
+}
+
+fn encode(schema: &AvroSchema, value: &RionValue, out: &mut Vec<u8>) -> Result<()> {
+    let mismatch = || FerionError::invalid(format!("Cannot write {value:?} as Avro {schema:?}"));
+    match (schema, value) {
+        (AvroSchema::Union(members), _) => {
+            let Some(index) = members.iter().position(|member| member.accepts(value)) else {
+                return Err(mismatch());
+            };
+            write_long(index as i64, out);
+            return encode(&members[index], value, out);
+        }
+        (AvroSchema::Record { fields, .. }, RionValue::Object(object)) => {
+            for (name, field) in fields {
+                let value = object.get(name.as_bytes()).unwrap_or(&NULL);
+                encode(field, value, out)
+                    .map_err(|e| FerionError::invalid(format!("Field {name:?}: {e}")))?;
+            }
+            return Ok(());
+        }
+        _ if !schema.accepts(value) => return Err(mismatch()),
+        _ => {}
+    }
+    match value {
+        RionValue::Null => {}
+        RionValue::Bool(b) => out.push(*b as u8),
+        RionValue::PosInt(n) => write_long(*n as i64, out),
+        RionValue::NegInt(n) => write_long(*n as i64, out),
+        RionValue::Float(n) if *schema == AvroSchema::Float => {
+            out.extend_from_slice(&(*n as f32).to_le_bytes())
+        }
+        RionValue::Float(n) => out.extend_from_slice(&n.to_le_bytes()),
+        RionValue::String(s) => match schema {
+            AvroSchema::Enum { symbols, .. } => {
+                let index = symbols.iter().position(|symbol| **symbol == **s);
+                write_long(index.unwrap_or_default() as i64, out);
+            }
+            _ => write_bytes(s.as_bytes(), out),
+        },
+        RionValue::Bytes(bytes) if matches!(schema, AvroSchema::Fixed { .. }) => {
+            out.extend_from_slice(bytes)
+        }
+        RionValue::Bytes(bytes) => write_bytes(bytes, out),
+        RionValue::DateTime(dt) if *schema == AvroSchema::TimestampMillis => {
+            write_long(dt.datetime().timestamp_millis(), out)
+        }
+        RionValue::DateTime(dt) => write_long(dt.datetime().timestamp_micros(), out),
+        RionValue::Array(elements) => {
+            let AvroSchema::Array(items) = schema else {
+                unreachable!("only arrays accept array values")
+            };
+            if !elements.is_empty() {
+                write_long(elements.len() as i64, out);
+                for element in elements {
+                    encode(items, element, out)?;
+                }
+            }
+            write_long(0, out);
+        }
+        RionValue::Object(object) => {
+            let AvroSchema::Map(values) = schema else {
+                unreachable!("records are written above")
+            };
+            if !object.is_empty() {
+                write_long(object.len() as i64, out);
+                for (key, value) in object {
+                    std::str::from_utf8(key)
+                        .map_err(|_| FerionError::invalid("Avro map keys must be UTF-8"))?;
+                    write_bytes(key, out);
+                    encode(values, value, out)?;
+                }
+            }
+            write_long(0, out);
+        }
+        RionValue::Table { .. } | RionValue::Extended { .. } => return Err(mismatch()),
+    }
+    Ok(())
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(FerionError::invalid("Unexpected end of Avro datum"));
+    }
+    let (taken, rest) = data.split_at(len);
+    *data = rest;
+    Ok(taken)
+}
+
+fn take_array<const N: usize>(data: &mut &[u8]) -> Result<[u8; N]> {
+    let (taken, rest) = data
+        .split_first_chunk()
+        .ok_or_else(|| FerionError::invalid("Unexpected end of Avro datum"))?;
+    *data = rest;
+    Ok(*taken)
+}
+
+// Longs are zig-zag encoded variable length integers
+fn read_long(data: &mut &[u8]) -> Result<i64> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(data, 1)?[0];
+        n |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((n >> 1) as i64 ^ -((n & 1) as i64));
+        }
+    }
+    Err(FerionError::invalid("Avro long is too long"))
+}
+
+fn write_long(n: i64, out: &mut Vec<u8>) {
+    let mut n = ((n << 1) ^ (n >> 63)) as u64;
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn read_bytes<'a>(data: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let len = read_long(data)?;
+    take(
+        data,
+        usize::try_from(len).map_err(|_| FerionError::invalid("Negative Avro length"))?,
+    )
+}
+
+fn write_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    write_long(bytes.len() as i64, out);
+    out.extend_from_slice(bytes);
+}
+
+// Arrays and maps are a series of blocks ending with an empty one, a negative
+// count is followed by the block size in bytes
+//
+// Items that can take no bytes don't run out of data, so their total count is
+// held to MAX_EMPTY_ITEMS instead
+fn read_blocks(
+    data: &mut &[u8],
+    items_can_be_empty: bool,
+    mut item: impl FnMut(&mut &[u8]) -> Result<()>,
+) -> Result<()> {
+    let mut empty_budget = MAX_EMPTY_ITEMS as u64;
+    loop {
+        let count = match read_long(data)? {
+            0 => return Ok(()),
+            count if count < 0 => {
+                read_long(data)?;
+                count.unsigned_abs()
+            }
+            count => count as u64,
+        };
+        if items_can_be_empty {
+            empty_budget = empty_budget.checked_sub(count).ok_or_else(|| {
+                FerionError::invalid({
+                    format!("Avro block count {count} is over the limit for empty items")
+                })
+            })?;
+        }
+        for _ in 0..count {
+            item(data)?;
+        }
+    }
+}
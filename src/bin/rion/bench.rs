@@ -0,0 +1,106 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::Result;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The JSON file to encode
+    file: PathBuf,
+    /// Times to repeat each encode and decode, timings are averaged
+    #[arg(long, default_value_t = 100)]
+    iterations: u32,
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+struct Report {
+    format: &'static str,
+    bytes: usize,
+    encode: Duration,
+    decode: Duration,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let input: Value = serde_json::from_slice(&std::fs::read(&args.file)?)?;
+    let iterations = args.iterations.max(1);
+
+    let rion = ferion::to_bytes(&input)?;
+    let decoded: Value = ferion::from_bytes(&rion)?;
+    if decoded != input {
+        return Err("Decoded RION does not match the original JSON".into());
+    }
+    let json_bytes = serde_json::to_vec(&input)?;
+
+    let reports = [
+        Report {
+            format: "rion",
+            bytes: rion.len(),
+            encode: time(iterations, || ferion::to_bytes(&input).map(drop))?,
+            decode: time(iterations, || ferion::from_bytes::<Value>(&rion).map(drop))?,
+        },
+        Report {
+            format: "json",
+            bytes: json_bytes.len(),
+            encode: time(iterations, || serde_json::to_vec(&input).map(drop))?,
+            decode: time(iterations, || {
+                serde_json::from_slice::<Value>(&json_bytes).map(drop)
+            })?,
+        },
+    ];
+
+    if args.json {
+        let reports = reports
+            .iter()
+            .map(|r| {
+                json!({
+                    "format": r.format,
+                    "bytes": r.bytes,
+                    "ratio": r.bytes as f64 / json_bytes.len() as f64,
+                    "encode_ns": r.encode.as_nanos() as u64,
+                    "decode_ns": r.decode.as_nanos() as u64,
+                })
+            })
+            .collect::<Vec<_>>();
+        let report = json!({
+            "file": args.file.display().to_string(),
+            "iterations": iterations,
+            "formats": reports,
+        });
+        println!("{report}");
+        return Ok(());
+    }
+    println!("{} ({iterations} iterations)", args.file.display());
+    println!(
+        "\n{:<8} {:>12} {:>8} {:>14} {:>14}",
+        "format", "bytes", "ratio", "encode", "decode"
+    );
+    for r in &reports {
+        println!(
+            "{:<8} {:>12} {:>8.2} {:>14} {:>14}",
+            r.format,
+            r.bytes,
+            r.bytes as f64 / json_bytes.len() as f64,
+            format!("{:?}", r.encode),
+            format!("{:?}", r.decode)
+        );
+    }
+    Ok(())
+}
+
+// Average time of `f` over `iterations` runs
+fn time<E: std::error::Error + 'static>(
+    iterations: u32,
+    mut f: impl FnMut() -> std::result::Result<(), E>,
+) -> Result<Duration> {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f()?;
+    }
+    Ok(start.elapsed() / iterations)
+}
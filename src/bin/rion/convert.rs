@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::Result;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// JSON files, or directories with --recursive
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+    /// Convert every .json file under the given directories
+    #[arg(short, long)]
+    recursive: bool,
+    /// Where to write the .rion files, defaults to next to each input.
+    /// Files found in a directory keep their path relative to it
+    #[arg(short, long)]
+    out_dir: Option<PathBuf>,
+    /// Number of worker threads, defaults to the number of CPUs
+    #[arg(short, long)]
+    jobs: Option<usize>,
+}
+
+struct Job {
+    input: PathBuf,
+    output: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let mut jobs = Vec::new();
+    for input in &args.inputs {
+        if input.is_dir() {
+            if !args.recursive {
+                return Err(format!("{} is a directory, use --recursive", input.display()).into());
+            }
+            let mut files = Vec::new();
+            find_json(input, &mut files)?;
+            for file in files {
+                let relative = file.strip_prefix(input)?.to_path_buf();
+                jobs.push(job(file, &relative, args.out_dir.as_deref()));
+            }
+        } else {
+            let name = PathBuf::from(input.file_name().ok_or("Input has no file name")?);
+            jobs.push(job(input.clone(), &name, args.out_dir.as_deref()));
+        }
+    }
+
+    // Workers writing the same file would race, check before starting any
+    let mut outputs = HashMap::new();
+    let mut collisions = Vec::new();
+    for job in &jobs {
+        if let Some(other) = outputs.insert(&job.output, &job.input) {
+            collisions.push(format!(
+                "{} and {} both convert to {}",
+                other.display(),
+                job.input.display(),
+                job.output.display()
+            ));
+        }
+    }
+    for collision in &collisions {
+        eprintln!("{collision}");
+    }
+    if !collisions.is_empty() {
+        return Err("Inputs would overwrite each other's output".into());
+    }
+
+    let workers = args
+        .jobs
+        .or_else(|| std::thread::available_parallelism().ok().map(Into::into))
+        .unwrap_or(1)
+        .clamp(1, jobs.len().max(1));
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let failures = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(job) = jobs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Err(e) = convert(job) {
+                        failures
+                            .lock()
+                            .unwrap()
+                            .push(format!("{}: {e}", job.input.display()));
+                    }
+                    let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                    eprint!("\rConverted {done}/{}", jobs.len());
+                }
+            });
+        }
+    });
+    eprintln!();
+
+    let failures = failures.into_inner().unwrap();
+    for failure in &failures {
+        eprintln!("{failure}");
+    }
+    if !failures.is_empty() {
+        return Err(format!(
+            "{} of {} files failed to convert",
+            failures.len(),
+            jobs.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn job(input: PathBuf, relative: &Path, out_dir: Option<&Path>) -> Job {
+    let output = match out_dir {
+        Some(out_dir) => out_dir.join(relative),
+        None => input.clone(),
+    }
+    .with_extension("rion");
+    Job { input, output }
+}
+
+fn find_json(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_json(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn convert(job: &Job) -> Result<()> {
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&job.input)?)?;
+    let rion = ferion::to_bytes(&json)?;
+    if let Some(parent) = job.output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&job.output, rion)?;
+    Ok(())
+}
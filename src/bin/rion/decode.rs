@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use ferion::RionValue;
+
+use crate::{json::to_json, stdio, Result};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The RION file to decode, `-` reads stdin. Typed at a terminal each
+    /// line is decoded as hex
+    #[arg(default_value = "-")]
+    file: PathBuf,
+    /// Print the JSON on one line
+    #[arg(long)]
+    compact: bool,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let decode = |data: &[u8]| -> Result<String> {
+        let json = to_json(&RionValue::from_slice(data)?)?;
+        Ok(match args.compact {
+            true => serde_json::to_string(&json)?,
+            false => serde_json::to_string_pretty(&json)?,
+        })
+    };
+    if stdio::is_interactive(&args.file) {
+        return stdio::prompt_lines("rion hex> ", |line| decode(&stdio::from_hex(line)?));
+    }
+    let mut json = decode(&stdio::read_input(&args.file)?)?;
+    json.push('\n');
+    stdio::write_output(json.as_bytes())
+}
@@ -0,0 +1,33 @@
+use std::{io::IsTerminal, path::PathBuf};
+
+use serde_json::Value;
+
+use crate::{stdio, Result};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The JSON file to encode, `-` reads stdin. Typed at a terminal each
+    /// line is encoded on its own
+    #[arg(default_value = "-")]
+    file: PathBuf,
+    /// Write raw bytes even when stdout is a terminal, which otherwise gets
+    /// hex
+    #[arg(long)]
+    raw: bool,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let encode = |json: &[u8]| -> Result<Vec<u8>> {
+        Ok(ferion::to_bytes(&serde_json::from_slice::<Value>(json)?)?)
+    };
+    if stdio::is_interactive(&args.file) {
+        return stdio::prompt_lines("json> ", |line| {
+            Ok(stdio::to_hex(&encode(line.as_bytes())?))
+        });
+    }
+    let rion = encode(&stdio::read_input(&args.file)?)?;
+    if std::io::stdout().is_terminal() && !args.raw {
+        return stdio::write_output(format!("{}\n", stdio::to_hex(&rion)).as_bytes());
+    }
+    stdio::write_output(&rion)
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use ferion::{Path, RionValue};
+
+use crate::{json::to_json, stdio, Result};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The RION file to read, `-` reads stdin
+    file: PathBuf,
+    /// Paths to look up, e.g. `users[3].email` or `users[*].email`
+    #[arg(required = true)]
+    paths: Vec<String>,
+    /// Print every value as JSON, instead of plain strings and numbers
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let paths = args
+        .paths
+        .iter()
+        .map(|path| path.parse::<Path>())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let value = RionValue::from_slice(&stdio::read_input(&args.file)?)?;
+    let mut missing = Vec::new();
+    for (path, text) in paths.iter().zip(&args.paths) {
+        let found = value.query_path(path);
+        if found.is_empty() {
+            missing.push(text.as_str());
+        }
+        for value in found {
+            if args.json {
+                println!("{}", to_json(value)?);
+            } else {
+                println!("{}", plain(value)?);
+            }
+        }
+    }
+    if !missing.is_empty() {
+        return Err(format!("No value at {}", missing.join(", ")).into());
+    }
+    Ok(())
+}
+
+// Like `jq -r`, strings are printed without quotes
+fn plain(value: &RionValue) -> Result<String> {
+    Ok(match value {
+        RionValue::String(s) => s.to_string(),
+        RionValue::DateTime(dt) => dt.to_rfc3339(),
+        value => to_json(value)?.to_string(),
+    })
+}
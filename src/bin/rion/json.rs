@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ferion::{RionValue, DEFAULT_MAX_DEPTH};
+use serde_json::{Map, Value};
+
+use crate::Result;
+
+/// Convert a decoded value to JSON, Bytes become base64 strings, DateTimes
+/// RFC 3339 strings, Tables arrays of row objects and Extended fields objects
+/// with their type id and base64 data. Ints past 64 bits become decimal strings
+///
+/// serde_json builds, writes and drops values recursively, so values nested
+/// deeper than [`DEFAULT_MAX_DEPTH`] are an error rather than a stack overflow
+pub fn to_json(value: &RionValue) -> Result<Value> {
+    json(value, 0)
+}
+
+fn json(value: &RionValue, depth: usize) -> Result<Value> {
+    if depth > DEFAULT_MAX_DEPTH {
+        return Err(format!("Value nests deeper than {DEFAULT_MAX_DEPTH} levels").into());
+    }
+    Ok(match value {
+        RionValue::Null => Value::Null,
+        RionValue::Bool(b) => Value::Bool(*b),
+        RionValue::PosInt(n) => {
+            u64::try_from(*n).map_or_else(|_| n.to_string().into(), Value::from)
+        }
+        RionValue::NegInt(n) => {
+            i64::try_from(*n).map_or_else(|_| n.to_string().into(), Value::from)
+        }
+        RionValue::Float(n) => Value::from(*n),
+        RionValue::String(s) => Value::String(s.to_string()),
+        RionValue::Bytes(bytes) => Value::String(STANDARD.encode(bytes)),
+        RionValue::DateTime(dt) => Value::String(dt.to_rfc3339()),
+        RionValue::Array(elements) => elements
+            .iter()
+            .map(|element| json(element, depth + 1))
+            .collect::<Result<_>>()?,
+        RionValue::Object(fields) => Value::Object(object(fields, depth + 1)?),
+        RionValue::Table { columns, rows } => rows
+            .iter()
+            .map(|row| {
+                let fields = columns
+                    .iter()
+                    .zip(row)
+                    .map(|(key, cell)| Ok((key_name(key), json(cell, depth + 2)?)));
+                Ok(Value::Object(fields.collect::<Result<_>>()?))
+            })
+            .collect::<Result<_>>()?,
+        RionValue::Extended { type_id, data } => serde_json::json!({
+            "type_id": type_id,
+            "data": STANDARD.encode(data),
+        }),
+    })
+}
+
+fn object<'a>(
+    fields: impl IntoIterator<Item = (&'a Arc<[u8]>, &'a RionValue)>,
+    depth: usize,
+) -> Result<Map<String, Value>> {
+    fields
+        .into_iter()
+        .map(|(key, value)| Ok((key_name(key), json(value, depth)?)))
+        .collect()
+}
+
+fn key_name(key: &[u8]) -> String {
+    String::from_utf8_lossy(key).into_owned()
+}
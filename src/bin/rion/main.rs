@@ -0,0 +1,71 @@
+//! Command line tools for inspecting and converting RION files
+
+use clap::{Parser, Subcommand};
+
+mod bench;
+mod convert;
+mod decode;
+mod encode;
+mod get;
+mod json;
+mod ndjson;
+mod print;
+mod stats;
+mod stdio;
+mod to_table;
+mod watch;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(name = "rion", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show a breakdown of where the bytes of a RION file go
+    Stats(stats::Args),
+    /// Print the values at one or more paths
+    Get(get::Args),
+    /// Pretty print a RION file in diagnostic notation
+    Print(print::Args),
+    /// Compare encoded sizes and timings of RION and JSON for a JSON file
+    Bench(bench::Args),
+    /// Convert JSON files to RION
+    Convert(convert::Args),
+    /// Decode RION from a file or stdin and print it as JSON
+    Decode(decode::Args),
+    /// Encode JSON from a file or stdin as RION on stdout
+    Encode(encode::Args),
+    /// Convert newline-delimited JSON to RION values written back to back, or
+    /// back with --decode, a record at a time
+    Ndjson(ndjson::Args),
+    /// Convert a JSON array of uniform objects to a RION table
+    ToTable(to_table::Args),
+    /// Keep converting JSON files in a directory to RION, and RION files to
+    /// JSON, as they change
+    Watch(watch::Args),
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Stats(args) => stats::run(args),
+        Command::Get(args) => get::run(args),
+        Command::Print(args) => print::run(args),
+        Command::Bench(args) => bench::run(args),
+        Command::Convert(args) => convert::run(args),
+        Command::Decode(args) => decode::run(args),
+        Command::Encode(args) => encode::run(args),
+        Command::Ndjson(args) => ndjson::run(args),
+        Command::ToTable(args) => to_table::run(args),
+        Command::Watch(args) => watch::run(args),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
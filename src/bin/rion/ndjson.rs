@@ -0,0 +1,35 @@
+use std::{
+    io::{BufWriter, ErrorKind},
+    path::PathBuf,
+};
+
+use ferion::FerionError;
+
+use crate::{stdio, Result};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The file to convert, `-` reads stdin
+    #[arg(default_value = "-")]
+    file: PathBuf,
+    /// Convert RION values back to JSON lines
+    #[arg(short, long)]
+    decode: bool,
+    /// Largest RION value accepted when decoding, in bytes
+    #[arg(long, default_value_t = 16 << 20)]
+    limit: usize,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let input = stdio::open_input(&args.file)?;
+    let output = BufWriter::new(std::io::stdout().lock());
+    let result = match args.decode {
+        true => ferion::rion_to_ndjson(input, output, args.limit),
+        false => ferion::ndjson_to_rion(input, output),
+    };
+    match result {
+        // A reader closing the pipe early is not an error
+        Err(FerionError::Io(e)) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result.map(drop)?),
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    io::{IsTerminal, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use ferion::{RionValue, DEFAULT_MAX_DEPTH};
+
+use crate::{stdio, Result};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The RION file to print, `-` reads stdin
+    file: PathBuf,
+    /// Spaces per indentation level, 0 prints everything on one line
+    #[arg(long, default_value_t = 2)]
+    indent: usize,
+    /// Bytes fields longer than this are truncated, 0 never truncates
+    #[arg(long, default_value_t = 32)]
+    max_bytes: usize,
+    #[arg(long, value_enum, default_value_t = Color::Auto)]
+    color: Color,
+    /// Page the output through $PAGER (or `less -R`)
+    #[arg(long)]
+    pager: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let value = RionValue::from_slice(&stdio::read_input(&args.file)?)?;
+    let printer = Printer {
+        indent: args.indent,
+        max_bytes: args.max_bytes,
+        color: match args.color {
+            Color::Auto => std::io::stdout().is_terminal(),
+            Color::Always => true,
+            Color::Never => false,
+        },
+    };
+    let mut out = String::new();
+    printer.value(&value, 0, &mut out)?;
+    out.push('\n');
+
+    if !args.pager {
+        std::io::stdout().write_all(out.as_bytes())?;
+        return Ok(());
+    }
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let program = parts.next().ok_or("PAGER is empty")?;
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closing early is not an error
+        let _ = stdin.write_all(out.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+const KEY: &str = "\x1b[34m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[36m";
+const LITERAL: &str = "\x1b[35m";
+const BYTES: &str = "\x1b[33m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders values in diagnostic notation: JSON-like, with `h'..'` for bytes,
+/// `dt'..'` for datetimes and `table [columns] [rows]` for tables. Values are
+/// printed recursively, so nesting past [`DEFAULT_MAX_DEPTH`] is an error
+struct Printer {
+    indent: usize,
+    max_bytes: usize,
+    color: bool,
+}
+
+impl Printer {
+    fn paint(&self, color: &str, text: &str, out: &mut String) {
+        if self.color {
+            out.push_str(color);
+            out.push_str(text);
+            out.push_str(RESET);
+        } else {
+            out.push_str(text);
+        }
+    }
+
+    fn value(&self, value: &RionValue, depth: usize, out: &mut String) -> Result<()> {
+        if depth > DEFAULT_MAX_DEPTH {
+            return Err(format!("Value nests deeper than {DEFAULT_MAX_DEPTH} levels").into());
+        }
+        match value {
+            RionValue::Null => self.paint(LITERAL, "null", out),
+            RionValue::Bool(b) => self.paint(LITERAL, &b.to_string(), out),
+            RionValue::PosInt(n) => self.paint(NUMBER, &n.to_string(), out),
+            RionValue::NegInt(n) => self.paint(NUMBER, &n.to_string(), out),
+            RionValue::Float(n) => self.paint(NUMBER, &format!("{n:?}"), out),
+            RionValue::String(s) => self.paint(STRING, &format!("{s:?}"), out),
+            RionValue::Bytes(bytes) => self.bytes(bytes, out),
+            RionValue::DateTime(dt) => self.paint(STRING, &format!("dt'{}'", dt.to_rfc3339()), out),
+            RionValue::Extended { type_id, data } => {
+                self.paint(LITERAL, &format!("ext{type_id} "), out);
+                self.bytes(data, out);
+            }
+            RionValue::Array(elements) => {
+                self.list(('[', ']'), elements, depth, out, |p, e, out| {
+                    p.value(e, depth + 1, out)
+                })?
+            }
+            RionValue::Object(fields) => {
+                self.list(('{', '}'), fields, depth, out, |p, (key, value), out| {
+                    p.key(key, out);
+                    out.push_str(": ");
+                    p.value(value, depth + 1, out)
+                })?
+            }
+            RionValue::Table { columns, rows } => {
+                self.paint(LITERAL, "table ", out);
+                self.list(('[', ']'), columns, depth, out, |p, key, out| {
+                    p.key(key, out);
+                    Ok(())
+                })?;
+                out.push(' ');
+                self.list(('[', ']'), rows, depth, out, |p, row, out| {
+                    p.list(('[', ']'), row, depth + 1, out, |p, cell, out| {
+                        p.value(cell, depth + 2, out)
+                    })
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&self, key: &[u8], out: &mut String) {
+        match std::str::from_utf8(key) {
+            Ok(key) => self.paint(KEY, &format!("{key:?}"), out),
+            Err(_) => self.bytes(key, out),
+        }
+    }
+
+    fn bytes(&self, bytes: &[u8], out: &mut String) {
+        let shown = match self.max_bytes {
+            0 => bytes.len(),
+            max => bytes.len().min(max),
+        };
+        let hex = bytes[..shown]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        self.paint(BYTES, &format!("h'{hex}'"), out);
+        if shown < bytes.len() {
+            let note = format!(" /* {} more bytes */", bytes.len() - shown);
+            self.paint(DIM, &note, out);
+        }
+    }
+
+    fn list<T>(
+        &self,
+        (open, close): (char, char),
+        items: impl IntoIterator<Item = T>,
+        depth: usize,
+        out: &mut String,
+        item: impl Fn(&Self, T, &mut String) -> Result<()>,
+    ) -> Result<()> {
+        out.push(open);
+        let mut empty = true;
+        for (i, value) in items.into_iter().enumerate() {
+            empty = false;
+            if i > 0 {
+                out.push(',');
+                if self.indent == 0 {
+                    out.push(' ');
+                }
+            }
+            self.newline(depth + 1, out);
+            item(self, value, out)?;
+        }
+        if !empty {
+            self.newline(depth, out);
+        }
+        out.push(close);
+        Ok(())
+    }
+
+    fn newline(&self, depth: usize, out: &mut String) {
+        if self.indent > 0 {
+            out.push('\n');
+            out.push_str(&" ".repeat(depth * self.indent));
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use ferion::{analyze, RionValue, Usage};
+
+use crate::{json::to_json, stdio, Result};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The RION file to analyze, `-` reads stdin
+    file: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let data = stdio::read_input(&args.file)?;
+    let analysis = analyze(&data)?;
+    let json_len = serde_json::to_vec(&to_json(&RionValue::from_slice(&data)?)?)?.len();
+
+    println!(
+        "{}: {} bytes, max depth {}",
+        args.file.display(),
+        analysis.total_bytes,
+        analysis.max_depth
+    );
+    println!(
+        "\n{:<10} {:>10} {:>12} {:>8}",
+        "type", "count", "bytes", "share"
+    );
+    let total = analysis.total_bytes.max(1) as f64;
+    let row = |name: &str, usage: &Usage| {
+        println!(
+            "{name:<10} {:>10} {:>12} {:>7.1}%",
+            usage.count,
+            usage.bytes,
+            usage.bytes as f64 / total * 100.0
+        )
+    };
+    for (name, usage) in &analysis.types {
+        row(name, usage);
+    }
+    row("keys", &analysis.keys);
+    if analysis.keys.count > 0 {
+        println!(
+            "\nKey overhead: {:.1} bytes per key",
+            analysis.keys.bytes as f64 / analysis.keys.count as f64
+        );
+    }
+    println!(
+        "Minified JSON: {json_len} bytes (RION is {:.1}% of JSON)",
+        analysis.total_bytes as f64 / json_len.max(1) as f64 * 100.0
+    );
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Reading inputs and writing outputs that may be pipes
+//!
+//! A path of `-` stands for stdin. Everything is read and written as raw
+//! bytes, so binary RION can be piped through the commands. Line by line
+//! prompting only happens when stdin is a terminal.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, IsTerminal, Read, Write},
+    path::Path,
+};
+
+use crate::Result;
+
+/// The whole content of `path`, or of stdin for `-`
+pub fn read_input(path: &Path) -> Result<Vec<u8>> {
+    if path != Path::new("-") {
+        return Ok(std::fs::read(path)?);
+    }
+    let mut data = Vec::new();
+    std::io::stdin().lock().read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// A buffered reader over `path`, or stdin for `-`, for reading a bit at a
+/// time
+pub fn open_input(path: &Path) -> Result<Box<dyn BufRead>> {
+    if path != Path::new("-") {
+        return Ok(Box::new(BufReader::new(File::open(path)?)));
+    }
+    Ok(Box::new(std::io::stdin().lock()))
+}
+
+/// Whether `path` is stdin and a person is typing into it
+pub fn is_interactive(path: &Path) -> bool {
+    path == Path::new("-") && std::io::stdin().is_terminal()
+}
+
+/// Write `data` to stdout as is, a reader closing the pipe early is not an
+/// error
+pub fn write_output(data: &[u8]) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    match stdout.write_all(data).and_then(|()| stdout.flush()) {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Call `line` with each non-empty line typed on stdin until it is closed,
+/// printing `prompt` before each one
+///
+/// Errors from `line` are reported and the loop goes on.
+pub fn prompt_lines(prompt: &str, mut line: impl FnMut(&str) -> Result<String>) -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut input = String::new();
+    loop {
+        eprint!("{prompt}");
+        input.clear();
+        if stdin.lock().read_line(&mut input)? == 0 {
+            return Ok(());
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+        match line(input) {
+            Ok(output) => println!("{output}"),
+            Err(e) => eprintln!("error: {e}"),
+        }
+    }
+}
+
+pub fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn from_hex(text: &str) -> Result<Vec<u8>> {
+    let text: String = text.split_whitespace().collect();
+    if !text.len().is_multiple_of(2) {
+        return Err("Hex input has an odd number of digits".into());
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| Ok(u8::from_str_radix(&text[i..i + 2], 16)?))
+        .collect()
+}
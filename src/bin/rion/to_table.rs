@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use ferion::{RionArray, RionTable, TableLayout};
+
+use crate::Result;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// A JSON file holding an array of objects that all have the same keys
+    input: PathBuf,
+    /// Where to write the table, defaults to the input with a .rion extension
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Store string columns with at most this many different values as a
+    /// dictionary
+    #[arg(long, value_name = "MAX_ENTRIES")]
+    dictionary: Option<usize>,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&args.input)?)?;
+    let rows = match &json {
+        serde_json::Value::Array(rows) if !rows.is_empty() => rows.len(),
+        serde_json::Value::Array(_) => return Err("The array is empty".into()),
+        _ => return Err("Expected a top-level JSON array of objects".into()),
+    };
+
+    // The same encoding `rion convert` writes, an array of objects
+    let objects = ferion::to_bytes(&json)?;
+    let table = RionTable::from_array(&RionArray::from_slice(&objects)?)?;
+    let encoded = match args.dictionary {
+        Some(max_entries) => table.encode_with_dictionary(TableLayout::RowMajor, max_entries),
+        None => table.encode(),
+    };
+
+    let output = args
+        .output
+        .unwrap_or_else(|| args.input.with_extension("rion"));
+    std::fs::write(&output, &encoded)?;
+
+    let saved = objects.len() as i64 - encoded.len() as i64;
+    println!(
+        "{rows} rows, {} columns written to {}",
+        table.column_names.len(),
+        output.display()
+    );
+    println!("array of objects: {:>10} bytes", objects.len());
+    println!("table:            {:>10} bytes", encoded.len());
+    println!(
+        "saved:            {saved:>10} bytes ({:.1}%)",
+        saved as f64 * 100.0 / objects.len() as f64
+    );
+    Ok(())
+}
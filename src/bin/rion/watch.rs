@@ -0,0 +1,141 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use ferion::RionValue;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::{json::to_json, Result};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory to watch for .json and .rion files
+    src: PathBuf,
+    /// Where to write the converted files, keeping their path relative to SRC
+    #[arg(short, long)]
+    out: PathBuf,
+    /// Milliseconds to wait for a burst of changes to settle before converting
+    #[arg(long, default_value_t = 100)]
+    debounce: u64,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    if !args.src.is_dir() {
+        return Err(format!("{} is not a directory", args.src.display()).into());
+    }
+    // Events report absolute paths
+    let src = args.src.canonicalize()?;
+    std::fs::create_dir_all(&args.out)?;
+    let out = args.out.canonicalize()?;
+
+    // Bring the output up to date before watching for changes
+    let mut initial = BTreeSet::new();
+    find_sources(&src, &mut initial)?;
+    sync(&src, &out, initial);
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&src, RecursiveMode::Recursive)?;
+    eprintln!("Watching {} for changes", src.display());
+
+    let debounce = Duration::from_millis(args.debounce);
+    while let Ok(event) = receiver.recv() {
+        // Editors write a file in several steps, collect them all
+        let mut changed = BTreeSet::new();
+        let mut next = Some(event);
+        while let Some(event) = next {
+            match event {
+                Ok(event) if is_change(&event.kind) => changed.extend(event.paths),
+                Ok(_) => {}
+                Err(e) => eprintln!("watch error: {e}"),
+            }
+            next = receiver.recv_timeout(debounce).ok();
+        }
+        sync(&src, &out, changed);
+    }
+    Ok(())
+}
+
+fn is_change(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    )
+}
+
+fn find_sources(dir: &Path, files: &mut BTreeSet<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_sources(&path, files)?;
+        } else if target_extension(&path).is_some() {
+            files.insert(path);
+        }
+    }
+    Ok(())
+}
+
+// .json files become .rion files and the other way around
+fn target_extension(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()? {
+        "json" => Some("rion"),
+        "rion" => Some("json"),
+        _ => None,
+    }
+}
+
+// Convert the changed files under `src` into `out`, removing the output of
+// deleted ones
+fn sync(src: &Path, out: &Path, changed: impl IntoIterator<Item = PathBuf>) {
+    for input in changed {
+        // The output directory may be inside the watched one
+        if input.starts_with(out) {
+            continue;
+        }
+        let Some(extension) = target_extension(&input) else {
+            continue;
+        };
+        let Ok(relative) = input.strip_prefix(src) else {
+            continue;
+        };
+        let output = out.join(relative).with_extension(extension);
+        let result = if input.exists() {
+            convert(&input, &output)
+        } else {
+            match std::fs::remove_file(&output) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        };
+        match result {
+            Ok(()) if input.exists() => {
+                eprintln!("{} -> {}", relative.display(), output.display())
+            }
+            Ok(()) => eprintln!(
+                "{} removed, deleted {}",
+                relative.display(),
+                output.display()
+            ),
+            Err(e) => eprintln!("{}: {e}", relative.display()),
+        }
+    }
+}
+
+fn convert(input: &Path, output: &Path) -> Result<()> {
+    let data = std::fs::read(input)?;
+    let converted = match target_extension(input) {
+        Some("rion") => {
+            let json: serde_json::Value = serde_json::from_slice(&data)?;
+            ferion::to_bytes(&json)?
+        }
+        _ => serde_json::to_vec_pretty(&to_json(&RionValue::from_slice(&data)?)?)?,
+    };
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(output, converted)?;
+    Ok(())
+}
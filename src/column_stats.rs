@@ -0,0 +1,292 @@
+//! Per-column statistics written into a table
+//!
+//! [`RionTable::encode_with_stats`] writes the min, max, null count and
+//! distinct count of every column in a [`RionField::TABLE_STATS`] extended
+//! field right after the row count. They come before the rows rather than
+//! after so a reader finds them with [`TableStats::read`] without walking the
+//! rows, and can skip over tables whose values can't match a predicate by the
+//! table's length. Readers that don't need them pass over the field, so the
+//! table decodes as usual.
+
+use std::collections::HashSet;
+
+use crate::{
+    field::NormalField,
+    get_normal_header,
+    object::{get_raw, object_field, write_entry},
+    types::{NormalRionType, RionFieldType},
+    FerionError, Result, RionArray, RionField, RionFieldRef, RionObject, RionTable, TableLayout,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RionValue;
+
+    fn table_data() -> Vec<u8> {
+        RionValue::Table {
+            columns: vec![b"id"[..].into(), b"name"[..].into()],
+            rows: vec![
+                vec![RionValue::PosInt(3), "c".into()],
+                vec![RionValue::PosInt(1), RionValue::Null],
+                vec![RionValue::PosInt(2), "a".into()],
+                vec![RionValue::PosInt(2), "a".into()],
+            ],
+        }
+        .encode_to_vec()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let data = table_data();
+        let table = RionTable::from_slice(&data).unwrap();
+        let stats = table.stats();
+        assert_eq!(stats.rows, 4);
+        let id = stats.column("id").unwrap();
+        assert_eq!(id.min, Some(RionField::from(1u64)));
+        assert_eq!(id.max, Some(RionField::from(3u64)));
+        assert_eq!((id.null_count, id.distinct), (0, 3));
+        let name = stats.column("name").unwrap();
+        assert_eq!((name.null_count, name.distinct), (1, 2));
+        assert!(name.may_contain(&RionField::from("b")));
+        assert!(!name.may_contain(&RionField::from("d")));
+        assert!(!id.may_contain(&RionField::from(9u64)));
+        assert!(!id.may_contain(&RionField::from(None::<u64>)));
+    }
+
+    #[test]
+    fn test_stats_field() {
+        let data = table_data();
+        let table = RionTable::from_slice(&data).unwrap();
+        let encoded = table.encode_with_stats(TableLayout::RowMajor);
+
+        let stats = TableStats::read(&encoded).unwrap().unwrap();
+        assert_eq!(stats, table.stats());
+        let (decoded, read) =
+            RionTable::from_slice_with_stats(&encoded, TableLayout::RowMajor).unwrap();
+        assert_eq!(decoded, table);
+        assert_eq!(read, Some(stats));
+
+        // Readers that don't use the stats skip them
+        assert_eq!(RionTable::from_slice(&encoded).unwrap(), table);
+        let value = RionValue::from_slice(&encoded).unwrap();
+        assert_eq!(value, RionValue::from_slice(&data).unwrap());
+        #[cfg(feature = "serde")]
+        {
+            #[derive(Debug, PartialEq, serde::Deserialize)]
+            struct Row {
+                id: u64,
+                name: Option<String>,
+            }
+            let rows: Vec<Row> = crate::from_bytes(&data).unwrap();
+            assert_eq!(crate::from_bytes::<Vec<Row>>(&encoded).unwrap(), rows);
+        }
+        let mut appended = encoded.clone();
+        assert!(RionTable::append_rows(&mut appended, &[1u64.into(), "d".into()]).is_err());
+
+        // A table without stats
+        assert_eq!(TableStats::read(&data).unwrap(), None);
+        let (_, read) = RionTable::from_slice_with_stats(&data, TableLayout::RowMajor).unwrap();
+        assert_eq!(read, None);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TableStats {
+    pub rows: u64,
+    /// In the same order as the table's columns
+    pub columns: Vec<ColumnStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ColumnStats {
+    pub name: Vec<u8>,
+    /// Smallest non-null cell by [`RionField`]'s ordering, `None` when every
+    /// cell is null
+    pub min: Option<RionField<'static>>,
+    pub max: Option<RionField<'static>>,
+    pub null_count: u64,
+    /// Number of different non-null cells when the table was written
+    pub distinct: u64,
+}
+
+impl ColumnStats {
+    /// Whether a cell equal to `value` could be in the column, `false` means
+    /// the table can be skipped
+    pub fn may_contain(&self, value: &RionField) -> bool {
+        if value.is_null() {
+            return self.null_count > 0;
+        }
+        match (&self.min, &self.max) {
+            (Some(min), Some(max)) => min <= value && value <= max,
+            _ => false,
+        }
+    }
+
+    fn encode(&self, content: &mut Vec<u8>) {
+        let mut fields = Vec::new();
+        write_entry(&mut fields, "name", &RionField::bytes(&self.name));
+        write_entry(&mut fields, "nulls", &self.null_count.into());
+        write_entry(&mut fields, "distinct", &self.distinct.into());
+        if let (Some(min), Some(max)) = (&self.min, &self.max) {
+            write_entry(&mut fields, "min", min);
+            write_entry(&mut fields, "max", max);
+        }
+        object_field(fields).encode(content).unwrap();
+    }
+
+    fn decode(field: &RionField) -> Result<Self> {
+        let mut encoded = Vec::new();
+        field.encode(&mut encoded)?;
+        let object = RionObject::from_slice(&encoded)?;
+        let count = |key: &str| {
+            object
+                .get(key)
+                .and_then(RionField::as_u64)
+                .ok_or_else(|| FerionError::invalid(format!("Column stats have no {key}")))
+        };
+        Ok(ColumnStats {
+            name: object
+                .get("name")
+                .ok_or_else(|| FerionError::invalid("Column stats have no name"))?
+                .as_bytes()
+                .to_vec(),
+            min: object.get("min").cloned().map(RionField::into_owned),
+            max: object.get("max").cloned().map(RionField::into_owned),
+            null_count: count("nulls")?,
+            distinct: count("distinct")?,
+        })
+    }
+}
+
+impl TableStats {
+    pub fn column(&self, name: impl AsRef<[u8]>) -> Option<&ColumnStats> {
+        self.columns
+            .iter()
+            .find(|column| column.name == name.as_ref())
+    }
+
+    /// Encode the stats as an object, the content of the
+    /// [`RionField::TABLE_STATS`] field
+    pub fn encode(&self) -> Vec<u8> {
+        let mut columns = Vec::new();
+        for column in &self.columns {
+            column.encode(&mut columns);
+        }
+        let mut stats = Vec::new();
+        write_entry(&mut stats, "rows", &self.rows.into());
+        write_entry(
+            &mut stats,
+            "columns",
+            &RionField::Normal(NormalField {
+                field_type: NormalRionType::Array,
+                data: columns.into(),
+            }),
+        );
+        let mut encoded = Vec::new();
+        object_field(stats).encode(&mut encoded).unwrap();
+        encoded
+    }
+
+    /// Decode stats on their own, as written by [`encode`](Self::encode)
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let stats = RionObject::from_slice(data)?;
+        let rows = stats
+            .get("rows")
+            .and_then(RionField::as_u64)
+            .ok_or_else(|| FerionError::invalid("Table stats have no row count"))?;
+        let columns = match get_raw(&stats, "columns")? {
+            Some(columns) if columns != [0xA0] => RionArray::from_slice(&columns)?
+                .elements
+                .iter()
+                .map(ColumnStats::decode)
+                .collect::<Result<_>>()?,
+            _ => Vec::new(),
+        };
+        Ok(TableStats { rows, columns })
+    }
+
+    /// The stats of the table at the start of `data`, without decoding the
+    /// table itself
+    pub fn read(data: &[u8]) -> Result<Option<Self>> {
+        let (lead, _, content) = get_normal_header(data)?;
+        let RionFieldType::Normal(NormalRionType::Table) = lead.field_type() else {
+            return Err(FerionError::unexpected("a table", lead.field_type()));
+        };
+        let (_, after_count) = RionField::parse(content)?;
+        if crate::skip_table_stats(after_count)?.len() == after_count.len() {
+            return Ok(None);
+        }
+        let (RionFieldRef::Extended { data, .. }, _) = RionFieldRef::parse(after_count)? else {
+            unreachable!("skip_table_stats skipped an extended field");
+        };
+        Self::decode(data).map(Some)
+    }
+}
+
+impl RionTable<'_> {
+    pub fn stats(&self) -> TableStats {
+        let columns = self.column_names.len();
+        let rows = self.row_count();
+        let stats = self
+            .column_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let cells = self.rows[..rows * columns].iter().skip(i).step_by(columns);
+                let mut stats = ColumnStats {
+                    name: name.to_vec(),
+                    ..Default::default()
+                };
+                let mut distinct = HashSet::new();
+                for cell in cells {
+                    if cell.is_null() {
+                        stats.null_count += 1;
+                    } else {
+                        distinct.insert(cell);
+                    }
+                }
+                stats.distinct = distinct.len() as u64;
+                stats.min = distinct
+                    .iter()
+                    .min()
+                    .map(|cell| (*cell).clone().into_owned());
+                stats.max = distinct
+                    .iter()
+                    .max()
+                    .map(|cell| (*cell).clone().into_owned());
+                stats
+            })
+            .collect();
+        TableStats {
+            rows: rows as u64,
+            columns: stats,
+        }
+    }
+
+    /// Encode the table with its [`TableStats`] in a
+    /// [`RionField::TABLE_STATS`] field after the row count
+    pub fn encode_with_stats(&self, layout: TableLayout) -> Vec<u8> {
+        let mut stats = Vec::new();
+        RionField::Extended {
+            type_id: RionField::TABLE_STATS,
+            data: self.stats().encode().into(),
+        }
+        .encode(&mut stats)
+        .unwrap();
+        let cells = &self.rows[..self.row_count() * self.column_names.len()];
+        self.encode_cells(layout, cells, &stats)
+    }
+}
+
+impl<'a> RionTable<'a> {
+    /// Decode a table and its stats, if it has any
+    pub fn from_slice_with_stats(
+        data: &'a [u8],
+        layout: TableLayout,
+    ) -> Result<(Self, Option<TableStats>)> {
+        let stats = TableStats::read(data)?;
+        Ok((RionTable::from_slice_with_layout(data, layout)?, stats))
+    }
+}
@@ -0,0 +1,553 @@
+use std::fmt;
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
+
+use crate::{field::ShortField, types::ShortRionType, FerionError, Result, RionField};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(data: &[u8]) -> Result<RionDateTime> {
+        RionDateTime::try_from(RionField::Short(ShortField::try_new(
+            ShortRionType::UTCDateTime,
+            data,
+        )?))
+    }
+
+    #[test]
+    fn test_precision_levels() {
+        let year = decode(&[0x07, 0xE8]).unwrap();
+        assert_eq!(year.precision(), Precision::Year);
+        assert_eq!(year.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+
+        let minute = decode(&[0x07, 0xE8, 2, 29, 13, 45]).unwrap();
+        assert_eq!(minute.precision(), Precision::Minute);
+        assert_eq!(
+            minute,
+            RionDateTime::from_components(2024, 2, 29, 13, 45, 0, 0).unwrap()
+        );
+
+        let micros = decode(&[0x07, 0xE8, 2, 29, 13, 45, 10, 0, 0, 1]).unwrap();
+        assert_eq!(micros.precision(), Precision::Microsecond);
+        assert_eq!(micros.nanosecond(), 1_000);
+        assert_eq!(micros.to_rfc3339(), "2024-02-29T13:45:10.000001+00:00");
+    }
+
+    #[test]
+    fn test_unset_components() {
+        // Zero months and days stand for unset ones
+        let month = decode(&[0x07, 0xE8, 0, 0]).unwrap();
+        assert_eq!(month.precision(), Precision::Day);
+        assert_eq!((month.month(), month.day()), (1, 1));
+        assert!(decode(&[0x07]).is_err());
+        assert!(decode(&[0x07, 0xE8, 1, 1, 0, 0, 0, 5]).is_err());
+        assert!(decode(&[0x07, 0xE8, 2, 30]).is_err());
+        assert!(decode(&[0x07, 0xE8, 1, 1, 24]).is_err());
+        assert!(RionDateTime::from_components(2023, 2, 29, 0, 0, 0, 0).is_none());
+    }
+
+    #[test]
+    fn test_fraction_bounds() {
+        let date = [0x07, 0xE8, 1, 1, 0, 0, 0];
+        let decode_fraction = |fraction: &[u8]| decode(&[&date[..], fraction].concat());
+        let millis = decode_fraction(&[0x03, 0xE7]).unwrap();
+        assert_eq!(millis.nanosecond(), 999_000_000);
+        assert!(decode_fraction(&[0x03, 0xE8]).is_err());
+        assert!(decode_fraction(&[0xFF, 0xFF]).is_err());
+        let micros = decode_fraction(&[0x0F, 0x42, 0x3F]).unwrap();
+        assert_eq!(micros.nanosecond(), 999_999_000);
+        assert!(decode_fraction(&[0x0F, 0x42, 0x40]).is_err());
+        assert!(decode_fraction(&[0xFF, 0xFF, 0xFF]).is_err());
+        let nanos = decode_fraction(&[0x3B, 0x9A, 0xC9, 0xFF]).unwrap();
+        assert_eq!(nanos.nanosecond(), 999_999_999);
+        assert!(decode_fraction(&[0x3B, 0x9A, 0xCA, 0x00]).is_err());
+        assert!(crate::RionValue::from_slice(&[
+            0x79, 0x07, 0xE8, 0x01, 0x01, 0x00, 0x00, 0x00, 0xFF, 0xFF
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_year_range() {
+        let ides = RionDateTime::from_components(-44, 3, 15, 12, 0, 0, 0).unwrap();
+        assert_eq!(ides.to_rfc3339(), "-0044-03-15T12:00:00+00:00");
+        assert!(ides.to_field(YearRange::Error).is_err());
+        assert!(RionField::try_from(ides).is_err());
+        let field = ides.to_field(YearRange::Extended).unwrap();
+        assert_eq!(
+            field,
+            RionField::Extended {
+                type_id: RionField::WIDE_DATE_TIME,
+                data: [0xFF, 0xFF, 0xFF, 0xD4, 3, 15, 12][..].into(),
+            }
+        );
+        assert_eq!(RionDateTime::try_from(field.clone()).unwrap(), ides);
+        let mut encoded = Vec::new();
+        field.encode(&mut encoded).unwrap();
+        let value = crate::RionValue::from_slice(&encoded).unwrap();
+        assert_eq!(value, crate::RionValue::DateTime(ides));
+        assert!(value.encode_to_vec().is_err());
+
+        // Years that fit are still written as UTCDateTime fields
+        let far = RionDateTime::from_components(70_000, 1, 1, 0, 0, 0, 0).unwrap();
+        assert_eq!(far.to_rfc3339(), "+70000-01-01T00:00:00+00:00");
+        let near = RionDateTime::from_components(1, 1, 1, 0, 0, 0, 0).unwrap();
+        assert!(far.to_field(YearRange::Extended).unwrap() > near.try_into().unwrap());
+        let year_zero = RionDateTime::from_components(0, 1, 1, 0, 0, 0, 0).unwrap();
+        assert!(year_zero
+            .to_field(YearRange::Extended)
+            .unwrap()
+            .is_short_type(ShortRionType::UTCDateTime));
+        assert!(
+            RionDateTime::from_components(RionDateTime::MAX_YEAR + 1, 1, 1, 0, 0, 0, 0).is_none()
+        );
+        assert!(
+            RionDateTime::from_components(RionDateTime::MIN_YEAR - 1, 12, 31, 0, 0, 0, 0).is_none()
+        );
+        // A wide year one past the range
+        let past_max = RionField::Extended {
+            type_id: RionField::WIDE_DATE_TIME,
+            data: [0x00, 0x03, 0xFF, 0xFF, 0x06, 0x01][..].into(),
+        };
+        assert!(RionDateTime::try_from(past_max).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn test_year_range_matches_chrono() {
+        use chrono::NaiveDate;
+
+        let first = RionDateTime::from_components(RionDateTime::MIN_YEAR, 1, 1, 0, 0, 0, 0);
+        let first = first.unwrap().to_field(YearRange::Extended).unwrap();
+        let first = RionDateTime::try_from(first).unwrap().datetime();
+        assert_eq!(first.date_naive(), NaiveDate::MIN);
+        let last =
+            RionDateTime::from_components(RionDateTime::MAX_YEAR, 12, 31, 23, 59, 59, 999_999_999);
+        let last = last.unwrap().to_field(YearRange::Extended).unwrap();
+        let last = RionDateTime::try_from(last).unwrap().datetime();
+        assert_eq!(last.date_naive(), NaiveDate::MAX);
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn test_explicit_precision() {
+        use chrono::{TimeZone, Timelike, Utc};
+
+        let dt = Utc.with_ymd_and_hms(2024, 2, 29, 13, 45, 10).unwrap()
+            + chrono::Duration::microseconds(1_500);
+        let field = RionField::datetime_with_precision(dt, Precision::Minute).unwrap();
+        assert_eq!(field.as_bytes(), [0x07, 0xE8, 2, 29, 13, 45]);
+        let decoded = RionDateTime::try_from(field).unwrap();
+        assert_eq!(decoded, RionDateTime::new(dt, Precision::Minute));
+        assert_eq!(
+            decoded.datetime(),
+            Utc.with_ymd_and_hms(2024, 2, 29, 13, 45, 0).unwrap()
+        );
+
+        let implied = RionDateTime::from(dt);
+        assert_eq!(implied.precision(), Precision::Microsecond);
+        let millis = implied.with_precision(Precision::Millisecond);
+        assert_eq!(millis.datetime().nanosecond(), 1_000_000);
+        assert_eq!(RionField::try_from(millis).unwrap().as_bytes().len(), 9);
+        let year = RionField::datetime_with_precision(dt, Precision::Year).unwrap();
+        assert_eq!(
+            RionDateTime::try_from(year).unwrap(),
+            implied.with_precision(Precision::Year)
+        );
+        for precision in [
+            Precision::Second,
+            Precision::Millisecond,
+            Precision::Nanosecond,
+        ] {
+            let dt = RionDateTime::new(dt, precision);
+            assert_eq!(dt.to_rfc3339(), dt.datetime().to_rfc3339());
+        }
+    }
+}
+
+/// How much of a date time an encoded UTCDateTime field carries
+///
+/// Writers may stop after any component, the ones that follow are read as the
+/// start of the period: a field with only a year is midnight on January 1st.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Precision {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+impl Precision {
+    // The step sub-second data is truncated to
+    const fn nanosecond_step(self) -> u32 {
+        match self {
+            Precision::Millisecond => 1_000_000,
+            Precision::Microsecond => 1_000,
+            Precision::Nanosecond => 1,
+            _ => 1_000_000_000,
+        }
+    }
+
+    // The precision of a field holding `len` bytes of data after the year
+    fn from_data_len(len: usize) -> Option<Self> {
+        Some(match len {
+            0 => Precision::Year,
+            1 => Precision::Month,
+            2 => Precision::Day,
+            3 => Precision::Hour,
+            4 => Precision::Minute,
+            5 => Precision::Second,
+            7 => Precision::Millisecond,
+            8 => Precision::Microsecond,
+            9 => Precision::Nanosecond,
+            _ => return None,
+        })
+    }
+}
+
+/// What to do when encoding a date time whose year a UTCDateTime field can't
+/// hold, which is any year before 0 or after 65535
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum YearRange {
+    /// Fail to encode it
+    #[default]
+    Error,
+    /// Write it as a [`RionField::WIDE_DATE_TIME`] extended field, which only
+    /// readers that know that type can decode. Other years are still written
+    /// as UTCDateTime fields
+    Extended,
+}
+
+/// A decoded UTCDateTime field along with the precision it was written with
+///
+/// Holds the UTC components as they were written, so it needs nothing beyond
+/// this crate. Converts to and from `DateTime<Utc>` with the `chrono` feature.
+/// Orders chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RionDateTime {
+    year: i32,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+    precision: Precision,
+}
+
+impl RionDateTime {
+    /// The earliest year a date time can have, that of chrono's `NaiveDate::MIN`
+    pub const MIN_YEAR: i32 = -262_143;
+    /// The latest year a date time can have, that of chrono's `NaiveDate::MAX`
+    pub const MAX_YEAR: i32 = 262_142;
+
+    /// A date time from its UTC components, at the shortest precision that
+    /// keeps them all, `None` if they aren't a date and time a field can hold
+    ///
+    /// Years from [`MIN_YEAR`](Self::MIN_YEAR) to [`MAX_YEAR`](Self::MAX_YEAR)
+    /// are accepted, see [`YearRange`] for encoding the ones outside of 0 to
+    /// 65535
+    pub fn from_components(
+        year: i32,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        nanosecond: u32,
+    ) -> Option<Self> {
+        let valid = (Self::MIN_YEAR..=Self::MAX_YEAR).contains(&year)
+            && (1..=12).contains(&month)
+            && (1..=days_in_month(year, month)).contains(&day)
+            && hour < 24
+            && minute < 60
+            && second < 60
+            && nanosecond < 1_000_000_000;
+        if !valid {
+            return None;
+        }
+        let datetime = RionDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            nanosecond,
+            precision: Precision::Nanosecond,
+        };
+        Some(datetime.with_precision(datetime.shortest_precision()))
+    }
+
+    /// `datetime` truncated to `precision`
+    #[cfg(feature = "chrono")]
+    pub fn new(datetime: DateTime<Utc>, precision: Precision) -> Self {
+        RionDateTime {
+            year: datetime.year(),
+            month: datetime.month() as u8,
+            day: datetime.day() as u8,
+            hour: datetime.hour() as u8,
+            minute: datetime.minute() as u8,
+            second: datetime.second() as u8,
+            // Leap seconds carry over into the nanoseconds
+            nanosecond: datetime.nanosecond().min(999_999_999),
+            precision,
+        }
+        .with_precision(precision)
+    }
+
+    /// The same date time at another precision, only lowering it drops data
+    pub fn with_precision(self, precision: Precision) -> Self {
+        let keep = |component: Precision, value: u8, default: u8| match precision >= component {
+            true => value,
+            false => default,
+        };
+        RionDateTime {
+            month: keep(Precision::Month, self.month, 1),
+            day: keep(Precision::Day, self.day, 1),
+            hour: keep(Precision::Hour, self.hour, 0),
+            minute: keep(Precision::Minute, self.minute, 0),
+            second: keep(Precision::Second, self.second, 0),
+            nanosecond: self.nanosecond - self.nanosecond % precision.nanosecond_step(),
+            precision,
+            ..self
+        }
+    }
+
+    // The shortest precision that keeps every component
+    fn shortest_precision(&self) -> Precision {
+        let nanos = self.nanosecond;
+        if nanos == 0 {
+            match (self.hour, self.minute, self.second) {
+                (0, 0, 0) => Precision::Day,
+                (_, 0, 0) => Precision::Hour,
+                (_, _, 0) => Precision::Minute,
+                _ => Precision::Second,
+            }
+        } else if nanos.is_multiple_of(1_000_000) {
+            Precision::Millisecond
+        } else if nanos.is_multiple_of(1_000) {
+            Precision::Microsecond
+        } else {
+            Precision::Nanosecond
+        }
+    }
+
+    /// The date time, with the components past its precision at their defaults
+    #[cfg(feature = "chrono")]
+    pub fn datetime(&self) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(
+            self.year,
+            self.month.into(),
+            self.day.into(),
+            self.hour.into(),
+            self.minute.into(),
+            self.second.into(),
+        )
+        .single()
+        .and_then(|dt| dt.with_nanosecond(self.nanosecond))
+        .expect("Components are checked when created")
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// The field holding this date time, with the components up to its
+    /// precision
+    pub fn to_field(self, years: YearRange) -> Result<RionField<'static>> {
+        let standard = (0..=0xFFFF).contains(&self.year);
+        let mut data = Vec::with_capacity(13);
+        match (standard, years) {
+            (true, _) => data.extend_from_slice(&(self.year as u16).to_be_bytes()),
+            (false, YearRange::Extended) => data.extend_from_slice(&self.year.to_be_bytes()),
+            (false, YearRange::Error) => {
+                return Err(FerionError::invalid(format!(
+                    "Year {} doesn't fit a UTC date time field",
+                    self.year
+                )))
+            }
+        }
+        let components = [self.month, self.day, self.hour, self.minute, self.second];
+        let count = match self.precision {
+            Precision::Year => 0,
+            Precision::Month => 1,
+            Precision::Day => 2,
+            Precision::Hour => 3,
+            Precision::Minute => 4,
+            _ => 5,
+        };
+        data.extend_from_slice(&components[..count]);
+        let nanos = self.nanosecond;
+        match self.precision {
+            // Milliseconds (2 bytes)
+            Precision::Millisecond => {
+                data.extend_from_slice(&((nanos / 1_000_000) as u16).to_be_bytes())
+            }
+            // Microseconds (3 bytes)
+            Precision::Microsecond => data.extend_from_slice(&(nanos / 1_000).to_be_bytes()[1..]),
+            // Nanoseconds (4 bytes)
+            Precision::Nanosecond => data.extend_from_slice(&nanos.to_be_bytes()),
+            _ => {}
+        }
+        Ok(match standard {
+            true => RionField::Short(ShortField::try_new(ShortRionType::UTCDateTime, &data)?)
+                .into_owned(),
+            false => RionField::Extended {
+                type_id: RionField::WIDE_DATE_TIME,
+                data: data.into(),
+            },
+        })
+    }
+
+    /// RFC 3339 with a `+00:00` offset and as many fractional digits as the
+    /// sub-second data needs, like chrono's `to_rfc3339`
+    pub fn to_rfc3339(&self) -> String {
+        self.to_string()
+    }
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for RionDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.year {
+            year @ 0..=9999 => write!(f, "{year:04}")?,
+            year => write!(f, "{year:+05}")?,
+        }
+        write!(
+            f,
+            "-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        let nanos = self.nanosecond;
+        if nanos == 0 {
+        } else if nanos.is_multiple_of(1_000_000) {
+            write!(f, ".{:03}", nanos / 1_000_000)?;
+        } else if nanos.is_multiple_of(1_000) {
+            write!(f, ".{:06}", nanos / 1_000)?;
+        } else {
+            write!(f, ".{nanos:09}")?;
+        }
+        f.write_str("+00:00")
+    }
+}
+
+// The shortest precision that keeps every component
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for RionDateTime {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        let datetime = RionDateTime::new(datetime, Precision::Nanosecond);
+        datetime.with_precision(datetime.shortest_precision())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<RionDateTime> for DateTime<Utc> {
+    fn from(value: RionDateTime) -> Self {
+        value.datetime()
+    }
+}
+
+impl TryFrom<RionField<'_>> for RionDateTime {
+    type Error = FerionError;
+    fn try_from(value: RionField<'_>) -> Result<Self> {
+        let data = value.as_bytes();
+        let split = match &value {
+            RionField::Extended { type_id, data } if *type_id == RionField::WIDE_DATE_TIME => data
+                .split_first_chunk()
+                .map(|(year, rest)| (i32::from_be_bytes(*year), rest)),
+            field if field.is_short_type(ShortRionType::UTCDateTime) => data
+                .split_first_chunk()
+                .map(|(year, rest)| (u16::from_be_bytes(*year) as i32, rest)),
+            _ => {
+                return Err(FerionError::invalid(format!(
+                    "Field is not a UTC date time: {value:?}"
+                )))
+            }
+        };
+        let invalid_length = || {
+            FerionError::invalid(format!(
+                "Invalid date time length {}: {data:x?}",
+                data.len()
+            ))
+        };
+        let (year, rest) = split.ok_or_else(invalid_length)?;
+        let precision = Precision::from_data_len(rest.len()).ok_or_else(invalid_length)?;
+        let (components, subsec) = rest.split_at(rest.len().min(5));
+        // Missing components default to the start of the period, as do zero
+        // months and days
+        let component = |i: usize, default: u8| match components.get(i) {
+            Some(0) | None => default,
+            Some(&value) => value,
+        };
+        // Scaled in 64 bits so sub-second values of a second or more are
+        // rejected rather than overflowing
+        let nanos = match *subsec {
+            [] => 0,
+            [a, b] => u64::from(u16::from_be_bytes([a, b])) * 1_000_000,
+            [a, b, c] => u64::from(u32::from_be_bytes([0, a, b, c])) * 1_000,
+            [a, b, c, d] => u64::from(u32::from_be_bytes([a, b, c, d])),
+            _ => unreachable!("Checked by the precision"),
+        };
+        let nanos = u32::try_from(nanos)
+            .ok()
+            .filter(|&nanos| nanos < 1_000_000_000)
+            .ok_or_else(|| {
+                FerionError::invalid(format!("Invalid date time fraction of a second: {data:x?}"))
+            })?;
+        let datetime = RionDateTime::from_components(
+            year,
+            component(0, 1),
+            component(1, 1),
+            component(2, 0),
+            component(3, 0),
+            component(4, 0),
+            nanos,
+        )
+        .ok_or_else(|| FerionError::invalid(format!("Invalid date time: {data:x?}")))?;
+        Ok(datetime.with_precision(precision))
+    }
+}
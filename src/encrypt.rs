@@ -0,0 +1,181 @@
+use std::error::Error;
+
+use crate::{redact::for_each_match, FerionError, Result, RionField, RionValue};
+
+/// Encrypts and decrypts the values of fields picked by [`encrypt`]
+///
+/// Bring your own algorithm, e.g. an AEAD such as AES-GCM or
+/// ChaCha20-Poly1305 that stores its nonce and tag in the ciphertext.
+/// `decrypt` should fail rather than return garbage for a wrong key.
+pub trait Cipher {
+    type Error: Error + Send + Sync + 'static;
+
+    fn encrypt(&self, plaintext: &[u8]) -> std::result::Result<Vec<u8>, Self::Error>;
+
+    fn decrypt(&self, ciphertext: &[u8]) -> std::result::Result<Vec<u8>, Self::Error>;
+}
+
+/// Whether `value` is a field written by [`encrypt`]
+pub fn is_encrypted(value: &RionValue) -> bool {
+    matches!(value, RionValue::Extended { type_id, .. } if *type_id == RionField::ENCRYPTED)
+}
+
+/// Encrypt every value matched by `paths`, leaving the rest of the document
+/// readable
+///
+/// Paths use the [`Path`](crate::Path) syntax, e.g. `users.*.password`. Each
+/// matched value is encoded and replaced by an extended field of type
+/// [`RionField::ENCRYPTED`] holding the ciphertext.
+pub fn encrypt<C: Cipher>(value: &mut RionValue, paths: &[&str], cipher: &C) -> Result<()> {
+    for_each_match(value, paths, &mut |value| {
+        let data = cipher.encrypt(&value.encode_to_vec()?).map_err(external)?;
+        *value = RionValue::Extended {
+            type_id: RionField::ENCRYPTED,
+            data,
+        };
+        Ok(())
+    })
+}
+
+/// The value behind a field written by [`encrypt`], e.g. one found with
+/// [`RionValue::query`]. Values that aren't encrypted are returned as they are
+pub fn decrypt_field<C: Cipher>(value: &RionValue, cipher: &C) -> Result<RionValue> {
+    match value {
+        RionValue::Extended { type_id, data } if *type_id == RionField::ENCRYPTED => {
+            RionValue::from_slice(&cipher.decrypt(data).map_err(external)?)
+        }
+        value => Ok(value.clone()),
+    }
+}
+
+/// Decrypt every field written by [`encrypt`] in `value`, including ones
+/// encrypted again inside them
+pub fn decrypt<C: Cipher>(value: &mut RionValue, cipher: &C) -> Result<()> {
+    // Walked with a stack, documents can nest deeper than the call stack allows
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        if is_encrypted(value) {
+            *value = decrypt_field(value, cipher)?;
+        }
+        match value {
+            RionValue::Array(elements) => stack.extend(elements),
+            RionValue::Object(fields) => stack.extend(fields.values_mut()),
+            RionValue::Table { rows, .. } => stack.extend(rows.iter_mut().flatten()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn external(err: impl Error + Send + Sync + 'static) -> FerionError {
+    FerionError::External(Box::new(err))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    // XORs every byte with the key and appends it, so a wrong key is caught
+    struct Xor(u8);
+
+    #[derive(Debug)]
+    struct WrongKey;
+
+    impl std::fmt::Display for WrongKey {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Wrong key")
+        }
+    }
+
+    impl Error for WrongKey {}
+
+    impl Cipher for Xor {
+        type Error = WrongKey;
+
+        fn encrypt(&self, plaintext: &[u8]) -> std::result::Result<Vec<u8>, WrongKey> {
+            let mut out: Vec<u8> = plaintext.iter().map(|byte| byte ^ self.0).collect();
+            out.push(self.0);
+            Ok(out)
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> std::result::Result<Vec<u8>, WrongKey> {
+            match ciphertext.split_last() {
+                Some((&key, data)) if key == self.0 => {
+                    Ok(data.iter().map(|byte| byte ^ self.0).collect())
+                }
+                _ => Err(WrongKey),
+            }
+        }
+    }
+
+    fn document() -> RionValue {
+        let mut card = BTreeMap::new();
+        card.insert(b"number"[..].into(), "4111 1111 1111 1111".into());
+        card.insert(b"expiry"[..].into(), "12/30".into());
+        let mut doc = BTreeMap::new();
+        doc.insert(b"id"[..].into(), RionValue::PosInt(42));
+        doc.insert(b"name"[..].into(), "Alice".into());
+        doc.insert(b"card"[..].into(), RionValue::Object(card));
+        RionValue::Object(doc)
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let original = document();
+        let mut doc = original.clone();
+        encrypt(&mut doc, &["card.number", "name"], &Xor(0x5A)).unwrap();
+        assert!(is_encrypted(doc.get("name").unwrap()));
+        assert_eq!(doc.get("id"), Some(&RionValue::PosInt(42)));
+        let card = doc.get("card").unwrap();
+        assert!(is_encrypted(card.get("number").unwrap()));
+        assert_eq!(card.get("expiry").unwrap().as_str(), Some("12/30"));
+
+        // The rest of the document still decodes without the key
+        let encoded = doc.encode_to_vec().unwrap();
+        let decoded = RionValue::from_slice(&encoded).unwrap();
+        assert_eq!(decoded, doc);
+        let name = decrypt_field(decoded.get("name").unwrap(), &Xor(0x5A)).unwrap();
+        assert_eq!(name.as_str(), Some("Alice"));
+        let id = decrypt_field(decoded.get("id").unwrap(), &Xor(0x5A)).unwrap();
+        assert_eq!(id, RionValue::PosInt(42));
+
+        assert!(decrypt_field(decoded.get("name").unwrap(), &Xor(1)).is_err());
+        let mut wrong = decoded.clone();
+        assert!(decrypt(&mut wrong, &Xor(1)).is_err());
+        let mut doc = decoded;
+        decrypt(&mut doc, &Xor(0x5A)).unwrap();
+        assert_eq!(doc, original);
+    }
+
+    #[test]
+    fn test_encrypt_nested() {
+        // Encrypting a field inside an already encrypted one
+        let original = document();
+        let mut doc = original.clone();
+        encrypt(&mut doc, &["card.number"], &Xor(1)).unwrap();
+        encrypt(&mut doc, &["card"], &Xor(1)).unwrap();
+        assert!(is_encrypted(doc.get("card").unwrap()));
+        decrypt(&mut doc, &Xor(1)).unwrap();
+        assert_eq!(doc, original);
+    }
+
+    #[test]
+    fn test_encrypt_table_column() {
+        let mut doc = RionValue::Table {
+            columns: vec![b"id"[..].into(), b"ssn"[..].into()],
+            rows: vec![
+                vec![RionValue::PosInt(1), "078-05-1120".into()],
+                vec![RionValue::PosInt(2), RionValue::Null],
+            ],
+        };
+        let original = doc.clone();
+        encrypt(&mut doc, &["ssn"], &Xor(7)).unwrap();
+        let RionValue::Table { rows, .. } = &doc else {
+            panic!("Expected a table");
+        };
+        assert!(rows.iter().all(|row| is_encrypted(&row[1])));
+        decrypt(&mut doc, &Xor(7)).unwrap();
+        assert_eq!(doc, original);
+    }
+}
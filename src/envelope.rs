@@ -0,0 +1,269 @@
+use std::{collections::BTreeMap, fmt, marker::PhantomData};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    object::{get_raw, object_field, write_entry, write_raw_entry},
+    FerionError, Result, RionDateTime, RionField, RionObject, YearRange,
+};
+
+/// Envelopes this version of the crate writes, newer ones are rejected
+const VERSION: u64 = 1;
+
+/// A payload with the metadata needed to route and decode it
+///
+/// The payload is stored as its encoded bytes and only decoded into `T` when
+/// [`payload`](Self::payload) is called, so forwarding an envelope never
+/// touches it. Encoded as an object with a version number `v`, so fields can be
+/// added later without breaking older readers.
+pub struct Envelope<T> {
+    headers: BTreeMap<String, String>,
+    content_type: Option<String>,
+    schema_id: Option<String>,
+    timestamp: DateTime<Utc>,
+    payload: Vec<u8>,
+    _payload: PhantomData<fn() -> T>,
+}
+
+impl<T> Envelope<T> {
+    /// Wrap an already encoded payload, timestamped now
+    pub fn from_raw(payload: Vec<u8>) -> Self {
+        Envelope {
+            headers: BTreeMap::new(),
+            content_type: None,
+            schema_id: None,
+            timestamp: Utc::now(),
+            payload,
+            _payload: PhantomData,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn new(payload: &T) -> std::result::Result<Self, crate::SerializeError>
+    where
+        T: serde::Serialize,
+    {
+        Ok(Self::from_raw(crate::to_bytes(payload)?))
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    pub fn with_schema_id(mut self, schema_id: impl Into<String>) -> Self {
+        self.schema_id = Some(schema_id.into());
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    pub fn headers(&self) -> &BTreeMap<String, String> {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.headers
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn schema_id(&self) -> Option<&str> {
+        self.schema_id.as_deref()
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    pub fn raw_payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn into_raw_payload(self) -> Vec<u8> {
+        self.payload
+    }
+
+    /// Decode the payload, borrowing from the envelope where `T` allows it
+    #[cfg(feature = "serde")]
+    pub fn payload<'a>(&'a self) -> std::result::Result<T, crate::DeserializeError>
+    where
+        T: serde::Deserialize<'a>,
+    {
+        crate::from_bytes(&self.payload)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut content = Vec::new();
+        write_entry(&mut content, "v", &VERSION.into());
+        if !self.headers.is_empty() {
+            let mut headers = Vec::new();
+            for (name, value) in &self.headers {
+                write_entry(&mut headers, name, &value.as_str().into());
+            }
+            write_entry(&mut content, "headers", &object_field(headers));
+        }
+        if let Some(content_type) = &self.content_type {
+            write_entry(&mut content, "content_type", &content_type.as_str().into());
+        }
+        if let Some(schema_id) = &self.schema_id {
+            write_entry(&mut content, "schema_id", &schema_id.as_str().into());
+        }
+        // Extended years can't fail, the envelope has no other way to hold them
+        let timestamp = RionDateTime::from(self.timestamp).to_field(YearRange::Extended);
+        write_entry(&mut content, "timestamp", &timestamp.unwrap());
+        write_raw_entry(&mut content, "payload", &self.payload);
+        let mut encoded = Vec::new();
+        object_field(content).encode(&mut encoded).unwrap();
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let object = RionObject::from_slice(data)?;
+        let version = object
+            .get("v")
+            .and_then(RionField::as_u64)
+            .ok_or_else(|| FerionError::invalid("Envelope has no version"))?;
+        if version > VERSION {
+            return Err(FerionError::invalid(format!(
+                "Unsupported envelope version {version}"
+            )));
+        }
+        let string = |key: &str| -> Result<Option<String>> {
+            match object.get(key) {
+                Some(field) => Ok(Some(
+                    field
+                        .as_str()
+                        .ok_or_else(|| {
+                            FerionError::invalid(format!("Envelope {key} is not a string"))
+                        })?
+                        .to_string(),
+                )),
+                None => Ok(None),
+            }
+        };
+        let mut headers = BTreeMap::new();
+        if let Some(raw) = get_raw(&object, "headers")? {
+            for (name, value) in RionObject::from_slice(&raw)?.iter() {
+                let name = std::str::from_utf8(name)?;
+                let value = value.as_str().ok_or_else(|| {
+                    FerionError::invalid(format!("Header {name} is not a string"))
+                })?;
+                headers.insert(name.to_string(), value.to_string());
+            }
+        }
+        let timestamp = object
+            .get("timestamp")
+            .ok_or_else(|| FerionError::invalid("Envelope has no timestamp"))?
+            .clone()
+            .try_into()?;
+        Ok(Envelope {
+            headers,
+            content_type: string("content_type")?,
+            schema_id: string("schema_id")?,
+            timestamp,
+            payload: get_raw(&object, "payload")?
+                .ok_or_else(|| FerionError::invalid("Envelope has no payload"))?,
+            _payload: PhantomData,
+        })
+    }
+}
+
+// Implemented by hand so they don't require `T` to implement them too
+
+impl<T> Clone for Envelope<T> {
+    fn clone(&self) -> Self {
+        Envelope {
+            headers: self.headers.clone(),
+            content_type: self.content_type.clone(),
+            schema_id: self.schema_id.clone(),
+            timestamp: self.timestamp,
+            payload: self.payload.clone(),
+            _payload: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Envelope<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.headers == other.headers
+            && self.content_type == other.content_type
+            && self.schema_id == other.schema_id
+            && self.timestamp == other.timestamp
+            && self.payload == other.payload
+    }
+}
+
+impl<T> fmt::Debug for Envelope<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Envelope")
+            .field("headers", &self.headers)
+            .field("content_type", &self.content_type)
+            .field("schema_id", &self.schema_id)
+            .field("timestamp", &self.timestamp)
+            .field("payload", &self.payload)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let envelope = Envelope::<u64>::from_raw(vec![0x21, 0x2A])
+            .with_header("trace-id", "abc123")
+            .with_content_type("application/rion")
+            .with_schema_id("orders.v2")
+            .with_timestamp(timestamp);
+        let decoded = Envelope::<u64>::decode(&envelope.encode()).unwrap();
+        assert_eq!(decoded, envelope);
+        assert_eq!(decoded.header("trace-id"), Some("abc123"));
+        assert_eq!(decoded.timestamp(), timestamp);
+        assert_eq!(decoded.raw_payload(), [0x21, 0x2A]);
+    }
+
+    #[test]
+    fn test_envelope_version() {
+        let mut content = Vec::new();
+        write_entry(&mut content, "v", &RionField::from(VERSION + 1));
+        let mut data = Vec::new();
+        object_field(content).encode(&mut data).unwrap();
+        assert!(Envelope::<u64>::decode(&data).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_typed_payload() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Order {
+            id: u64,
+            item: String,
+        }
+        let order = Order {
+            id: 1,
+            item: "book".to_string(),
+        };
+        let envelope = Envelope::new(&order).unwrap();
+        let decoded = Envelope::<Order>::decode(&envelope.encode()).unwrap();
+        assert_eq!(decoded.payload().unwrap(), order);
+        assert!(decoded.headers().is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use std::{error::Error, fmt::Display, str::Utf8Error};
+
+use crate::{InvalidKey, KeyMismatch, RionFieldType};
+
+/// Why encoded data couldn't be parsed or a field couldn't be converted
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FerionError {
+    /// The input ends this many bytes short of the end of a field
+    Truncated(usize),
+    /// A lead byte whose upper nibble is no field type
+    InvalidLeadByte(u8),
+    /// A length, or the bytes encoding it, too long for the field
+    LengthOverflow(usize),
+    /// A UTF8 or Key field that isn't valid UTF-8
+    InvalidUtf8(Utf8Error),
+    /// A field of another type than the one needed
+    UnexpectedType {
+        expected: &'static str,
+        found: RionFieldType,
+    },
+    /// A number that doesn't fit the type it is read as
+    OutOfRange(&'static str),
+    /// An Int64Negative field holding this value, below `i64::MIN`. It can
+    /// still be read as an i128
+    NegativeOutOfRange(i128),
+    /// This many bytes are left after the value
+    TrailingData(usize),
+    /// A table's row count, more rows than its columns and content can hold
+    RowCount(u64),
+    /// An object has this key more than once, see
+    /// [`DuplicateKeys::Reject`](crate::DuplicateKeys::Reject)
+    DuplicateKey(Vec<u8>),
+    InvalidKey(InvalidKey),
+    KeyMismatch(KeyMismatch),
+    /// Writing the encoded data failed
+    Io(std::io::Error),
+    /// An error from the crate of a format converted to or from, such as
+    /// serde_json or parquet
+    External(Box<dyn Error + Send + Sync>),
+    /// Anything else, described by the message
+    Invalid(String),
+}
+
+pub(crate) type Result<T, E = FerionError> = std::result::Result<T, E>;
+
+// The public error types can be sent between threads and wrapped in
+// `Box<dyn Error + Send + Sync>` or `anyhow::Error`, keep it that way
+const _: () = {
+    const fn assert_error<T: Error + Send + Sync + 'static>() {}
+    assert_error::<FerionError>();
+    assert_error::<InvalidKey>();
+    assert_error::<KeyMismatch>();
+    #[cfg(feature = "serde")]
+    assert_error::<crate::DeserializeError>();
+    #[cfg(feature = "serde")]
+    assert_error::<crate::SerializeError>();
+};
+
+impl FerionError {
+    pub(crate) fn unexpected(expected: &'static str, found: RionFieldType) -> Self {
+        FerionError::UnexpectedType { expected, found }
+    }
+
+    // For errors no variant describes, their message goes in `Invalid`
+    pub(crate) fn invalid(msg: impl Into<String>) -> Self {
+        FerionError::Invalid(msg.into())
+    }
+
+    /// Whether more input could have made the data valid
+    pub fn is_truncated(&self) -> bool {
+        matches!(self, FerionError::Truncated(_))
+    }
+}
+
+impl Display for FerionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FerionError::Truncated(missing) => {
+                write!(f, "Input ends {missing} bytes short of the end of a field")
+            }
+            FerionError::InvalidLeadByte(lead) => write!(f, "Invalid lead byte {lead:#04x}"),
+            FerionError::LengthOverflow(len) => write!(f, "Length {len} is too long for the field"),
+            FerionError::InvalidUtf8(err) => write!(f, "Invalid UTF-8: {err}"),
+            FerionError::UnexpectedType { expected, found } => {
+                write!(f, "Expected {expected}, found {found:?}")
+            }
+            FerionError::OutOfRange(target) => write!(f, "Value is out of range for {target}"),
+            FerionError::NegativeOutOfRange(value) => {
+                write!(f, "Negative integer {value} is below the smallest i64")
+            }
+            FerionError::TrailingData(len) => write!(f, "{len} bytes of extra data after value"),
+            FerionError::RowCount(rows) => {
+                write!(f, "Table row count {rows} is larger than its content")
+            }
+            FerionError::DuplicateKey(key) => {
+                write!(f, "Duplicate key {}", String::from_utf8_lossy(key))
+            }
+            FerionError::InvalidKey(err) => write!(f, "Invalid key: {err}"),
+            FerionError::KeyMismatch(err) => write!(f, "{err}"),
+            FerionError::Io(err) => write!(f, "IO error: {err}"),
+            FerionError::External(err) => write!(f, "{err}"),
+            FerionError::Invalid(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl Error for FerionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FerionError::InvalidUtf8(err) => Some(err),
+            FerionError::InvalidKey(err) => Some(err),
+            FerionError::KeyMismatch(err) => Some(err),
+            FerionError::Io(err) => Some(err),
+            FerionError::External(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+// Boxed errors keep their type when they already are one, as do IO and
+// UTF-8 errors so they stay available as the source
+impl From<Box<dyn Error>> for FerionError {
+    fn from(err: Box<dyn Error>) -> Self {
+        let err = match err.downcast::<FerionError>() {
+            Ok(err) => return *err,
+            Err(err) => err,
+        };
+        let err = match err.downcast::<std::io::Error>() {
+            Ok(err) => return FerionError::Io(*err),
+            Err(err) => err,
+        };
+        match err.downcast::<Utf8Error>() {
+            Ok(err) => FerionError::InvalidUtf8(*err),
+            Err(err) => FerionError::Invalid(err.to_string()),
+        }
+    }
+}
+
+impl From<Utf8Error> for FerionError {
+    fn from(err: Utf8Error) -> Self {
+        FerionError::InvalidUtf8(err)
+    }
+}
+
+impl From<InvalidKey> for FerionError {
+    fn from(err: InvalidKey) -> Self {
+        FerionError::InvalidKey(err)
+    }
+}
+
+impl From<KeyMismatch> for FerionError {
+    fn from(err: KeyMismatch) -> Self {
+        FerionError::KeyMismatch(err)
+    }
+}
+
+impl From<std::io::Error> for FerionError {
+    fn from(err: std::io::Error) -> Self {
+        FerionError::Io(err)
+    }
+}
+
+#[cfg(any(feature = "json", feature = "avro"))]
+impl From<serde_json::Error> for FerionError {
+    fn from(err: serde_json::Error) -> Self {
+        FerionError::External(Box::new(err))
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for FerionError {
+    fn from(err: parquet::errors::ParquetError) -> Self {
+        FerionError::External(Box::new(err))
+    }
+}
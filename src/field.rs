@@ -1,7 +1,11 @@
-use crate::{bytes_to_int, get_header, int_to_bytes, needed_bytes_usize, types::*, Result};
-use chrono::{DateTime, Datelike, Timelike, Utc};
+use crate::{
+    bytes_to_int, error::Result, get_header, int_to_bytes, needed_bytes_usize, types::*,
+    FerionError, Precision, RionDateTime, RionFieldRef, YearRange,
+};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Timelike, Utc};
 use core::str;
-use std::{borrow::Cow, error::Error};
+use std::{borrow::Cow, ops::Range};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ShortField<'a> {
@@ -10,15 +14,22 @@ pub struct ShortField<'a> {
 }
 
 impl<'a> ShortField<'a> {
+    /// # Panics
+    ///
+    /// If `data` is longer than 15 bytes, see [`ShortField::try_new`]
     pub fn new(field_type: ShortRionType, data: &'a [u8]) -> Self {
-        let data_len = data.len() as u8;
-        if data_len > 15 {
-            panic!("Data too large for short field");
+        Self::try_new(field_type, data).expect("Data too large for short field")
+    }
+
+    /// Like [`ShortField::new`], failing if `data` is longer than 15 bytes
+    pub fn try_new(field_type: ShortRionType, data: &'a [u8]) -> Result<Self> {
+        if data.len() > 15 {
+            return Err(FerionError::LengthOverflow(data.len()));
         }
-        ShortField {
+        Ok(ShortField {
             field_type,
             data: data.into(),
-        }
+        })
     }
 
     pub fn null(field_type: ShortRionType) -> Self {
@@ -35,11 +46,11 @@ impl<'a> ShortField<'a> {
         let (lead_byte, data, input) = get_header(input)?;
         let field_type = lead_byte.field_type();
         let RionFieldType::Short(field_type) = field_type else {
-            return Err("Field type is not short".into());
+            return Err(FerionError::unexpected("a short field", field_type));
         };
         let data_len = data.len();
         if data_len > 15 {
-            return Err("Data length too large for short field".into());
+            return Err(FerionError::LengthOverflow(data_len));
         }
         Ok((
             ShortField {
@@ -77,30 +88,49 @@ impl<'a> ShortField<'a> {
         Some(u64::from_be_bytes(bytes))
     }
 
+    /// `None` unless the value fits in an i64, see [`as_i128`](Self::as_i128)
     pub fn as_neg_int(&self) -> Option<i64> {
         if self.data.len() > 8 || self.field_type != ShortRionType::Int64Negative {
             return None;
         }
         let mut bytes = [0; 8];
         bytes[8 - self.data.len()..].copy_from_slice(&self.data);
-        Some(-(i64::from_be_bytes(bytes) + 1))
+        // Stored as -(value + 1), which for i64::MIN is i64::MAX
+        let magnitude = i64::try_from(u64::from_be_bytes(bytes)).ok()?;
+        Some(-magnitude - 1)
+    }
+
+    /// The value of either integer type, including those past 64 bits
+    pub fn as_i128(&self) -> Option<i128> {
+        // 15 bytes at most, so the magnitude always fits
+        let magnitude = self.data.iter().fold(0, |acc, &b| acc << 8 | b as i128);
+        match self.field_type {
+            ShortRionType::Int64Positive => Some(magnitude),
+            ShortRionType::Int64Negative => Some(-magnitude - 1),
+            _ => None,
+        }
     }
 
+    // Floats may have their trailing zero bytes trimmed, 1 to 4 bytes hold a
+    // f32 and 5 to 8 bytes a f64
     pub fn as_f32(&self) -> Option<f32> {
-        if self.data.len() > 4 || self.field_type != ShortRionType::Float {
+        if !(1..=4).contains(&self.data.len()) || self.field_type != ShortRionType::Float {
             return None;
         }
         let mut bytes = [0; 4];
-        bytes[4 - self.data.len()..].copy_from_slice(&self.data);
+        bytes[..self.data.len()].copy_from_slice(&self.data);
         Some(f32::from_be_bytes(bytes))
     }
 
     pub fn as_f64(&self) -> Option<f64> {
+        if self.data.len() <= 4 {
+            return self.as_f32().map(f64::from);
+        }
         if self.data.len() > 8 || self.field_type != ShortRionType::Float {
             return None;
         }
         let mut bytes = [0; 8];
-        bytes[8 - self.data.len()..].copy_from_slice(&self.data);
+        bytes[..self.data.len()].copy_from_slice(&self.data);
         Some(f64::from_be_bytes(bytes))
     }
 
@@ -117,14 +147,24 @@ pub struct NormalField<'a> {
 }
 
 impl<'a> NormalField<'a> {
+    /// # Panics
+    ///
+    /// If the length of `data` takes more than 15 bytes, see
+    /// [`NormalField::try_new`]
     pub fn new(field_type: NormalRionType, data: &'a [u8]) -> Self {
+        Self::try_new(field_type, data).expect("Data too large for normal field")
+    }
+
+    /// Like [`NormalField::new`], failing if the length of `data` takes more
+    /// than 15 bytes
+    pub fn try_new(field_type: NormalRionType, data: &'a [u8]) -> Result<Self> {
         if needed_bytes_usize(data.len()) > 15 {
-            panic!("Data too large for normal field");
+            return Err(FerionError::LengthOverflow(data.len()));
         }
-        NormalField {
+        Ok(NormalField {
             field_type,
             data: data.into(),
-        }
+        })
     }
 
     pub fn null(field_type: NormalRionType) -> Self {
@@ -140,20 +180,16 @@ impl<'a> NormalField<'a> {
         field_type: NormalRionType,
     ) -> Result<(Self, &'a [u8])> {
         match length_length {
-            16.. => return Err("Length too large for normal field".into()),
-            l if l > input.len() => return Err("Input too short for length field".into()),
+            16.. => return Err(FerionError::LengthOverflow(length_length)),
+            l if l > input.len() => return Err(FerionError::Truncated(l - input.len())),
             0 => return Ok((NormalField::null(field_type), input)),
             _ => {}
         }
         let data_len = bytes_to_int(&input[..length_length])? as usize;
+        let input = &input[length_length..];
         if data_len > input.len() {
-            return Err(format!(
-                "Input too short for data field ({}), expected {data_len}",
-                input.len()
-            )
-            .into());
+            return Err(FerionError::Truncated(data_len - input.len()));
         }
-        let input = &input[length_length..];
         let data = (&input[..data_len]).into();
         Ok((NormalField { field_type, data }, &input[data_len..]))
     }
@@ -161,7 +197,7 @@ impl<'a> NormalField<'a> {
     pub fn extend(&self, data: &mut impl std::io::Write) -> Result<()> {
         let length_length = needed_bytes_usize(self.data.len());
         if length_length > 15 {
-            return Err("Data length too large for normal field".into());
+            return Err(FerionError::LengthOverflow(self.data.len()));
         }
         data.write_all(&[self.field_type.to_byte() << 4 | length_length as u8])?;
         // lead_byte.length() == bytes needed to represent d_len
@@ -195,10 +231,36 @@ pub enum RionField<'a> {
     Tiny(LeadByte), // Has field type and 4 bits of data
     Short(ShortField<'a>),
     Normal(NormalField<'a>), // Short encoding also included
-                             // TODO Extended
+    /// A field of an extended type, kept as is so it re-encodes byte for byte
+    ///
+    /// Extended fields are framed like normal ones: the lead byte holds the
+    /// length of the length, and the content starts with the type id.
+    Extended {
+        type_id: u8,
+        data: Cow<'a, [u8]>,
+    },
 }
 
 impl<'a> RionField<'a> {
+    /// Type id of the extended field written before a container to give its
+    /// number of entries, see [`Serializer::count_hints`](crate::Serializer::count_hints)
+    pub const COUNT_HINT: u8 = 0x01;
+
+    /// Type id of the extended field holding a date time whose year doesn't
+    /// fit a UTCDateTime field: the year as a 4 byte signed integer followed by
+    /// the rest of the UTCDateTime data, see [`YearRange`]
+    pub const WIDE_DATE_TIME: u8 = 0x02;
+
+    /// Type id of the extended field holding a value encrypted by
+    /// [`encrypt`](crate::encrypt), its data is the ciphertext of the encoded
+    /// value
+    pub const ENCRYPTED: u8 = 0x03;
+
+    /// Type id of the extended field written after a table's row count to
+    /// hold its [`TableStats`](crate::TableStats), see
+    /// [`RionTable::encode_with_stats`](crate::RionTable::encode_with_stats)
+    pub const TABLE_STATS: u8 = 0x04;
+
     pub fn expect<T: From<Self>>(self) -> T {
         self.into()
     }
@@ -221,6 +283,29 @@ impl<'a> RionField<'a> {
         Self::key(key.as_bytes())
     }
 
+    /// A UTF8 field holding `value`, failing with
+    /// [`FerionError::LengthOverflow`] if its length can't be encoded
+    pub fn try_from_str(value: &'a str) -> Result<Self> {
+        let value_len = value.len();
+        Ok(match value_len {
+            0 => RionField::Normal(NormalField {
+                field_type: NormalRionType::UTF8,
+                data: (&[]).into(),
+            }),
+            1..=15 => RionField::Short(ShortField {
+                field_type: ShortRionType::UTF8,
+                data: value.as_bytes().into(),
+            }),
+            _ if needed_bytes_usize(value_len) > 15 => {
+                return Err(FerionError::LengthOverflow(value_len))
+            }
+            _ => RionField::Normal(NormalField {
+                field_type: NormalRionType::UTF8,
+                data: value.as_bytes().into(),
+            }),
+        })
+    }
+
     pub fn bytes(data: &'a [u8]) -> Self {
         RionField::Normal(NormalField {
             field_type: NormalRionType::Bytes,
@@ -248,26 +333,24 @@ impl<'a> RionField<'a> {
         value.into()
     }
 
+    /// A UTCDateTime field holding `dt` up to `precision`, dropping the rest
+    ///
+    /// Converting a `DateTime<Utc>` picks the shortest precision that loses
+    /// nothing, this allows truncating on purpose, e.g. for privacy. Fails
+    /// for years a UTCDateTime field can't hold.
+    #[cfg(feature = "chrono")]
+    pub fn datetime_with_precision(dt: DateTime<Utc>, precision: Precision) -> Result<Self> {
+        RionDateTime::new(dt, precision).try_into()
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &'a str) -> Self {
         value.into()
     }
 
     pub fn parse(data: &'a [u8]) -> Result<(RionField<'a>, &'a [u8])> {
-        let (lead, length, mut rest) = get_header(data)?;
-        let parsed = match lead.field_type() {
-            RionFieldType::Short(short) => ShortField::new(short, length).into(),
-            RionFieldType::Normal(normal) => {
-                // let (normal, rest) = NormalField::parse(rest, length, normal)?;
-                // (RionField::Normal(normal), rest)
-                let length = bytes_to_int(length)? as usize;
-                let field = NormalField::new(normal, &rest[..length]);
-                rest = &rest[length..];
-                field.into()
-            }
-            RionFieldType::Tiny(lead) => RionField::Tiny(lead),
-            RionFieldType::Extended => todo!(),
-        };
-        Ok((parsed, rest))
+        let (field, rest) = RionFieldRef::parse(data)?;
+        Ok((field.into(), rest))
     }
 
     pub fn encode(&self, data: &mut impl std::io::Write) -> Result<()> {
@@ -281,6 +364,17 @@ impl<'a> RionField<'a> {
             RionField::Normal(normal) => {
                 normal.extend(data)?;
             }
+            RionField::Extended {
+                type_id,
+                data: content,
+            } => {
+                let length = 1 + content.len();
+                let length_length = needed_bytes_usize(length);
+                data.write_all(&[RionFieldType::Extended.to_byte() << 4 | length_length as u8])?;
+                int_to_bytes(&(length as u64), data)?;
+                data.write_all(&[*type_id])?;
+                data.write_all(content)?;
+            }
         }
         Ok(())
     }
@@ -288,7 +382,7 @@ impl<'a> RionField<'a> {
     pub fn from_slice(buf: &'a [u8]) -> Result<Self> {
         let (field, rest) = Self::parse(buf)?;
         if !rest.is_empty() {
-            return Err("Extra data after field".into());
+            return Err(FerionError::TrailingData(rest.len()));
         }
         Ok(field)
     }
@@ -306,6 +400,7 @@ impl<'a> RionField<'a> {
             RionField::Tiny(lead) => lead.is_null(),
             RionField::Short(short) => short.is_null(),
             RionField::Normal(normal) => normal.is_null(),
+            RionField::Extended { .. } => false,
         }
     }
 
@@ -321,10 +416,66 @@ impl<'a> RionField<'a> {
         match self {
             RionField::Short(short) => short.as_bytes(),
             RionField::Normal(normal) => normal.as_bytes(),
+            RionField::Extended { data, .. } => data,
             _ => &[],
         }
     }
 
+    /// The value of an Int64Positive field that fits in an i64, or an
+    /// Int64Negative field
+    pub fn as_i64(&self) -> Option<i64> {
+        let RionField::Short(short) = self else {
+            return None;
+        };
+        match short.field_type {
+            ShortRionType::Int64Positive => short.as_pos_int()?.try_into().ok(),
+            ShortRionType::Int64Negative => short.as_neg_int(),
+            _ => None,
+        }
+    }
+
+    /// The value of any integer field, including Int64Negative fields below
+    /// `i64::MIN` and those past 64 bits
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            RionField::Short(short) => short.as_i128(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            RionField::Short(short) => short.as_pos_int(),
+            _ => None,
+        }
+    }
+
+    /// The value of a Float field of any width
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            RionField::Short(short) => short.as_f64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            RionField::Tiny(lead) => lead.as_bool(),
+            _ => None,
+        }
+    }
+
+    // A null of the same type
+    fn into_null(self) -> Self {
+        match self {
+            RionField::Tiny(lead) => RionField::Tiny(LeadByte(lead.byte() & 0xF0)),
+            RionField::Short(short) => RionField::Short(ShortField::null(short.field_type)),
+            RionField::Normal(normal) => RionField::Normal(NormalField::null(normal.field_type)),
+            // Extended fields have no null of their own
+            RionField::Extended { .. } => RionField::Tiny(LeadByte::NULL),
+        }
+    }
+
     // Bytes needed to encode this field
     pub fn needed_bytes(&self) -> usize {
         1 + match self {
@@ -333,15 +484,49 @@ impl<'a> RionField<'a> {
                 let data_len = normal.data.len();
                 data_len + needed_bytes_usize(data_len)
             }
+            RionField::Extended { data, .. } => {
+                let content_len = 1 + data.len();
+                content_len + needed_bytes_usize(content_len)
+            }
             _ => 0,
         }
     }
 
+    /// Copy any borrowed data so the field no longer borrows its input
+    pub fn into_owned(self) -> RionField<'static> {
+        match self {
+            RionField::Tiny(lead) => RionField::Tiny(lead),
+            RionField::Short(short) => RionField::Short(ShortField {
+                field_type: short.field_type,
+                data: Cow::Owned(short.data.into_owned()),
+            }),
+            RionField::Normal(normal) => RionField::Normal(NormalField {
+                field_type: normal.field_type,
+                data: Cow::Owned(normal.data.into_owned()),
+            }),
+            RionField::Extended { type_id, data } => RionField::Extended {
+                type_id,
+                data: Cow::Owned(data.into_owned()),
+            },
+        }
+    }
+
     pub fn to_data(self) -> Option<Cow<'a, [u8]>> {
         // pub fn to_data(self) -> Option<&'a [u8]> {
         match self {
             RionField::Short(short) => Some(short.data),
             RionField::Normal(normal) => Some(normal.data),
+            RionField::Extended { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// The data of a Bytes or UTF8 field, moved out without copying when the
+    /// field already owns it
+    pub fn into_bytes(self) -> Option<Vec<u8>> {
+        match self.field_type() {
+            RionFieldType::Normal(NormalRionType::Bytes | NormalRionType::UTF8)
+            | RionFieldType::Short(ShortRionType::UTF8) => self.to_data().map(Cow::into_owned),
             _ => None,
         }
     }
@@ -360,11 +545,45 @@ impl<'a> RionField<'a> {
         }
     }
 
+    /// Borrow this field as a [`RionFieldRef`]
+    pub fn as_field_ref(&self) -> RionFieldRef<'_> {
+        match self {
+            RionField::Tiny(lead) => RionFieldRef::Tiny(*lead),
+            RionField::Short(short) => RionFieldRef::Short(short.field_type, &short.data),
+            RionField::Normal(normal) => RionFieldRef::Normal(normal.field_type, &normal.data),
+            RionField::Extended { type_id, data } => RionFieldRef::Extended {
+                type_id: *type_id,
+                data,
+            },
+        }
+    }
+
     pub fn field_type(&self) -> RionFieldType {
         match self {
             RionField::Tiny(lead) => RionFieldType::Tiny(*lead),
             RionField::Short(short) => RionFieldType::Short(short.field_type),
             RionField::Normal(normal) => RionFieldType::Normal(normal.field_type),
+            RionField::Extended { .. } => RionFieldType::Extended,
+        }
+    }
+}
+
+impl<'a> From<RionFieldRef<'a>> for RionField<'a> {
+    fn from(value: RionFieldRef<'a>) -> Self {
+        match value {
+            RionFieldRef::Tiny(lead) => RionField::Tiny(lead),
+            RionFieldRef::Short(field_type, data) => RionField::Short(ShortField {
+                field_type,
+                data: data.into(),
+            }),
+            RionFieldRef::Normal(field_type, data) => RionField::Normal(NormalField {
+                field_type,
+                data: data.into(),
+            }),
+            RionFieldRef::Extended { type_id, data } => RionField::Extended {
+                type_id,
+                data: data.into(),
+            },
         }
     }
 }
@@ -381,70 +600,153 @@ impl<'a> From<ShortField<'a>> for RionField<'a> {
     }
 }
 
+/// The bytes of an int or float field, kept on the stack so a field can be
+/// encoded into a slice without allocating
+pub(crate) struct InlineNumber {
+    field_type: ShortRionType,
+    bytes: [u8; 16],
+    range: Range<usize>,
+}
+
+impl InlineNumber {
+    pub(crate) fn signed(value: i128) -> Self {
+        // Negative values are stored as their magnitude minus one
+        match value {
+            ..0 => Self::int(ShortRionType::Int64Negative, (-(value + 1)) as u128),
+            _ => Self::int(ShortRionType::Int64Positive, value as u128),
+        }
+    }
+
+    pub(crate) fn unsigned(value: u128) -> Self {
+        Self::int(ShortRionType::Int64Positive, value)
+    }
+
+    fn int(field_type: ShortRionType, magnitude: u128) -> Self {
+        // Keep at least one byte, a zero length field is null
+        let zeros = (magnitude.leading_zeros() / 8).min(15);
+        InlineNumber {
+            field_type,
+            bytes: magnitude.to_be_bytes(),
+            range: zeros as usize..16,
+        }
+    }
+
+    // Trailing zero bytes of the mantissa are trimmed, keeping enough bytes
+    // that the width can still be told apart (1-4 for f32, 5-8 for f64)
+    pub(crate) fn f32(value: f32) -> Self {
+        let zeros = (value.to_bits().trailing_zeros() / 8).min(3);
+        Self::float(&value.to_be_bytes(), zeros as usize)
+    }
+
+    pub(crate) fn f64(value: f64) -> Self {
+        let zeros = (value.to_bits().trailing_zeros() / 8).min(3);
+        Self::float(&value.to_be_bytes(), zeros as usize)
+    }
+
+    fn float(value: &[u8], zeros: usize) -> Self {
+        let mut bytes = [0; 16];
+        bytes[..value.len()].copy_from_slice(value);
+        InlineNumber {
+            field_type: ShortRionType::Float,
+            bytes,
+            range: 0..value.len() - zeros,
+        }
+    }
+
+    /// Fails for ints whose magnitude needs all 16 bytes
+    pub(crate) fn field(&self) -> Result<RionField<'_>> {
+        let data = &self.bytes[self.range.clone()];
+        ShortField::try_new(self.field_type, data).map(RionField::Short)
+    }
+
+    // Ints up to 64 bits and floats always fit a short field
+    fn into_field<'a>(self) -> RionField<'a> {
+        self.field()
+            .expect("Data too large for short field")
+            .into_owned()
+    }
+}
+
 impl From<i64> for RionField<'_> {
     fn from(value: i64) -> Self {
-        let field_type = if value < 0 {
-            ShortRionType::Int64Negative
-        } else {
-            ShortRionType::Int64Positive
-        };
-        let value = if value < 0 { -(value + 1) } else { value };
-        let bytes = value.to_be_bytes();
-        let zeros = value.leading_zeros() / 8;
-        RionField::Short(ShortField {
-            field_type,
-            data: bytes[zeros as usize..].to_vec().into(),
-        })
+        InlineNumber::signed(value.into()).into_field()
     }
 }
 impl From<u64> for RionField<'_> {
     fn from(value: u64) -> Self {
-        let bytes = value.to_be_bytes();
-        let zeros = value.leading_zeros() / 8;
-        RionField::Short(ShortField {
-            field_type: ShortRionType::Int64Positive,
-            data: bytes[zeros as usize..].to_vec().into(),
+        InlineNumber::unsigned(value.into()).into_field()
+    }
+}
+
+/// Fails if the magnitude needs more than the 15 bytes an integer field holds
+impl TryFrom<i128> for RionField<'_> {
+    type Error = FerionError;
+    fn try_from(value: i128) -> Result<Self> {
+        Ok(InlineNumber::signed(value).field()?.into_owned())
+    }
+}
+impl TryFrom<u128> for RionField<'_> {
+    type Error = FerionError;
+    fn try_from(value: u128) -> Result<Self> {
+        Ok(InlineNumber::unsigned(value).field()?.into_owned())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<DateTime<Utc>> for RionField<'_> {
+    type Error = FerionError;
+    fn try_from(dt: DateTime<Utc>) -> Result<Self> {
+        RionDateTime::from(dt).try_into()
+    }
+}
+
+impl TryFrom<RionDateTime> for RionField<'_> {
+    type Error = FerionError;
+    fn try_from(value: RionDateTime) -> Result<Self> {
+        value.to_field(YearRange::Error)
+    }
+}
+
+// Calendar dates carry no time of day, so only the date components are written
+#[cfg(feature = "chrono")]
+impl TryFrom<NaiveDate> for RionField<'_> {
+    type Error = FerionError;
+    fn try_from(date: NaiveDate) -> Result<Self> {
+        date.and_time(chrono::NaiveTime::MIN).and_utc().try_into()
+    }
+}
+
+// Naive date times are written as UTC, without any sub-second data
+#[cfg(feature = "chrono")]
+impl TryFrom<NaiveDateTime> for RionField<'_> {
+    type Error = FerionError;
+    fn try_from(dt: NaiveDateTime) -> Result<Self> {
+        dt.with_nanosecond(0).unwrap_or(dt).and_utc().try_into()
+    }
+}
+
+impl<'a> From<&'a [u8]> for RionField<'a> {
+    fn from(value: &'a [u8]) -> Self {
+        RionField::bytes(value)
+    }
+}
+
+impl From<Vec<u8>> for RionField<'static> {
+    fn from(value: Vec<u8>) -> Self {
+        RionField::Normal(NormalField {
+            field_type: NormalRionType::Bytes,
+            data: value.into(),
         })
     }
 }
 
-impl From<DateTime<Utc>> for RionField<'_> {
-    fn from(dt: DateTime<Utc>) -> Self {
-        let year = dt.year();
-        if year > 0xFFFF {
-            println!("Year is too large, truncating to 2^16-1");
-        }
-        let mut data = Vec::with_capacity(11);
-        let components = [
-            dt.month() as u8,
-            dt.day() as u8,
-            dt.hour() as u8,
-            dt.minute() as u8,
-            dt.second() as u8,
-        ];
-        // Find the last non-zero component
-        let last_non_zero = components.iter().rposition(|&x| x != 0).unwrap_or(0);
-        // Add all components up to and including the last non-zero one
-        data.extend_from_slice(&components[..=last_non_zero]);
-        let nanos = dt.nanosecond();
-        if nanos > 0 {
-            if nanos % 1_000_000 == 0 {
-                // Milliseconds (2 bytes)
-                data.extend_from_slice(&((nanos / 1_000_000) as u16).to_be_bytes());
-            } else if nanos % 1_000 == 0 {
-                // Microseconds (3 bytes)
-                let micros = nanos / 1_000;
-                data.extend_from_slice(&[(micros >> 16) as u8, (micros >> 8) as u8, micros as u8]);
-            } else {
-                // Nanoseconds (4 bytes)
-                data.extend_from_slice(&nanos.to_be_bytes());
-            }
+// None is a null of the type `T::default()` would be encoded as
+impl<'a, T: Into<RionField<'a>> + Default> From<Option<T>> for RionField<'a> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => T::default().into().into_null(),
         }
-        RionField::Short(ShortField {
-            // lead_byte: LeadByte::from_type(RionFieldType::UTCDateTime, 11),
-            field_type: ShortRionType::UTCDateTime,
-            data: data.into(),
-        })
     }
 }
 
@@ -455,30 +757,10 @@ impl From<bool> for RionField<'_> {
     }
 }
 
+// The length of a `str` takes at most 8 bytes, so this can't fail
 impl<'a> From<&'a str> for RionField<'a> {
     fn from(value: &'a str) -> Self {
-        let value_len = value.len();
-        match value_len {
-            0 => RionField::Normal(NormalField {
-                field_type: NormalRionType::UTF8,
-                data: (&[]).into(),
-            }),
-            1..=15 => RionField::Short(ShortField {
-                field_type: ShortRionType::UTF8,
-                data: value.as_bytes().into(),
-            }),
-            _ => {
-                // let data = value.as_bytes().to_vec();
-                let num_bytes = needed_bytes_usize(value_len);
-                if num_bytes > 15 {
-                    println!("Warning: UTF-8 length field is too long, truncating to 15 bytes");
-                } // TODO handle this
-                RionField::Normal(NormalField {
-                    field_type: NormalRionType::UTF8,
-                    data: value.as_bytes().into(),
-                })
-            }
-        }
+        RionField::try_from_str(value).expect("str length fits in 8 bytes")
     }
 }
 
@@ -490,149 +772,155 @@ impl From<String> for RionField<'static> {
                 field_type: ShortRionType::UTF8,
                 data: value.into_bytes().into(),
             }),
-            _ => {
-                let num_bytes = needed_bytes_usize(value_len);
-                if num_bytes > 15 {
-                    println!("Warning: UTF-8 length field is too long, truncating to 15 bytes");
-                } // TODO handle this
-                RionField::Normal(NormalField {
-                    field_type: NormalRionType::UTF8,
-                    data: value.into_bytes().into(),
-                })
-            }
+            _ => RionField::Normal(NormalField {
+                field_type: NormalRionType::UTF8,
+                data: value.into_bytes().into(),
+            }),
         }
     }
 }
 
 impl From<f32> for RionField<'_> {
     fn from(value: f32) -> Self {
-        let bytes = value.to_be_bytes();
-        let zeros = value.to_bits().leading_zeros() / 8;
-        RionField::Short(ShortField {
-            field_type: ShortRionType::Float,
-            data: bytes[zeros as usize..].to_vec().into(),
-        })
+        InlineNumber::f32(value).into_field()
     }
 }
 
 impl From<f64> for RionField<'_> {
     fn from(value: f64) -> Self {
-        let bytes = value.to_be_bytes();
-        let zeros = value.to_bits().leading_zeros() / 8;
-        RionField::Short(ShortField {
-            field_type: ShortRionType::Float,
-            data: bytes[zeros as usize..].to_vec().into(),
-        })
+        InlineNumber::f64(value).into_field()
     }
 }
 
 impl TryFrom<RionField<'_>> for i64 {
-    type Error = Box<dyn std::error::Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> Result<Self> {
+        let field_type = value.field_type();
         let out = match value {
             RionField::Short(short) => match short.field_type {
-                ShortRionType::Int64Positive => short.as_pos_int().unwrap().try_into()?,
-                ShortRionType::Int64Negative => short.as_neg_int().unwrap(),
-                _ => return Err("Field is not an integer".into()),
+                ShortRionType::Int64Positive => short
+                    .as_pos_int()
+                    .ok_or(FerionError::LengthOverflow(short.as_bytes().len()))?
+                    .try_into()
+                    .map_err(|_| FerionError::OutOfRange("i64"))?,
+                ShortRionType::Int64Negative if short.as_bytes().len() > 8 => {
+                    return Err(FerionError::LengthOverflow(short.as_bytes().len()))
+                }
+                ShortRionType::Int64Negative => match short.as_neg_int() {
+                    Some(value) => value,
+                    None => {
+                        let value = short.as_i128().expect("checked to be an integer");
+                        return Err(FerionError::NegativeOutOfRange(value));
+                    }
+                },
+                _ => return Err(FerionError::unexpected("an integer", field_type)),
             },
-            _ => return Err("Field is not an integer".into()),
+            _ => return Err(FerionError::unexpected("an integer", field_type)),
         };
         Ok(out)
     }
 }
+impl TryFrom<RionField<'_>> for i128 {
+    type Error = FerionError;
+    fn try_from(value: RionField<'_>) -> Result<Self> {
+        value
+            .as_i128()
+            .ok_or_else(|| FerionError::unexpected("an integer", value.field_type()))
+    }
+}
 impl TryFrom<RionField<'_>> for u64 {
-    type Error = Box<dyn std::error::Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> Result<Self> {
+        let field_type = value.field_type();
         match value {
-            RionField::Short(short) => short
-                .as_pos_int()
-                .ok_or_else(|| format!("Field is not a positive integer: {:?}", short).into()),
-            _ => Err("Field is not a positive integer".into()),
+            RionField::Short(short) => short.as_pos_int(),
+            _ => None,
         }
+        .ok_or(FerionError::unexpected("a positive integer", field_type))
     }
 }
 impl TryFrom<RionField<'_>> for u32 {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> std::result::Result<Self, Self::Error> {
         let value = u64::try_from(value)?;
         if value > u32::MAX as u64 {
-            return Err(format!("Value ({value:?}) is too large for u32").into());
+            return Err(FerionError::OutOfRange("u32"));
         }
         Ok(value as u32)
     }
 }
 impl TryFrom<RionField<'_>> for u16 {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> std::result::Result<Self, Self::Error> {
         let value = u64::try_from(value)?;
         if value > u16::MAX as u64 {
-            return Err(format!("Value ({value:?}) is too large for u16").into());
+            return Err(FerionError::OutOfRange("u16"));
         }
         Ok(value as u16)
     }
 }
 impl TryFrom<RionField<'_>> for u8 {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> std::result::Result<Self, Self::Error> {
         let value = u64::try_from(value)?;
         if value > u8::MAX as u64 {
-            return Err(format!("Value ({value:?}) is too large for u8").into());
+            return Err(FerionError::OutOfRange("u8"));
         }
         Ok(value as u8)
     }
 }
 impl TryFrom<RionField<'_>> for i32 {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> std::result::Result<Self, Self::Error> {
         let value = i64::try_from(value)?;
         if value < i32::MIN as i64 || value > i32::MAX as i64 {
-            return Err(format!("Value ({value:?}) is too large for i32").into());
+            return Err(FerionError::OutOfRange("i32"));
         }
         Ok(value as i32)
     }
 }
 impl TryFrom<RionField<'_>> for i16 {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> std::result::Result<Self, Self::Error> {
         let value = i64::try_from(value)?;
         if value < i16::MIN as i64 || value > i16::MAX as i64 {
-            return Err(format!("Value ({value:?}) is too large for i16").into());
+            return Err(FerionError::OutOfRange("i16"));
         }
         Ok(value as i16)
     }
 }
 impl TryFrom<RionField<'_>> for i8 {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> std::result::Result<Self, Self::Error> {
         let value = i64::try_from(value)?;
         if value < i8::MIN as i64 || value > i8::MAX as i64 {
-            return Err(format!("Value ({value:?}) is too large for i8").into());
+            return Err(FerionError::OutOfRange("i8"));
         }
         Ok(value as i8)
     }
 }
 
 impl TryFrom<RionField<'_>> for f32 {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> Result<Self> {
+        let field_type = value.field_type();
         match value {
-            RionField::Short(short) => short
-                .as_f32()
-                .ok_or_else(|| format!("Field is not a f32: {:?}", short).into()),
-            _ => Err("Field is not a f32".into()),
+            RionField::Short(short) => short.as_f32(),
+            _ => None,
         }
+        .ok_or(FerionError::unexpected("a f32", field_type))
     }
 }
 
 impl TryFrom<RionField<'_>> for f64 {
-    type Error = Box<dyn std::error::Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> Result<Self> {
+        let field_type = value.field_type();
         match value {
-            RionField::Short(short) => short
-                .as_f64()
-                .ok_or_else(|| format!("Field is not a f64: {:?}", short).into()),
-            _ => Err("Field is not a f64".into()),
+            RionField::Short(short) => short.as_f64(),
+            _ => None,
         }
+        .ok_or(FerionError::unexpected("a f64", field_type))
     }
 }
 
@@ -659,50 +947,194 @@ impl TryFrom<RionField<'_>> for f64 {
 // }
 
 impl TryFrom<RionField<'_>> for String {
-    type Error = Box<dyn std::error::Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> Result<Self> {
-        match value {
-            RionField::Short(short) => {
-                let str = short
-                    .as_str()
-                    .ok_or_else(|| format!("Field is not a string: {:?}", short))?;
-                Ok(str.to_string())
+        match value.field_type() {
+            RionFieldType::Short(ShortRionType::UTF8 | ShortRionType::Key)
+            | RionFieldType::Normal(NormalRionType::UTF8 | NormalRionType::Key) => {
+                Ok(str::from_utf8(value.as_bytes())?.to_string())
             }
-            RionField::Normal(normal) => {
-                let str = normal
-                    .as_str()
-                    .ok_or_else(|| format!("Field is not a string: {:?}", normal))?;
-                Ok(str.to_string())
-            }
-            _ => Err("Field is not a string".into()),
+            field_type => Err(FerionError::unexpected("a string", field_type)),
         }
     }
 }
 
+impl TryFrom<RionField<'_>> for Vec<u8> {
+    type Error = FerionError;
+    fn try_from(value: RionField<'_>) -> Result<Self> {
+        let field_type = value.field_type();
+        value
+            .into_bytes()
+            .ok_or(FerionError::unexpected("bytes or a string", field_type))
+    }
+}
+
 impl TryFrom<RionField<'_>> for char {
-    type Error = Box<dyn std::error::Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> Result<Self> {
         let string: String = value.try_into()?;
         string
             .chars()
             .next()
-            .ok_or_else(|| "String is empty".into())
+            .ok_or_else(|| FerionError::invalid("String is empty"))
     }
 }
 
 impl TryFrom<RionField<'_>> for bool {
-    type Error = Box<dyn std::error::Error>;
+    type Error = FerionError;
     fn try_from(value: RionField<'_>) -> Result<Self> {
         match value {
             RionField::Tiny(lead) => {
                 let value = lead.byte() & 0x0F;
                 if value == 0 {
-                    return Err("Field is null".into());
+                    return Err(FerionError::invalid("Field is null"));
                 }
                 Ok(value == 2)
             }
-            _ => Err("Field is not a boolean".into()),
+            _ => Err(FerionError::unexpected("a boolean", value.field_type())),
+        }
+    }
+}
+
+// Fields carrying only part of a date time read as the start of the period,
+// see `RionDateTime`
+#[cfg(feature = "chrono")]
+impl TryFrom<RionField<'_>> for DateTime<Utc> {
+    type Error = FerionError;
+    fn try_from(value: RionField<'_>) -> Result<Self> {
+        Ok(crate::RionDateTime::try_from(value)?.into())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<RionField<'_>> for NaiveDate {
+    type Error = FerionError;
+    fn try_from(value: RionField<'_>) -> Result<Self> {
+        let dt = DateTime::<Utc>::try_from(value)?;
+        if dt.time() != chrono::NaiveTime::MIN {
+            return Err(FerionError::invalid(format!(
+                "Date time {dt} is not a calendar date"
+            )));
+        }
+        Ok(dt.date_naive())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<RionField<'_>> for NaiveDateTime {
+    type Error = FerionError;
+    fn try_from(value: RionField<'_>) -> Result<Self> {
+        DateTime::<Utc>::try_from(value).map(|dt| dt.naive_utc())
+    }
+}
+
+// Fields of different kinds sort by kind, in this order
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Null,
+    Bool,
+    Number,
+    DateTime,
+    String,
+    Bytes,
+    Key,
+    Array,
+    Object,
+    Table,
+    Extended,
+}
+
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl RionField<'_> {
+    fn kind(&self) -> Kind {
+        if self.is_null() {
+            return Kind::Null;
+        }
+        match self.field_type() {
+            RionFieldType::Tiny(_) => Kind::Bool,
+            RionFieldType::Short(
+                ShortRionType::Int64Positive | ShortRionType::Int64Negative | ShortRionType::Float,
+            ) => Kind::Number,
+            RionFieldType::Short(ShortRionType::UTCDateTime) => Kind::DateTime,
+            RionFieldType::Extended if self.is_wide_date_time() => Kind::DateTime,
+            RionFieldType::Short(ShortRionType::UTF8)
+            | RionFieldType::Normal(NormalRionType::UTF8) => Kind::String,
+            RionFieldType::Short(ShortRionType::Key)
+            | RionFieldType::Normal(NormalRionType::Key) => Kind::Key,
+            RionFieldType::Normal(NormalRionType::Bytes) => Kind::Bytes,
+            RionFieldType::Normal(NormalRionType::Array) => Kind::Array,
+            RionFieldType::Normal(NormalRionType::Object) => Kind::Object,
+            RionFieldType::Normal(NormalRionType::Table) => Kind::Table,
+            RionFieldType::Extended => Kind::Extended,
+        }
+    }
+
+    pub(crate) fn is_wide_date_time(&self) -> bool {
+        matches!(self, RionField::Extended { type_id, .. } if *type_id == Self::WIDE_DATE_TIME)
+    }
+
+    fn number(&self) -> Option<Number> {
+        let RionField::Short(short) = self else {
+            return None;
+        };
+        match short.field_type {
+            ShortRionType::Int64Positive | ShortRionType::Int64Negative => {
+                short.as_i128().map(Number::Int)
+            }
+            ShortRionType::Float => short.as_f64().map(Number::Float),
+            _ => None,
+        }
+    }
+
+    // Compare the values of two fields of the same kind
+    fn cmp_value(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        match self.kind() {
+            Kind::Bool => self.as_bool().cmp(&other.as_bool()),
+            Kind::Number => match (self.number(), other.number()) {
+                (Some(Number::Int(a)), Some(Number::Int(b))) => a.cmp(&b),
+                (Some(Number::Float(a)), Some(Number::Float(b))) => a.total_cmp(&b),
+                (Some(Number::Int(a)), Some(Number::Float(b))) => (a as f64).total_cmp(&b),
+                (Some(Number::Float(a)), Some(Number::Int(b))) => a.total_cmp(&(b as f64)),
+                _ => Ordering::Equal,
+            },
+            Kind::DateTime => {
+                // Compare the instants, not the precisions they were written with
+                let instant = |field: &Self| {
+                    RionDateTime::try_from(field.clone())
+                        .ok()
+                        .map(|dt| dt.with_precision(Precision::Nanosecond))
+                };
+                instant(self).cmp(&instant(other))
+            }
+            _ => self.as_bytes().cmp(other.as_bytes()),
         }
     }
 }
-// TODO Datetime into impl
+
+/// Type-aware ordering: nulls first, then booleans, numbers (ints and floats
+/// compared by value), datetimes, strings, bytes, keys and containers
+///
+/// Fields with equal values but different encodings, e.g. `1` and `1.0`, are
+/// ordered by their encoding so the ordering stays consistent with `Eq`.
+impl Ord for RionField<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.kind()
+            .cmp(&other.kind())
+            .then_with(|| self.cmp_value(other))
+            .then_with(|| {
+                (self.field_type().to_byte(), self.as_bytes())
+                    .cmp(&(other.field_type().to_byte(), other.as_bytes()))
+            })
+    }
+}
+
+impl PartialOrd for RionField<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,123 @@
+//! Borrowed and owned views of a single field
+//!
+//! [`RionField`] keeps its data in a `Cow`, so one type covers fields parsed
+//! from a buffer and fields built from owned values. When the difference
+//! matters the two types below say which one is meant:
+//!
+//! - [`RionFieldRef`] only ever borrows its input and is `Copy`. Its accessors
+//!   return data with the lifetime of the input rather than of the field, so
+//!   a `&str` read from it can outlive the field itself.
+//! - [`RionFieldBuf`] is a [`RionField`] that owns all of its data.
+//!
+//! [`RionFieldRef::to_field`] is free, [`RionFieldRef::to_buf`] copies the
+//! data once, and [`RionField::as_field_ref`] borrows any field as a ref.
+
+use crate::{
+    bytes_to_int,
+    error::Result,
+    get_header,
+    types::{LeadByte, NormalRionType, RionFieldType, ShortRionType},
+    FerionError, RionField,
+};
+
+/// A [`RionField`] that owns its data
+pub type RionFieldBuf = RionField<'static>;
+
+/// A field borrowed from an encoded buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RionFieldRef<'a> {
+    Tiny(LeadByte),
+    Short(ShortRionType, &'a [u8]),
+    Normal(NormalRionType, &'a [u8]),
+    /// The type id and the data following it
+    Extended {
+        type_id: u8,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> RionFieldRef<'a> {
+    /// Parse the field at the start of `data`, returning it and the rest
+    pub fn parse(data: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        let (lead, length, rest) = get_header(data)?;
+        let field_type = lead.field_type();
+        let (content, rest) = match field_type {
+            RionFieldType::Tiny(lead) => return Ok((RionFieldRef::Tiny(lead), rest)),
+            RionFieldType::Short(short) => return Ok((RionFieldRef::Short(short, length), rest)),
+            RionFieldType::Normal(_) | RionFieldType::Extended => {
+                let length = bytes_to_int(length)? as usize;
+                if length > rest.len() {
+                    return Err(FerionError::Truncated(length - rest.len()));
+                }
+                rest.split_at(length)
+            }
+        };
+        let field = match field_type {
+            RionFieldType::Normal(normal) => RionFieldRef::Normal(normal, content),
+            _ => {
+                let Some((&type_id, data)) = content.split_first() else {
+                    return Err(FerionError::invalid("Extended field without a type id"));
+                };
+                RionFieldRef::Extended { type_id, data }
+            }
+        };
+        Ok((field, rest))
+    }
+
+    /// Parse `buf` as exactly one field
+    pub fn from_slice(buf: &'a [u8]) -> Result<Self> {
+        let (field, rest) = Self::parse(buf)?;
+        if !rest.is_empty() {
+            return Err(FerionError::TrailingData(rest.len()));
+        }
+        Ok(field)
+    }
+
+    pub fn field_type(&self) -> RionFieldType {
+        match self {
+            RionFieldRef::Tiny(lead) => RionFieldType::Tiny(*lead),
+            RionFieldRef::Short(short, _) => RionFieldType::Short(*short),
+            RionFieldRef::Normal(normal, _) => RionFieldType::Normal(*normal),
+            RionFieldRef::Extended { .. } => RionFieldType::Extended,
+        }
+    }
+
+    /// The data of the field, borrowed from the input
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match *self {
+            RionFieldRef::Tiny(_) => &[],
+            RionFieldRef::Short(_, data)
+            | RionFieldRef::Normal(_, data)
+            | RionFieldRef::Extended { data, .. } => data,
+        }
+    }
+
+    /// The text of a UTF8 or Key field, borrowed from the input
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            RionFieldRef::Short(ShortRionType::UTF8 | ShortRionType::Key, data)
+            | RionFieldRef::Normal(NormalRionType::UTF8 | NormalRionType::Key, data) => {
+                std::str::from_utf8(data).ok()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        match self {
+            RionFieldRef::Tiny(lead) => lead.is_null(),
+            RionFieldRef::Short(_, data) | RionFieldRef::Normal(_, data) => data.is_empty(),
+            RionFieldRef::Extended { .. } => false,
+        }
+    }
+
+    /// The same field as a [`RionField`] borrowing the same data
+    pub fn to_field(self) -> RionField<'a> {
+        self.into()
+    }
+
+    /// The same field with its data copied
+    pub fn to_buf(self) -> RionFieldBuf {
+        self.to_field().into_owned()
+    }
+}
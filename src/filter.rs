@@ -0,0 +1,501 @@
+//! Re-encoding values with some of their fields dropped or rewritten
+//!
+//! A [`Filter`] copies encoded values from a reader to a writer, omitting,
+//! truncating or redacting the fields its paths match. It works on the encoded
+//! bytes and never decodes a value into a [`RionValue`], subtrees none of the
+//! paths reach are copied as they are. A container's length is written before
+//! its content, so each top-level value is read whole before it is rewritten.
+//!
+//! Paths use the [`Path`] syntax and match object keys and array indices, the
+//! same way [`redact`](crate::redact) does. Tables are copied unchanged. A
+//! [`COUNT_HINT`](RionField::COUNT_HINT) before a container the filter changes
+//! is dropped, since the count may no longer hold.
+
+use std::io::{Read, Write};
+
+use crate::{
+    field::{NormalField, ShortField},
+    frame::read_frame,
+    needed_bytes,
+    path::{Path, Segment},
+    types::{LeadByte, NormalRionType, ShortRionType},
+    FerionError, Redaction, Result, RionField, RionFieldRef, RionValue,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{RionArray, RionObject};
+
+    fn user(name: &str, password: &str) -> RionObject<'static> {
+        let mut user = RionObject::new();
+        user.add_field("name", name.to_string());
+        user.add_field("password", password.to_string());
+        user.add_field("raw_payload", RionField::bytes(&[7; 40]).into_owned());
+        user
+    }
+
+    fn filtered(filter: &Filter, value: &[u8]) -> RionValue {
+        let mut out = Vec::new();
+        filter.rewrite(value, &mut out).unwrap();
+        RionValue::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter::new()
+            .omit("*.password")
+            .unwrap()
+            .truncate("*.raw_payload", 4)
+            .unwrap();
+        let mut doc = RionObject::new();
+        doc.add_field("admin", user("Alice", "hunter2"));
+        doc.add_field("count", 2u64);
+        let value = filtered(&filter, &doc.encode());
+        let admin = value.get("admin").unwrap();
+        assert_eq!(admin.get("password"), None);
+        assert_eq!(admin.get("name").unwrap().as_str(), Some("Alice"));
+        assert_eq!(
+            admin.get("raw_payload"),
+            Some(&RionValue::Bytes(vec![7; 4]))
+        );
+        assert_eq!(value.get("count"), Some(&RionValue::PosInt(2)));
+
+        // Values no path reaches are copied byte for byte
+        let encoded = user("Bo", "pw").encode();
+        let mut out = Vec::new();
+        Filter::new()
+            .omit("users.password")
+            .unwrap()
+            .rewrite(&encoded, &mut out)
+            .unwrap();
+        assert_eq!(out, encoded);
+    }
+
+    #[test]
+    fn test_filter_arrays() {
+        let mut users = RionArray::new();
+        for name in ["Alice", "Bob", "Cy"] {
+            users.add_element(user(name, "pw"));
+        }
+        let filter = Filter::new()
+            .omit("[1]")
+            .unwrap()
+            .rule("*.name", FilterAction::Truncate(2))
+            .unwrap()
+            .rule("*.password", FilterAction::Redact(Redaction::Null))
+            .unwrap();
+        let encoded = users.encode();
+        let RionValue::Array(users) = &filtered(&filter, &encoded) else {
+            panic!("Expected an array");
+        };
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[1].get("name").unwrap().as_str(), Some("Cy"));
+        assert_eq!(users[0].get("name").unwrap().as_str(), Some("Al"));
+        assert_eq!(users[0].get("password"), Some(&RionValue::Null));
+
+        // A count hint is kept only while the count still holds
+        let hinted = [&[0xF1, 0x02, RionField::COUNT_HINT, 3], &encoded[..]].concat();
+        let mut out = Vec::new();
+        filter.rewrite(&hinted, &mut out).unwrap();
+        assert!(RionFieldRef::from_slice(&out).is_ok());
+        out.clear();
+        Filter::new().rewrite(&hinted, &mut out).unwrap();
+        assert_eq!(out, hinted);
+
+        // UTF8 is cut at a character boundary
+        let filter = Filter::new().truncate("", 2).unwrap();
+        assert_eq!(
+            filtered(&filter, &RionValue::from("été").encode_to_vec().unwrap()),
+            "é".into()
+        );
+    }
+
+    #[test]
+    fn test_filter_copy() {
+        let mut stream = Vec::new();
+        for name in ["Alice", "Bob"] {
+            stream.extend(user(name, "pw").encode());
+        }
+        let mut out = Vec::new();
+        let filter = Filter::new().omit("password").unwrap();
+        assert_eq!(filter.copy(&stream[..], &mut out, 1024).unwrap(), 2);
+        let (_, rest) = RionFieldRef::parse(&out).unwrap();
+        let first = RionValue::from_slice(&out[..out.len() - rest.len()]).unwrap();
+        assert_eq!(first.get("password"), None);
+        assert_eq!(
+            RionValue::from_slice(rest)
+                .unwrap()
+                .get("name")
+                .unwrap()
+                .as_str(),
+            Some("Bob")
+        );
+        assert!(filter.copy(&stream[..10], &mut Vec::new(), 1024).is_err());
+    }
+}
+
+/// Copies encoded values, omitting or rewriting the fields matched by its
+/// paths
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    rules: Vec<(Path, FilterAction)>,
+}
+
+/// What a [`Filter`] does with a matched field
+#[derive(Debug, Clone)]
+pub enum FilterAction {
+    /// Drop the field, along with its key in an object
+    Omit,
+    /// Cut Bytes and UTF8 fields to at most this many bytes, UTF8 at a
+    /// character boundary. Other fields are kept as they are.
+    Truncate(usize),
+    /// Replace the field like [`redact`](crate::redact) does
+    Redact(Redaction),
+}
+
+// Rules with the segments left to match, in the order they were added
+type Rules<'f> = Vec<(&'f [Segment], &'f FilterAction)>;
+
+// What happens to a single field
+enum Plan<'f> {
+    Copy,
+    Omit,
+    Replace(&'f FilterAction),
+    Rewrite(NormalRionType, Rules<'f>),
+}
+
+// Lengths and replacements worked out before writing, in the order they are
+// written
+#[derive(Default)]
+struct Measured {
+    lengths: Vec<u64>,
+    replacements: Vec<Vec<u8>>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `action` to the fields matched by `path`, when several paths
+    /// match the same field [`FilterAction::Omit`] wins, otherwise the first
+    /// one added
+    pub fn rule(mut self, path: &str, action: FilterAction) -> Result<Self> {
+        self.rules.push((path.parse()?, action));
+        Ok(self)
+    }
+
+    pub fn omit(self, path: &str) -> Result<Self> {
+        self.rule(path, FilterAction::Omit)
+    }
+
+    pub fn truncate(self, path: &str, len: usize) -> Result<Self> {
+        self.rule(path, FilterAction::Truncate(len))
+    }
+
+    /// Write the value encoded in `data`, filtered, to `writer`
+    ///
+    /// `data` may start with a count hint for the value. Nothing is written if
+    /// the value itself is omitted.
+    pub fn rewrite(&self, data: &[u8], writer: &mut impl Write) -> Result<()> {
+        let mut entries = Entries {
+            data,
+            object: false,
+            index: 0,
+        };
+        let entry = entries.next_entry()?;
+        if !entries.data.is_empty() {
+            return Err(FerionError::invalid("Extra data after field"));
+        }
+        let rules = self
+            .rules
+            .iter()
+            .map(|(path, action)| (path.segments(), action))
+            .collect();
+        let plan = plan(entry.value, rules);
+        let mut measured = Measured::default();
+        measure(entry.value, entry.value_data, &plan, &mut measured)?;
+        measured.lengths.reverse();
+        measured.replacements.reverse();
+        if matches!(plan, Plan::Copy) {
+            writer.write_all(entry.hint_data)?;
+        }
+        write(entry.value, entry.value_data, plan, &mut measured, writer)
+    }
+
+    /// Filter every value read from `reader` into `writer`, returning how many
+    /// values were read
+    ///
+    /// Values are read with [`read_frame`], each at most `limit` bytes long.
+    pub fn copy(
+        &self,
+        mut reader: impl Read,
+        mut writer: impl Write,
+        limit: usize,
+    ) -> Result<usize> {
+        let mut count = 0;
+        let mut out = Vec::new();
+        let mut hint = Vec::new();
+        while let Some(frame) = read_frame(&mut reader, limit)? {
+            // Read along with the value it counts
+            if is_count_hint(RionFieldRef::parse(&frame)?.0) {
+                hint = frame;
+                continue;
+            }
+            count += 1;
+            out.clear();
+            match hint.is_empty() {
+                true => self.rewrite(&frame, &mut out)?,
+                false => self.rewrite(&[&hint[..], &frame].concat(), &mut out)?,
+            }
+            hint.clear();
+            writer.write_all(&out)?;
+        }
+        Ok(count)
+    }
+}
+
+fn plan<'f>(field: RionFieldRef, rules: Rules<'f>) -> Plan<'f> {
+    let mut matched = rules
+        .iter()
+        .filter(|(path, _)| path.is_empty())
+        .map(|&(_, action)| action);
+    if let Some(first) = matched.next() {
+        let omit = |action: &FilterAction| matches!(action, FilterAction::Omit);
+        if omit(first) || matched.any(omit) {
+            return Plan::Omit;
+        }
+        return Plan::Replace(first);
+    }
+    match field {
+        _ if rules.is_empty() => Plan::Copy,
+        RionFieldRef::Normal(
+            normal @ (NormalRionType::Object | NormalRionType::Array),
+            content,
+        ) if !content.is_empty() => Plan::Rewrite(normal, rules),
+        _ => Plan::Copy,
+    }
+}
+
+// The rules that carry on into the entry `key` of an object, or `index` of an
+// array
+fn descend<'f>(rules: &Rules<'f>, key: Option<&[u8]>, index: usize) -> Rules<'f> {
+    rules
+        .iter()
+        .filter_map(|&(path, action)| {
+            let (segment, rest) = path.split_first()?;
+            let matches = match key {
+                Some(key) => segment.matches_key(key),
+                None => segment.matches_index(index),
+            };
+            matches.then_some((rest, action))
+        })
+        .collect()
+}
+
+// Work out the length of everything `write` will write for the field, which is
+// encoded as `data`
+fn measure(field: RionFieldRef, data: &[u8], plan: &Plan, measured: &mut Measured) -> Result<u64> {
+    match plan {
+        Plan::Copy => Ok(data.len() as u64),
+        Plan::Omit => Ok(0),
+        Plan::Replace(action) => {
+            let replacement = replacement(field, data, action)?;
+            let len = replacement.len() as u64;
+            measured.replacements.push(replacement);
+            Ok(len)
+        }
+        Plan::Rewrite(_, rules) => {
+            let slot = measured.lengths.len();
+            measured.lengths.push(0);
+            let mut len = 0;
+            for entry in Entries::new(field)? {
+                let entry = entry?;
+                let plan = plan_entry(&entry, rules);
+                if matches!(plan, Plan::Omit) {
+                    continue;
+                }
+                len += entry.prefix_len(&plan)
+                    + measure(entry.value, entry.value_data, &plan, measured)?;
+            }
+            measured.lengths[slot] = len;
+            Ok(1 + needed_bytes(len) as u64 + len)
+        }
+    }
+}
+
+// Write the field as measured, taking its lengths and replacements off the end
+// of `measured`
+fn write(
+    field: RionFieldRef,
+    data: &[u8],
+    plan: Plan,
+    measured: &mut Measured,
+    writer: &mut impl Write,
+) -> Result<()> {
+    match plan {
+        Plan::Copy => writer.write_all(data)?,
+        Plan::Omit => {}
+        Plan::Replace(_) => {
+            let replacement = measured
+                .replacements
+                .pop()
+                .expect("Measured before writing");
+            writer.write_all(&replacement)?;
+        }
+        Plan::Rewrite(normal, rules) => {
+            let len = measured.lengths.pop().expect("Measured before writing");
+            let lead = LeadByte::normal(normal, needed_bytes(len) as u8);
+            writer.write_all(&[lead.byte()])?;
+            crate::int_to_bytes(&len, writer)?;
+            for entry in Entries::new(field)? {
+                let entry = entry?;
+                let plan = plan_entry(&entry, &rules);
+                if matches!(plan, Plan::Omit) {
+                    continue;
+                }
+                writer.write_all(entry.key_data)?;
+                if matches!(plan, Plan::Copy) {
+                    writer.write_all(entry.hint_data)?;
+                }
+                write(entry.value, entry.value_data, plan, measured, writer)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn plan_entry<'f>(entry: &Entry, rules: &Rules<'f>) -> Plan<'f> {
+    plan(entry.value, descend(rules, entry.key, entry.index))
+}
+
+// The encoding of the field once `action` is applied to it
+fn replacement(field: RionFieldRef, data: &[u8], action: &FilterAction) -> Result<Vec<u8>> {
+    let replaced = match (action, field) {
+        (FilterAction::Omit, _) => unreachable!("Omitted fields are never replaced"),
+        (FilterAction::Truncate(len), RionFieldRef::Short(ShortRionType::UTF8, text))
+        | (FilterAction::Truncate(len), RionFieldRef::Normal(NormalRionType::UTF8, text))
+            if text.len() > *len =>
+        {
+            let mut cut = *len;
+            // Back up to the start of the character at `cut`
+            while cut > 0 && text[cut] & 0xC0 == 0x80 {
+                cut -= 1;
+            }
+            match cut {
+                1..=15 => RionField::Short(ShortField::try_new(ShortRionType::UTF8, &text[..cut])?),
+                _ => RionField::Normal(NormalField::try_new(NormalRionType::UTF8, &text[..cut])?),
+            }
+        }
+        (FilterAction::Truncate(len), RionFieldRef::Normal(NormalRionType::Bytes, bytes))
+            if bytes.len() > *len =>
+        {
+            RionField::bytes(&bytes[..*len])
+        }
+        (FilterAction::Truncate(_), _) => return Ok(data.to_vec()),
+        // Same replacements as `redact`
+        (FilterAction::Redact(policy), _) => {
+            return match policy {
+                Redaction::Null => RionValue::Null,
+                Redaction::Placeholder(placeholder) => placeholder.as_str().into(),
+                Redaction::Hash(hash) => RionValue::Bytes(hash(data)),
+            }
+            .encode_to_vec();
+        }
+    };
+    let mut out = Vec::with_capacity(replaced.needed_bytes());
+    replaced.encode(&mut out)?;
+    Ok(out)
+}
+
+fn is_count_hint(field: RionFieldRef) -> bool {
+    matches!(field, RionFieldRef::Extended { type_id, .. } if type_id == RionField::COUNT_HINT)
+}
+
+// An element of an array, or a key and value of an object
+struct Entry<'a> {
+    index: usize,
+    key: Option<&'a [u8]>,
+    key_data: &'a [u8],
+    hint_data: &'a [u8],
+    value: RionFieldRef<'a>,
+    value_data: &'a [u8],
+}
+
+impl Entry<'_> {
+    // The length of what is written before the value
+    fn prefix_len(&self, plan: &Plan) -> u64 {
+        let hint_len = match plan {
+            Plan::Copy => self.hint_data.len(),
+            _ => 0,
+        };
+        (self.key_data.len() + hint_len) as u64
+    }
+}
+
+struct Entries<'a> {
+    data: &'a [u8],
+    object: bool,
+    index: usize,
+}
+
+impl<'a> Entries<'a> {
+    fn new(field: RionFieldRef<'a>) -> Result<Self> {
+        let RionFieldRef::Normal(normal, data) = field else {
+            return Err(FerionError::invalid(format!(
+                "Expected an object or array, found {field:?}"
+            )));
+        };
+        Ok(Entries {
+            data,
+            object: normal == NormalRionType::Object,
+            index: 0,
+        })
+    }
+
+    // The next field along with its encoding
+    fn next_field(&mut self) -> Result<(RionFieldRef<'a>, &'a [u8])> {
+        let (field, rest) = RionFieldRef::parse(self.data)?;
+        let data = &self.data[..self.data.len() - rest.len()];
+        self.data = rest;
+        Ok((field, data))
+    }
+
+    fn next_entry(&mut self) -> Result<Entry<'a>> {
+        let (key, key_data) = match self.object {
+            true => {
+                let (key, key_data) = self.next_field()?;
+                (Some(key.as_bytes()), key_data)
+            }
+            false => (None, &[][..]),
+        };
+        let (mut value, mut value_data) = self.next_field()?;
+        let mut hint_data = &[][..];
+        if is_count_hint(value) {
+            hint_data = value_data;
+            (value, value_data) = self.next_field()?;
+        }
+        let index = self.index;
+        self.index += 1;
+        Ok(Entry {
+            index,
+            key,
+            key_data,
+            hint_data,
+            value,
+            value_data,
+        })
+    }
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<Entry<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(self.next_entry())
+    }
+}
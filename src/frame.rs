@@ -0,0 +1,176 @@
+//! Reading encoded values off a byte stream one at a time
+//!
+//! Every encoded field carries its own length, so a stream of values needs no
+//! extra framing: writing values back to back is enough, and [`read_frame`]
+//! reads exactly one of them back.
+//!
+//! Large Bytes fields can be written straight from a reader with
+//! [`write_bytes_from`], along with [`write_normal_header`] for the containers
+//! around them. Their length has to be known up front, this tree has no
+//! chunked encoding for content of unknown length.
+
+use std::io::{self, Read, Write};
+
+use crate::{
+    bytes_to_int, int_to_bytes, needed_bytes,
+    types::{LeadByte, NormalRionType, RionFieldType},
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{RionField, RionObject, RionValue};
+
+    #[test]
+    fn test_read_frames() {
+        let mut stream = Vec::new();
+        let values = [
+            RionValue::Bool(true),
+            RionValue::from("short"),
+            RionValue::Array(vec![1u64.into(), "x".repeat(40).into()]),
+        ];
+        for value in &values {
+            value.encode(&mut stream).unwrap();
+        }
+        let frames = FrameReader::new(&stream[..], 1024)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        for (frame, value) in frames.iter().zip(&values) {
+            assert_eq!(&RionValue::from_slice(frame).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_read_frame_errors() {
+        // Truncated in the middle of the content
+        let err = read_frame(&mut &[0x65, b'a', b'b'][..], 1024).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = read_frame(&mut &[0x01, 0x05, 0, 0, 0, 0, 0][..], 4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(read_frame(&mut &[][..], 4).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_write_bytes_from() {
+        let blob: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+        let key = RionField::key(b"blob");
+        let content_len = key.needed_bytes() as u64 + normal_field_len(blob.len() as u64);
+
+        let mut stream = Vec::new();
+        write_normal_header(&mut stream, NormalRionType::Object, content_len).unwrap();
+        key.encode(&mut stream).unwrap();
+        write_bytes_from(&mut stream, &blob[..], blob.len() as u64).unwrap();
+
+        let mut object = RionObject::new();
+        object.add_field("blob", RionField::bytes(&blob));
+        assert_eq!(stream, object.encode());
+        assert_eq!(stream.len() as u64, normal_field_len(content_len));
+
+        // Only the given length is read
+        let mut short = Vec::new();
+        write_bytes_from(&mut short, &blob[..], 3).unwrap();
+        assert_eq!(short, [0x01, 0x03, 0, 1, 2]);
+        let err = write_bytes_from(&mut Vec::new(), &blob[..10], 11).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
+
+/// An iterator over the encoded values in a stream, see [`read_frame`]
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    reader: R,
+    limit: usize,
+}
+
+/// Read the next encoded value from `reader`, at most `limit` bytes long
+///
+/// Returns `None` when the reader is at its end before the first byte, and an
+/// `UnexpectedEof` error when it ends partway through a value.
+pub fn read_frame(reader: &mut impl Read, limit: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut lead = [0];
+    loop {
+        match reader.read(&mut lead) {
+            Ok(0) => return Ok(None),
+            Ok(_) => break,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let lead_byte = LeadByte::try_from(lead[0]).map_err(|e| invalid(e.to_string()))?;
+    let mut frame = vec![lead[0]];
+    let content_len = match lead_byte.field_type() {
+        RionFieldType::Tiny(_) => 0,
+        RionFieldType::Short(_) => lead_byte.length() as usize,
+        // Extended fields are framed like normal ones
+        RionFieldType::Normal(_) | RionFieldType::Extended => {
+            let mut length = vec![0; lead_byte.length() as usize];
+            reader.read_exact(&mut length)?;
+            frame.extend_from_slice(&length);
+            let length = bytes_to_int(&length).map_err(|e| invalid(e.to_string()))?;
+            usize::try_from(length).map_err(|e| invalid(e.to_string()))?
+        }
+    };
+    if frame.len().saturating_add(content_len) > limit {
+        return Err(invalid(format!(
+            "Value of {content_len} bytes is over the {limit} byte limit"
+        )));
+    }
+    let start = frame.len();
+    frame.resize(start + content_len, 0);
+    reader.read_exact(&mut frame[start..])?;
+    Ok(Some(frame))
+}
+
+/// The encoded size of a normal field holding `len` bytes of content
+pub fn normal_field_len(len: u64) -> u64 {
+    1 + needed_bytes(len) as u64 + len
+}
+
+/// Write the lead byte and length of a normal field with `len` bytes of
+/// content, which the caller writes next
+pub fn write_normal_header(
+    writer: &mut impl Write,
+    field_type: NormalRionType,
+    len: u64,
+) -> io::Result<()> {
+    let lead = LeadByte::normal(field_type, needed_bytes(len) as u8);
+    writer.write_all(&[lead.byte()])?;
+    int_to_bytes(&len, writer)
+}
+
+/// Write a Bytes field holding the next `len` bytes of `reader`
+///
+/// The content is copied through a small buffer, so blobs of any size can be
+/// attached without holding them in memory. Fails with `UnexpectedEof` when
+/// the reader ends early, the field is then left incomplete.
+pub fn write_bytes_from(writer: &mut impl Write, reader: impl Read, len: u64) -> io::Result<()> {
+    write_normal_header(writer, NormalRionType::Bytes, len)?;
+    let copied = io::copy(&mut reader.take(len), writer)?;
+    if copied < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("Reader ended after {copied} of {len} bytes"),
+        ));
+    }
+    Ok(())
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R, limit: usize) -> Self {
+        FrameReader { reader, limit }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        read_frame(&mut self.reader, self.limit).transpose()
+    }
+}
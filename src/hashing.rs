@@ -0,0 +1,140 @@
+//! Hashing encoded output as it is written
+//!
+//! Any [`Digest`] works, e.g. `sha2::Sha256` with the `sha2` feature, or
+//! `blake3::Hasher` with blake3's `traits-preview` feature.
+
+use std::io::{self, Write};
+
+use digest::{Digest, Output};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // FNV-1a, enough to check the writer feeds every byte to the digest
+    #[derive(Default, Clone)]
+    struct Fnv(u64);
+
+    impl digest::HashMarker for Fnv {}
+
+    impl digest::OutputSizeUser for Fnv {
+        type OutputSize = digest::consts::U8;
+    }
+
+    impl digest::Update for Fnv {
+        fn update(&mut self, data: &[u8]) {
+            for &b in data {
+                self.0 = (self.0 ^ b as u64).wrapping_mul(0x100_0000_01b3);
+            }
+        }
+    }
+
+    impl digest::FixedOutput for Fnv {
+        fn finalize_into(self, out: &mut Output<Self>) {
+            out.copy_from_slice(&self.0.to_be_bytes());
+        }
+    }
+
+    impl digest::Reset for Fnv {
+        fn reset(&mut self) {
+            *self = Self::default();
+        }
+    }
+
+    impl digest::FixedOutputReset for Fnv {
+        fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+            out.copy_from_slice(&self.0.to_be_bytes());
+            self.reset();
+        }
+    }
+
+    #[test]
+    fn test_digest_writer() {
+        let mut writer = DigestWriter::<_, Fnv>::new(Vec::new());
+        writer.write_all(b"hello ").unwrap();
+        writer.write_all(b"world").unwrap();
+        let (out, digest) = writer.finalize();
+        assert_eq!(out, b"hello world");
+        assert_eq!(digest, Fnv::digest(b"hello world"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_writer_with_digest() {
+        let value = ("a", 1u8);
+        let (out, digest) = crate::to_writer_with_digest::<Fnv, _, _>(Vec::new(), &value).unwrap();
+        let encoded = crate::to_bytes(&value).unwrap();
+        assert_eq!(out, encoded);
+        assert_eq!(digest, Fnv::digest(&encoded));
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_sha256() {
+        let mut writer = Sha256Writer::new(Vec::new());
+        writer.write_all(b"abc").unwrap();
+        let (_, digest) = writer.finalize();
+        // SHA-256 of "abc" starts with ba7816bf
+        assert_eq!(digest[..4], [0xba, 0x78, 0x16, 0xbf]);
+    }
+}
+
+/// A writer that hashes everything written through it
+#[derive(Debug, Clone)]
+pub struct DigestWriter<W, D> {
+    inner: W,
+    digest: D,
+}
+
+#[cfg(feature = "sha2")]
+pub type Sha256Writer<W> = DigestWriter<W, sha2::Sha256>;
+
+impl<W: Write, D: Digest> DigestWriter<W, D> {
+    pub fn new(inner: W) -> Self {
+        Self::with_digest(inner, D::new())
+    }
+
+    /// Continue a digest that has already seen some data
+    pub fn with_digest(inner: W, digest: D) -> Self {
+        DigestWriter { inner, digest }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The inner writer and the digest of everything written to it
+    pub fn finalize(self) -> (W, Output<D>) {
+        (self.inner, self.digest.finalize())
+    }
+}
+
+impl<W: Write, D: Digest> Write for DigestWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        // Only what the inner writer accepted, the rest will be written again
+        self.digest.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Like [`to_writer`](crate::to_writer), also returning the digest of the
+/// encoded bytes
+#[cfg(feature = "serde")]
+pub fn to_writer_with_digest<D, W, T>(
+    writer: W,
+    value: &T,
+) -> Result<(W, Output<D>), crate::SerializeError>
+where
+    D: Digest,
+    W: Write,
+    T: serde::Serialize,
+{
+    let mut writer = DigestWriter::<W, D>::new(writer);
+    crate::to_writer(&mut writer, value)?;
+    Ok(writer.finalize())
+}
@@ -0,0 +1,104 @@
+use std::{collections::HashSet, sync::Arc};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shared_strings() {
+        let mut interner = Interner::new();
+        let a = interner.intern_str("info");
+        let b = interner.intern_str("info");
+        assert!(Arc::ptr_eq(&a, &b));
+        let key = interner.intern_key(b"level");
+        assert!(Arc::ptr_eq(&key, &interner.intern_key(b"level")));
+        assert_eq!(interner.len(), 2);
+
+        interner.clear();
+        assert!(interner.is_empty());
+        assert!(!Arc::ptr_eq(&a, &interner.intern_str("info")));
+    }
+
+    #[test]
+    fn test_max_len() {
+        let mut interner = Interner::with_max_len(4);
+        let long = interner.intern_str("a longer message");
+        assert!(!Arc::ptr_eq(
+            &long,
+            &interner.intern_str("a longer message")
+        ));
+        assert!(interner.is_empty());
+    }
+}
+
+/// Shares repeated strings and object keys between decoded values
+///
+/// Pass one to [`RionValue::from_slice_interned`](crate::RionValue::from_slice_interned)
+/// so every occurrence of the same string decodes to a clone of one
+/// [`Arc<str>`] instead of its own allocation. An interner can be reused
+/// across documents, e.g. every record of a log batch.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+    keys: HashSet<Arc<[u8]>>,
+    max_len: Option<usize>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only intern strings and keys of at most `max_len` bytes
+    ///
+    /// Long strings are rarely repeated, interning them only grows the interner.
+    pub fn with_max_len(max_len: usize) -> Self {
+        Interner {
+            max_len: Some(max_len),
+            ..Self::default()
+        }
+    }
+
+    fn accepts(&self, len: usize) -> bool {
+        self.max_len.is_none_or(|max_len| len <= max_len)
+    }
+
+    pub fn intern_str(&mut self, value: &str) -> Arc<str> {
+        if !self.accepts(value.len()) {
+            return value.into();
+        }
+        if let Some(shared) = self.strings.get(value) {
+            return shared.clone();
+        }
+        let shared: Arc<str> = value.into();
+        self.strings.insert(shared.clone());
+        shared
+    }
+
+    pub fn intern_key(&mut self, key: &[u8]) -> Arc<[u8]> {
+        if !self.accepts(key.len()) {
+            return key.into();
+        }
+        if let Some(shared) = self.keys.get(key) {
+            return shared.clone();
+        }
+        let shared: Arc<[u8]> = key.into();
+        self.keys.insert(shared.clone());
+        shared
+    }
+
+    /// Number of distinct strings and keys held
+    pub fn len(&self) -> usize {
+        self.strings.len() + self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every held string, values already decoded keep theirs
+    pub fn clear(&mut self) {
+        self.strings.clear();
+        self.keys.clear();
+    }
+}
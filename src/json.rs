@@ -0,0 +1,213 @@
+//! Direct conversions between [`RionValue`] and `serde_json::Value`
+//!
+//! JSON has no bytes or date times, going to JSON Bytes become standard
+//! base64 strings, DateTimes RFC 3339 strings and Tables arrays of row
+//! objects. Strings coming from JSON stay strings, nothing is guessed back.
+//!
+//! [`ndjson_to_rion`] and [`rion_to_ndjson`] convert streams a record at a
+//! time, between one JSON value per line and RION values written back to back.
+
+use std::{
+    io::{BufRead, Read, Write},
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{Map, Number, Value};
+
+use crate::{frame::read_frame, value::utf8_key, FerionError, Result, RionFieldRef, RionValue};
+
+/// Numbers become integers when they fit in 64 bits and floats otherwise
+impl From<Value> for RionValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => RionValue::Null,
+            Value::Bool(b) => RionValue::Bool(b),
+            Value::Number(n) => number(&n),
+            Value::String(s) => s.into(),
+            Value::Array(elements) => {
+                RionValue::Array(elements.into_iter().map(RionValue::from).collect())
+            }
+            Value::Object(fields) => RionValue::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key.into_bytes().into(), value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn number(n: &Number) -> RionValue {
+    match (n.as_u64(), n.as_i64()) {
+        (Some(n), _) => n.into(),
+        (_, Some(n)) => n.into(),
+        _ => RionValue::Float(n.as_f64().unwrap_or(f64::NAN)),
+    }
+}
+
+/// Errors on non-finite floats, keys that are not UTF-8 and extended fields
+impl TryFrom<&RionValue> for Value {
+    type Error = FerionError;
+    fn try_from(value: &RionValue) -> Result<Self> {
+        Ok(match value {
+            RionValue::Null => Value::Null,
+            RionValue::Bool(b) => Value::Bool(*b),
+            // Past 64 bits only with serde_json's arbitrary_precision
+            RionValue::PosInt(n) => serde_json::to_value(n)?,
+            RionValue::NegInt(n) => serde_json::to_value(n)?,
+            RionValue::Float(n) => Number::from_f64(*n)
+                .map(Value::Number)
+                .ok_or_else(|| FerionError::invalid(format!("{n} has no JSON representation")))?,
+            RionValue::String(s) => Value::String(s.to_string()),
+            RionValue::Bytes(bytes) => Value::String(STANDARD.encode(bytes)),
+            RionValue::DateTime(dt) => Value::String(dt.to_rfc3339()),
+            RionValue::Array(elements) => Value::Array(
+                elements
+                    .iter()
+                    .map(Value::try_from)
+                    .collect::<Result<_>>()?,
+            ),
+            RionValue::Object(fields) => Value::Object(object(fields)?),
+            RionValue::Table { columns, rows } => Value::Array(
+                rows.iter()
+                    .map(|row| Ok(Value::Object(object(columns.iter().zip(row))?)))
+                    .collect::<Result<_>>()?,
+            ),
+            RionValue::Extended { type_id, .. } => {
+                return Err(FerionError::invalid(format!(
+                    "Extended type {type_id} has no JSON representation"
+                )))
+            }
+        })
+    }
+}
+
+impl TryFrom<RionValue> for Value {
+    type Error = FerionError;
+    fn try_from(value: RionValue) -> Result<Self> {
+        Value::try_from(&value)
+    }
+}
+
+fn object<'a>(
+    fields: impl IntoIterator<Item = (&'a Arc<[u8]>, &'a RionValue)>,
+) -> Result<Map<String, Value>> {
+    fields
+        .into_iter()
+        .map(|(key, value)| Ok((utf8_key(key)?, value.try_into()?)))
+        .collect()
+}
+
+/// Encode each line of JSON read from `reader` as a RION value written to
+/// `writer`, returning how many values were written
+///
+/// Blank lines are skipped. Errors name the line they happened on.
+pub fn ndjson_to_rion(reader: impl BufRead, mut writer: impl Write) -> Result<usize> {
+    let mut count = 0;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let json: Value = serde_json::from_str(&line)
+            .map_err(|e| FerionError::invalid(format!("Line {}: {e}", i + 1)))?;
+        RionValue::from(json).encode(&mut writer)?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Write each RION value read from `reader` to `writer` as a line of JSON,
+/// returning how many lines were written
+///
+/// Values are read with [`read_frame`], each at most `limit` bytes long, and
+/// count hints between them are skipped.
+pub fn rion_to_ndjson(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    limit: usize,
+) -> Result<usize> {
+    let mut count = 0;
+    while let Some(frame) = read_frame(&mut reader, limit)? {
+        if let (RionFieldRef::Extended { type_id, .. }, _) = RionFieldRef::parse(&frame)? {
+            if type_id == crate::RionField::COUNT_HINT {
+                continue;
+            }
+        }
+        let json = Value::try_from(RionValue::from_slice(&frame)?)
+            .map_err(|e| FerionError::invalid(format!("Value {}: {e}", count + 1)))?;
+        serde_json::to_writer(&mut writer, &json)?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = json!({
+            "name": "Alice",
+            "age": 30,
+            "balance": -12,
+            "score": 1.5,
+            "tags": ["a", null, true],
+            "nested": {"empty": []},
+        });
+        let value = RionValue::from(json.clone());
+        assert_eq!(value.get("age"), Some(&RionValue::PosInt(30)));
+        assert_eq!(value.get("balance"), Some(&RionValue::NegInt(-12)));
+        assert_eq!(Value::try_from(&value).unwrap(), json);
+        assert_eq!(Value::try_from(value).unwrap(), json);
+    }
+
+    #[test]
+    fn test_json_policies() {
+        // Built from components, the json feature doesn't need chrono
+        let joined = crate::RionDateTime::from_components(2024, 2, 29, 12, 0, 0, 0).unwrap();
+        let value = RionValue::Array(vec![
+            RionValue::Bytes(vec![1, 2, 3]),
+            RionValue::DateTime(joined),
+            RionValue::Table {
+                columns: vec![b"id"[..].into()],
+                rows: vec![vec![RionValue::PosInt(1)], vec![RionValue::PosInt(2)]],
+            },
+        ]);
+        assert_eq!(
+            Value::try_from(&value).unwrap(),
+            json!(["AQID", "2024-02-29T12:00:00+00:00", [{"id": 1}, {"id": 2}]])
+        );
+
+        assert!(Value::try_from(RionValue::Float(f64::NAN)).is_err());
+        let bad_key = RionValue::Object([(b"\xFF"[..].into(), RionValue::Null)].into());
+        assert!(Value::try_from(bad_key).is_err());
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let lines = "{\"id\":1,\"tags\":[\"a\"]}\n\n[true,null]\n\"last\"\n";
+        let mut rion = Vec::new();
+        assert_eq!(ndjson_to_rion(lines.as_bytes(), &mut rion).unwrap(), 3);
+        let (_, rest) = RionFieldRef::parse(&rion).unwrap();
+        let first = RionValue::from_slice(&rion[..rion.len() - rest.len()]).unwrap();
+        assert_eq!(first.get("id"), Some(&RionValue::PosInt(1)));
+
+        let mut json = Vec::new();
+        assert_eq!(rion_to_ndjson(&rion[..], &mut json, 1024).unwrap(), 3);
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            lines.replace("\n\n", "\n")
+        );
+
+        let error = ndjson_to_rion("1\n{\n".as_bytes(), Vec::new()).unwrap_err();
+        assert!(error.to_string().starts_with("Line 2: "));
+        assert!(rion_to_ndjson(&rion[..rion.len() - 1], Vec::new(), 1024).is_err());
+    }
+}
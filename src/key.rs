@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+
+use crate::{FerionError, RionField};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_valid_keys() {
+        let key = RionKey::try_from("name").unwrap();
+        assert_eq!(key.as_bytes(), b"name");
+        assert_eq!(key.as_str(), Some("name"));
+        assert_eq!(RionField::from(key), RionField::key(b"name"));
+
+        let raw = RionKey::new(&[0xFF, 0x00][..]).unwrap();
+        assert_eq!(raw.as_str(), None);
+        assert_eq!(RionKey::new_utf8(&[0xFF][..]), Err(InvalidKey::InvalidUtf8));
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert_eq!(RionKey::try_from(""), Err(InvalidKey::Empty));
+        let long = vec![b'a'; RionKey::MAX_LEN + 1];
+        assert_eq!(
+            RionKey::new(long).unwrap_err(),
+            InvalidKey::TooLong(RionKey::MAX_LEN + 1)
+        );
+        assert!(RionKey::new(vec![b'a'; RionKey::MAX_LEN]).is_ok());
+    }
+}
+
+/// An object key that is known to be valid
+///
+/// Keys can't be empty, since an empty key encodes the same as a null one, and
+/// are at most [`RionKey::MAX_LEN`] bytes long. They don't have to be UTF-8,
+/// use [`RionKey::new_utf8`] or build one from a `&str` to require that.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RionKey<'a>(Cow<'a, [u8]>);
+
+impl<'a> RionKey<'a> {
+    /// Longest key accepted, keys are names and anything longer is almost
+    /// certainly data in the wrong place
+    pub const MAX_LEN: usize = u16::MAX as usize;
+
+    pub fn new(key: impl Into<Cow<'a, [u8]>>) -> Result<Self, InvalidKey> {
+        let key = key.into();
+        match key.len() {
+            0 => Err(InvalidKey::Empty),
+            len if len > Self::MAX_LEN => Err(InvalidKey::TooLong(len)),
+            _ => Ok(RionKey(key)),
+        }
+    }
+
+    /// Like [`RionKey::new`], also requiring the key to be UTF-8
+    pub fn new_utf8(key: impl Into<Cow<'a, [u8]>>) -> Result<Self, InvalidKey> {
+        let key = Self::new(key)?;
+        match key.as_str() {
+            Some(_) => Ok(key),
+            None => Err(InvalidKey::InvalidUtf8),
+        }
+    }
+
+    // The key held by a Key field, as found while parsing
+    pub(crate) fn from_field(field: RionField<'a>) -> crate::Result<Self> {
+        if !field.is_key() {
+            return Err(FerionError::invalid(format!(
+                "Expected a key, found {field:?}"
+            )));
+        }
+        Ok(Self::new(field.to_data().unwrap_or_default())?)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    pub fn into_inner(self) -> Cow<'a, [u8]> {
+        self.0
+    }
+
+    pub fn into_owned(self) -> RionKey<'static> {
+        RionKey(Cow::Owned(self.0.into_owned()))
+    }
+}
+
+impl AsRef<[u8]> for RionKey<'_> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> TryFrom<&'a str> for RionKey<'a> {
+    type Error = InvalidKey;
+    fn try_from(key: &'a str) -> Result<Self, InvalidKey> {
+        Self::new(key.as_bytes())
+    }
+}
+
+impl TryFrom<String> for RionKey<'static> {
+    type Error = InvalidKey;
+    fn try_from(key: String) -> Result<Self, InvalidKey> {
+        Self::new(key.into_bytes())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for RionKey<'a> {
+    type Error = InvalidKey;
+    fn try_from(key: &'a [u8]) -> Result<Self, InvalidKey> {
+        Self::new(key)
+    }
+}
+
+impl TryFrom<Vec<u8>> for RionKey<'static> {
+    type Error = InvalidKey;
+    fn try_from(key: Vec<u8>) -> Result<Self, InvalidKey> {
+        Self::new(key)
+    }
+}
+
+impl<'a> From<RionKey<'a>> for RionField<'a> {
+    fn from(key: RionKey<'a>) -> Self {
+        match key.0 {
+            Cow::Borrowed(key) => RionField::key(key),
+            Cow::Owned(key) => RionField::key(&key).into_owned(),
+        }
+    }
+}
+
+/// Why a key was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidKey {
+    Empty,
+    /// Longer than [`RionKey::MAX_LEN`], holding the length
+    TooLong(usize),
+    InvalidUtf8,
+}
+
+impl std::fmt::Display for InvalidKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidKey::Empty => write!(f, "Keys can't be empty"),
+            InvalidKey::TooLong(len) => write!(
+                f,
+                "Key of {len} bytes is longer than the limit of {}",
+                RionKey::MAX_LEN
+            ),
+            InvalidKey::InvalidUtf8 => write!(f, "Key is not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidKey {}
@@ -1,51 +1,171 @@
 #![cfg_attr(feature = "specialization", feature(min_specialization))]
 
-use std::error::Error;
+mod analyze;
 mod array;
+#[cfg(feature = "avro")]
+pub mod avro;
+mod column_stats;
+mod date_time;
+mod encrypt;
+#[cfg(feature = "chrono")]
+mod envelope;
+mod error;
 mod field;
+mod field_ref;
+mod filter;
+pub mod frame;
+#[cfg(feature = "digest")]
+mod hashing;
+mod intern;
+#[cfg(feature = "json")]
+mod json;
+mod key;
 mod object;
-mod table;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod path;
+pub mod prelude;
+mod raw;
+mod redact;
+pub mod rpc;
+pub mod schema;
+#[cfg(any(feature = "rusqlite", feature = "sqlx"))]
+pub mod sql;
+pub mod table;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod types;
+mod value;
+pub mod wal;
 
 #[cfg(feature = "serde")]
 mod serde;
 #[cfg(feature = "serde")]
 pub use serde::*;
 
+#[cfg(feature = "parquet")]
+pub use crate::parquet::ParquetWriter;
+pub use analyze::{analyze, Analysis, Usage};
 pub use array::RionArray;
-pub use object::RionObject;
-pub use table::RionTable;
+pub use column_stats::{ColumnStats, TableStats};
+pub use date_time::{Precision, RionDateTime, YearRange};
+pub use encrypt::{decrypt, decrypt_field, encrypt, is_encrypted, Cipher};
+#[cfg(feature = "chrono")]
+pub use envelope::Envelope;
+pub use error::FerionError;
+#[cfg(feature = "digest")]
+pub use hashing::*;
+pub use intern::Interner;
+#[cfg(feature = "json")]
+pub use json::{ndjson_to_rion, rion_to_ndjson};
+pub use key::{InvalidKey, RionKey};
+pub use object::{DuplicateKeys, RionObject};
+pub use path::{Path, Segment};
+pub use raw::{FromRawField, RawIndex, RawObject};
+pub use redact::{redact, Redaction};
+pub use table::{KeyMismatch, RionTable, TableLayout};
+pub use value::RionValue;
 
+#[cfg(test)]
+mod temp_dir;
 #[cfg(test)]
 mod test;
 pub use field::RionField;
-use types::LeadByte;
+pub use field_ref::{RionFieldBuf, RionFieldRef};
+pub use filter::{Filter, FilterAction};
+pub use types::{LeadByte, NormalRionType, RionFieldType, ShortRionType};
 
-type Result<T> = std::result::Result<T, Box<dyn Error>>;
+use error::Result;
 
-fn get_lead_byte(data: &[u8]) -> Result<(LeadByte, &[u8])> {
+fn get_lead_byte(data: &[u8]) -> error::Result<(LeadByte, &[u8])> {
     let Some(lead) = data.first() else {
-        return Err("Data is empty".into());
+        return Err(FerionError::Truncated(1));
     };
     Ok((LeadByte::try_from(*lead)?, &data[1..]))
 }
 
 // Get the header of a RION object
-fn get_header(data: &[u8]) -> Result<(LeadByte, &[u8], &[u8])> {
+fn get_header(data: &[u8]) -> error::Result<(LeadByte, &[u8], &[u8])> {
     let (lead, rest) = get_lead_byte(data)?;
     let length_length = lead.length() as usize;
     if length_length > rest.len() {
-        return Err(
-            format!("Not enough data in {rest:x?} for length_length {length_length}").into(),
-        );
+        return Err(FerionError::Truncated(length_length - rest.len()));
     }
     Ok((lead, &rest[..length_length], &rest[length_length..]))
 }
 
-fn bytes_to_int(bytes: &[u8]) -> Result<u64> {
+// The data after the count hint at the start of `data`, or all of it if there
+// is none. Readers that don't use the hints pass over them, see
+// Serializer::count_hints
+fn skip_count_hint(data: &[u8]) -> error::Result<&[u8]> {
+    let rest = skip_extended(data, RionField::COUNT_HINT)?;
+    if rest.len() != data.len() {
+        // A hint belongs to the array or object right after it, another hint
+        // or any other field there is malformed
+        let (lead, _) = get_lead_byte(rest)?;
+        let found = lead.field_type();
+        if !matches!(
+            found,
+            RionFieldType::Normal(NormalRionType::Array | NormalRionType::Object)
+        ) {
+            return Err(FerionError::unexpected(
+                "an array or object after a count hint",
+                found,
+            ));
+        }
+    }
+    Ok(rest)
+}
+
+// The data after the table stats after a table's row count, or all of it if
+// there are none. Readers that don't use them pass over them, see
+// RionTable::encode_with_stats
+fn skip_table_stats(data: &[u8]) -> error::Result<&[u8]> {
+    skip_extended(data, RionField::TABLE_STATS)
+}
+
+fn skip_extended(data: &[u8], skipped: u8) -> error::Result<&[u8]> {
+    if data
+        .first()
+        .is_some_and(|&lead| lead >> 4 == RionFieldType::Extended.to_byte())
+    {
+        if let (RionFieldRef::Extended { type_id, .. }, rest) = RionFieldRef::parse(data)? {
+            if type_id == skipped {
+                return Ok(rest);
+            }
+        }
+    }
+    Ok(data)
+}
+
+// Number of fields in `data`, found by skipping over each by its length.
+// Count hints belong to the field after them and aren't counted
+#[cfg(feature = "serde")]
+fn count_fields(mut data: &[u8]) -> Result<usize> {
+    let mut count = 0;
+    while !data.is_empty() {
+        let (lead, length, rest) = get_header(data)?;
+        let field_type = lead.field_type();
+        if field_type != RionFieldType::Extended || rest.first() != Some(&RionField::COUNT_HINT) {
+            count += 1;
+        }
+        data = match field_type {
+            RionFieldType::Normal(_) | RionFieldType::Extended => {
+                let length = bytes_to_int(length)? as usize;
+                rest.get(length..).ok_or_else(|| {
+                    FerionError::invalid(format!("Not enough data for field of length {length}"))
+                })?
+            }
+            _ => rest,
+        };
+    }
+    Ok(count)
+}
+
+fn bytes_to_int(bytes: &[u8]) -> error::Result<u64> {
     match bytes.len() {
         0..=8 => Ok(bytes.iter().fold(0u64, |acc, &b| acc << 8 | b as u64)),
-        _ => Err("Too many bytes to convert to u64".into()),
+        len => Err(FerionError::LengthOverflow(len)),
     }
 }
 
@@ -64,20 +184,17 @@ fn int_to_bytes(int: &u64, w: &mut impl std::io::Write) -> std::io::Result<()> {
 
 /// Get the header of a RION object
 /// Returns the lead byte, the length of the data, and the remaining data
-fn get_normal_header(data: &[u8]) -> Result<(LeadByte, usize, &[u8])> {
+fn get_normal_header(data: &[u8]) -> error::Result<(LeadByte, usize, &[u8])> {
     let (lead, length, rest) = get_header(data)?;
     let types::RionFieldType::Normal(_) = lead.field_type() else {
-        return Err("Expected a Normal encoded field".into());
+        return Err(FerionError::unexpected("a normal field", lead.field_type()));
     };
     let data_len = bytes_to_int(length)?;
-    let data_len: usize = data_len.try_into()?;
+    let data_len: usize = data_len
+        .try_into()
+        .map_err(|_| FerionError::OutOfRange("usize"))?;
     if data_len > rest.len() {
-        return Err(format!(
-            "Not enough data in {data:x?} (len: (rest) {} + (header) {}) for length {data_len}",
-            rest.len(),
-            1 + length.len()
-        )
-        .into());
+        return Err(FerionError::Truncated(data_len - rest.len()));
     }
     Ok((lead, data_len, rest))
 }
@@ -1,17 +1,61 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap},
+    hash::{Hash, Hasher},
+};
 
 use crate::{
+    error::Result,
     field::NormalField,
-    get_normal_header,
+    get_normal_header, needed_bytes_usize,
     types::{NormalRionType, RionFieldType},
-    Result, RionField,
+    FerionError, RionField, RionKey,
 };
 
+/// What parsing an object does with a key that appears more than once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Fail with [`FerionError::DuplicateKey`], for input where differing
+    /// readers picking different values would be a problem
+    Reject,
+    /// Keep the first value of the key
+    FirstWins,
+    /// Keep the last value of the key
+    #[default]
+    LastWins,
+    /// Keep every value: each key maps to an array of its values, in the order
+    /// they appear, even keys that appear once
+    Multimap,
+}
+
 // Struct to represent a RION object
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct RionObject<'a> {
     // pub data: Cow<'a, [u8]>,
+    /// Call [`recompute_len`](Self::recompute_len) after changing these
+    /// directly
     pub fields: HashMap<Cow<'a, [u8]>, RionField<'a>>,
+    // Encoded length of the keys and values, kept up to date by add_field
+    byte_len: usize,
+}
+
+impl<'a> PartialEq for RionObject<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.fields == other.fields
+    }
+}
+
+impl Eq for RionObject<'_> {}
+
+// Hashed in key order so equal objects hash the same
+impl Hash for RionObject<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for (key, field) in self.iter_sorted() {
+            key.hash(state);
+            field.hash(state);
+        }
+    }
 }
 
 impl<'a> Default for RionObject<'a> {
@@ -25,51 +69,188 @@ impl<'a> RionObject<'a> {
     pub fn new() -> Self {
         RionObject {
             fields: HashMap::new(),
+            byte_len: 0,
         }
     }
 
-    fn parse(data: &'a [u8]) -> Result<(Self, &[u8])> {
-        let (lead, data_len, mut data) = get_normal_header(data)?;
+    fn parse(data: &'a [u8], duplicates: DuplicateKeys) -> Result<(Self, &'a [u8])> {
+        let (lead, data_len, mut data) = get_normal_header(crate::skip_count_hint(data)?)?;
         let RionFieldType::Normal(NormalRionType::Object) = lead.field_type() else {
-            return Err("Expected a RION object".into());
+            return Err(FerionError::unexpected("an object", lead.field_type()));
         };
         let total = data.len();
         let mut fields = HashMap::new();
+        let mut values: HashMap<_, Vec<_>> = HashMap::new();
+        // Set when the fields kept don't add up to the whole content, when
+        // repeated keys were dropped or count hints skipped
+        let mut recount = false;
         while total - data.len() < data_len {
             let (key, rest) = RionField::parse(data)?;
-            if !key.is_key() {
-                return Err(format!("Expected a key, found {key:?} in {data:x?}").into());
-            }
-            let (value, rest) = RionField::parse(rest)?;
+            let key = RionKey::from_field(key)?.into_inner();
+            let hinted = crate::skip_count_hint(rest)?;
+            recount |= hinted.len() < rest.len();
+            let (value, rest) = RionField::parse(hinted)?;
             data = rest;
-            fields.insert(key.to_data().unwrap(), value);
+            if duplicates == DuplicateKeys::Multimap {
+                values.entry(key).or_default().push(value);
+                continue;
+            }
+            match fields.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+                Entry::Occupied(mut entry) => match duplicates {
+                    DuplicateKeys::Reject => {
+                        return Err(FerionError::DuplicateKey(entry.key().to_vec()))
+                    }
+                    DuplicateKeys::FirstWins => recount = true,
+                    DuplicateKeys::LastWins | DuplicateKeys::Multimap => {
+                        entry.insert(value);
+                        recount = true;
+                    }
+                },
+            }
+        }
+        let mut object = RionObject {
+            fields,
+            byte_len: data_len,
+        };
+        if duplicates == DuplicateKeys::Multimap {
+            object.fields = values
+                .into_iter()
+                .map(|(key, values)| (key, RionField::from(values)))
+                .collect();
+            object.recompute_len();
+        } else if recount {
+            object.recompute_len();
         }
-        Ok((RionObject { fields }, data))
+        Ok((object, data))
     }
 
+    /// Parse an object, keeping the last value of any repeated key, see
+    /// [`from_slice_with_duplicates`](Self::from_slice_with_duplicates)
     pub fn from_slice(data: &'a [u8]) -> Result<Self> {
-        let (object, rest) = Self::parse(data)?;
+        Self::from_slice_with_duplicates(data, DuplicateKeys::default())
+    }
+
+    /// Parse an object, handling repeated keys as `duplicates` says
+    pub fn from_slice_with_duplicates(data: &'a [u8], duplicates: DuplicateKeys) -> Result<Self> {
+        let (object, rest) = Self::parse(data, duplicates)?;
         if !rest.is_empty() {
-            return Err("Extra data after object".into());
+            return Err(FerionError::TrailingData(rest.len()));
         }
         Ok(object)
     }
 
-    // Add a field to the RION object
-    pub fn add_field_bytes(&mut self, key: &'a [u8], field: impl Into<RionField<'a>>) {
-        self.fields.insert(key.into(), field.into());
+    /// Add a field to the RION object, returning the field it replaced
+    pub fn insert(
+        &mut self,
+        key: RionKey<'a>,
+        field: impl Into<RionField<'a>>,
+    ) -> Option<RionField<'a>> {
+        let field = field.into();
+        self.byte_len += field.needed_bytes();
+        let key_len = RionField::key(key.as_bytes()).needed_bytes();
+        let previous = self.fields.insert(key.into_inner(), field);
+        match &previous {
+            Some(previous) => self.byte_len -= previous.needed_bytes(),
+            None => self.byte_len += key_len,
+        }
+        previous
+    }
+
+    /// Like [`insert`](Self::insert) with an unchecked key
+    ///
+    /// Panics if `key` is not a valid [`RionKey`], e.g. if it is empty
+    pub fn add_field_bytes(
+        &mut self,
+        key: &'a [u8],
+        field: impl Into<RionField<'a>>,
+    ) -> Option<RionField<'a>> {
+        match RionKey::new(key) {
+            Ok(key) => self.insert(key, field),
+            Err(e) => panic!("Invalid key {key:x?}: {e}"),
+        }
+    }
+
+    /// Panics if `key` is not a valid [`RionKey`], e.g. if it is empty
+    pub fn add_field(
+        &mut self,
+        key: &'a str,
+        field: impl Into<RionField<'a>>,
+    ) -> Option<RionField<'a>> {
+        self.add_field_bytes(key.as_bytes(), field)
+    }
+
+    /// Length of [`encode`](Self::encode)'s output, without encoding
+    pub fn encoded_len(&self) -> usize {
+        1 + needed_bytes_usize(self.byte_len) + self.byte_len
+    }
+
+    /// Recount the encoded length from scratch, needed after editing
+    /// [`fields`](Self::fields) directly
+    pub fn recompute_len(&mut self) -> usize {
+        self.byte_len = self
+            .fields
+            .iter()
+            .map(|(key, field)| RionField::key(key).needed_bytes() + field.needed_bytes())
+            .sum();
+        self.encoded_len()
+    }
+
+    /// Copy any borrowed keys and values so the object outlives its input
+    pub fn into_owned(self) -> RionObject<'static> {
+        RionObject {
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(key, field)| (Cow::Owned(key.into_owned()), field.into_owned()))
+                .collect(),
+            byte_len: self.byte_len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
     }
 
-    pub fn add_field(&mut self, key: &'a str, field: impl Into<RionField<'a>>) {
-        self.add_field_bytes(key.as_bytes(), field);
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.fields.contains_key(key.as_ref())
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&RionField<'a>> {
+        self.fields.get(key.as_ref())
+    }
+
+    /// Iterate over the fields in no particular order, see
+    /// [`iter_sorted`](Self::iter_sorted) for a deterministic order
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &RionField<'a>)> {
+        self.fields.iter().map(|(key, field)| (key.as_ref(), field))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.fields.keys().map(AsRef::as_ref)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &RionField<'a>> {
+        self.fields.values()
+    }
+
+    /// Iterate over the fields sorted by key, the order they are encoded in
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&[u8], &RionField<'a>)> {
+        let mut fields = self.iter().collect::<Vec<_>>();
+        fields.sort_unstable_by_key(|(key, _)| *key);
+        fields.into_iter()
     }
 
     // Encode the RION object to its binary representation
     pub fn encode(&self) -> Vec<u8> {
         let mut content = Vec::new();
-        let mut fields = self.fields.iter().collect::<Vec<_>>();
-        fields.sort_unstable_by_key(|f| f.0);
-        for (key, field) in fields {
+        for (key, field) in self.iter_sorted() {
             // Encode key
             let key_field = RionField::key(key);
             key_field.encode(&mut content).unwrap();
@@ -77,11 +258,7 @@ impl<'a> RionObject<'a> {
             field.encode(&mut content).unwrap();
         }
         let content_len = content.len();
-        let length_length = content_len.div_ceil(64);
-        if length_length > 15 {
-            println!("Warning: Object length field is too long, truncating to 15 bytes");
-        }
-        println!("Content length: {content_len} - Num Bytes {length_length}");
+        let length_length = needed_bytes_usize(content_len);
         let length_bytes = content_len.to_be_bytes();
         let mut encoded = Vec::with_capacity(1 + content_len + length_length);
         encoded.push(0xC0 | length_length as u8 & 0x0F);
@@ -108,3 +285,55 @@ impl<'a> From<RionObject<'a>> for RionField<'a> {
         })
     }
 }
+
+// Helpers for writing objects with a fixed key order straight into a buffer
+
+pub(crate) fn object_field(content: Vec<u8>) -> RionField<'static> {
+    RionField::Normal(NormalField {
+        field_type: NormalRionType::Object,
+        data: content.into(),
+    })
+}
+
+pub(crate) fn write_entry(content: &mut Vec<u8>, key: &str, value: &RionField) {
+    RionField::key_str(key).encode(content).unwrap();
+    value.encode(content).unwrap();
+}
+
+// `value` is a single encoded value, an empty one is written as null so the
+// object stays well formed
+pub(crate) fn write_raw_entry(content: &mut Vec<u8>, key: &str, value: &[u8]) {
+    RionField::key_str(key).encode(content).unwrap();
+    if value.is_empty() {
+        content.push(0x10);
+    } else {
+        content.extend_from_slice(value);
+    }
+}
+
+// The value at `key` re-encoded on its own
+pub(crate) fn get_raw(object: &RionObject, key: &str) -> Result<Option<Vec<u8>>> {
+    let Some(field) = object.get(key) else {
+        return Ok(None);
+    };
+    let mut encoded = Vec::new();
+    field.encode(&mut encoded)?;
+    Ok(Some(encoded))
+}
+
+// Keys are written sorted, like RionObject::encode
+impl<'a, K: AsRef<[u8]>, V: Into<RionField<'a>>> From<HashMap<K, V>> for RionField<'_> {
+    fn from(map: HashMap<K, V>) -> Self {
+        let mut fields = map.into_iter().collect::<Vec<_>>();
+        fields.sort_unstable_by(|(a, _), (b, _)| a.as_ref().cmp(b.as_ref()));
+        let mut content = Vec::new();
+        for (key, field) in fields {
+            RionField::key(key.as_ref()).encode(&mut content).unwrap();
+            field.into().encode(&mut content).unwrap();
+        }
+        RionField::Normal(NormalField {
+            field_type: NormalRionType::Object,
+            data: content.into(),
+        })
+    }
+}
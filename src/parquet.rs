@@ -0,0 +1,360 @@
+//! Export of tables to Parquet
+//!
+//! Every table becomes a row group of a Parquet file with one optional column
+//! per table column. Column types are inferred from the cells of the first
+//! table: booleans, 64 bit integers, doubles (for columns mixing integers and
+//! floats), UTF-8 strings, byte arrays and UTC timestamps in microseconds. A
+//! column holding only nulls is written as strings.
+
+use std::{io::Write, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use parquet::{
+    basic::{LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+    data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::types::Type,
+};
+
+use crate::{
+    types::{NormalRionType, RionFieldType, ShortRionType},
+    FerionError, Result, RionField, RionTable,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{temp_dir::TempDir, RionValue};
+    use chrono::TimeZone;
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::Field,
+    };
+
+    fn table_data(offset: u64) -> Vec<u8> {
+        let at = Utc.with_ymd_and_hms(2024, 2, 29, 12, 0, 0).unwrap();
+        RionValue::Table {
+            columns: vec![
+                b"id"[..].into(),
+                b"name"[..].into(),
+                b"score"[..].into(),
+                b"at"[..].into(),
+            ],
+            rows: vec![
+                vec![
+                    RionValue::PosInt(offset.into()),
+                    "a".into(),
+                    RionValue::Float(1.5),
+                    at.into(),
+                ],
+                vec![
+                    RionValue::PosInt((offset + 1).into()),
+                    RionValue::Null,
+                    RionValue::PosInt(2),
+                    at.into(),
+                ],
+            ],
+        }
+        .encode_to_vec()
+        .unwrap()
+    }
+
+    fn read_rows(path: &std::path::Path) -> Vec<Vec<(String, Field)>> {
+        let reader = SerializedFileReader::new(std::fs::File::open(path).unwrap()).unwrap();
+        reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(name, field)| (name.clone(), field.clone()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        let dir = TempDir::new("parquet-write");
+        let path = dir.0.join("table.parquet");
+        let (first, second) = (table_data(0), table_data(2));
+        let first = RionTable::from_slice(&first).unwrap();
+        let mut writer = ParquetWriter::new(std::fs::File::create(&path).unwrap(), &first).unwrap();
+        writer.write_table(&first).unwrap();
+        writer
+            .write_table(&RionTable::from_slice(&second).unwrap())
+            .unwrap();
+        writer.close().unwrap();
+
+        let rows = read_rows(&path);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[3][0], ("id".to_string(), Field::Long(3)));
+        assert_eq!(rows[0][1], ("name".to_string(), Field::Str("a".into())));
+        assert_eq!(rows[1][1], ("name".to_string(), Field::Null));
+        assert_eq!(rows[1][2], ("score".to_string(), Field::Double(2.0)));
+        let micros = Utc
+            .with_ymd_and_hms(2024, 2, 29, 12, 0, 0)
+            .unwrap()
+            .timestamp_micros();
+        assert_eq!(
+            rows[0][3],
+            ("at".to_string(), Field::TimestampMicros(micros))
+        );
+    }
+
+    #[test]
+    fn test_mismatched_tables() {
+        let data = table_data(0);
+        let table = RionTable::from_slice(&data).unwrap();
+        let mut writer = ParquetWriter::new(Vec::new(), &table).unwrap();
+
+        let mut renamed = table.clone();
+        renamed.column_names[0] = b"key"[..].into();
+        assert!(writer.write_table(&renamed).is_err());
+
+        let mut retyped = table.clone();
+        retyped.rows[0] = RionField::from("one");
+        assert!(writer.write_table(&retyped).is_err());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnType {
+    Bool,
+    Int,
+    Float,
+    String,
+    Bytes,
+    DateTime,
+}
+
+impl ColumnType {
+    // The type of a single cell, `None` for nulls
+    fn of(field: &RionField) -> Result<Option<Self>> {
+        if field.is_null() {
+            return Ok(None);
+        }
+        let column_type = match field.field_type() {
+            RionFieldType::Tiny(_) => ColumnType::Bool,
+            RionFieldType::Short(ShortRionType::Int64Positive | ShortRionType::Int64Negative) => {
+                ColumnType::Int
+            }
+            RionFieldType::Short(ShortRionType::Float) => ColumnType::Float,
+            RionFieldType::Short(ShortRionType::UTF8)
+            | RionFieldType::Normal(NormalRionType::UTF8) => ColumnType::String,
+            RionFieldType::Short(ShortRionType::UTCDateTime) => ColumnType::DateTime,
+            RionFieldType::Normal(NormalRionType::Bytes) => ColumnType::Bytes,
+            field_type => {
+                return Err(FerionError::invalid(format!(
+                    "{field_type:?} fields can't be written to Parquet"
+                )))
+            }
+        };
+        Ok(Some(column_type))
+    }
+
+    fn merge(self, other: ColumnType) -> Option<Self> {
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (ColumnType::Int, ColumnType::Float) | (ColumnType::Float, ColumnType::Int) => {
+                Some(ColumnType::Float)
+            }
+            _ => None,
+        }
+    }
+
+    fn parquet_type(self, name: &str) -> Result<Type> {
+        let (physical, logical) = match self {
+            ColumnType::Bool => (PhysicalType::BOOLEAN, None),
+            ColumnType::Int => (PhysicalType::INT64, None),
+            ColumnType::Float => (PhysicalType::DOUBLE, None),
+            ColumnType::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            ColumnType::Bytes => (PhysicalType::BYTE_ARRAY, None),
+            ColumnType::DateTime => (
+                PhysicalType::INT64,
+                Some(LogicalType::Timestamp {
+                    is_adjusted_to_u_t_c: true,
+                    unit: TimeUnit::MICROS(Default::default()),
+                }),
+            ),
+        };
+        Ok(Type::primitive_type_builder(name, physical)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(logical)
+            .build()?)
+    }
+}
+
+/// Writes tables with the same columns to a Parquet file, one row group each
+pub struct ParquetWriter<W: Write + Send> {
+    writer: SerializedFileWriter<W>,
+    column_names: Vec<Vec<u8>>,
+    column_types: Vec<ColumnType>,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Start a file whose columns are those of `table`
+    ///
+    /// Only the schema is taken from `table`, pass it to
+    /// [`write_table`](Self::write_table) to write its rows.
+    pub fn new(writer: W, table: &RionTable) -> Result<Self> {
+        let column_types = column_types(table)?;
+        let fields = table
+            .column_names
+            .iter()
+            .zip(&column_types)
+            .map(|(name, column_type)| {
+                let name = std::str::from_utf8(name).map_err(|_| {
+                    FerionError::invalid(format!("Column name {name:x?} is not valid UTF-8"))
+                })?;
+                Ok(Arc::new(
+                    column_type
+                        .unwrap_or(ColumnType::String)
+                        .parquet_type(name)?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?;
+        let properties = WriterProperties::builder().build();
+        Ok(ParquetWriter {
+            writer: SerializedFileWriter::new(writer, Arc::new(schema), Arc::new(properties))?,
+            column_names: table
+                .column_names
+                .iter()
+                .map(|name| name.to_vec())
+                .collect(),
+            column_types: column_types
+                .into_iter()
+                .map(|column_type| column_type.unwrap_or(ColumnType::String))
+                .collect(),
+        })
+    }
+
+    /// Write the rows of `table` as a new row group
+    ///
+    /// Errors if its columns differ from those of the first table, in name or
+    /// type.
+    pub fn write_table(&mut self, table: &RionTable) -> Result<()> {
+        if !table.column_names.iter().eq(&self.column_names) {
+            return Err(FerionError::invalid(
+                "Table columns differ from the Parquet schema",
+            ));
+        }
+        let width = self.column_names.len();
+        let mut row_group = self.writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            let column_type = self.column_types[index];
+            let cells = table.rows.iter().skip(index).step_by(width.max(1));
+            let levels = cells
+                .clone()
+                .map(|cell| i16::from(!cell.is_null()))
+                .collect::<Vec<_>>();
+            let cells = cells.filter(|cell| !cell.is_null());
+            let name = String::from_utf8_lossy(&self.column_names[index]);
+            let mismatch = |cell: &RionField| {
+                FerionError::invalid(format!(
+                    "Column {name:?} holds {cell:?} in a {column_type:?} column"
+                ))
+            };
+            match column_type {
+                ColumnType::Bool => {
+                    let values = cells
+                        .map(|cell| cell.as_bool().ok_or_else(|| mismatch(cell)))
+                        .collect::<std::result::Result<Vec<_>, _>>()?;
+                    column
+                        .typed::<BoolType>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                ColumnType::Int => {
+                    let values = cells
+                        .map(|cell| cell.as_i64().ok_or_else(|| mismatch(cell)))
+                        .collect::<std::result::Result<Vec<_>, _>>()?;
+                    column
+                        .typed::<Int64Type>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                ColumnType::Float => {
+                    let values = cells
+                        .map(|cell| {
+                            cell.as_f64()
+                                .or_else(|| cell.as_i64().map(|n| n as f64))
+                                .or_else(|| cell.as_u64().map(|n| n as f64))
+                                .ok_or_else(|| mismatch(cell))
+                        })
+                        .collect::<std::result::Result<Vec<_>, _>>()?;
+                    column
+                        .typed::<DoubleType>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                ColumnType::String | ColumnType::Bytes => {
+                    let values = cells
+                        .map(|cell| match ColumnType::of(cell) {
+                            Ok(Some(cell_type)) if cell_type == column_type => {
+                                Ok(ByteArray::from(cell.as_bytes().to_vec()))
+                            }
+                            _ => Err(mismatch(cell)),
+                        })
+                        .collect::<std::result::Result<Vec<_>, _>>()?;
+                    column
+                        .typed::<ByteArrayType>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                ColumnType::DateTime => {
+                    let values = cells
+                        .map(|cell| {
+                            DateTime::<Utc>::try_from(cell.clone())
+                                .map(|dt| dt.timestamp_micros())
+                                .map_err(|_| mismatch(cell))
+                        })
+                        .collect::<std::result::Result<Vec<_>, _>>()?;
+                    column
+                        .typed::<Int64Type>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+            }
+            column.close()?;
+            index += 1;
+        }
+        row_group.close()?;
+        Ok(())
+    }
+
+    /// Write the file footer and return the underlying writer
+    pub fn close(self) -> Result<W> {
+        Ok(self.writer.into_inner()?)
+    }
+}
+
+// The type of every column, `None` if it only holds nulls
+fn column_types(table: &RionTable) -> Result<Vec<Option<ColumnType>>> {
+    let width = table.column_names.len();
+    let mut types = vec![None; width];
+    for (i, cell) in table.rows.iter().enumerate() {
+        let Some(cell_type) = ColumnType::of(cell)? else {
+            continue;
+        };
+        let column = &mut types[i % width];
+        *column = match *column {
+            None => Some(cell_type),
+            Some(column_type) => Some(column_type.merge(cell_type).ok_or_else(|| {
+                FerionError::invalid({
+                    let name = String::from_utf8_lossy(&table.column_names[i % width]);
+                    format!("Column {name:?} mixes {column_type:?} and {cell_type:?} values")
+                })
+            })?),
+        };
+    }
+    Ok(types)
+}
+
+impl RionTable<'_> {
+    /// Write the table to `writer` as a Parquet file with a single row group
+    pub fn write_parquet<W: Write + Send>(&self, writer: W) -> Result<W> {
+        let mut parquet = ParquetWriter::new(writer, self)?;
+        parquet.write_table(self)?;
+        parquet.close()
+    }
+}
@@ -0,0 +1,279 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{FerionError, Result, RionValue};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn document() -> RionValue {
+        let users = (0..4)
+            .map(|i| {
+                let mut user = BTreeMap::new();
+                user.insert(b"email"[..].into(), format!("user{i}@example.com").into());
+                RionValue::Object(user)
+            })
+            .collect::<Vec<_>>();
+        let mut doc = BTreeMap::new();
+        doc.insert(b"users"[..].into(), RionValue::Array(users));
+        doc.insert(b"odd.key"[..].into(), RionValue::Bool(true));
+        RionValue::Object(doc)
+    }
+
+    #[test]
+    fn test_parse_path() {
+        let path: Path = "users[3].email".parse().unwrap();
+        assert_eq!(
+            path.segments(),
+            [
+                Segment::Key("users".to_string()),
+                Segment::Index(3),
+                Segment::Key("email".to_string()),
+            ]
+        );
+        let path: Path = "users.*[\"a.b\"]".parse().unwrap();
+        assert_eq!(
+            path.segments(),
+            [
+                Segment::Key("users".to_string()),
+                Segment::Wildcard,
+                Segment::Key("a.b".to_string()),
+            ]
+        );
+        assert_eq!(path.to_string(), "users.*[\"a.b\"]");
+        assert!(Path::from_str("users[").is_err());
+        assert!(Path::from_str("users[x]").is_err());
+        assert!(Path::from_str("a..b").is_err());
+    }
+
+    #[test]
+    fn test_query() {
+        let doc = document();
+        let email = doc.query("users[3].email").unwrap();
+        assert_eq!(email, [&RionValue::from("user3@example.com")]);
+        assert_eq!(doc.query("users.1.email").unwrap().len(), 1);
+        assert_eq!(doc.query("users[*].email").unwrap().len(), 4);
+        assert_eq!(
+            doc.query("[\"odd.key\"]").unwrap(),
+            [&RionValue::Bool(true)]
+        );
+        assert!(doc.query("users[9].email").unwrap().is_empty());
+        assert_eq!(doc.query("").unwrap(), [&doc]);
+    }
+
+    #[test]
+    fn test_query_table() {
+        let table = RionValue::Table {
+            columns: vec![b"id"[..].into(), b"name"[..].into()],
+            rows: vec![
+                vec![RionValue::PosInt(1), "a".into()],
+                vec![RionValue::PosInt(2), "b".into()],
+            ],
+        };
+        assert_eq!(table.query("[1].name").unwrap(), [&RionValue::from("b")]);
+        assert_eq!(table.query("id").unwrap().len(), 2);
+        assert_eq!(table.query("*.id").unwrap().len(), 2);
+    }
+}
+
+/// A path selecting values inside a [`RionValue`]
+///
+/// Segments are object keys, table column names or array indices separated by
+/// `.`, e.g. `users.3.email`, with `[3]` as an alternative way to write an
+/// index and `["odd.key"]` for keys containing `.` or brackets. `*` matches
+/// every key, index or column, and an index on a table selects a row.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path(Vec<Segment>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// An object key or table column, numeric keys also index arrays
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl Path {
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn push_front(&mut self, segment: Segment) {
+        self.0.insert(0, segment);
+    }
+}
+
+// Written so that it parses back to the same path
+impl Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Index(index) => write!(f, "[{index}]")?,
+                Segment::Key(key)
+                    if key.is_empty() || key == "*" || key.contains(['.', '[', ']', '"']) =>
+                {
+                    write!(f, "[\"{key}\"]")?
+                }
+                segment => {
+                    if i > 0 {
+                        write!(f, ".")?;
+                    }
+                    match segment {
+                        Segment::Key(key) => write!(f, "{key}")?,
+                        _ => write!(f, "*")?,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Path {
+    type Err = FerionError;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut rest = path;
+        // Whether a key is allowed next, i.e. at the start or after a `.`
+        let mut expect_key = true;
+        while !rest.is_empty() {
+            if let Some(bracketed) = rest.strip_prefix('[') {
+                let end = bracketed.find(']').ok_or_else(|| {
+                    FerionError::invalid(format!("Unclosed '[' in path {path:?}"))
+                })?;
+                let inner = &bracketed[..end];
+                let segment = if inner == "*" {
+                    Segment::Wildcard
+                } else if let Some(key) = inner.strip_prefix('"').and_then(|k| k.strip_suffix('"'))
+                {
+                    Segment::Key(key.to_string())
+                } else {
+                    Segment::Index(inner.parse().map_err(|_| {
+                        FerionError::invalid(format!("Invalid index {inner:?} in path {path:?}"))
+                    })?)
+                };
+                segments.push(segment);
+                rest = &bracketed[end + 1..];
+                expect_key = false;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                if expect_key {
+                    return Err(FerionError::invalid(format!(
+                        "Empty segment in path {path:?}"
+                    )));
+                }
+                rest = after;
+                expect_key = true;
+            } else if expect_key {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let key = &rest[..end];
+                segments.push(match key {
+                    "*" => Segment::Wildcard,
+                    key => Segment::Key(key.to_string()),
+                });
+                rest = &rest[end..];
+                expect_key = false;
+            } else {
+                return Err(FerionError::invalid(format!(
+                    "Expected '.' or '[' in path {path:?}"
+                )));
+            }
+        }
+        if expect_key && !segments.is_empty() {
+            return Err(FerionError::invalid(format!("Path {path:?} ends with '.'")));
+        }
+        Ok(Path(segments))
+    }
+}
+
+impl Segment {
+    pub(crate) fn matches_key(&self, key: &[u8]) -> bool {
+        match self {
+            Segment::Key(name) => name.as_bytes() == key,
+            Segment::Index(_) => false,
+            Segment::Wildcard => true,
+        }
+    }
+
+    pub(crate) fn matches_index(&self, index: usize) -> bool {
+        match self {
+            Segment::Key(name) => name.parse() == Ok(index),
+            Segment::Index(i) => *i == index,
+            Segment::Wildcard => true,
+        }
+    }
+}
+
+impl RionValue {
+    /// Every value selected by `path`, see [`Path`] for the syntax
+    pub fn query(&self, path: &str) -> Result<Vec<&RionValue>> {
+        Ok(self.query_path(&path.parse()?))
+    }
+
+    pub fn query_path(&self, path: &Path) -> Vec<&RionValue> {
+        let mut out = Vec::new();
+        collect(self, path.segments(), &mut out);
+        out
+    }
+}
+
+fn collect<'a>(value: &'a RionValue, path: &[Segment], out: &mut Vec<&'a RionValue>) {
+    let Some((segment, rest)) = path.split_first() else {
+        out.push(value);
+        return;
+    };
+    match value {
+        RionValue::Object(fields) => {
+            for (_, field) in fields.iter().filter(|(key, _)| segment.matches_key(key)) {
+                collect(field, rest, out);
+            }
+        }
+        RionValue::Array(elements) => {
+            for (_, element) in elements
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| segment.matches_index(*i))
+            {
+                collect(element, rest, out);
+            }
+        }
+        RionValue::Table { columns, rows } => {
+            if let Segment::Index(_) | Segment::Wildcard = segment {
+                // Select rows, then a column of each
+                let Some((column, rest)) = rest.split_first() else {
+                    return;
+                };
+                for (_, row) in rows
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| segment.matches_index(*i))
+                {
+                    for (cell, _) in row
+                        .iter()
+                        .zip(columns)
+                        .filter(|(_, name)| column.matches_key(name))
+                    {
+                        collect(cell, rest, out);
+                    }
+                }
+                return;
+            }
+            for (i, _) in columns
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| segment.matches_key(name))
+            {
+                for row in rows {
+                    collect(&row[i], rest, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
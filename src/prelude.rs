@@ -0,0 +1,21 @@
+//! The items most code using this crate needs, in one import
+//!
+//! ```
+//! # #[cfg(feature = "serde")] {
+//! use ferion::prelude::*;
+//!
+//! let mut object = RionObject::new();
+//! object.add_field("age", 30u64);
+//! let bytes = to_bytes(&object).unwrap();
+//! let value = RionValue::from_slice(&bytes).unwrap();
+//! assert_eq!(value.query("age").unwrap(), [&RionValue::PosInt(30)]);
+//! assert_eq!(from_bytes::<u32>(&to_bytes(&30u32).unwrap()).unwrap(), 30);
+//! # }
+//! ```
+
+pub use crate::{
+    FromRawField, RionArray, RionDateTime, RionField, RionKey, RionObject, RionTable, RionValue,
+};
+
+#[cfg(feature = "serde")]
+pub use crate::{from_bytes, to_bytes, DeserializeError, FileError, SerializeError};
@@ -0,0 +1,413 @@
+//! Lazy field access over encoded objects
+//!
+//! [`RawObject`] finds a field by scanning the keys of an encoded object and
+//! skipping over the values it passes, without decoding any of them.
+//! [`rion_ref!`](crate::rion_ref) generates typed accessor structs on top of
+//! it, so `UserRef::wrap(&bytes)?.name()?` reads just the `name` field.
+
+use std::borrow::Cow;
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+
+use crate::{
+    get_normal_header,
+    types::{NormalRionType, RionFieldType, ShortRionType},
+    FerionError, Result, RionDateTime, RionField, RionFieldRef,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RionObject;
+
+    crate::rion_ref! {
+        struct UserRef<'a> {
+            name: &'a str,
+            age: i64,
+            avatar: &'a [u8],
+            address: AddressRef<'a>,
+        }
+    }
+
+    crate::rion_ref! {
+        struct AddressRef<'a> {
+            city: &'a str,
+        }
+    }
+
+    fn user() -> Vec<u8> {
+        let mut address = RionObject::new();
+        address.add_field("city", "Paris");
+        let mut user = RionObject::new();
+        user.add_field("name", "Alice");
+        user.add_field("age", 30i64);
+        user.add_field("address", address);
+        user.add_field("nickname", None::<&str>);
+        user.encode()
+    }
+
+    #[test]
+    fn test_raw_object() {
+        let data = user();
+        let raw = RawObject::new(&data).unwrap();
+        assert_eq!(raw.get("age").unwrap(), Some(RionField::from(30i64)));
+        assert_eq!(raw.get("missing").unwrap(), None);
+        assert_eq!(raw.get_as::<&str>("nickname").unwrap(), None);
+        assert!(raw.get_as::<i64>("name").is_err());
+        assert_eq!(raw.iter().count(), 4);
+        assert!(RawObject::new(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_sorted_lookup() {
+        let keys = (0..50).map(|i| format!("key{i:02}")).collect::<Vec<_>>();
+        let mut object = RionObject::new();
+        for (i, key) in keys.iter().enumerate() {
+            object.add_field(key, i as u64);
+        }
+        let data = object.encode();
+        let raw = RawObject::new(&data).unwrap();
+        assert_eq!(
+            raw.seek_to_key("key37").unwrap(),
+            Some(RionField::from(37u64))
+        );
+        assert_eq!(raw.seek_to_key("key").unwrap(), None);
+        assert_eq!(raw.seek_to_key("key375").unwrap(), None);
+
+        let index = raw.sorted_index().unwrap();
+        assert_eq!(index.len(), 50);
+        assert_eq!(index.seek_to_key("key00"), Some(&RionField::from(0u64)));
+        assert_eq!(index.seek_to_key("key49"), Some(&RionField::from(49u64)));
+        assert_eq!(index.seek_to_key("key50"), None);
+
+        // Keys out of order are only written by hand
+        let unsorted = [0xC1, 0x06, 0xE1, b'b', 0x10, 0xE1, b'a', 0x10];
+        let raw = RawObject::new(&unsorted).unwrap();
+        assert!(raw.sorted_index().is_err());
+        assert!(raw.get("a").unwrap().unwrap().is_null());
+        assert!(raw.seek_to_key("a").is_err());
+        assert!(raw.seek_to_key("c").is_err());
+    }
+
+    #[test]
+    fn test_generated_accessors() {
+        let data = user();
+        let user = UserRef::wrap(&data).unwrap();
+        assert_eq!(user.name().unwrap(), Some("Alice"));
+        assert_eq!(user.age().unwrap(), Some(30));
+        assert_eq!(user.avatar().unwrap(), None);
+        let address = user.address().unwrap().unwrap();
+        assert_eq!(address.city().unwrap(), Some("Paris"));
+        assert!(UserRef::wrap(&[0x12]).is_err());
+    }
+}
+
+/// An encoded object whose fields are located on demand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawObject<'a> {
+    content: &'a [u8],
+}
+
+impl<'a> RawObject<'a> {
+    /// Wrap an encoded object, only its header is checked
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        let (lead, data_len, rest) = get_normal_header(data)?;
+        let RionFieldType::Normal(NormalRionType::Object) = lead.field_type() else {
+            return Err(FerionError::invalid(format!(
+                "Expected a RION object, found {:?}",
+                lead.field_type()
+            )));
+        };
+        if rest.len() != data_len {
+            return Err(FerionError::invalid(format!(
+                "Object length {data_len} doesn't match {}",
+                rest.len()
+            )));
+        }
+        Ok(RawObject { content: rest })
+    }
+
+    /// The value of `key`, borrowing from the encoded object
+    pub fn get(&self, key: &str) -> Result<Option<RionField<'a>>> {
+        self.get_bytes(key.as_bytes())
+    }
+
+    pub fn get_bytes(&self, key: &[u8]) -> Result<Option<RionField<'a>>> {
+        for entry in self.iter() {
+            let (name, value) = entry?;
+            if name == key {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Like [`get`](Self::get) for an object whose keys are sorted, as
+    /// [`RionObject::encode`](crate::RionObject::encode) and
+    /// [`RionValue::encode`](crate::RionValue::encode) write them
+    ///
+    /// Serialized structs keep their fields in declaration order, so their
+    /// keys usually aren't sorted. Fails on keys out of order rather than
+    /// report a key that is there as missing, which means a key that isn't
+    /// there is only reported once every key after it has been checked.
+    /// Values are skipped by their length, only keys are compared.
+    pub fn seek_to_key(&self, key: &str) -> Result<Option<RionField<'a>>> {
+        let key = key.as_bytes();
+        let mut previous: Option<&[u8]> = None;
+        for entry in self.iter() {
+            let (name, value) = entry?;
+            if previous.is_some_and(|previous| previous >= name) {
+                return Err(FerionError::invalid(format!(
+                    "Key {name:x?} is out of order"
+                )));
+            }
+            previous = Some(name);
+            if name == key {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Locate every entry once so that lookups binary search the keys, for
+    /// an object looked up many times
+    ///
+    /// Fails if the keys aren't sorted, as they are in canonical encodings.
+    pub fn sorted_index(&self) -> Result<RawIndex<'a>> {
+        let entries = self.iter().collect::<Result<Vec<_>>>()?;
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 >= pair[1].0) {
+            return Err(FerionError::invalid(format!(
+                "Key {:x?} is out of order",
+                pair[1].0
+            )));
+        }
+        Ok(RawIndex { entries })
+    }
+
+    /// The value of `key` converted to `T`, `None` if it is missing or null
+    pub fn get_as<T: FromRawField<'a>>(&self, key: &str) -> Result<Option<T>> {
+        match self.get(key)? {
+            Some(field) if !field.is_null() => T::from_raw_field(field).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The keys and values in encoded order
+    pub fn iter(&self) -> impl Iterator<Item = Result<(&'a [u8], RionField<'a>)>> {
+        let mut rest = self.content;
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let entry = (|| {
+                let (key, after_key) = RionFieldRef::parse(rest)?;
+                let (RionFieldRef::Short(ShortRionType::Key, name)
+                | RionFieldRef::Normal(NormalRionType::Key, name)) = key
+                else {
+                    return Err(FerionError::invalid(format!(
+                        "Expected a key, found {key:?}"
+                    )));
+                };
+                let (value, after_value) = RionField::parse(after_key)?;
+                rest = after_value;
+                Ok((name, value))
+            })();
+            if entry.is_err() {
+                // Stop after the first malformed entry
+                rest = &[];
+            }
+            Some(entry)
+        })
+    }
+}
+
+/// The entries of a [`RawObject`] with sorted keys, see
+/// [`RawObject::sorted_index`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawIndex<'a> {
+    entries: Vec<(&'a [u8], RionField<'a>)>,
+}
+
+impl<'a> RawIndex<'a> {
+    /// The value of `key`, found by binary search
+    pub fn seek_to_key(&self, key: &str) -> Option<&RionField<'a>> {
+        let index = self
+            .entries
+            .binary_search_by(|(name, _)| (*name).cmp(key.as_bytes()))
+            .ok()?;
+        Some(&self.entries[index].1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// The data of a parsed field, which borrows from the input
+fn borrowed(data: Cow<'_, [u8]>) -> Result<&[u8]> {
+    match data {
+        Cow::Borrowed(data) => Ok(data),
+        Cow::Owned(data) if data.is_empty() => Ok(&[]),
+        Cow::Owned(_) => Err(FerionError::invalid("Expected a field borrowing its data")),
+    }
+}
+
+/// Types a field located by [`RawObject`] can be read as
+pub trait FromRawField<'a>: Sized {
+    fn from_raw_field(field: RionField<'a>) -> Result<Self>;
+}
+
+impl<'a> FromRawField<'a> for RionField<'a> {
+    fn from_raw_field(field: RionField<'a>) -> Result<Self> {
+        Ok(field)
+    }
+}
+
+impl<'a> FromRawField<'a> for &'a str {
+    fn from_raw_field(field: RionField<'a>) -> Result<Self> {
+        match field.field_type() {
+            RionFieldType::Short(ShortRionType::UTF8)
+            | RionFieldType::Normal(NormalRionType::UTF8) => Ok(std::str::from_utf8(borrowed(
+                field.to_data().unwrap_or_default(),
+            )?)?),
+            field_type => Err(FerionError::invalid(format!(
+                "Expected a string, found {field_type:?}"
+            ))),
+        }
+    }
+}
+
+impl<'a> FromRawField<'a> for &'a [u8] {
+    fn from_raw_field(field: RionField<'a>) -> Result<Self> {
+        match field.field_type() {
+            RionFieldType::Normal(NormalRionType::Bytes) => {
+                borrowed(field.to_data().unwrap_or_default())
+            }
+            field_type => Err(FerionError::invalid(format!(
+                "Expected bytes, found {field_type:?}"
+            ))),
+        }
+    }
+}
+
+impl<'a> FromRawField<'a> for RawObject<'a> {
+    fn from_raw_field(field: RionField<'a>) -> Result<Self> {
+        match field.field_type() {
+            RionFieldType::Normal(NormalRionType::Object) => Ok(RawObject {
+                content: borrowed(field.to_data().unwrap_or_default())?,
+            }),
+            field_type => Err(FerionError::invalid(format!(
+                "Expected an object, found {field_type:?}"
+            ))),
+        }
+    }
+}
+
+impl FromRawField<'_> for String {
+    fn from_raw_field(field: RionField<'_>) -> Result<Self> {
+        field.as_str().map(str::to_string).ok_or_else(|| {
+            FerionError::invalid(format!("Expected a string, found {:?}", field.field_type()))
+        })
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromRawField<'_> for DateTime<Utc> {
+    fn from_raw_field(field: RionField<'_>) -> Result<Self> {
+        field.try_into()
+    }
+}
+
+impl FromRawField<'_> for RionDateTime {
+    fn from_raw_field(field: RionField<'_>) -> Result<Self> {
+        field.try_into()
+    }
+}
+
+macro_rules! impl_from_raw_field {
+    ($($ty:ty => $accessor:ident),*) => {$(
+        impl FromRawField<'_> for $ty {
+            fn from_raw_field(field: RionField<'_>) -> Result<Self> {
+                field.$accessor().ok_or_else(|| {
+                    FerionError::invalid(format!("Expected {}, found {:?}", stringify!($ty), field.field_type()))
+                })
+            }
+        }
+    )*};
+}
+
+impl_from_raw_field!(i64 => as_i64, u64 => as_u64, f64 => as_f64, bool => as_bool);
+
+/// Generate a struct reading named fields lazily out of an encoded object
+///
+/// Each field becomes a method returning `Result<Option<T>>`, `None` when the
+/// key is missing or null, where `T` implements [`FromRawField`]. Fields can be
+/// other generated structs to reach into nested objects.
+///
+/// ```
+/// ferion::rion_ref! {
+///     pub struct UserRef<'a> {
+///         name: &'a str,
+///         age: i64,
+///     }
+/// }
+///
+/// let mut user = ferion::RionObject::new();
+/// user.add_field("name", "Alice");
+/// let data = user.encode();
+/// let user = UserRef::wrap(&data).unwrap();
+/// assert_eq!(user.name().unwrap(), Some("Alice"));
+/// assert_eq!(user.age().unwrap(), None);
+/// ```
+#[macro_export]
+macro_rules! rion_ref {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident<$lt:lifetime> {
+            $($(#[$field_meta:meta])* $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis struct $name<$lt> {
+            raw: $crate::RawObject<$lt>,
+        }
+
+        // Not every accessor is used by every caller
+        #[allow(dead_code)]
+        impl<$lt> $name<$lt> {
+            /// Wrap an encoded object without decoding its fields
+            $vis fn wrap(data: &$lt [u8]) -> std::result::Result<Self, $crate::FerionError> {
+                Ok($name {
+                    raw: $crate::RawObject::new(data)?,
+                })
+            }
+
+            $vis fn raw(&self) -> $crate::RawObject<$lt> {
+                self.raw
+            }
+
+            $(
+                $(#[$field_meta])*
+                $vis fn $field(&self) -> std::result::Result<Option<$ty>, $crate::FerionError> {
+                    self.raw.get_as(stringify!($field))
+                }
+            )*
+        }
+
+        impl<$lt> $crate::FromRawField<$lt> for $name<$lt> {
+            fn from_raw_field(
+                field: $crate::RionField<$lt>,
+            ) -> std::result::Result<Self, $crate::FerionError> {
+                Ok($name {
+                    raw: $crate::RawObject::from_raw_field(field)?,
+                })
+            }
+        }
+    };
+}
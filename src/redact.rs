@@ -0,0 +1,166 @@
+use crate::{
+    path::{Path, Segment},
+    Result, RionValue,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn document() -> RionValue {
+        let mut user = BTreeMap::new();
+        user.insert(b"name"[..].into(), "Alice".into());
+        user.insert(b"password"[..].into(), "hunter2".into());
+        let mut other = user.clone();
+        other.insert(b"name"[..].into(), "Bob".into());
+        let mut doc = BTreeMap::new();
+        doc.insert(
+            b"users"[..].into(),
+            RionValue::Array(vec![RionValue::Object(user), RionValue::Object(other)]),
+        );
+        doc.insert(b"token"[..].into(), "secret".into());
+        RionValue::Object(doc)
+    }
+
+    #[test]
+    fn test_redact_null() {
+        let mut doc = document();
+        redact(&mut doc, &["token", "users.*.password"], Redaction::Null).unwrap();
+        assert_eq!(doc.get("token"), Some(&RionValue::Null));
+        let RionValue::Array(users) = doc.get("users").unwrap() else {
+            panic!("Expected an array of users");
+        };
+        for user in users {
+            assert_eq!(user.get("password"), Some(&RionValue::Null));
+            assert!(user.get("name").unwrap().as_str().is_some());
+        }
+    }
+
+    #[test]
+    fn test_redact_placeholder_by_index() {
+        let mut doc = document();
+        let policy = Redaction::Placeholder("***".to_string());
+        redact(&mut doc, &["users.1.name"], policy).unwrap();
+        let RionValue::Array(users) = doc.get("users").unwrap() else {
+            panic!("Expected an array of users");
+        };
+        assert_eq!(users[0].get("name").unwrap().as_str(), Some("Alice"));
+        assert_eq!(users[1].get("name").unwrap().as_str(), Some("***"));
+    }
+
+    #[test]
+    fn test_redact_hash() {
+        let mut doc = document();
+        let policy = Redaction::Hash(|bytes| vec![bytes.len() as u8]);
+        redact(&mut doc, &["token"], policy).unwrap();
+        // Hash of the encoded "secret" field (lead byte + 6 bytes)
+        assert_eq!(doc.get("token"), Some(&RionValue::Bytes(vec![7])));
+    }
+
+    #[test]
+    fn test_redact_table_column() {
+        let mut doc = RionValue::Table {
+            columns: vec![b"id"[..].into(), b"email"[..].into()],
+            rows: vec![
+                vec![RionValue::PosInt(1), "a@example.com".into()],
+                vec![RionValue::PosInt(2), "b@example.com".into()],
+            ],
+        };
+        redact(&mut doc, &["email"], Redaction::Null).unwrap();
+        let RionValue::Table { rows, .. } = &doc else {
+            panic!("Expected a table");
+        };
+        assert!(rows.iter().all(|row| row[1].is_null() && !row[0].is_null()));
+    }
+}
+
+/// What to replace a redacted value with
+#[derive(Debug, Clone)]
+pub enum Redaction {
+    /// Replace the value with null
+    Null,
+    /// Replace the value with a fixed string
+    Placeholder(String),
+    /// Replace the value with a Bytes field holding the digest of its encoding
+    Hash(fn(&[u8]) -> Vec<u8>),
+}
+
+/// Replace every value matched by `paths` according to `policy`
+///
+/// Paths use the [`Path`] syntax, e.g. `users.*.password` or `users[1].name`.
+pub fn redact(value: &mut RionValue, paths: &[&str], policy: Redaction) -> Result<()> {
+    for_each_match(value, paths, &mut |value| {
+        *value = match &policy {
+            Redaction::Null => RionValue::Null,
+            Redaction::Placeholder(placeholder) => placeholder.as_str().into(),
+            Redaction::Hash(hash) => RionValue::Bytes(hash(&value.encode_to_vec()?)),
+        };
+        Ok(())
+    })
+}
+
+/// Call `f` on every value matched by `paths`
+pub(crate) fn for_each_match(
+    value: &mut RionValue,
+    paths: &[&str],
+    f: &mut impl FnMut(&mut RionValue) -> Result<()>,
+) -> Result<()> {
+    for path in paths {
+        let path: Path = path.parse()?;
+        visit_path(value, path.segments(), f)?;
+    }
+    Ok(())
+}
+
+fn visit_path(
+    value: &mut RionValue,
+    path: &[Segment],
+    f: &mut impl FnMut(&mut RionValue) -> Result<()>,
+) -> Result<()> {
+    let Some((segment, rest)) = path.split_first() else {
+        return f(value);
+    };
+    match value {
+        RionValue::Object(fields) => {
+            for (_, field) in fields
+                .iter_mut()
+                .filter(|(key, _)| segment.matches_key(key))
+            {
+                visit_path(field, rest, f)?;
+            }
+        }
+        RionValue::Array(elements) => {
+            for (i, element) in elements.iter_mut().enumerate() {
+                if segment.matches_index(i) {
+                    visit_path(element, rest, f)?;
+                }
+            }
+        }
+        RionValue::Table { columns, rows } => {
+            // Like `RionValue::query`, an index or `*` selects rows before a column
+            let (rows_segment, column, rest) = match segment {
+                Segment::Key(_) => (&Segment::Wildcard, segment, rest),
+                _ => match rest.split_first() {
+                    Some((column, rest)) => (segment, column, rest),
+                    None => return Ok(()),
+                },
+            };
+            for (_, row) in rows
+                .iter_mut()
+                .enumerate()
+                .filter(|(i, _)| rows_segment.matches_index(*i))
+            {
+                for (cell, _) in row
+                    .iter_mut()
+                    .zip(columns.iter())
+                    .filter(|(_, name)| column.matches_key(name))
+                {
+                    visit_path(cell, rest, f)?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
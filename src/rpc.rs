@@ -0,0 +1,272 @@
+//! Request and response envelopes for RPC over RION
+//!
+//! Messages are objects with fixed keys, `id` and `method` plus `params` for a
+//! request, and `id` plus either `result` or `error` for a response. Payloads
+//! are kept as raw encoded values so a router can forward them without
+//! decoding, and [`read_message`] / [`write_message`] move messages over any
+//! byte stream using [`frame`](crate::frame).
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{Read, Write},
+};
+
+use crate::{
+    frame::read_frame,
+    object::{get_raw, object_field, write_entry, write_raw_entry},
+    FerionError, Result, RionField, RionObject,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_request_round_trip() {
+        let request = Message::Request(Request::new(7, "users.get", vec![0x21, 0x2A]));
+        let decoded = Message::decode(&request.encode()).unwrap();
+        assert_eq!(decoded, request);
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let ok = Message::Response(Response::ok(7, vec![0x65, b'A', b'l', b'i', b'c', b'e']));
+        assert_eq!(Message::decode(&ok.encode()).unwrap(), ok);
+
+        let error = RpcError {
+            code: -32601,
+            message: "Method not found".to_string(),
+            data: Some(vec![0x12]),
+        };
+        let failed = Message::Response(Response::error(8, error));
+        assert_eq!(Message::decode(&failed.encode()).unwrap(), failed);
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut stream = Vec::new();
+        let messages = [
+            Message::Request(Request::new(1, "ping", vec![0x10])),
+            Message::Response(Response::ok(1, vec![0x12])),
+        ];
+        for message in &messages {
+            write_message(&mut stream, message).unwrap();
+        }
+        let mut reader = &stream[..];
+        for message in &messages {
+            assert_eq!(
+                read_message(&mut reader, 1024).unwrap().as_ref(),
+                Some(message)
+            );
+        }
+        assert!(read_message(&mut reader, 1024).unwrap().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_typed_payloads() {
+        let request = Request::with_params(1, "add", &(2u32, 3u32)).unwrap();
+        assert_eq!(request.params_as::<(u32, u32)>().unwrap(), (2, 3));
+        let response = Response::with_result(1, &5u32).unwrap();
+        assert_eq!(response.result_as::<u32>().unwrap().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let mut object = RionObject::new();
+        object.add_field("id", 1u64);
+        let encoded = object.encode();
+        assert!(Message::decode(&encoded).is_err());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    /// Chosen by the caller, echoed back in the response
+    pub id: u64,
+    pub method: String,
+    /// A single encoded value
+    pub params: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub id: u64,
+    /// A single encoded value, or the reason the call failed
+    pub result: std::result::Result<Vec<u8>, RpcError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    /// Extra details as a single encoded value
+    pub data: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Request(Request),
+    Response(Response),
+}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RPC error {}: {}", self.code, self.message)
+    }
+}
+
+impl Error for RpcError {}
+
+impl Request {
+    pub fn new(id: u64, method: impl Into<String>, params: Vec<u8>) -> Self {
+        Request {
+            id,
+            method: method.into(),
+            params,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn with_params<T: serde::Serialize>(
+        id: u64,
+        method: impl Into<String>,
+        params: &T,
+    ) -> std::result::Result<Self, crate::SerializeError> {
+        Ok(Self::new(id, method, crate::to_bytes(params)?))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn params_as<'a, T: serde::Deserialize<'a>>(
+        &'a self,
+    ) -> std::result::Result<T, crate::DeserializeError> {
+        crate::from_bytes(&self.params)
+    }
+}
+
+impl Response {
+    pub fn ok(id: u64, result: Vec<u8>) -> Self {
+        Response {
+            id,
+            result: Ok(result),
+        }
+    }
+
+    pub fn error(id: u64, error: RpcError) -> Self {
+        Response {
+            id,
+            result: Err(error),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn with_result<T: serde::Serialize>(
+        id: u64,
+        result: &T,
+    ) -> std::result::Result<Self, crate::SerializeError> {
+        Ok(Self::ok(id, crate::to_bytes(result)?))
+    }
+
+    /// Decode the result, an error response is returned as the outer `Ok`
+    #[cfg(feature = "serde")]
+    pub fn result_as<'a, T: serde::Deserialize<'a>>(
+        &'a self,
+    ) -> std::result::Result<std::result::Result<T, &'a RpcError>, crate::DeserializeError> {
+        match &self.result {
+            Ok(result) => crate::from_bytes(result).map(Ok),
+            Err(error) => Ok(Err(error)),
+        }
+    }
+}
+
+impl Message {
+    pub fn id(&self) -> u64 {
+        match self {
+            Message::Request(request) => request.id,
+            Message::Response(response) => response.id,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut content = Vec::new();
+        let id = RionField::from(self.id());
+        match self {
+            Message::Request(request) => {
+                write_entry(&mut content, "id", &id);
+                write_entry(&mut content, "method", &request.method.as_str().into());
+                write_raw_entry(&mut content, "params", &request.params);
+            }
+            Message::Response(response) => {
+                write_entry(&mut content, "id", &id);
+                match &response.result {
+                    Ok(result) => write_raw_entry(&mut content, "result", result),
+                    Err(error) => {
+                        let mut fields = Vec::new();
+                        write_entry(&mut fields, "code", &error.code.into());
+                        write_entry(&mut fields, "message", &error.message.as_str().into());
+                        if let Some(data) = &error.data {
+                            write_raw_entry(&mut fields, "data", data);
+                        }
+                        write_entry(&mut content, "error", &object_field(fields));
+                    }
+                }
+            }
+        }
+        let mut encoded = Vec::new();
+        object_field(content).encode(&mut encoded).unwrap();
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let object = RionObject::from_slice(data)?;
+        let id = object
+            .get("id")
+            .and_then(RionField::as_u64)
+            .ok_or_else(|| FerionError::invalid("Message has no integer id"))?;
+        if let Some(method) = object.get("method") {
+            let method = method
+                .as_str()
+                .ok_or_else(|| FerionError::invalid("Request method is not a string"))?;
+            return Ok(Message::Request(Request::new(
+                id,
+                method,
+                get_raw(&object, "params")?.unwrap_or_else(|| vec![0x10]),
+            )));
+        }
+        let result = match get_raw(&object, "error")? {
+            Some(error) => {
+                let error = RionObject::from_slice(&error)?;
+                Err(RpcError {
+                    code: error
+                        .get("code")
+                        .and_then(RionField::as_i64)
+                        .ok_or_else(|| FerionError::invalid("Error has no integer code"))?,
+                    message: error
+                        .get("message")
+                        .and_then(RionField::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    data: get_raw(&error, "data")?,
+                })
+            }
+            None => Ok(get_raw(&object, "result")?
+                .ok_or_else(|| FerionError::invalid("Response has no result or error"))?),
+        };
+        Ok(Message::Response(Response { id, result }))
+    }
+}
+
+/// Write a message to a stream, see [`read_message`]
+pub fn write_message(writer: &mut impl Write, message: &Message) -> std::io::Result<()> {
+    writer.write_all(&message.encode())
+}
+
+/// Read the next message of at most `limit` bytes, `None` at the end of the
+/// stream
+pub fn read_message(reader: &mut impl Read, limit: usize) -> Result<Option<Message>> {
+    match read_frame(reader, limit)? {
+        Some(frame) => Ok(Some(Message::decode(&frame)?)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,451 @@
+use serde_json::{json, Map, Value};
+
+use super::{Field, Schema};
+use crate::{FerionError, RionValue};
+
+impl Schema {
+    /// Export the schema as a JSON Schema document
+    ///
+    /// Bytes are described as base64 strings and datetimes as `date-time`
+    /// strings, matching how they would be written to JSON.
+    pub fn to_json_schema(&self) -> Value {
+        match self {
+            Schema::Any => json!({}),
+            Schema::Null => json!({ "type": "null" }),
+            Schema::Bool => json!({ "type": "boolean" }),
+            Schema::Int => json!({ "type": "integer" }),
+            Schema::Float => json!({ "type": "number" }),
+            Schema::String => json!({ "type": "string" }),
+            Schema::Bytes => json!({ "type": "string", "contentEncoding": "base64" }),
+            Schema::DateTime => json!({ "type": "string", "format": "date-time" }),
+            Schema::Array(items) => json!({ "type": "array", "items": items.to_json_schema() }),
+            Schema::Object(fields) => {
+                let properties = fields
+                    .iter()
+                    .map(|(key, field)| (key.clone(), field.schema.to_json_schema()))
+                    .collect::<Map<_, _>>();
+                let required = fields
+                    .iter()
+                    .filter(|(_, field)| !field.optional)
+                    .map(|(key, _)| Value::from(key.as_str()))
+                    .collect::<Vec<_>>();
+                json!({ "type": "object", "properties": properties, "required": required })
+            }
+            // Tables have no JSON equivalent, describe them as an array of row objects
+            Schema::Table(columns) => {
+                let properties = columns
+                    .iter()
+                    .map(|(name, schema)| (name.clone(), schema.to_json_schema()))
+                    .collect::<Map<_, _>>();
+                let required = columns
+                    .iter()
+                    .map(|(name, _)| Value::from(name.as_str()))
+                    .collect::<Vec<_>>();
+                json!({
+                    "type": "array",
+                    "items": { "type": "object", "properties": properties, "required": required },
+                })
+            }
+            Schema::Nullable(inner) => {
+                json!({ "anyOf": [inner.to_json_schema(), { "type": "null" }] })
+            }
+            Schema::Union(members) => json!({
+                "anyOf": members.iter().map(Schema::to_json_schema).collect::<Vec<_>>()
+            }),
+        }
+    }
+
+    /// Read a schema back from a JSON Schema document
+    ///
+    /// Understands what [`to_json_schema`](Self::to_json_schema) writes, plus
+    /// a list of names for `type`. Other keywords are ignored, and tables come
+    /// back as arrays of objects.
+    pub fn from_json_schema(schema: &Value) -> crate::Result<Schema> {
+        let schema = match schema {
+            Value::Bool(true) => return Ok(Schema::Any),
+            Value::Object(schema) => schema,
+            _ => {
+                return Err(FerionError::invalid(format!(
+                    "Unsupported JSON Schema {schema}"
+                )))
+            }
+        };
+        if let Some(Value::Array(members)) = schema.get("anyOf") {
+            let members = members
+                .iter()
+                .map(Schema::from_json_schema)
+                .collect::<crate::Result<Vec<_>>>()?;
+            return Ok(union(members));
+        }
+        let name = match schema.get("type") {
+            None => return Ok(Schema::Any),
+            Some(Value::String(name)) => name,
+            Some(Value::Array(names)) => {
+                let members = names
+                    .iter()
+                    .map(|name| {
+                        let mut single = schema.clone();
+                        single.insert("type".to_string(), name.clone());
+                        Schema::from_json_schema(&Value::Object(single))
+                    })
+                    .collect::<crate::Result<Vec<_>>>()?;
+                return Ok(union(members));
+            }
+            Some(other) => {
+                return Err(FerionError::invalid(format!(
+                    "Unsupported JSON Schema type {other}"
+                )))
+            }
+        };
+        Ok(match name.as_str() {
+            "null" => Schema::Null,
+            "boolean" => Schema::Bool,
+            "integer" => Schema::Int,
+            "number" => Schema::Float,
+            "string" if schema.get("format") == Some(&json!("date-time")) => Schema::DateTime,
+            "string" if schema.get("contentEncoding") == Some(&json!("base64")) => Schema::Bytes,
+            "string" => Schema::String,
+            "array" => match schema.get("items") {
+                Some(items) => Schema::Array(Box::new(Schema::from_json_schema(items)?)),
+                None => Schema::Array(Box::new(Schema::Any)),
+            },
+            "object" => {
+                let required = schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let mut fields = std::collections::BTreeMap::new();
+                let properties = schema.get("properties").and_then(Value::as_object);
+                for (key, property) in properties.into_iter().flatten() {
+                    let field = Field {
+                        schema: Schema::from_json_schema(property)?,
+                        optional: !required.contains(&json!(key)),
+                    };
+                    fields.insert(key.clone(), field);
+                }
+                Schema::Object(fields)
+            }
+            _ => {
+                return Err(FerionError::invalid(format!(
+                    "Unknown JSON Schema type {name}"
+                )))
+            }
+        })
+    }
+}
+
+// A null member makes the rest nullable, like `Schema::merge`
+fn union(mut members: Vec<Schema>) -> Schema {
+    let nullable = members.contains(&Schema::Null);
+    members.retain(|member| *member != Schema::Null);
+    let schema = match members.len() {
+        0 => return Schema::Null,
+        1 => members.remove(0),
+        _ => Schema::Union(members),
+    };
+    match nullable {
+        true => Schema::Nullable(Box::new(schema)),
+        false => schema,
+    }
+}
+
+/// A location in a document that does not conform to a schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// JSON Pointer to the offending value, e.g. `/users/3/email`
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Check a decoded value against a JSON Schema document
+///
+/// RION types are mapped to the JSON types they would be written as: Bytes
+/// and DateTime are strings, and Tables are arrays of row objects. Supports
+/// the `type`, `enum`, `const`, `properties`, `required`,
+/// `additionalProperties`, `items`, `minItems`, `maxItems`, `minLength`,
+/// `maxLength`, `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`,
+/// `allOf`, `anyOf`, `oneOf` and `not` keywords, other keywords are ignored.
+pub fn validate(value: &RionValue, schema: &Value) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    check(value, schema, &mut String::new(), &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check(value: &RionValue, schema: &Value, path: &mut String, errors: &mut Vec<ValidationError>) {
+    let mut fail = |message: String| {
+        errors.push(ValidationError {
+            path: path.clone(),
+            message,
+        })
+    };
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => return fail("No value is allowed here".to_string()),
+        Value::Object(schema) => schema,
+        _ => return fail("Schema must be an object or a boolean".to_string()),
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let matches = match expected {
+            Value::String(name) => has_type(value, name),
+            Value::Array(names) => names
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|name| has_type(value, name)),
+            _ => true,
+        };
+        if !matches {
+            // Other keywords mostly describe values of the expected type
+            return fail(format!(
+                "Expected type {expected}, found {}",
+                type_name(value)
+            ));
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.iter().any(|option| json_eq(value, option)) {
+            fail(format!(
+                "Value is not one of {}",
+                Value::Array(options.clone())
+            ));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if !json_eq(value, constant) {
+            fail(format!("Value is not {constant}"));
+        }
+    }
+
+    if let Some(number) = as_number(value) {
+        let bound = |key| schema.get(key).and_then(Value::as_f64);
+        if bound("minimum").is_some_and(|min| number < min) {
+            fail(format!(
+                "{number} is less than the minimum {}",
+                schema["minimum"]
+            ));
+        }
+        if bound("maximum").is_some_and(|max| number > max) {
+            fail(format!(
+                "{number} is greater than the maximum {}",
+                schema["maximum"]
+            ));
+        }
+        if bound("exclusiveMinimum").is_some_and(|min| number <= min) {
+            fail(format!(
+                "{number} is not greater than {}",
+                schema["exclusiveMinimum"]
+            ));
+        }
+        if bound("exclusiveMaximum").is_some_and(|max| number >= max) {
+            fail(format!(
+                "{number} is not less than {}",
+                schema["exclusiveMaximum"]
+            ));
+        }
+    }
+
+    if let RionValue::String(s) = value {
+        let length = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                fail(format!("String is shorter than {min} characters"));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                fail(format!("String is longer than {max} characters"));
+            }
+        }
+    }
+
+    let rows;
+    let elements = match value {
+        RionValue::Array(elements) => Some(elements.as_slice()),
+        RionValue::Table {
+            columns,
+            rows: table,
+        } => {
+            rows = table
+                .iter()
+                .map(|row| {
+                    RionValue::Object(columns.iter().cloned().zip(row.iter().cloned()).collect())
+                })
+                .collect::<Vec<_>>();
+            Some(rows.as_slice())
+        }
+        _ => None,
+    };
+    if let Some(elements) = elements {
+        let count = elements.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if count < min {
+                fail(format!("Array has fewer than {min} items"));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if count > max {
+                fail(format!("Array has more than {max} items"));
+            }
+        }
+        if let Some(items) = schema.get("items") {
+            for (i, element) in elements.iter().enumerate() {
+                with_segment(path, &i.to_string(), |path| {
+                    check(element, items, path, errors)
+                });
+            }
+        }
+    }
+
+    if let RionValue::Object(fields) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(key.as_bytes()) {
+                    errors.push(ValidationError {
+                        path: path.clone(),
+                        message: format!("Missing required property {key:?}"),
+                    });
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let additional = schema.get("additionalProperties");
+        for (key, field) in fields {
+            let key = String::from_utf8_lossy(key);
+            let field_schema = match properties.and_then(|properties| properties.get(key.as_ref()))
+            {
+                Some(field_schema) => field_schema,
+                None => match additional {
+                    Some(additional) => additional,
+                    None => continue,
+                },
+            };
+            with_segment(path, &key, |path| check(field, field_schema, path, errors));
+        }
+    }
+
+    if let Some(Value::Array(all)) = schema.get("allOf") {
+        for sub in all {
+            check(value, sub, path, errors);
+        }
+    }
+    if let Some(Value::Array(any)) = schema.get("anyOf") {
+        if !any.iter().any(|sub| is_valid(value, sub, path)) {
+            errors.push(ValidationError {
+                path: path.clone(),
+                message: "Value does not match any schema in anyOf".to_string(),
+            });
+        }
+    }
+    if let Some(Value::Array(one)) = schema.get("oneOf") {
+        let matched = one.iter().filter(|sub| is_valid(value, sub, path)).count();
+        if matched != 1 {
+            errors.push(ValidationError {
+                path: path.clone(),
+                message: format!("Value matches {matched} schemas in oneOf, expected exactly 1"),
+            });
+        }
+    }
+    if let Some(not) = schema.get("not") {
+        if is_valid(value, not, path) {
+            errors.push(ValidationError {
+                path: path.clone(),
+                message: "Value matches the schema in not".to_string(),
+            });
+        }
+    }
+}
+
+fn is_valid(value: &RionValue, schema: &Value, path: &mut String) -> bool {
+    let mut errors = Vec::new();
+    check(value, schema, path, &mut errors);
+    errors.is_empty()
+}
+
+// Append a JSON Pointer segment for the duration of `f`
+fn with_segment(path: &mut String, segment: &str, f: impl FnOnce(&mut String)) {
+    let len = path.len();
+    path.push('/');
+    path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    f(path);
+    path.truncate(len);
+}
+
+fn has_type(value: &RionValue, name: &str) -> bool {
+    match name {
+        "integer" => match value {
+            RionValue::Float(f) => f.fract() == 0.0,
+            value => matches!(value, RionValue::PosInt(_) | RionValue::NegInt(_)),
+        },
+        "number" => as_number(value).is_some(),
+        name => type_name(value) == name,
+    }
+}
+
+fn type_name(value: &RionValue) -> &'static str {
+    match value {
+        RionValue::Null => "null",
+        RionValue::Bool(_) => "boolean",
+        RionValue::PosInt(_) | RionValue::NegInt(_) => "integer",
+        RionValue::Float(_) => "number",
+        RionValue::String(_) | RionValue::Bytes(_) | RionValue::DateTime(_) => "string",
+        RionValue::Array(_) | RionValue::Table { .. } => "array",
+        RionValue::Object(_) => "object",
+        RionValue::Extended { .. } => "extended",
+    }
+}
+
+fn as_number(value: &RionValue) -> Option<f64> {
+    match value {
+        RionValue::PosInt(n) => Some(*n as f64),
+        RionValue::NegInt(n) => Some(*n as f64),
+        RionValue::Float(n) => Some(*n),
+        _ => None,
+    }
+}
+
+// Compare against a JSON value the way `enum` and `const` need to
+fn json_eq(value: &RionValue, json: &Value) -> bool {
+    match (value, json) {
+        (RionValue::Null, Value::Null) => true,
+        (RionValue::Bool(a), Value::Bool(b)) => a == b,
+        (RionValue::PosInt(a), Value::Number(b)) => {
+            b.as_u64().map(u128::from) == Some(*a) || b.as_f64() == Some(*a as f64)
+        }
+        (RionValue::NegInt(a), Value::Number(b)) => {
+            b.as_i64().map(i128::from) == Some(*a) || b.as_f64() == Some(*a as f64)
+        }
+        (RionValue::Float(a), Value::Number(b)) => b.as_f64() == Some(*a),
+        (RionValue::String(a), Value::String(b)) => **a == **b,
+        (RionValue::DateTime(a), Value::String(b)) => a.to_rfc3339() == *b,
+        (RionValue::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_eq(a, b))
+        }
+        (RionValue::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, a)| {
+                    std::str::from_utf8(key)
+                        .ok()
+                        .and_then(|key| b.get(key))
+                        .is_some_and(|b| json_eq(a, b))
+                })
+        }
+        _ => false,
+    }
+}
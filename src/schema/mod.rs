@@ -0,0 +1,400 @@
+//! Schemas describing the shape of RION documents
+
+use std::collections::BTreeMap;
+
+use crate::RionValue;
+
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+pub use json::{validate, ValidationError};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn object(fields: &[(&str, RionValue)]) -> RionValue {
+        RionValue::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.as_bytes().into(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_infer_scalars() {
+        assert_eq!(infer(&[RionValue::PosInt(1)]), Schema::Int);
+        assert_eq!(
+            infer(&[RionValue::PosInt(1), RionValue::Float(1.5)]),
+            Schema::Float
+        );
+        assert_eq!(
+            infer(&[RionValue::Null, "a".into()]),
+            Schema::Nullable(Box::new(Schema::String))
+        );
+        assert_eq!(
+            infer(&[RionValue::Bool(true), "a".into()]),
+            Schema::Union(vec![Schema::Bool, Schema::String])
+        );
+        assert_eq!(infer(&[]), Schema::Any);
+    }
+
+    #[test]
+    fn test_infer_optional_fields() {
+        let docs = [
+            object(&[("id", RionValue::PosInt(1)), ("name", "a".into())]),
+            object(&[("id", RionValue::NegInt(-2))]),
+        ];
+        let Schema::Object(fields) = infer(&docs) else {
+            panic!("Expected an object schema");
+        };
+        assert_eq!(fields["id"], Field::required(Schema::Int));
+        assert_eq!(
+            fields["name"],
+            Field {
+                schema: Schema::String,
+                optional: true
+            }
+        );
+
+        // Missing from the first document only
+        let docs = [
+            object(&[("id", RionValue::PosInt(1))]),
+            object(&[("id", RionValue::PosInt(2)), ("name", "b".into())]),
+            object(&[("id", RionValue::PosInt(3)), ("name", "c".into())]),
+        ];
+        let Schema::Object(fields) = infer(&docs) else {
+            panic!("Expected an object schema");
+        };
+        assert_eq!(fields["id"], Field::required(Schema::Int));
+        assert!(fields["name"].optional);
+    }
+
+    #[test]
+    fn test_infer_arrays_and_tables() {
+        let docs = [RionValue::Array(vec![
+            RionValue::PosInt(1),
+            RionValue::Null,
+        ])];
+        assert_eq!(
+            infer(&docs),
+            Schema::Array(Box::new(Schema::Nullable(Box::new(Schema::Int))))
+        );
+
+        let table = RionValue::Table {
+            columns: vec![b"id"[..].into(), b"tag"[..].into()],
+            rows: vec![
+                vec![RionValue::PosInt(1), RionValue::Null],
+                vec![RionValue::PosInt(2), "x".into()],
+            ],
+        };
+        assert_eq!(
+            infer(&[table]),
+            Schema::Table(vec![
+                ("id".to_string(), Schema::Int),
+                (
+                    "tag".to_string(),
+                    Schema::Nullable(Box::new(Schema::String))
+                ),
+            ])
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_export_json_schema() {
+        let mut doc = BTreeMap::new();
+        doc.insert(b"id"[..].into(), RionValue::PosInt(1));
+        doc.insert(b"tags"[..].into(), RionValue::Array(vec!["a".into()]));
+        let schema = infer(&[RionValue::Object(doc), RionValue::Null]);
+        assert_eq!(
+            schema.to_json_schema(),
+            serde_json::json!({
+                "anyOf": [
+                    {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "integer" },
+                            "tags": { "type": "array", "items": { "type": "string" } },
+                        },
+                        "required": ["id", "tags"],
+                    },
+                    { "type": "null" },
+                ]
+            })
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_import_json_schema() {
+        let mut doc = BTreeMap::new();
+        doc.insert(b"id"[..].into(), RionValue::PosInt(1));
+        doc.insert(b"avatar"[..].into(), RionValue::Bytes(vec![1]));
+        doc.insert(b"tags"[..].into(), RionValue::Array(vec!["a".into()]));
+        let mut other = doc.clone();
+        other.insert(b"id"[..].into(), "a".into());
+        other.insert(b"seen"[..].into(), RionValue::Null);
+        let schema = infer(&[RionValue::Object(doc), RionValue::Object(other)]);
+        let imported = Schema::from_json_schema(&schema.to_json_schema()).unwrap();
+        assert_eq!(imported, schema);
+
+        let partner = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "at": { "type": ["string", "null"], "format": "date-time" },
+                "any": {},
+            },
+            "required": ["at"],
+        });
+        let Schema::Object(fields) = Schema::from_json_schema(&partner).unwrap() else {
+            panic!("Expected an object schema");
+        };
+        assert_eq!(
+            fields["at"],
+            Field::required(Schema::Nullable(Box::new(Schema::DateTime)))
+        );
+        assert!(fields["any"].optional && fields["any"].schema == Schema::Any);
+        assert!(Schema::from_json_schema(&serde_json::json!({ "type": "tuple" })).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_validate_json_schema() {
+        let contract = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer", "minimum": 1 },
+                "email": { "type": "string", "maxLength": 16 },
+                "roles": { "type": "array", "items": { "enum": ["admin", "user"] } },
+            },
+            "required": ["id", "email"],
+            "additionalProperties": false,
+        });
+        let valid = object(&[
+            ("id", RionValue::PosInt(3)),
+            ("email", "a@example.com".into()),
+            ("roles", RionValue::Array(vec!["admin".into()])),
+        ]);
+        assert_eq!(validate(&valid, &contract), Ok(()));
+
+        let invalid = object(&[
+            ("id", RionValue::PosInt(0)),
+            ("roles", RionValue::Array(vec!["root".into()])),
+            ("extra", RionValue::Null),
+        ]);
+        let errors = validate(&invalid, &contract).unwrap_err();
+        let mut paths = errors.iter().map(|e| e.path.as_str()).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, ["", "/extra", "/id", "/roles/0"]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_validate_rion_types() {
+        let table = RionValue::Table {
+            columns: vec![b"id"[..].into()],
+            rows: vec![vec![RionValue::PosInt(1)], vec!["2".into()]],
+        };
+        let schema = serde_json::json!({
+            "type": "array",
+            "items": { "type": "object", "properties": { "id": { "type": "integer" } } },
+        });
+        let errors = validate(&table, &schema).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "/1/id");
+
+        let schema = serde_json::json!({ "type": "string" });
+        assert!(validate(&RionValue::Bytes(vec![1]), &schema).is_ok());
+        assert!(validate(
+            &RionValue::NegInt(-1),
+            &serde_json::json!({ "type": "number" })
+        )
+        .is_ok());
+        assert!(validate(
+            &RionValue::Null,
+            &serde_json::json!({ "anyOf": [{ "type": "null" }] })
+        )
+        .is_ok());
+    }
+}
+
+/// The inferred type of a value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    /// No samples were seen, e.g. the elements of empty arrays
+    Any,
+    Null,
+    Bool,
+    Int,
+    Float,
+    String,
+    Bytes,
+    DateTime,
+    Array(Box<Schema>),
+    Object(BTreeMap<String, Field>),
+    /// Column names and types, in column order
+    Table(Vec<(String, Schema)>),
+    /// The schema, or null
+    Nullable(Box<Schema>),
+    /// One of several incompatible schemas
+    Union(Vec<Schema>),
+}
+
+/// A field of an object schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub schema: Schema,
+    /// Whether the field was missing from some of the samples
+    pub optional: bool,
+}
+
+impl Field {
+    pub fn required(schema: Schema) -> Self {
+        Field {
+            schema,
+            optional: false,
+        }
+    }
+}
+
+/// Derive a schema that every one of `documents` conforms to
+pub fn infer(documents: &[RionValue]) -> Schema {
+    documents
+        .iter()
+        .map(Schema::of)
+        .fold(Schema::Any, Schema::merge)
+}
+
+impl Schema {
+    /// The schema of a single value
+    pub fn of(value: &RionValue) -> Schema {
+        match value {
+            RionValue::Null => Schema::Null,
+            RionValue::Bool(_) => Schema::Bool,
+            RionValue::PosInt(_) | RionValue::NegInt(_) => Schema::Int,
+            RionValue::Float(_) => Schema::Float,
+            RionValue::String(_) => Schema::String,
+            // Extended fields are opaque, only their bytes are known
+            RionValue::Bytes(_) | RionValue::Extended { .. } => Schema::Bytes,
+            RionValue::DateTime(_) => Schema::DateTime,
+            RionValue::Array(elements) => Schema::Array(Box::new(infer(elements))),
+            RionValue::Object(fields) => Schema::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key_name(key), Field::required(Schema::of(value))))
+                    .collect(),
+            ),
+            RionValue::Table { columns, rows } => Schema::Table(
+                columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let schema = rows
+                            .iter()
+                            .filter_map(|row| row.get(i))
+                            .map(Schema::of)
+                            .fold(Schema::Any, Schema::merge);
+                        (key_name(column), schema)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The narrowest schema that both `self` and `other` conform to
+    pub fn merge(self, other: Schema) -> Schema {
+        match (self, other) {
+            (Schema::Any, other) | (other, Schema::Any) => other,
+            (a, b) if a == b => a,
+            (Schema::Null, Schema::Nullable(inner)) | (Schema::Nullable(inner), Schema::Null) => {
+                Schema::Nullable(inner)
+            }
+            (Schema::Null, other) | (other, Schema::Null) => Schema::Nullable(Box::new(other)),
+            (Schema::Nullable(a), Schema::Nullable(b)) => Schema::Nullable(Box::new(a.merge(*b))),
+            (Schema::Nullable(a), b) | (b, Schema::Nullable(a)) => {
+                Schema::Nullable(Box::new(a.merge(b)))
+            }
+            (Schema::Int, Schema::Float) | (Schema::Float, Schema::Int) => Schema::Float,
+            (Schema::Array(a), Schema::Array(b)) => Schema::Array(Box::new(a.merge(*b))),
+            (Schema::Object(mut a), Schema::Object(b)) => {
+                let mut merged = BTreeMap::new();
+                for (key, field) in b {
+                    let field = match a.remove(&key) {
+                        // Fields seen in both are optional if either side says so
+                        Some(existing) => Field {
+                            schema: existing.schema.merge(field.schema),
+                            optional: existing.optional || field.optional,
+                        },
+                        None => Field {
+                            schema: field.schema,
+                            optional: true,
+                        },
+                    };
+                    merged.insert(key, field);
+                }
+                for (key, field) in a {
+                    merged.insert(
+                        key,
+                        Field {
+                            optional: true,
+                            ..field
+                        },
+                    );
+                }
+                Schema::Object(merged)
+            }
+            (Schema::Table(a), Schema::Table(b))
+                if a.iter().map(|c| &c.0).eq(b.iter().map(|c| &c.0)) =>
+            {
+                Schema::Table(
+                    a.into_iter()
+                        .zip(b)
+                        .map(|((name, a), (_, b))| (name, a.merge(b)))
+                        .collect(),
+                )
+            }
+            (Schema::Union(members), other) | (other, Schema::Union(members)) => {
+                let mut merged = members;
+                merged.push(other);
+                Schema::union(merged)
+            }
+            (a, b) => Schema::Union(vec![a, b]),
+        }
+    }
+
+    // Flatten members into a union, merging those of a compatible kind
+    fn union(members: Vec<Schema>) -> Schema {
+        let mut out: Vec<Schema> = Vec::new();
+        for member in members {
+            let member = match member {
+                Schema::Union(inner) => {
+                    out.extend(inner);
+                    continue;
+                }
+                member => member,
+            };
+            match out.iter().position(|existing| existing.compatible(&member)) {
+                Some(i) => {
+                    let existing = out.remove(i);
+                    out.insert(i, existing.merge(member));
+                }
+                None => out.push(member),
+            }
+        }
+        if out.len() == 1 {
+            return out.remove(0);
+        }
+        Schema::Union(out)
+    }
+
+    // Whether merging the two gives something narrower than a union
+    fn compatible(&self, other: &Schema) -> bool {
+        !matches!(self.clone().merge(other.clone()), Schema::Union(_))
+    }
+}
+
+fn key_name(key: &[u8]) -> String {
+    String::from_utf8_lossy(key).into_owned()
+}
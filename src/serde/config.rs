@@ -0,0 +1,439 @@
+//! Loading service configuration from RION and JSON files
+//!
+//! [`load`] reads a single file. [`Loader`] stacks layers on top of each
+//! other, typically a defaults file, an environment specific file and
+//! environment variables. Objects are merged key by key and any other value
+//! replaces the one below it. Reading `.json` files needs the `json` feature.
+//!
+//! Errors name the file they come from, and for values that don't fit the
+//! target type the key path and the layer that set it.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+
+use super::{DeserializeError, Deserializer};
+use crate::RionValue;
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn write(dir: &TempDir, name: &str, value: &RionValue) -> PathBuf {
+        let path = dir.0.join(name);
+        fs::write(&path, value.encode_to_vec().unwrap()).unwrap();
+        path
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        database: Database,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Database {
+        host: String,
+        port: u16,
+        pool: Option<u32>,
+    }
+
+    fn object(fields: &[(&str, RionValue)]) -> RionValue {
+        RionValue::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.as_bytes().into(), value.clone()))
+                .collect(),
+        )
+    }
+
+    fn defaults() -> RionValue {
+        let database = object(&[
+            ("host", "localhost".into()),
+            ("port", RionValue::PosInt(5432)),
+        ]);
+        object(&[("name", "api".into()), ("database", database)])
+    }
+
+    #[test]
+    fn test_load_file() {
+        let dir = TempDir::new("config-load");
+        let path = write(&dir, "base.rion", &defaults());
+        let config: Config = load(&path).unwrap();
+        assert_eq!(config.database.host, "localhost");
+        assert_eq!(config.database.port, 5432);
+
+        let missing = load::<Config>(dir.0.join("missing.rion")).unwrap_err();
+        assert!(matches!(missing, ConfigError::Io(path, _) if path.ends_with("missing.rion")));
+        let format = load::<Config>(dir.0.join("base.toml")).unwrap_err();
+        assert!(matches!(format, ConfigError::Format(_)));
+    }
+
+    #[test]
+    fn test_layers() {
+        let dir = TempDir::new("config-layers");
+        let base = write(&dir, "base.rion", &defaults());
+        let database = object(&[("host", "db.internal".into())]);
+        let prod = write(&dir, "prod.rion", &object(&[("database", database)]));
+        let vars = [
+            ("APP_DATABASE__POOL", "16"),
+            ("APP_NAME", "api-prod"),
+            ("OTHER_NAME", "ignored"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+
+        let loader = Loader::new()
+            .file(&base)
+            .optional_file(&prod)
+            .optional_file(dir.0.join("local.rion"))
+            .env("APP");
+        let config: Config = loader.load_with_vars(vars).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                name: "api-prod".to_string(),
+                database: Database {
+                    host: "db.internal".to_string(),
+                    port: 5432,
+                    pool: Some(16),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_error_origin() {
+        let dir = TempDir::new("config-origin");
+        let base = write(&dir, "base.rion", &defaults());
+        let vars = [("APP_DATABASE__PORT".to_string(), "99999".to_string())];
+        let err = Loader::new()
+            .file(&base)
+            .env("APP")
+            .load_with_vars::<Config>(vars)
+            .unwrap_err();
+        let ConfigError::Deserialize { key, origin, .. } = &err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(key, "database.port");
+        assert_eq!(origin.as_deref(), Some("APP_DATABASE__PORT"));
+
+        let bad = dir.0.join("bad.rion");
+        fs::write(&bad, [0xFF]).unwrap();
+        let err = load::<Config>(&bad).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse(path, _) if path == bad));
+    }
+
+    #[test]
+    fn test_numeric_env_strings() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Release {
+            name: String,
+            version: String,
+            build: u32,
+            stable: bool,
+        }
+        let vars = [
+            ("PRB_NAME", "2024"),
+            ("PRB_VERSION", "1.10"),
+            ("PRB_BUILD", "7"),
+            ("PRB_STABLE", "true"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+        let release: Release = Loader::new().env("PRB").load_with_vars(vars).unwrap();
+        assert_eq!(
+            release,
+            Release {
+                name: "2024".to_string(),
+                version: "1.10".to_string(),
+                build: 7,
+                stable: true,
+            }
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_layer() {
+        let dir = TempDir::new("config-json");
+        let base = write(&dir, "base.rion", &defaults());
+        let json = dir.0.join("override.json");
+        fs::write(&json, r#"{"database": {"port": 6543}}"#).unwrap();
+        let config: Config = Loader::new().file(&base).file(&json).load().unwrap();
+        assert_eq!(config.database.port, 6543);
+        assert_eq!(config.database.host, "localhost");
+    }
+}
+
+/// Load `T` from a single `.rion` or `.json` file
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, ConfigError> {
+    Loader::new().file(path).load()
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A file could not be read
+    Io(PathBuf, io::Error),
+    /// A file is not valid RION or JSON, or doesn't hold an object
+    Parse(PathBuf, String),
+    /// A file is neither `.rion` nor `.json`
+    Format(PathBuf),
+    /// The merged layers could not be encoded
+    Encode(String),
+    /// The merged configuration doesn't fit the target type
+    Deserialize {
+        /// Path to the offending value, e.g. `database.port`
+        key: String,
+        /// The file or environment variable that set it, if known
+        origin: Option<String>,
+        error: DeserializeError,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            ConfigError::Parse(path, err) => write!(f, "{}: {err}", path.display()),
+            ConfigError::Format(path) => {
+                write!(f, "{}: expected a .rion or .json file", path.display())
+            }
+            ConfigError::Encode(err) => write!(f, "failed to encode configuration: {err}"),
+            ConfigError::Deserialize {
+                key,
+                origin: Some(origin),
+                error,
+            } => write!(f, "{key} (set by {origin}): {error}"),
+            ConfigError::Deserialize { key, error, .. } => write!(f, "{key}: {error}"),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(_, err) => Some(err),
+            ConfigError::Deserialize { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Layers of configuration, applied in the order they are added
+#[derive(Debug, Clone, Default)]
+pub struct Loader {
+    layers: Vec<Layer>,
+}
+
+#[derive(Debug, Clone)]
+enum Layer {
+    File { path: PathBuf, required: bool },
+    Env { prefix: String },
+}
+
+// The layer that last set each key path
+type Origins = BTreeMap<Vec<String>, String>;
+
+impl Loader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file that must exist
+    pub fn file(mut self, path: impl AsRef<Path>) -> Self {
+        self.layers.push(Layer::File {
+            path: path.as_ref().to_path_buf(),
+            required: true,
+        });
+        self
+    }
+
+    /// Add a file that is skipped when it doesn't exist
+    pub fn optional_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.layers.push(Layer::File {
+            path: path.as_ref().to_path_buf(),
+            required: false,
+        });
+        self
+    }
+
+    /// Add the environment variables starting with `{prefix}_`
+    ///
+    /// The rest of the name is lowercased and split on `__` into a key path,
+    /// so `APP_DATABASE__PORT` sets `database.port`. Values are strings, and
+    /// are only read as a boolean or a number where the target type doesn't
+    /// take a string there.
+    pub fn env(mut self, prefix: impl Into<String>) -> Self {
+        self.layers.push(Layer::Env {
+            prefix: prefix.into(),
+        });
+        self
+    }
+
+    /// Merge every layer and deserialize the result
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        self.load_with_vars(std::env::vars())
+    }
+
+    fn load_with_vars<T: DeserializeOwned>(
+        &self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<T, ConfigError> {
+        let mut vars: Vec<_> = vars.into_iter().collect();
+        vars.sort();
+        let mut merged = RionValue::Object(BTreeMap::new());
+        let mut origins = Origins::new();
+        let mut env_names = BTreeSet::new();
+        for layer in &self.layers {
+            match layer {
+                Layer::File { path, required } => {
+                    if !required && !path.exists() {
+                        continue;
+                    }
+                    let value = read_file(path)?;
+                    let origin = path.display().to_string();
+                    merge(&mut merged, value, &mut Vec::new(), &mut origins, &origin);
+                }
+                Layer::Env { prefix } => {
+                    let prefix = format!("{prefix}_");
+                    for (name, value) in &vars {
+                        let Some(keys) = name.strip_prefix(&prefix).and_then(env_keys) else {
+                            continue;
+                        };
+                        let value = keys.iter().rev().fold(value.as_str().into(), |value, key| {
+                            RionValue::Object([(key.as_bytes().into(), value)].into())
+                        });
+                        merge(&mut merged, value, &mut Vec::new(), &mut origins, name);
+                        env_names.insert(name.as_str());
+                    }
+                }
+            }
+        }
+        loop {
+            let data = merged
+                .encode_to_vec()
+                .map_err(|e| ConfigError::Encode(e.to_string()))?;
+            let err = match serde_path_to_error::deserialize(&mut Deserializer::new(&data)) {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            let keys: Vec<String> = err
+                .path()
+                .iter()
+                .map_while(|segment| match segment {
+                    serde_path_to_error::Segment::Map { key } => Some(key.clone()),
+                    serde_path_to_error::Segment::Enum { variant } => Some(variant.clone()),
+                    _ => None,
+                })
+                .collect();
+            // A variable the target type doesn't take as a string is read as
+            // a boolean or number instead, and the whole tried again. Each
+            // retry types one more variable, so this ends
+            let from_env = keys.len() == err.path().iter().count()
+                && origins
+                    .get(&keys)
+                    .is_some_and(|origin| env_names.contains(origin.as_str()));
+            if from_env {
+                if let Some(slot) = lookup(&mut merged, &keys) {
+                    if let Some(typed) = slot.as_str().and_then(typed_env_value) {
+                        *slot = typed;
+                        continue;
+                    }
+                }
+            }
+            // The closest key a layer set, arrays are replaced as a whole
+            let origin = (0..=keys.len())
+                .rev()
+                .find_map(|len| origins.get(&keys[..len]))
+                .cloned();
+            return Err(ConfigError::Deserialize {
+                key: err.path().to_string(),
+                origin,
+                error: err.into_inner(),
+            });
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<RionValue, ConfigError> {
+    let read = || std::fs::read(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e));
+    let parse_error = |e: &dyn Display| ConfigError::Parse(path.to_path_buf(), e.to_string());
+    let value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("rion") => RionValue::from_slice(&read()?).map_err(|e| parse_error(&e))?,
+        #[cfg(feature = "json")]
+        Some("json") => serde_json::from_slice::<serde_json::Value>(&read()?)
+            .map_err(|e| parse_error(&e))?
+            .into(),
+        _ => return Err(ConfigError::Format(path.to_path_buf())),
+    };
+    if !matches!(value, RionValue::Object(_)) {
+        return Err(parse_error(&"expected an object at the top level"));
+    }
+    Ok(value)
+}
+
+// Lay `value` over `target`, recording `origin` for every key it sets
+fn merge(
+    target: &mut RionValue,
+    mut value: RionValue,
+    keys: &mut Vec<String>,
+    origins: &mut Origins,
+    origin: &str,
+) {
+    if let (RionValue::Object(target), RionValue::Object(fields)) = (&mut *target, &mut value) {
+        for (key, value) in std::mem::take(fields) {
+            keys.push(String::from_utf8_lossy(&key).into_owned());
+            let slot = target.entry(key).or_insert(RionValue::Null);
+            merge(slot, value, keys, origins, origin);
+            keys.pop();
+        }
+        return;
+    }
+    origins.retain(|set, _| !set.starts_with(keys));
+    origins.insert(keys.clone(), origin.to_string());
+    *target = value;
+}
+
+// The key path in what follows the prefix of a variable name
+fn env_keys(name: &str) -> Option<Vec<String>> {
+    let keys: Vec<String> = name.split("__").map(str::to_lowercase).collect();
+    keys.iter().all(|key| !key.is_empty()).then_some(keys)
+}
+
+// The value at a key path of nested objects
+fn lookup<'a>(mut value: &'a mut RionValue, keys: &[String]) -> Option<&'a mut RionValue> {
+    for key in keys {
+        let RionValue::Object(fields) = value else {
+            return None;
+        };
+        value = fields.get_mut(key.as_bytes())?;
+    }
+    Some(value)
+}
+
+// A variable's value as a boolean or number, if it reads as one
+fn typed_env_value(text: &str) -> Option<RionValue> {
+    if let Ok(value) = text.parse::<bool>() {
+        return Some(value.into());
+    }
+    if let Ok(value) = text.parse::<i64>() {
+        return Some(value.into());
+    }
+    if let Ok(value) = text.parse::<u64>() {
+        return Some(value.into());
+    }
+    match text.parse::<f64>() {
+        Ok(value) if value.is_finite() => Some(value.into()),
+        _ => None,
+    }
+}
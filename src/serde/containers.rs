@@ -0,0 +1,287 @@
+//! `Serialize` and `Deserialize` for the field and container types
+//!
+//! Fields are written as the values they hold rather than as raw bytes, so a
+//! `RionObject` embedded in a struct serializes like any other map. Typed nulls
+//! become `None`, datetimes RFC 3339 strings and tables a sequence of row maps.
+//!
+//! Deserializing goes the other way from any self-describing format: maps
+//! become objects, sequences arrays and a sequence of maps with the same keys
+//! a table.
+
+use std::fmt;
+
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::{Error, SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    types::{LeadByte, NormalRionType, RionFieldType, ShortRionType},
+    RionArray, RionDateTime, RionField, RionKey, RionObject, RionTable,
+};
+
+// Keys are strings when they are valid UTF-8 and bytes otherwise
+struct Key<'a>(&'a [u8]);
+
+impl Serialize for Key<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(self.0) {
+            Ok(key) => serializer.serialize_str(key),
+            Err(_) => serializer.serialize_bytes(self.0),
+        }
+    }
+}
+
+impl Serialize for RionField<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_null() {
+            return serializer.serialize_none();
+        }
+        match self.field_type() {
+            RionFieldType::Tiny(_) => serializer.serialize_bool(self.as_bool().unwrap_or_default()),
+            RionFieldType::Short(ShortRionType::Int64Positive) => serializer.serialize_u64(
+                self.as_u64()
+                    .ok_or_else(|| S::Error::custom("Integer too long"))?,
+            ),
+            RionFieldType::Short(ShortRionType::Int64Negative) => serializer.serialize_i64(
+                self.as_i64()
+                    .ok_or_else(|| S::Error::custom("Integer out of range"))?,
+            ),
+            RionFieldType::Short(ShortRionType::Float) => match self.as_bytes().len() {
+                1..=4 => serializer.serialize_f32(self.as_f64().unwrap_or_default() as f32),
+                _ => serializer.serialize_f64(
+                    self.as_f64()
+                        .ok_or_else(|| S::Error::custom("Float too long"))?,
+                ),
+            },
+            RionFieldType::Short(ShortRionType::UTCDateTime) => {
+                let datetime = RionDateTime::try_from(self.clone()).map_err(S::Error::custom)?;
+                serializer.serialize_str(&datetime.to_rfc3339())
+            }
+            RionFieldType::Short(ShortRionType::Key)
+            | RionFieldType::Normal(NormalRionType::Key) => {
+                Key(self.as_bytes()).serialize(serializer)
+            }
+            RionFieldType::Short(ShortRionType::UTF8)
+            | RionFieldType::Normal(NormalRionType::UTF8) => serializer.serialize_str(
+                self.as_str()
+                    .ok_or_else(|| S::Error::custom("Invalid UTF-8 in string field"))?,
+            ),
+            RionFieldType::Normal(NormalRionType::Bytes) => {
+                serializer.serialize_bytes(self.as_bytes())
+            }
+            RionFieldType::Normal(container) => {
+                // The field only holds the content, parse it back with its header
+                let mut encoded = Vec::new();
+                self.encode(&mut encoded).map_err(S::Error::custom)?;
+                match container {
+                    NormalRionType::Array => RionArray::from_slice(&encoded)
+                        .map_err(S::Error::custom)?
+                        .serialize(serializer),
+                    NormalRionType::Object => RionObject::from_slice(&encoded)
+                        .map_err(S::Error::custom)?
+                        .serialize(serializer),
+                    _ => RionTable::from_slice(&encoded)
+                        .map_err(S::Error::custom)?
+                        .serialize(serializer),
+                }
+            }
+            RionFieldType::Extended => Err(S::Error::custom("Extended fields are not supported")),
+        }
+    }
+}
+
+impl Serialize for RionArray<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.elements)
+    }
+}
+
+impl Serialize for RionObject<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, field) in self.iter_sorted() {
+            map.serialize_entry(&Key(key), field)?;
+        }
+        map.end()
+    }
+}
+
+// A row of a table, as a map from column name to cell
+struct Row<'a, 'b> {
+    columns: &'a [std::borrow::Cow<'b, [u8]>],
+    cells: &'a [RionField<'b>],
+}
+
+impl Serialize for Row<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, cell) in self.columns.iter().zip(self.cells) {
+            map.serialize_entry(&Key(column), cell)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for RionTable<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let columns = self.column_names.len();
+        let mut seq = serializer.serialize_seq(Some(self.row_count()))?;
+        if columns > 0 {
+            for cells in self.rows.chunks_exact(columns) {
+                seq.serialize_element(&Row {
+                    columns: &self.column_names,
+                    cells,
+                })?;
+            }
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RionField<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(FieldVisitor)
+    }
+}
+
+struct FieldVisitor;
+
+impl<'de> Visitor<'de> for FieldVisitor {
+    type Value = RionField<'static>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(RionField::bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(RionField::int64(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(RionField::uint64(v))
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E> {
+        RionField::try_from(v).map_err(E::custom)
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> {
+        RionField::try_from(v).map_err(E::custom)
+    }
+
+    fn visit_f32<E: de::Error>(self, v: f32) -> Result<Self::Value, E> {
+        Ok(RionField::f32(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(RionField::f64(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(RionField::from(v).into_owned())
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(RionField::bytes(v).into_owned())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(RionField::Tiny(LeadByte::NULL))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        RionField::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut elements =
+            Vec::with_capacity(super::cautious_capacity::<RionField>(seq.size_hint()));
+        while let Some(element) = seq.next_element::<RionField<'static>>()? {
+            elements.push(element);
+        }
+        Ok(elements.into())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        ObjectVisitor.visit_map(map).map(RionField::from)
+    }
+}
+
+impl<'de> Deserialize<'de> for RionArray<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut array = RionArray::new();
+        array.extend(Vec::<RionField<'static>>::deserialize(deserializer)?);
+        Ok(array)
+    }
+}
+
+impl<'de> Deserialize<'de> for RionObject<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ObjectVisitor)
+    }
+}
+
+struct ObjectVisitor;
+
+impl<'de> Visitor<'de> for ObjectVisitor {
+    type Value = RionObject<'static>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut object = RionObject::new();
+        while let Some(OwnedKey(key)) = map.next_key()? {
+            let key = RionKey::new(key).map_err(de::Error::custom)?;
+            object.insert(key, map.next_value::<RionField<'static>>()?);
+        }
+        Ok(object)
+    }
+}
+
+// A map key, from a string or from bytes
+struct OwnedKey(Vec<u8>);
+
+impl<'de> Deserialize<'de> for OwnedKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(OwnedKeyVisitor)
+    }
+}
+
+struct OwnedKeyVisitor;
+
+impl<'de> Visitor<'de> for OwnedKeyVisitor {
+    type Value = OwnedKey;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string or bytes key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(OwnedKey(v.as_bytes().to_vec()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(OwnedKey(v.to_vec()))
+    }
+}
+
+// Rows need the same keys, the columns are sorted by name as with
+// `RionTable::from_objects`
+impl<'de> Deserialize<'de> for RionTable<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = Vec::<RionObject<'static>>::deserialize(deserializer)?;
+        RionTable::from_objects(&rows).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,179 @@
+//! Compact (de)serialization of calendar dates and times
+//!
+//! Use with `#[serde(with = "ferion::datetime::naive_date")]` or
+//! `#[serde(with = "ferion::datetime::naive_date_time")]`. Values are written
+//! as UTCDateTime fields instead of the strings chrono emits: dates keep only
+//! their date components and date times are truncated to the second. Strings
+//! are still accepted when decoding.
+//!
+//! [`RionDateTime`] is written the same way at its own precision, without an
+//! adapter.
+//!
+//! Other formats see a newtype struct holding the raw field data as bytes.
+//!
+//! The adapters need the `chrono` feature.
+
+#[cfg(feature = "chrono")]
+use std::fmt;
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+#[cfg(feature = "chrono")]
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser, Deserialize, Deserializer, Serialize, Serializer,
+};
+
+#[cfg(feature = "chrono")]
+use crate::YearRange;
+use crate::{field::ShortField, types::ShortRionType, RionDateTime, RionField};
+
+/// Newtype struct name the RION serializer writes as a UTCDateTime field
+pub(crate) const TOKEN: &str = "$ferion::UTCDateTime";
+
+#[cfg(feature = "chrono")]
+pub mod naive_date {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_field(RionField::try_from(*value), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+        match deserializer.deserialize_newtype_struct(TOKEN, RawVisitor)? {
+            Raw::DateTime(dt) => match dt.datetime() {
+                dt if dt.time() == NaiveTime::MIN => Ok(dt.date_naive()),
+                dt => Err(de::Error::custom(format!(
+                    "Date time {dt} is not a calendar date"
+                ))),
+            },
+            Raw::Str(s) => s.parse().map_err(de::Error::custom),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+pub mod naive_date_time {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &NaiveDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serialize_field(RionField::try_from(*value), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<NaiveDateTime, D::Error> {
+        match deserializer.deserialize_newtype_struct(TOKEN, RawVisitor)? {
+            Raw::DateTime(dt) => Ok(dt.datetime().naive_utc()),
+            Raw::Str(s) => s.parse().map_err(de::Error::custom),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Serialize for RionDateTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_field(self.to_field(YearRange::Error), serializer)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<'de> Deserialize<'de> for RionDateTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.deserialize_newtype_struct(TOKEN, RawVisitor)? {
+            Raw::DateTime(dt) => Ok(dt),
+            Raw::Str(s) => Err(de::Error::custom(format!("{s:?} is not a date time"))),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn serialize_field<S: Serializer>(
+    field: crate::Result<RionField>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let field = field.map_err(ser::Error::custom)?;
+    serializer.serialize_newtype_struct(TOKEN, &Data(field.as_bytes()))
+}
+
+#[cfg(feature = "chrono")]
+struct Data<'a>(&'a [u8]);
+
+#[cfg(feature = "chrono")]
+impl Serialize for Data<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+// The field a value written through `TOKEN` stands for
+pub(crate) fn from_data(data: &[u8]) -> crate::Result<RionField<'static>> {
+    let field = data_field(data)?;
+    RionDateTime::try_from(field.clone())?;
+    Ok(field.into_owned())
+}
+
+fn data_field(data: &[u8]) -> crate::Result<RionField<'_>> {
+    Ok(RionField::Short(ShortField::try_new(
+        ShortRionType::UTCDateTime,
+        data,
+    )?))
+}
+
+#[cfg(feature = "chrono")]
+enum Raw {
+    DateTime(RionDateTime),
+    Str(String),
+}
+
+#[cfg(feature = "chrono")]
+struct RawVisitor;
+
+#[cfg(feature = "chrono")]
+impl RawVisitor {
+    fn from_data<E: de::Error>(data: &[u8]) -> Result<Raw, E> {
+        data_field(data)
+            .and_then(RionDateTime::try_from)
+            .map(Raw::DateTime)
+            .map_err(E::custom)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<'de> Visitor<'de> for RawVisitor {
+    type Value = Raw;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a date time field or a date string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        // The RION deserializer hands out date time fields as RFC 3339
+        match DateTime::parse_from_rfc3339(v) {
+            Ok(dt) => Ok(Raw::DateTime(dt.to_utc().into())),
+            Err(_) => Ok(Raw::Str(v.to_string())),
+        }
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Self::from_data(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(super::cautious_capacity::<u8>(seq.size_hint()));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        Self::from_data(&bytes)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
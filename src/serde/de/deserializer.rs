@@ -2,17 +2,23 @@ use std::{
     error::Error,
     fmt::{Debug, Display},
     ops::{Deref, DerefMut},
+    time::Instant,
 };
 
 use serde::{
-    de::{SeqAccess, Visitor},
+    de::{
+        value::{BorrowedBytesDeserializer, BorrowedStrDeserializer},
+        DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+    },
     forward_to_deserialize_any,
 };
 
 use crate::{
-    bytes_to_int, get_header,
+    bytes_to_int,
+    field::ShortField,
+    get_normal_header,
     types::{LeadByte, NormalRionType, RionFieldType, ShortRionType},
-    RionField,
+    Batch, FerionError, Path, RionField, RionFieldRef, Segment, Stats,
 };
 
 impl serde::de::Error for DeserializeError {
@@ -23,9 +29,142 @@ impl serde::de::Error for DeserializeError {
         let msg = msg.to_string();
         DeserializeError::Custom(msg)
     }
+
+    fn invalid_type(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        DeserializeError::TypeMismatch(format!("invalid type: {unexp}, expected {exp}"))
+    }
+
+    fn invalid_value(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        DeserializeError::TypeMismatch(format!("invalid value: {unexp}, expected {exp}"))
+    }
+
+    fn invalid_length(len: usize, exp: &dyn serde::de::Expected) -> Self {
+        DeserializeError::TypeMismatch(format!("invalid length {len}, expected {exp}"))
+    }
 }
 
-impl std::error::Error for DeserializeError {}
+impl DeserializeError {
+    /// The input ended where the next field should start, more input may hold
+    /// the rest of the value
+    pub fn is_eof(&self) -> bool {
+        matches!(self.kind(), DeserializeError::Eod)
+    }
+
+    /// The input ended partway through a field, see
+    /// [`DeserializeError::Truncated`]
+    pub fn is_truncated(&self) -> bool {
+        matches!(self.kind(), DeserializeError::Truncated(_))
+    }
+
+    /// The input is well formed but doesn't fit the type it is decoded into
+    pub fn is_type_mismatch(&self) -> bool {
+        matches!(
+            self.kind(),
+            DeserializeError::InvalidType(..)
+                | DeserializeError::ExpectedNull
+                | DeserializeError::TypeMismatch(_)
+                | DeserializeError::UnknownColumn(_)
+                | DeserializeError::MissingColumn(_)
+        )
+    }
+
+    /// The input uses a field type or extended type this version doesn't
+    /// know, e.g. from a newer writer
+    pub fn is_unsupported_feature(&self) -> bool {
+        matches!(
+            self.kind(),
+            DeserializeError::UnknownExtended(_) | DeserializeError::UnknownFieldType(_)
+        )
+    }
+
+    /// The error without the location [`DeserializeError::At`] adds
+    pub fn kind(&self) -> &DeserializeError {
+        match self {
+            DeserializeError::At { error, .. } => error.kind(),
+            error => error,
+        }
+    }
+
+    /// Offset from the start of the input of the field that failed to decode
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            DeserializeError::At { offset, .. } => Some(*offset),
+            DeserializeError::InvalidUtf8 { offset }
+            | DeserializeError::NonCanonical(offset, _) => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Keys and indices leading to the field that failed to decode, e.g.
+    /// `users[3].address.zip`
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            DeserializeError::At { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Lead byte of the field that failed to decode
+    pub fn lead_byte(&self) -> Option<u8> {
+        match self {
+            DeserializeError::At { lead, .. } => *lead,
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeserializeError::Io(err) => Some(err),
+            DeserializeError::Utf8(err) => Some(err),
+            // The message of `error` is already part of this one
+            DeserializeError::At { error, .. } => error.source(),
+            _ => None,
+        }
+    }
+}
+
+// By hand as io::Error isn't PartialEq, IO errors of the same kind are equal
+impl PartialEq for DeserializeError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Eod, Self::Eod)
+            | (Self::ExpectedNull, Self::ExpectedNull)
+            | (Self::ExtraData, Self::ExtraData) => true,
+            (Self::DataLength(a, b, c), Self::DataLength(x, y, z)) => (a, b, c) == (x, y, z),
+            (Self::InvalidType(a, b), Self::InvalidType(x, y)) => (a, b) == (x, y),
+            (Self::InvalidUtf8 { offset: a }, Self::InvalidUtf8 { offset: b }) => a == b,
+            (Self::NonCanonical(a, b), Self::NonCanonical(x, y)) => (a, b) == (x, y),
+            (Self::UnknownExtended(a), Self::UnknownExtended(b)) => a == b,
+            (Self::UnknownColumn(a), Self::UnknownColumn(b)) => a == b,
+            (Self::MissingColumn(a), Self::MissingColumn(b)) => a == b,
+            (Self::Truncated(a), Self::Truncated(b)) => a == b,
+            (Self::UnknownFieldType(a), Self::UnknownFieldType(b)) => a == b,
+            (Self::TypeMismatch(a), Self::TypeMismatch(b)) => a == b,
+            (Self::DepthLimit(a), Self::DepthLimit(b)) => a == b,
+            (Self::InvalidData(a), Self::InvalidData(b)) => a == b,
+            (Self::Io(a), Self::Io(b)) => a.kind() == b.kind(),
+            (Self::Utf8(a), Self::Utf8(b)) => a == b,
+            (Self::Custom(a), Self::Custom(b)) => a == b,
+            (
+                Self::At {
+                    path,
+                    offset,
+                    lead,
+                    error,
+                },
+                Self::At {
+                    path: other_path,
+                    offset: other_offset,
+                    lead: other_lead,
+                    error: other_error,
+                },
+            ) => (path, offset, lead, error) == (other_path, other_offset, other_lead, other_error),
+            _ => false,
+        }
+    }
+}
 impl Debug for DeserializeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{self}")
@@ -46,6 +185,44 @@ impl Display for DeserializeError {
                 write!(f, "expected type {expected:?}, but got {actual:?}")?
             }
             DeserializeError::ExtraData => write!(f, "extra data found")?,
+            DeserializeError::InvalidUtf8 { offset } => {
+                write!(f, "invalid UTF-8 at byte {offset}")?
+            }
+            DeserializeError::NonCanonical(offset, reason) => {
+                write!(f, "non-canonical encoding at byte {offset}: {reason}")?
+            }
+            DeserializeError::UnknownExtended(type_id) => {
+                write!(f, "unknown extended type {type_id:#04x}")?
+            }
+            DeserializeError::UnknownColumn(column) => write!(f, "unknown column `{column}`")?,
+            DeserializeError::Truncated(missing) => {
+                write!(f, "input ends {missing} bytes short of the end of a field")?
+            }
+            DeserializeError::UnknownFieldType(lead) => {
+                write!(f, "unknown field type in lead byte {lead:#04x}")?
+            }
+            DeserializeError::TypeMismatch(msg) => write!(f, "{msg}")?,
+            DeserializeError::MissingColumn(field) => write!(f, "missing column `{field}`")?,
+            DeserializeError::DepthLimit(max) => {
+                write!(f, "containers nested deeper than the limit of {max}")?
+            }
+            DeserializeError::Io(err) => write!(f, "IO error: {err}")?,
+            DeserializeError::Utf8(err) => write!(f, "invalid UTF-8: {err}")?,
+            DeserializeError::At {
+                path,
+                offset,
+                lead,
+                error,
+            } => {
+                write!(f, "{error} at ")?;
+                if !path.is_empty() {
+                    write!(f, "{path}, ")?;
+                }
+                write!(f, "byte {offset}")?;
+                if let Some(lead) = lead {
+                    write!(f, " (lead byte {lead:#04x})")?
+                }
+            }
         }
         Ok(())
     }
@@ -57,9 +234,31 @@ impl From<String> for DeserializeError {
     }
 }
 
+impl From<FerionError> for DeserializeError {
+    fn from(err: FerionError) -> Self {
+        match err {
+            FerionError::Truncated(missing) => DeserializeError::Truncated(missing),
+            FerionError::InvalidLeadByte(lead) => DeserializeError::UnknownFieldType(lead),
+            FerionError::UnexpectedType { .. }
+            | FerionError::OutOfRange(_)
+            | FerionError::NegativeOutOfRange(_) => DeserializeError::TypeMismatch(err.to_string()),
+            FerionError::InvalidUtf8(err) => DeserializeError::Utf8(err),
+            FerionError::Io(err) => DeserializeError::Io(err),
+            err => DeserializeError::Custom(err.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for DeserializeError {
+    fn from(err: std::io::Error) -> Self {
+        DeserializeError::Io(err)
+    }
+}
+
+// Errors that carry a source keep it, see FerionError's conversion
 impl From<Box<dyn Error>> for DeserializeError {
     fn from(err: Box<dyn Error>) -> Self {
-        DeserializeError::Custom(err.to_string())
+        FerionError::from(err).into()
     }
 }
 
@@ -69,42 +268,211 @@ impl From<Box<dyn Error>> for DeserializeError {
 //     }
 // }
 
+/// Decode a `T` that takes up all of `data`
+///
+/// Bytes left after the value are an error, [`DeserializeError::ExtraData`],
+/// see [`from_bytes_lenient`] to ignore them instead.
 pub fn from_bytes<'de, T>(data: &'de [u8]) -> Result<T, DeserializeError>
+where
+    T: serde::de::Deserialize<'de>,
+{
+    from_bytes_with_stats(data).map(|(value, _)| value)
+}
+
+/// Like [`from_bytes`], but ignores any bytes left after the value
+pub fn from_bytes_lenient<'de, T>(data: &'de [u8]) -> Result<T, DeserializeError>
 where
     T: serde::de::Deserialize<'de>,
 {
     let mut deserializer = Deserializer::new(data);
-    T::deserialize(&mut deserializer)
+    T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(data, err))
 }
 
-// #[derive(Debug)]
-#[derive(PartialEq)]
+/// Decode a batch written by [`to_bytes_many`](crate::to_bytes_many) with the
+/// same `batch` layout, in a single pass over `data`
+pub fn from_bytes_many<'de, T>(data: &'de [u8], batch: Batch) -> Result<Vec<T>, DeserializeError>
+where
+    T: serde::de::Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::new(data);
+    let values = match batch {
+        Batch::Concatenated => {
+            let mut values = Vec::new();
+            while !deserializer.data.is_empty() {
+                let at = deserializer.data;
+                let value = T::deserialize(&mut deserializer);
+                values.push(value.map_err(|err| deserializer.locate(at, err))?);
+            }
+            values
+        }
+        Batch::Array => serde::Deserialize::deserialize(&mut deserializer)
+            .map_err(|err| deserializer.locate(data, err))?,
+    };
+    deserializer.end()?;
+    Ok(values)
+}
+
+/// Like [`from_bytes`], but rejects input that isn't in canonical form
+///
+/// Every field has to use the shortest encoding the encoder would have
+/// written, otherwise [`DeserializeError::NonCanonical`] is returned with the
+/// offset of the offending field. Use it to verify documents that are signed
+/// or hashed in their encoded form.
+pub fn from_bytes_strict<'de, T>(data: &'de [u8]) -> Result<T, DeserializeError>
+where
+    T: serde::de::Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::new_strict(data)?;
+    let value = T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(data, err))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Like [`from_bytes`], but skips UTF-8 validation of strings and keys
+///
+/// For data this process encoded itself and kept somewhere it can't be
+/// tampered with, like an in-memory cache, where validation is wasted work.
+///
+/// # Safety
+///
+/// Every string and key in `data` has to be valid UTF-8, see
+/// [`Deserializer::new_trusted`].
+pub unsafe fn from_bytes_trusted<'de, T>(data: &'de [u8]) -> Result<T, DeserializeError>
+where
+    T: serde::de::Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::new_trusted(data);
+    let value = T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(data, err))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Like [`from_bytes`], but also returns the counters collected while decoding
+pub fn from_bytes_with_stats<'de, T>(data: &'de [u8]) -> Result<(T, Stats), DeserializeError>
+where
+    T: serde::de::Deserialize<'de>,
+{
+    let start = Instant::now();
+    let mut deserializer = Deserializer::new(data);
+    let value = T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(data, err))?;
+    deserializer.end()?;
+    let mut stats = deserializer.stats;
+    stats.bytes = data.len() - deserializer.data.len();
+    stats.elapsed = start.elapsed();
+    #[cfg(feature = "tracing")]
+    stats.trace("decode");
+    Ok((value, stats))
+}
+
+#[non_exhaustive]
 pub enum DeserializeError {
     Eod,
     DataLength(usize, usize, Vec<u8>),         // Expected, Actual
     InvalidType(RionFieldType, RionFieldType), // Expected, Actual
     ExpectedNull,
     ExtraData,
+    InvalidUtf8 {
+        offset: usize,
+    },
+    NonCanonical(usize, &'static str), // Offset, reason
+    /// An extended field met without [`Deserializer::opaque_extended`],
+    /// holds its type id
+    UnknownExtended(u8),
+    /// A table column the struct has no field for, with
+    /// [`Deserializer::strict_columns`]
+    UnknownColumn(String),
+    /// A struct field the table has no column for, with
+    /// [`Deserializer::strict_columns`]
+    MissingColumn(&'static str),
+    /// The input ends inside a field, holds how many more bytes the field
+    /// needs. A network reader can wait for them and try again
+    Truncated(usize),
+    /// A lead byte of a field type this version doesn't know
+    UnknownFieldType(u8),
+    /// A value that doesn't fit the target type, as reported by its
+    /// `Deserialize` implementation
+    TypeMismatch(String),
+    /// Containers nest deeper than [`Deserializer::max_depth`] allows, holds
+    /// the limit
+    DepthLimit(usize),
     InvalidData(Vec<u8>),
+    /// Reading the input failed, kept as the [`source`](Error::source) so
+    /// callers can tell e.g. [`WouldBlock`](std::io::ErrorKind::WouldBlock)
+    /// apart
+    Io(std::io::Error),
+    /// A string without its position in the input isn't valid UTF-8, see
+    /// [`DeserializeError::InvalidUtf8`] for those with one
+    Utf8(std::str::Utf8Error),
     Custom(String),
+    /// Another error with where it happened: the keys and indices leading to
+    /// the field being decoded, its offset from the start of the input, and
+    /// its lead byte unless the input ends there. See
+    /// [`DeserializeError::kind`]
+    At {
+        path: Path,
+        offset: usize,
+        lead: Option<u8>,
+        error: Box<DeserializeError>,
+    },
+}
+
+/// How a [`Deserializer`] hands out strings and keys that aren't valid UTF-8
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf8Policy {
+    /// Fail with [`DeserializeError::InvalidUtf8`]
+    #[default]
+    Strict,
+    /// Replace invalid sequences with U+FFFD, copying the string
+    Lossy,
+    /// Hand out the raw bytes instead of a string
+    Raw,
+}
+
+// The text of a string or key after applying the `Utf8Policy`
+enum Text<'de> {
+    Str(&'de str),
+    Lossy(String),
+    Raw(&'de [u8]),
 }
 
 pub struct Deserializer<'de> {
     data: &'de [u8],
+    // The whole input, to report offsets from its start
+    input: &'de [u8],
+    utf8_policy: Utf8Policy,
+    opaque_extended: bool,
+    strict_columns: bool,
+    numeric_fields: bool,
+    // Skip UTF-8 validation, see `new_trusted`
+    trusted: bool,
+    // Entries in the next container, from a count hint before it
+    count_hint: Option<usize>,
+    // Containers around the data of this deserializer
+    depth: usize,
+    max_depth: usize,
+    stats: Stats,
 }
 
 pub struct BytesDeserializer<'de> {
     data: &'de [u8],
 }
 
-impl<'a, 'de> serde::Deserializer<'de> for &'a mut BytesDeserializer<'de> {
+impl<'de> serde::Deserializer<'de> for &mut BytesDeserializer<'de> {
     type Error = DeserializeError;
 
-    fn deserialize_any<V>(self, _: V) -> Result<V::Value, Self::Error>
+    // Each element is a single byte, anything but a u8 asking for one is a
+    // type mismatch rather than a wider value made up of the next bytes
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!("Only intended for bytes deserialization")
+        let Some(&byte) = self.data.first() else {
+            return Err(DeserializeError::Eod);
+        };
+        Err(serde::de::Error::invalid_type(
+            serde::de::Unexpected::Unsigned(byte.into()),
+            &visitor,
+        ))
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -126,8 +494,19 @@ impl<'a, 'de> serde::Deserializer<'de> for &'a mut BytesDeserializer<'de> {
         visitor.visit_seq(self)
     }
 
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.data.is_empty() {
+            return Err(DeserializeError::Eod);
+        }
+        self.data = &self.data[1..];
+        visitor.visit_unit()
+    }
+
     forward_to_deserialize_any! {
-      u64 u32 u16 i64 i32 i16 i8 bool f32 f64 char string str bytes byte_buf option unit unit_struct newtype_struct tuple tuple_struct map struct enum identifier ignored_any
+      u64 u32 u16 i64 i32 i16 i8 bool f32 f64 char string str bytes byte_buf option unit unit_struct newtype_struct tuple tuple_struct map struct enum identifier
     }
 }
 
@@ -153,14 +532,40 @@ impl<'de> SeqAccess<'de> for BytesDeserializer<'de> {
 }
 
 impl<'de> Deserializer<'de> {
+    // The text of a string or key, only checked when the input isn't trusted
+    fn utf8(&self, data: &'de [u8]) -> Result<&'de str, std::str::Utf8Error> {
+        if self.trusted {
+            // SAFETY: `new_trusted` callers promise every string is valid UTF-8
+            return Ok(unsafe { std::str::from_utf8_unchecked(data) });
+        }
+        std::str::from_utf8(data)
+    }
+
+    // The text of a string or key, invalid UTF-8 handled as `utf8_policy` says
+    fn text(&self, data: &'de [u8]) -> Result<Text<'de>, DeserializeError> {
+        match self.utf8(data) {
+            Ok(text) => Ok(Text::Str(text)),
+            Err(err) => match self.utf8_policy {
+                Utf8Policy::Strict => Err(DeserializeError::InvalidUtf8 {
+                    // `data` always points into the input
+                    offset: data.as_ptr() as usize - self.input.as_ptr() as usize
+                        + err.valid_up_to(),
+                }),
+                Utf8Policy::Lossy => Ok(Text::Lossy(String::from_utf8_lossy(data).into_owned())),
+                Utf8Policy::Raw => Ok(Text::Raw(data)),
+            },
+        }
+    }
+
     fn deserialize_string<V: Visitor<'de>>(
         &mut self,
         data: &'de [u8],
         visitor: V,
     ) -> Result<V::Value, DeserializeError> {
-        match std::str::from_utf8(data) {
-            Ok(data) => visitor.visit_str(data),
-            Err(_) => visitor.visit_borrowed_bytes(data),
+        match self.text(data)? {
+            Text::Str(text) => visitor.visit_borrowed_str(text),
+            Text::Lossy(text) => visitor.visit_string(text),
+            Text::Raw(data) => visitor.visit_borrowed_bytes(data),
         }
     }
 
@@ -169,6 +574,7 @@ impl<'de> Deserializer<'de> {
         normal: NormalRionType,
         // length: &[u8],
         data: &'de [u8],
+        count_hint: Option<usize>,
         visitor: V,
     ) -> Result<V::Value, DeserializeError> {
         // let len_data: usize = bytes_to_num(length)?;
@@ -178,6 +584,13 @@ impl<'de> Deserializer<'de> {
         // let field_data = &self.data[..len_data];
         // self.data = &self.data[len_data..];
         // println!("Normal: {normal:?} ({data:X?})");
+        let container = matches!(
+            normal,
+            NormalRionType::Array | NormalRionType::Object | NormalRionType::Table
+        );
+        if container && self.depth >= self.max_depth {
+            return Err(DeserializeError::DepthLimit(self.max_depth));
+        }
         match normal {
             // NormalRionType::Array => {
             //     visitor.visit_seq(SizedDeserializer::new(&mut Deserializer::new(data)))
@@ -186,20 +599,52 @@ impl<'de> Deserializer<'de> {
             //     visitor.visit_map(SizedDeserializer::new(&mut Deserializer::new(data)))
             // }
             NormalRionType::Array => {
-                let mut deserializer = Deserializer::new(data);
-                visitor.visit_seq(SizedDeserializer::new(&mut deserializer))
+                self.stats.containers += 1;
+                // Without a hint, count the elements by skipping over them
+                let len = match count_hint {
+                    Some(len) => Some(checked_hint(len, data.len(), data)?),
+                    None => crate::count_fields(data).ok(),
+                };
+                let mut deserializer = self.nested(data);
+                let result = visitor.visit_seq(SizedDeserializer::new(&mut deserializer, len));
+                self.stats.merge(deserializer.stats);
+                if result.is_ok() && !deserializer.data.is_empty() {
+                    return Err(DeserializeError::ExtraData);
+                }
+                result
             }
             NormalRionType::Object => {
-                let mut deserializer = Deserializer::new(data);
-                let result = visitor.visit_map(SizedDeserializer::new(&mut deserializer));
-                if !deserializer.data.is_empty() {
+                self.stats.containers += 1;
+                let len = match count_hint {
+                    Some(len) => Some(checked_hint(len, data.len() / 2, data)?),
+                    None => crate::count_fields(data).ok().map(|fields| fields / 2),
+                };
+                let mut deserializer = self.nested(data);
+                let result = visitor.visit_map(SizedDeserializer::new(&mut deserializer, len));
+                self.stats.merge(deserializer.stats);
+                if result.is_ok() && !deserializer.data.is_empty() {
                     return Err(DeserializeError::ExtraData);
                 }
                 result
             }
             NormalRionType::UTF8 | NormalRionType::Key => self.deserialize_string(data, visitor),
             NormalRionType::Bytes => visitor.visit_seq(BytesDeserializer { data }),
-            NormalRionType::Table => todo!(),
+            NormalRionType::Table => {
+                self.stats.containers += 1;
+                let mut deserializer = self.nested(data);
+                let (rows, columns) = deserializer.table_header()?;
+                let result = visitor.visit_seq(TableAccess {
+                    deserializer: &mut deserializer,
+                    columns: &columns,
+                    rows,
+                    row: 0,
+                });
+                self.stats.merge(deserializer.stats);
+                if result.is_ok() && !deserializer.data.is_empty() {
+                    return Err(DeserializeError::ExtraData);
+                }
+                result
+            }
         }
     }
 
@@ -212,32 +657,272 @@ impl<'de> Deserializer<'de> {
         // println!("Short: {short:?} ({length:X?})");
         match short {
             ShortRionType::Key | ShortRionType::UTF8 => self.deserialize_string(length, visitor),
+            // Integers past 64 bits come from arbitrary precision numbers
+            ShortRionType::Int64Positive if length.len() > 8 => {
+                visitor.visit_u128(wide_int(length))
+            }
+            ShortRionType::Int64Negative if length.len() > 8 => {
+                visitor.visit_i128(-(wide_int(length) as i128) - 1)
+            }
             ShortRionType::Int64Positive => {
                 let val = bytes_to_int(length)?;
                 visitor.visit_u64(val)
             }
-            ShortRionType::Int64Negative => {
-                let val = bytes_to_int(length)?;
-                let val = -(val as i64 + 1);
-                visitor.visit_i64(val)
-            }
+            // Stored as -(value + 1), values below i64::MIN need an i128
+            ShortRionType::Int64Negative => match i64::try_from(bytes_to_int(length)?) {
+                Ok(magnitude) => visitor.visit_i64(-magnitude - 1),
+                Err(_) => visitor.visit_i128(-(wide_int(length) as i128) - 1),
+            },
+            // Trailing zero bytes may be trimmed, pad them back on the right
             ShortRionType::Float => match length.len() {
-                4 => visitor.visit_f32(f32::from_be_bytes(length.try_into().unwrap())),
-                8 => visitor.visit_f64(f64::from_be_bytes(length.try_into().unwrap())),
+                len @ 1..=4 => {
+                    let mut bytes = [0; 4];
+                    bytes[..len].copy_from_slice(length);
+                    visitor.visit_f32(f32::from_be_bytes(bytes))
+                }
+                len @ 5..=8 => {
+                    let mut bytes = [0; 8];
+                    bytes[..len].copy_from_slice(length);
+                    visitor.visit_f64(f64::from_be_bytes(bytes))
+                }
                 _ => Err(DeserializeError::DataLength(
                     8,
                     length.len(),
                     length.to_vec(),
                 )),
             },
-            ShortRionType::UTCDateTime => todo!(),
+            ShortRionType::UTCDateTime => {
+                let field = RionField::Short(ShortField::try_new(short, length)?);
+                let datetime = crate::RionDateTime::try_from(field)?;
+                visitor.visit_string(datetime.to_rfc3339())
+            }
+        }
+    }
+}
+
+impl<'de> Deserializer<'de> {
+    // Read the row count, dictionaries and column names at the start of a
+    // table, leaving the cells
+    fn table_header(&mut self) -> Result<(u64, Vec<Column<'de>>), DeserializeError> {
+        let count = self.parse_next_field()?;
+        let rows = count
+            .as_u64()
+            .ok_or_else(|| format!("Expected a row count, found {count:?}"))?;
+        self.data = crate::skip_table_stats(self.data)?;
+        let mut dictionaries = Vec::new();
+        if self
+            .data
+            .first()
+            .is_some_and(|&lead| lead >> 4 == RionFieldType::OBJECT && lead & 0x0F != 0)
+        {
+            let (_, data_len, rest) = self.normal_header()?;
+            let (mut content, rest) = rest.split_at(data_len);
+            self.data = rest;
+            while !content.is_empty() {
+                let (name, next) = RionFieldRef::parse(content)?;
+                let (_, entries_len, next) = get_normal_header(next)?;
+                let (mut entries, next) = next.split_at(entries_len);
+                let mut encoded = Vec::new();
+                while !entries.is_empty() {
+                    let (_, after) = RionFieldRef::parse(entries)?;
+                    encoded.push(&entries[..entries.len() - after.len()]);
+                    entries = after;
+                }
+                dictionaries.push((name.as_bytes(), encoded));
+                content = next;
+            }
+        }
+        let mut columns = Vec::new();
+        while let Ok((field, rest)) = RionFieldRef::parse(self.data) {
+            let (RionFieldRef::Short(ShortRionType::Key, name)
+            | RionFieldRef::Normal(NormalRionType::Key, name)) = field
+            else {
+                break;
+            };
+            self.data = rest;
+            columns.push(Column {
+                name,
+                dictionary: None,
+            });
         }
+        for (name, entries) in dictionaries {
+            let column = columns
+                .iter_mut()
+                .find(|column| column.name == name)
+                .ok_or_else(|| format!("Dictionary for unknown column {name:x?}"))?;
+            column.dictionary = Some(entries);
+        }
+        // Every cell takes at least one byte, so the rows can't outnumber
+        // what's left of the table
+        if rows > 0 && (columns.is_empty() || rows > (self.data.len() / columns.len()) as u64) {
+            return Err(FerionError::RowCount(rows).into());
+        }
+        Ok((rows, columns))
     }
 }
 
+// The magnitude in a short integer field of up to 15 bytes
+fn wide_int(bytes: &[u8]) -> u128 {
+    bytes.iter().fold(0, |acc, &b| acc << 8 | b as u128)
+}
+
 impl<'de> Deserializer<'de> {
     pub fn new(data: &'de [u8]) -> Self {
-        Self { data }
+        Self {
+            data,
+            input: data,
+            utf8_policy: Utf8Policy::Strict,
+            opaque_extended: false,
+            strict_columns: false,
+            numeric_fields: false,
+            trusted: false,
+            count_hint: None,
+            depth: 0,
+            max_depth: crate::DEFAULT_MAX_DEPTH,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Fail with [`DeserializeError::DepthLimit`] instead of decoding arrays,
+    /// objects and tables nested deeper than `max_depth`,
+    /// [`DEFAULT_MAX_DEPTH`](crate::DEFAULT_MAX_DEPTH) by default
+    ///
+    /// Every level of nesting takes stack space while decoding, so keep the
+    /// limit low for input that isn't trusted.
+    /// [`RionValue::from_slice`](crate::RionValue::from_slice) doesn't recurse
+    /// and parses values nested to any depth.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// What to do with strings and keys holding invalid UTF-8,
+    /// [`Utf8Policy::Strict`] by default
+    pub fn utf8_policy(mut self, policy: Utf8Policy) -> Self {
+        self.utf8_policy = policy;
+        self
+    }
+
+    /// Hand out strings and keys holding invalid UTF-8 as bytes instead of
+    /// failing with [`DeserializeError::InvalidUtf8`], the same as
+    /// [`Utf8Policy::Raw`]
+    pub fn lenient_utf8(self, lenient: bool) -> Self {
+        self.utf8_policy(if lenient {
+            Utf8Policy::Raw
+        } else {
+            Utf8Policy::Strict
+        })
+    }
+
+    /// Hand out extended fields as bytes holding the whole encoded field,
+    /// which [`RionField::from_slice`] reads back as is, instead of failing
+    /// with [`DeserializeError::UnknownExtended`]
+    pub fn opaque_extended(mut self, opaque: bool) -> Self {
+        self.opaque_extended = opaque;
+        self
+    }
+
+    /// Fail when a table decoded into structs has a column the struct has no
+    /// field for, or lacks a column for one of its fields
+    ///
+    /// By default unknown columns are skipped and missing ones are left to
+    /// serde, so `Option` and `#[serde(default)]` fields fill in. This lets
+    /// producers add columns without breaking older consumers.
+    pub fn strict_columns(mut self, strict: bool) -> Self {
+        self.strict_columns = strict;
+        self
+    }
+
+    /// Read struct fields keyed by their index, as written by
+    /// [`Serializer::numeric_fields`](crate::Serializer::numeric_fields)
+    ///
+    /// Every Key field read as a struct field is taken as an index, so data
+    /// keyed by name can't be decoded this way.
+    pub fn numeric_fields(mut self, numeric: bool) -> Self {
+        self.numeric_fields = numeric;
+        self
+    }
+
+    // A deserializer for the content of a container within the same input
+    fn nested(&self, data: &'de [u8]) -> Deserializer<'de> {
+        Deserializer {
+            data,
+            input: self.input,
+            utf8_policy: self.utf8_policy,
+            opaque_extended: self.opaque_extended,
+            strict_columns: self.strict_columns,
+            numeric_fields: self.numeric_fields,
+            trusted: self.trusted,
+            count_hint: None,
+            depth: self.depth + 1,
+            max_depth: self.max_depth,
+            stats: Stats::default(),
+        }
+    }
+
+    // `err` located at the field starting at `at`, unless a field within it
+    // already located it
+    fn locate(&self, at: &[u8], err: DeserializeError) -> DeserializeError {
+        if let DeserializeError::At { .. } = err {
+            return err;
+        }
+        DeserializeError::At {
+            path: Path::default(),
+            offset: at.as_ptr() as usize - self.input.as_ptr() as usize,
+            lead: at.first().copied(),
+            error: Box::new(err),
+        }
+    }
+
+    // Like `locate` for an entry of a container, adding its key or index to
+    // the front of the path
+    fn locate_entry(
+        &self,
+        at: &[u8],
+        segment: Option<Segment>,
+        err: DeserializeError,
+    ) -> DeserializeError {
+        let mut err = self.locate(at, err);
+        if let (DeserializeError::At { path, .. }, Some(segment)) = (&mut err, segment) {
+            path.push_front(segment);
+        }
+        err
+    }
+
+    /// A deserializer that takes strings and keys as valid UTF-8 without
+    /// checking, see [`from_bytes_trusted`]
+    ///
+    /// # Safety
+    ///
+    /// Every string and key in `data` has to be valid UTF-8, as it is in
+    /// anything [`to_bytes`](crate::to_bytes) wrote. Invalid UTF-8 ends up in
+    /// `&str`s, which is undefined behavior.
+    pub unsafe fn new_trusted(data: &'de [u8]) -> Self {
+        Self {
+            trusted: true,
+            ..Self::new(data)
+        }
+    }
+
+    /// A deserializer over input checked to be in canonical form first, see
+    /// [`from_bytes_strict`]
+    pub fn new_strict(data: &'de [u8]) -> Result<Self, DeserializeError> {
+        super::strict::check_canonical(data)?;
+        Ok(Self::new(data))
+    }
+
+    /// Fail with [`DeserializeError::ExtraData`] if any input is left, for
+    /// after decoding a value that should take up all of it
+    pub fn end(&self) -> Result<(), DeserializeError> {
+        if self.data.is_empty() {
+            return Ok(());
+        }
+        Err(self.locate(self.data, DeserializeError::ExtraData))
+    }
+
+    /// Counters collected by this deserializer so far
+    pub fn stats(&self) -> Stats {
+        self.stats
     }
 
     pub fn next_byte(&mut self) -> Option<u8> {
@@ -264,39 +949,124 @@ impl<'de> Deserializer<'de> {
             .and_then(Result::ok)
     }
 
+    // The header of the next field, telling input that ends early apart from
+    // input that is invalid
+    fn header(&self) -> Result<(LeadByte, &'de [u8], &'de [u8]), DeserializeError> {
+        let Some((&first, rest)) = self.data.split_first() else {
+            return Err(DeserializeError::Eod);
+        };
+        let lead = LeadByte::new(first).ok_or(DeserializeError::UnknownFieldType(first))?;
+        let length_length = lead.length() as usize;
+        if length_length > rest.len() {
+            return Err(DeserializeError::Truncated(length_length - rest.len()));
+        }
+        Ok((lead, &rest[..length_length], &rest[length_length..]))
+    }
+
+    // Like `header` for a normal field, also checking its content is all there
+    fn normal_header(&self) -> Result<(LeadByte, usize, &'de [u8]), DeserializeError> {
+        let (lead, length, rest) = self.header()?;
+        let RionFieldType::Normal(_) = lead.field_type() else {
+            return Err(format!("Expected a normal field, found {:?}", lead.field_type()).into());
+        };
+        let data_len = bytes_to_int(length)? as usize;
+        if data_len > rest.len() {
+            return Err(DeserializeError::Truncated(data_len - rest.len()));
+        }
+        Ok((lead, data_len, rest))
+    }
+
+    // Why the next field failed to parse
+    fn invalid_field(&self) -> DeserializeError {
+        let (lead, length, rest) = match self.header() {
+            Ok(header) => header,
+            Err(e) => return e,
+        };
+        if let RionFieldType::Normal(_) | RionFieldType::Extended = lead.field_type() {
+            match bytes_to_int(length) {
+                Ok(data_len) if data_len as usize > rest.len() => {
+                    return DeserializeError::Truncated(data_len as usize - rest.len())
+                }
+                _ => {}
+            }
+        }
+        DeserializeError::InvalidData(self.data.to_vec())
+    }
+
+    // Consume a null field of any type
+    fn next_null(&mut self) -> Result<(), DeserializeError> {
+        let (lead, _, rest) = self.header()?;
+        if !lead.is_null() {
+            return Err(DeserializeError::ExpectedNull);
+        }
+        self.data = rest;
+        self.stats.fields += 1;
+        Ok(())
+    }
+
     fn deserialize_field<V>(&mut self, visitor: V) -> Result<V::Value, DeserializeError>
     where
         V: Visitor<'de>,
     {
-        let (lead, length, rest) = get_header(self.data)?;
+        let field = self.data;
+        let count_hint = self.count_hint.take();
+        let (lead, length, rest) = self.header()?;
+        self.stats.fields += 1;
+        self.data = rest;
         if lead.is_null() {
             return visitor.visit_none();
         }
-        self.data = rest;
         match lead.field_type() {
             RionFieldType::Tiny(lead) => visitor.visit_bool(lead.as_bool().unwrap()),
             RionFieldType::Short(short) => self.deserialize_short(short, length, visitor),
             RionFieldType::Normal(normal) => {
                 let length_length = bytes_to_int(length)? as usize;
                 if length_length > self.data.len() {
-                    return Err(DeserializeError::DataLength(
-                        length_length,
-                        self.data.len(),
-                        self.data.to_vec(),
-                    ));
+                    return Err(DeserializeError::Truncated(length_length - self.data.len()));
                 }
                 let (data, rest) = self.data.split_at(length_length);
                 self.data = rest;
-                self.deserialize_normal(normal, data, visitor)
+                self.deserialize_normal(normal, data, count_hint, visitor)
+            }
+            RionFieldType::Extended => {
+                let (parsed, rest) = RionField::parse(field).map_err(|_| self.invalid_field())?;
+                let RionField::Extended { type_id, data } = parsed else {
+                    unreachable!("Parsed from an extended lead byte")
+                };
+                if type_id == RionField::COUNT_HINT {
+                    // A hint describes the container after it, two in a row
+                    // would only let the input recurse without nesting
+                    let container = rest.first().is_some_and(|&lead| {
+                        matches!(lead >> 4, RionFieldType::ARRAY | RionFieldType::OBJECT)
+                    });
+                    if count_hint.is_some() || !container {
+                        let hint = &field[..field.len() - rest.len()];
+                        return Err(DeserializeError::InvalidData(hint.to_vec()));
+                    }
+                    self.data = rest;
+                    self.count_hint = Some(bytes_to_int(&data)? as usize);
+                    return self.deserialize_field(visitor);
+                }
+                if type_id == RionField::WIDE_DATE_TIME {
+                    self.data = rest;
+                    let field = RionField::Extended { type_id, data };
+                    let datetime = crate::RionDateTime::try_from(field)?;
+                    return visitor.visit_string(datetime.to_rfc3339());
+                }
+                if !self.opaque_extended {
+                    return Err(DeserializeError::UnknownExtended(type_id));
+                }
+                self.data = rest;
+                let encoded = &field[..field.len() - rest.len()];
+                visitor.visit_seq(BytesDeserializer { data: encoded })
             }
-            _ => Err(DeserializeError::InvalidData(self.data.to_vec())),
         }
     }
 
     fn parse_next_field(&mut self) -> Result<RionField<'de>, DeserializeError> {
-        let (field, rest) = RionField::parse(self.data)
-            .map_err(|_| DeserializeError::InvalidData(self.data.to_vec()))?;
+        let (field, rest) = RionField::parse(self.data).map_err(|_| self.invalid_field())?;
         self.data = rest;
+        self.stats.fields += 1;
         Ok(field)
     }
 
@@ -304,11 +1074,9 @@ impl<'de> Deserializer<'de> {
     where
         T: TryFrom<RionField<'de>, Error: Display>,
     {
-        let field = self.parse_next_field()?;
-        println!("{:?}", field);
-        field
+        self.parse_next_field()?
             .try_into()
-            .map_err(|e: T::Error| DeserializeError::Custom(e.to_string()))
+            .map_err(|e: T::Error| DeserializeError::TypeMismatch(e.to_string()))
     }
 
     // fn visit_field<V>(
@@ -356,7 +1124,7 @@ impl<'de> Deserializer<'de> {
     // }
 }
 
-impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> serde::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = DeserializeError;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -367,15 +1135,65 @@ impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     forward_to_deserialize_any! {
-      bool i64 u64 f32 f64 str ignored_any seq identifier map bytes string unit unit_struct newtype_struct
-      tuple tuple_struct struct
+      bool i64 u64 i128 u128 f32 f64 str ignored_any seq map bytes string struct
+    }
+
+    // Date times asked for with the date time token get their field data
+    // rather than an RFC 3339 string, which would lose their precision
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let lead = self.peek_lead().map(LeadByte::field_type);
+        if name == crate::serde::datetime::TOKEN
+            && lead == Some(RionFieldType::Short(ShortRionType::UTCDateTime))
+        {
+            let field = self.parse_next_field()?;
+            return visitor.visit_bytes(field.as_bytes());
+        }
+        self.deserialize_any(visitor)
+    }
+
+    // Struct fields and enum variants are matched against the key borrowed from
+    // the input, so decoding a struct never allocates for its keys
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (lead, length, rest) = self.header()?;
+        if lead.is_null() {
+            return self.deserialize_field(visitor);
+        }
+        let key = match lead.field_type() {
+            RionFieldType::Short(ShortRionType::Key | ShortRionType::UTF8) => {
+                self.data = rest;
+                length
+            }
+            RionFieldType::Normal(NormalRionType::Key | NormalRionType::UTF8) => {
+                let (_, data_len, rest) = self.normal_header()?;
+                let (key, rest) = rest.split_at(data_len);
+                self.data = rest;
+                key
+            }
+            // e.g. variant indices
+            _ => return self.deserialize_field(visitor),
+        };
+        self.stats.fields += 1;
+        if self.numeric_fields && lead.field_type().is_key() {
+            return visitor.visit_u64(bytes_to_int(key)?);
+        }
+        self.deserialize_string(key, visitor)
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        let value: _ = self.parse_field()?;
+        let value = self.parse_field()?;
         visitor.visit_i8(value)
     }
 
@@ -383,7 +1201,7 @@ impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: serde::de::Visitor<'de>,
     {
-        let value: _ = self.parse_field()?;
+        let value = self.parse_field()?;
         visitor.visit_i16(value)
     }
 
@@ -391,7 +1209,7 @@ impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: serde::de::Visitor<'de>,
     {
-        let value: _ = self.parse_field()?;
+        let value = self.parse_field()?;
         visitor.visit_i32(value)
     }
 
@@ -399,7 +1217,7 @@ impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: serde::de::Visitor<'de>,
     {
-        let value: _ = self.parse_field()?;
+        let value = self.parse_field()?;
         visitor.visit_u8(value)
     }
 
@@ -407,7 +1225,7 @@ impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: serde::de::Visitor<'de>,
     {
-        let value: _ = self.parse_field()?;
+        let value = self.parse_field()?;
         visitor.visit_u16(value)
     }
 
@@ -415,7 +1233,7 @@ impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: serde::de::Visitor<'de>,
     {
-        let value: _ = self.parse_field()?;
+        let value = self.parse_field()?;
         visitor.visit_u32(value)
     }
 
@@ -448,14 +1266,93 @@ impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
         let Some(first) = self.data.first() else {
             return Err(DeserializeError::Eod);
         };
-        let lead = LeadByte::try_from(*first)?;
+        let lead = LeadByte::new(*first).ok_or(DeserializeError::UnknownFieldType(*first))?;
         if lead.is_null() {
+            self.next_null()?;
             visitor.visit_none()
         } else {
             visitor.visit_some(self)
         }
     }
 
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        // Fixed size byte arrays ([u8; N]) may be stored as a single Bytes field
+        let Some(lead) = self.peek_lead() else {
+            return Err(DeserializeError::Eod);
+        };
+        if lead.field_type() != RionFieldType::Normal(NormalRionType::Bytes) {
+            return self.deserialize_field(visitor);
+        }
+        let (_, data_len, rest) = self.normal_header()?;
+        let (data, rest) = rest.split_at(data_len);
+        if data_len != len {
+            return Err(DeserializeError::DataLength(len, data_len, data.to_vec()));
+        }
+        self.data = rest;
+        self.stats.fields += 1;
+        visitor.visit_seq(BytesDeserializer { data })
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let Some(lead) = self.peek_lead() else {
+            return Err(DeserializeError::Eod);
+        };
+        if lead.field_type() != RionFieldType::Normal(NormalRionType::Object) {
+            return self.deserialize_tuple(len, visitor);
+        }
+        // Compatibility form: an Object with a single key wrapping the Array
+        let (_, data_len, rest) = self.normal_header()?;
+        let (data, rest) = rest.split_at(data_len);
+        let mut deserializer = self.nested(data);
+        let key = deserializer.parse_next_field()?;
+        if !key.is_key() {
+            return Err(DeserializeError::InvalidType(
+                RionFieldType::Short(ShortRionType::Key),
+                key.field_type(),
+            ));
+        }
+        let value = deserializer.deserialize_tuple(len, visitor)?;
+        if !deserializer.data.is_empty() {
+            return Err(DeserializeError::ExtraData);
+        }
+        self.data = rest;
+        self.stats.fields += 1;
+        self.stats.containers += 1;
+        self.stats.entries += 1;
+        self.stats.merge(deserializer.stats);
+        Ok(value)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.next_null()?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
@@ -484,11 +1381,21 @@ impl DerefMut for SizedDeserializer<'_, '_> {
 
 struct SizedDeserializer<'a, 'de> {
     serializer: &'a mut Deserializer<'de>,
+    // Number of entries, when known
+    len: Option<usize>,
+    // Index of the next element, or key of the next value, for error paths
+    index: usize,
+    key: Option<&'de [u8]>,
 }
 
 impl<'a, 'de> SizedDeserializer<'a, 'de> {
-    fn new(serializer: &'a mut Deserializer<'de>) -> Self {
-        Self { serializer }
+    fn new(serializer: &'a mut Deserializer<'de>, len: Option<usize>) -> Self {
+        Self {
+            serializer,
+            len,
+            index: 0,
+            key: None,
+        }
     }
 }
 
@@ -502,8 +1409,19 @@ impl<'de, 'a> serde::de::SeqAccess<'de> for SizedDeserializer<'a, 'de> {
         if self.data.is_empty() {
             return Ok(None);
         }
-        let value = seed.deserialize(&mut **self)?;
-        Ok(Some(value))
+        self.stats.entries += 1;
+        self.len = self.len.map(|len| len.saturating_sub(1));
+        let at = self.data;
+        let segment = Segment::Index(self.index);
+        self.index += 1;
+        let value = seed.deserialize(&mut **self);
+        Ok(Some(value.map_err(|err| {
+            self.locate_entry(at, Some(segment), err)
+        })?))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.len
     }
 }
 
@@ -527,15 +1445,204 @@ impl<'de, 'a> serde::de::MapAccess<'de> for SizedDeserializer<'a, 'de> {
             Ok(field) if field.is_key() => field,
             _ => return Ok(None),
         };
-        let key = seed.deserialize(&mut **self)?;
-        Ok(Some(key))
+        self.stats.entries += 1;
+        self.len = self.len.map(|len| len.saturating_sub(1));
+        let at = self.data;
+        self.key = RionFieldRef::parse(at).ok().map(|(key, _)| key.as_bytes());
+        let key = seed.deserialize(&mut **self);
+        Ok(Some(key.map_err(|err| self.locate(at, err))?))
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::DeserializeSeed<'de>,
     {
-        let value = seed.deserialize(&mut **self)?;
+        let at = self.data;
+        let key = self.key.take();
+        let value = seed.deserialize(&mut **self);
+        value.map_err(|err| self.locate_entry(at, key.map(key_segment), err))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.len
+    }
+}
+
+// A count hint, unless the container is too short to hold that many entries
+// of at least one byte each
+fn checked_hint(len: usize, max: usize, data: &[u8]) -> Result<usize, DeserializeError> {
+    if len > max {
+        return Err(DeserializeError::InvalidData(data.to_vec()));
+    }
+    Ok(len)
+}
+
+// The path segment of a key read from the input
+fn key_segment(key: &[u8]) -> Segment {
+    Segment::Key(String::from_utf8_lossy(key).into_owned())
+}
+
+// A column of a table being decoded, with the encoded entries of its
+// dictionary when it has one
+struct Column<'de> {
+    name: &'de [u8],
+    dictionary: Option<Vec<&'de [u8]>>,
+}
+
+// The rows of a table, handed out as maps from column name to cell
+struct TableAccess<'a, 'de> {
+    deserializer: &'a mut Deserializer<'de>,
+    columns: &'a [Column<'de>],
+    rows: u64,
+    // Index of the next row, for error paths
+    row: usize,
+}
+
+impl<'de> SeqAccess<'de> for TableAccess<'_, 'de> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.rows == 0 {
+            return Ok(None);
+        }
+        self.rows -= 1;
+        self.deserializer.stats.entries += 1;
+        let at = self.deserializer.data;
+        let segment = Segment::Index(self.row);
+        self.row += 1;
+        seed.deserialize(RowDeserializer {
+            deserializer: self.deserializer,
+            columns: self.columns,
+        })
+        .map(Some)
+        .map_err(|err| self.deserializer.locate_entry(at, Some(segment), err))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        usize::try_from(self.rows).ok()
+    }
+}
+
+struct RowDeserializer<'a, 'de> {
+    deserializer: &'a mut Deserializer<'de>,
+    columns: &'a [Column<'de>],
+}
+
+impl<'de> serde::Deserializer<'de> for RowDeserializer<'_, 'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(RowAccess {
+            deserializer: self.deserializer,
+            columns: self.columns,
+            next: 0,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.deserializer.strict_columns {
+            let names = self.columns.iter().map(|column| column.name);
+            if let Some(unknown) = names
+                .clone()
+                .find(|name| !fields.iter().any(|f| f.as_bytes() == *name))
+            {
+                return Err(DeserializeError::UnknownColumn(
+                    String::from_utf8_lossy(unknown).into_owned(),
+                ));
+            }
+            if let Some(missing) = fields
+                .iter()
+                .find(|f| !names.clone().any(|name| name == f.as_bytes()))
+            {
+                return Err(DeserializeError::MissingColumn(missing));
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+struct RowAccess<'a, 'de> {
+    deserializer: &'a mut Deserializer<'de>,
+    columns: &'a [Column<'de>],
+    next: usize,
+}
+
+impl<'de> MapAccess<'de> for RowAccess<'_, 'de> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some(column) = self.columns.get(self.next) else {
+            return Ok(None);
+        };
+        let name = column.name;
+        match self.deserializer.text(name)? {
+            Text::Str(name) => seed.deserialize(BorrowedStrDeserializer::new(name)),
+            Text::Lossy(name) => seed.deserialize(name.into_deserializer()),
+            Text::Raw(name) => seed.deserialize(BorrowedBytesDeserializer::new(name)),
+        }
+        .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let at = self.deserializer.data;
+        let name = self.columns[self.next].name;
+        let value = self.next_cell(seed);
+        value.map_err(|err| {
+            self.deserializer
+                .locate_entry(at, Some(key_segment(name)), err)
+        })
+    }
+}
+
+impl<'de> RowAccess<'_, 'de> {
+    fn next_cell<V>(&mut self, seed: V) -> Result<V::Value, DeserializeError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let column = &self.columns[self.next];
+        self.next += 1;
+        let deserializer = &mut *self.deserializer;
+        let Some(entries) = &column.dictionary else {
+            return seed.deserialize(deserializer);
+        };
+        // Dictionary columns hold the index of their entry
+        if deserializer.peek_lead().is_some_and(LeadByte::is_null) {
+            return seed.deserialize(deserializer);
+        }
+        let index = deserializer.parse_next_field()?;
+        let entry = index
+            .as_u64()
+            .and_then(|index| entries.get(index as usize))
+            .ok_or_else(|| format!("Invalid dictionary index {index:?}"))?;
+        let mut nested = deserializer.nested(entry);
+        let value = seed.deserialize(&mut nested)?;
+        deserializer.stats.merge(nested.stats);
         Ok(value)
     }
 }
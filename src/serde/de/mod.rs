@@ -1,4 +1,8 @@
 mod deserializer;
+mod strict;
 #[cfg(test)]
 mod tests;
-pub use deserializer::{from_bytes, DeserializeError, Deserializer};
+pub use deserializer::{
+    from_bytes, from_bytes_lenient, from_bytes_many, from_bytes_strict, from_bytes_trusted,
+    from_bytes_with_stats, DeserializeError, Deserializer, Utf8Policy,
+};
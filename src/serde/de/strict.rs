@@ -0,0 +1,69 @@
+use super::DeserializeError;
+use crate::{
+    get_header,
+    types::{NormalRionType, RionFieldType, ShortRionType},
+    RionFieldRef,
+};
+
+/// Check that every field in `data` uses its shortest encoding
+///
+/// The encoder never writes integers or lengths with leading zero bytes,
+/// floats with trimmable trailing zero bytes, or strings and keys of up to 15
+/// bytes as normal fields, so any of those means the input isn't canonical.
+pub(super) fn check_canonical(data: &[u8]) -> Result<(), DeserializeError> {
+    // Fields left to check and where they start in the whole input. The rest
+    // of a container is put here while checking the container within it
+    // rather than recursing, so any depth of nesting can be checked
+    let mut pending = vec![(data, 0)];
+    while let Some((data, offset)) = pending.pop() {
+        check_fields(data, offset, &mut pending)?;
+    }
+    Ok(())
+}
+
+// `offset` is where `data` starts in the whole input
+fn check_fields<'a>(
+    mut data: &'a [u8],
+    mut offset: usize,
+    pending: &mut Vec<(&'a [u8], usize)>,
+) -> Result<(), DeserializeError> {
+    while !data.is_empty() {
+        let non_canonical = |reason| DeserializeError::NonCanonical(offset, reason);
+        let (lead, length, _) = get_header(data)?;
+        let (field, rest) =
+            RionFieldRef::parse(data).map_err(|_| DeserializeError::InvalidData(data.to_vec()))?;
+        let content = field.as_bytes();
+        match lead.field_type() {
+            RionFieldType::Short(ShortRionType::Int64Positive | ShortRionType::Int64Negative)
+                if content.len() > 1 && content[0] == 0 =>
+            {
+                return Err(non_canonical("integer with leading zero bytes"));
+            }
+            // Floats keep at least 1 byte, or 5 for those that need 64 bits
+            RionFieldType::Short(ShortRionType::Float)
+                if !matches!(content.len(), 1 | 5) && content.last() == Some(&0) =>
+            {
+                return Err(non_canonical("float with trailing zero bytes"));
+            }
+            RionFieldType::Normal(_) | RionFieldType::Extended if length.first() == Some(&0) => {
+                return Err(non_canonical("length with leading zero bytes"));
+            }
+            RionFieldType::Normal(NormalRionType::UTF8 | NormalRionType::Key)
+                if (1..16).contains(&content.len()) =>
+            {
+                return Err(non_canonical("short string in a normal field"));
+            }
+            RionFieldType::Normal(
+                NormalRionType::Array | NormalRionType::Object | NormalRionType::Table,
+            ) => {
+                pending.push((rest, offset + data.len() - rest.len()));
+                (data, offset) = (content, offset + 1 + length.len());
+                continue;
+            }
+            _ => {}
+        }
+        offset += data.len() - rest.len();
+        data = rest;
+    }
+    Ok(())
+}
@@ -43,6 +43,7 @@ use super::*;
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_deserialize_float() {
         let data = vec![0x44, 0x40, 0x48, 0xF5, 0xC3]; // 3.14 (f32)
         let value: f32 = from_bytes(&data).unwrap();
@@ -51,6 +52,12 @@ use super::*;
         let data = vec![0x48, 0x40, 0x09, 0x21, 0xFB, 0x54, 0x44, 0x2D, 0x11]; // 3.14159265358979 (f64)
         let value: f64 = from_bytes(&data).unwrap();
         assert!((value - 3.14159265358979).abs() < f64::EPSILON);
+
+        // Trimmed trailing zero bytes
+        let data = vec![0x42, 0x3F, 0xC0]; // 1.5 (f32)
+        assert_eq!(from_bytes::<f32>(&data).unwrap(), 1.5);
+        let data = vec![0x45, 0x3F, 0xF8, 0x00, 0x00, 0x00]; // 1.5 (f64)
+        assert_eq!(from_bytes::<f64>(&data).unwrap(), 1.5);
     }
 
     #[test]
@@ -123,6 +130,34 @@ use super::*;
         assert_eq!(value.address.city, "Some");
     }
 
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        #[serde(borrow)]
+        tags: Vec<&'a str>,
+    }
+
+    #[test]
+    fn test_deserialize_borrowed_struct() {
+        let data = vec![
+            0xC1, 0x14, // Start of object
+            0xE4, b'n', b'a', b'm', b'e', 0x65, b'A', b'l', b'i', b'c', b'e', // name: "Alice"
+            0xE4, b't', b'a', b'g', b's', 0xA1, 0x02, 0x61, b'x', // tags: ["x"]
+        ];
+        let value: Borrowed = from_bytes(&data).unwrap();
+        assert_eq!(value, Borrowed { name: "Alice", tags: vec!["x"] });
+        // The strings point into the input instead of being copied
+        assert!(data.as_ptr_range().contains(&value.name.as_ptr()));
+
+        // Unknown keys are skipped without matching any field
+        let data = vec![
+            0xC1, 0x16, 0xE4, b'n', b'a', b'm', b'e', 0x61, b'A', 0xE5, b'o', b't', b'h', b'e',
+            b'r', 0x21, 0x01, 0xE4, b't', b'a', b'g', b's', 0xA1, 0x00,
+        ];
+        let value: Borrowed = from_bytes(&data).unwrap();
+        assert_eq!(value.name, "A");
+    }
+
     #[test]
     fn test_deserialize_tuple() {
         let data = vec![0xA1, 0x04, 0x21, 0x0A, 0x61, b'A']; // (10, 'A')
@@ -159,4 +194,626 @@ use super::*;
         let data = vec![0xE5, b'A', b'l', b'i', b'c', b'e'];
         let result: Result<Option<i32>, _> = from_bytes(&data);
         assert!(result.is_err())
-    }
\ No newline at end of file
+    }
+    #[test]
+    fn test_deserialize_stats() {
+        let data = vec![
+            0xC1, 0x0A, 0xE3, b'K', b'e', b'y', 0x65, b'V', b'a', b'l', b'u', b'e',
+        ];
+        let (_, stats): (std::collections::HashMap<String, String>, _) =
+            from_bytes_with_stats(&data).unwrap();
+        assert_eq!(stats.bytes, data.len());
+        assert_eq!(stats.containers, 1);
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.fields, 3);
+    }
+
+    #[test]
+    fn test_deserialize_unit_any_null() {
+        for data in [[0x00], [0x10], [0x50], [0xC0]] {
+            let value: () = from_bytes(&data).unwrap();
+            assert_eq!(value, ());
+        }
+        let result: Result<(), _> = from_bytes(&[0x21, 0x01]);
+        assert_eq!(result.unwrap_err().kind(), &DeserializeError::ExpectedNull);
+    }
+
+    #[test]
+    fn test_deserialize_fixed_bytes() {
+        let data = vec![0x01, 0x04, 0xDE, 0xAD, 0xBE, 0xEF];
+        let value: [u8; 4] = from_bytes(&data).unwrap();
+        assert_eq!(value, [0xDE, 0xAD, 0xBE, 0xEF]);
+
+        let result: Result<[u8; 3], _> = from_bytes(&data);
+        assert_eq!(
+            result.unwrap_err().kind(),
+            &DeserializeError::DataLength(3, 4, vec![0xDE, 0xAD, 0xBE, 0xEF])
+        );
+
+        // Only u8 elements are read from the bytes
+        let result: Result<[u32; 4], _> = from_bytes(&data);
+        assert!(matches!(result.unwrap_err().kind(), DeserializeError::TypeMismatch(_)));
+        let data = vec![0x01, 0x03, 0x00, 0x01, 0x02];
+        let result: Result<(u16, u8, u8), _> = from_bytes(&data);
+        assert!(matches!(result.unwrap_err().kind(), DeserializeError::TypeMismatch(_)));
+
+        // The per-element Array form still works
+        let data = vec![0xA1, 0x04, 0x21, 0x01, 0x21, 0x02];
+        let value: [u8; 2] = from_bytes(&data).unwrap();
+        assert_eq!(value, [1, 2]);
+    }
+
+    #[derive(Deserialize, serde::Serialize, Debug, PartialEq)]
+    struct Point(u8, i8);
+
+    #[test]
+    fn test_deserialize_tuple_struct() {
+        // Bare Array, as written by serialize_tuple_struct
+        let bytes = crate::to_bytes(&Point(3, -4)).unwrap();
+        assert_eq!(bytes, vec![0xA1, 0x04, 0x21, 0x03, 0x31, 0x03]);
+        assert_eq!(from_bytes::<Point>(&bytes).unwrap(), Point(3, -4));
+
+        // Object wrapping the Array
+        let data = vec![
+            0xC1, 0x0C, 0xE5, b'P', b'o', b'i', b'n', b't', 0xA1, 0x04, 0x21, 0x03, 0x31, 0x03,
+        ];
+        assert_eq!(from_bytes::<Point>(&data).unwrap(), Point(3, -4));
+    }
+
+    #[test]
+    fn test_deserialize_heapless() {
+        let data = vec![0x65, b'A', b'l', b'i', b'c', b'e'];
+        let value: heapless::String<8> = from_bytes(&data).unwrap();
+        assert_eq!(value.as_str(), "Alice");
+
+        // From a Bytes field and from an Array of ints
+        let data = vec![0x01, 0x03, 0x01, 0x02, 0x03];
+        let value: heapless::Vec<u8, 4> = from_bytes(&data).unwrap();
+        assert_eq!(value.as_slice(), &[1, 2, 3]);
+        let data = vec![0xA1, 0x06, 0x21, 0x01, 0x21, 0x02, 0x21, 0x03];
+        let value: heapless::Vec<u32, 4> = from_bytes(&data).unwrap();
+        assert_eq!(value.as_slice(), &[1, 2, 3]);
+
+        // Capacity is enforced
+        let result: Result<heapless::Vec<u32, 2>, _> = from_bytes(&data);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_deserialize_strict() {
+        let data = crate::to_bytes(&("Alice", 300u64, -1i64, 1.5f64)).unwrap();
+        let value: (String, u64, i64, f64) = from_bytes_strict(&data).unwrap();
+        assert_eq!(value, ("Alice".to_string(), 300, -1, 1.5));
+
+        let non_canonical = |data: &[u8]| from_bytes_strict::<serde::de::IgnoredAny>(data);
+        // Leading zero in an integer
+        assert!(from_bytes::<u64>(&[0x22, 0x00, 0x0A]).is_ok());
+        assert!(matches!(
+            non_canonical(&[0x22, 0x00, 0x0A]),
+            Err(DeserializeError::NonCanonical(0, _))
+        ));
+        // Short string in a normal field, nested in an array
+        assert!(matches!(
+            non_canonical(&[0xA1, 0x09, 0x21, 0x01, 0xD1, 0x05, b'A', b'l', b'i', b'c', b'e']),
+            Err(DeserializeError::NonCanonical(4, _))
+        ));
+        // Length with a leading zero
+        assert!(non_canonical(&[0x02, 0x00, 0x01, 0xFF]).is_err());
+        // Float with trimmable trailing zeros
+        assert!(non_canonical(&[0x44, 0x3F, 0xC0, 0x00, 0x00]).is_err());
+        assert!(non_canonical(&[0x41, 0x3F]).is_ok());
+    }
+
+    #[test]
+    fn test_deserialize_invalid_utf8() {
+        // An array holding "ok" and a string with an invalid second byte
+        let data = vec![0xA1, 0x06, 0x62, b'o', b'k', 0x62, b'a', 0xFF];
+        let result = from_bytes::<Vec<String>>(&data);
+        assert_eq!(result.unwrap_err().kind(), &DeserializeError::InvalidUtf8 { offset: 7 });
+
+        let mut deserializer = Deserializer::new(&data[5..]).lenient_utf8(true);
+        let name: std::ffi::OsString = crate::os_str::deserialize(&mut deserializer).unwrap();
+        #[cfg(unix)]
+        assert_eq!(std::os::unix::ffi::OsStrExt::as_bytes(name.as_os_str()), b"a\xFF");
+    }
+
+    #[test]
+    fn test_deserialize_trusted() {
+        let value = vec![("name".to_string(), "Zoë".to_string()); 3];
+        let data = crate::to_bytes(&value).unwrap();
+        // SAFETY: encoded above, every string is valid UTF-8
+        let decoded: Vec<(&str, &str)> = unsafe { from_bytes_trusted(&data) }.unwrap();
+        assert_eq!(decoded, [("name", "Zoë"); 3]);
+    }
+
+    #[test]
+    fn test_deserialize_extended() {
+        // An array holding 1 and an extended field of type 0x42
+        let data = vec![0xA1, 0x07, 0x21, 0x01, 0xF1, 0x03, 0x42, 0xAB, 0xCD];
+        let result = from_bytes::<(u8, Vec<u8>)>(&data);
+        assert_eq!(result.unwrap_err().kind(), &DeserializeError::UnknownExtended(0x42));
+
+        let mut deserializer = Deserializer::new(&data).opaque_extended(true);
+        let (n, raw) = <(u8, Vec<u8>)>::deserialize(&mut deserializer).unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(raw, data[4..]);
+        let field = crate::RionField::from_slice(&raw).unwrap();
+        assert!(matches!(field, crate::RionField::Extended { type_id: 0x42, .. }));
+    }
+
+    #[test]
+    fn test_count_hints() {
+        use serde::{de::IgnoredAny, Serialize};
+        use std::collections::HashMap;
+
+        // Reports the size hint of the sequence it reads
+        struct Hint(Option<usize>);
+        impl<'de> Deserialize<'de> for Hint {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct HintVisitor;
+                impl<'de> serde::de::Visitor<'de> for HintVisitor {
+                    type Value = Hint;
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("a sequence or map")
+                    }
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                        self,
+                        mut seq: A,
+                    ) -> Result<Hint, A::Error> {
+                        let hint = seq.size_hint();
+                        while seq.next_element::<IgnoredAny>()?.is_some() {}
+                        Ok(Hint(hint))
+                    }
+                    fn visit_map<A: serde::de::MapAccess<'de>>(
+                        self,
+                        mut map: A,
+                    ) -> Result<Hint, A::Error> {
+                        let hint = map.size_hint();
+                        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+                        Ok(Hint(hint))
+                    }
+                }
+                deserializer.deserialize_any(HintVisitor)
+            }
+        }
+
+        let value = (
+            vec![1u8, 2, 3],
+            HashMap::from([
+                ("a".to_string(), vec![true]),
+                ("b".to_string(), vec![false, true]),
+            ]),
+        );
+        let mut serializer = crate::Serializer::new().count_hints(true);
+        value.serialize(&mut serializer).unwrap();
+        let data = serializer.into_bytes();
+        // The outer array holds two entries
+        assert_eq!(data[..3], [0xF1, 0x02, crate::RionField::COUNT_HINT]);
+        assert_eq!(data[3], 2);
+
+        let decoded: (Vec<u8>, HashMap<String, Vec<bool>>) = from_bytes(&data).unwrap();
+        assert_eq!(decoded, value);
+        let (hint, _): (Hint, IgnoredAny) = from_bytes(&data).unwrap();
+        assert_eq!(hint.0, Some(3));
+        // Counted by skipping over the elements without one
+        let data = crate::to_bytes(&value).unwrap();
+        let (hint, _): (Hint, IgnoredAny) = from_bytes(&data).unwrap();
+        assert_eq!(hint.0, Some(3));
+        let (_, hint): (IgnoredAny, Hint) = from_bytes(&data).unwrap();
+        assert_eq!(hint.0, Some(2));
+    }
+
+    #[test]
+    fn test_deserialize_table() {
+        let json = r#"[{"id": 1, "name": "a", "tags": ["x"]}, {"id": 2, "name": null, "tags": ["y", "z"]}]"#;
+        let table: crate::RionTable = serde_json::from_str(json).unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.column_names[1].as_ref(), b"name");
+        assert!(table.rows[4].is_null());
+
+        // Back out through serde_json and through RION
+        let value: serde_json::Value = serde_json::to_value(&table).unwrap();
+        assert_eq!(value, serde_json::from_str::<serde_json::Value>(json).unwrap());
+        let bytes = crate::to_bytes(&table).unwrap();
+        assert_eq!(from_bytes::<crate::RionTable>(&bytes).unwrap(), table);
+
+        let mismatched = r#"[{"id": 1}, {"key": 2}]"#;
+        assert!(serde_json::from_str::<crate::RionTable>(mismatched).is_err());
+    }
+
+    #[test]
+    fn test_table_row_count_bounded() {
+        // About 1.2e10 rows and no columns
+        let data = [
+            0xB1, 0x0B, 0x25, 0x02, 0xE1, 0x61, 0xB1, 0x03, 0x20, 0xE1, 0x61, 0xA1, 0x00,
+        ];
+        assert!(from_bytes::<serde_json::Value>(&data).is_err());
+        // One column "a" and two rows, but only one cell
+        let data = [0xB1, 0x05, 0x21, 0x02, 0xE1, b'a', 0x10];
+        assert!(from_bytes::<serde_json::Value>(&data).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_table_columns() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Order {
+            id: u64,
+            status: String,
+            #[serde(default)]
+            priority: u8,
+        }
+
+        // A producer that added `region` and never wrote `priority`
+        let value = crate::RionValue::Table {
+            columns: vec![b"id"[..].into(), b"status"[..].into(), b"region"[..].into()],
+            rows: (0..20u64)
+                .map(|i| {
+                    vec![
+                        i.into(),
+                        ["open", "closed"][i as usize % 2].into(),
+                        "eu".into(),
+                    ]
+                })
+                .collect(),
+        };
+        let data = value.encode_to_vec().unwrap();
+        let orders: Vec<Order> = from_bytes(&data).unwrap();
+        assert_eq!(orders.len(), 20);
+        assert_eq!(
+            orders[1],
+            Order {
+                id: 1,
+                status: "closed".to_string(),
+                priority: 0
+            }
+        );
+
+        // Dictionary encoded columns decode to the same rows
+        let table = crate::RionTable::from_slice(&data).unwrap();
+        let dictionary = table.encode_with_dictionary(crate::TableLayout::RowMajor, 4);
+        assert!(dictionary.len() < data.len());
+        assert_eq!(from_bytes::<Vec<Order>>(&dictionary).unwrap(), orders);
+
+        let strict =
+            |data| Vec::<Order>::deserialize(&mut Deserializer::new(data).strict_columns(true));
+        assert_eq!(
+            strict(&data).unwrap_err().kind(),
+            &DeserializeError::UnknownColumn("region".into())
+        );
+        let mut narrow = table.clone();
+        narrow.column_names.pop();
+        narrow.rows = narrow
+            .rows
+            .chunks(3)
+            .flat_map(|row| row[..2].to_vec())
+            .collect();
+        let data = narrow.encode();
+        assert_eq!(
+            strict(&data).unwrap_err().kind(),
+            &DeserializeError::MissingColumn("priority")
+        );
+    }
+
+    #[test]
+    fn test_numeric_fields() {
+        use serde::Serialize;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Reading {
+            sensor_id: u32,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            label: Option<String>,
+            temperature: f64,
+        }
+
+        let reading = Reading {
+            sensor_id: 7,
+            label: None,
+            temperature: 21.5,
+        };
+        let mut serializer = crate::Serializer::new().numeric_fields(true);
+        reading.serialize(&mut serializer).unwrap();
+        let data = serializer.into_bytes();
+        let named = crate::to_bytes(&reading).unwrap();
+        assert!(data.len() < named.len());
+        // The skipped field keeps its index
+        let object = crate::RionObject::from_slice(&data).unwrap();
+        let mut keys = object.keys().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, [&[0][..], &[2]]);
+
+        let mut deserializer = Deserializer::new(&data).numeric_fields(true);
+        let decoded = Reading::deserialize(&mut deserializer).unwrap();
+        assert_eq!(decoded, reading);
+        // Names aren't indices
+        let mut deserializer = Deserializer::new(&named).numeric_fields(true);
+        assert!(Reading::deserialize(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn test_error_classification() {
+        assert!(from_bytes::<u32>(&[]).unwrap_err().is_eof());
+
+        // Cut off inside a short field and inside a normal one
+        let data = crate::to_bytes(&"hello").unwrap();
+        let error = from_bytes::<&str>(&data[..3]).unwrap_err();
+        assert_eq!(error.kind(), &DeserializeError::Truncated(3));
+        assert!(error.is_truncated() && !error.is_eof());
+        let data = crate::to_bytes(&"x".repeat(20)).unwrap();
+        assert!(from_bytes::<String>(&data[..10])
+            .unwrap_err()
+            .is_truncated());
+        let data = crate::to_bytes(&vec![1u32, 2, 3]).unwrap();
+        assert!(from_bytes::<Vec<u32>>(&data[..data.len() - 1])
+            .unwrap_err()
+            .is_truncated());
+
+        let data = crate::to_bytes(&"hello").unwrap();
+        let error = from_bytes::<u32>(&data).unwrap_err();
+        assert!(error.is_type_mismatch() && !error.is_truncated());
+        let error = from_bytes::<u32>(&[0x80]).unwrap_err();
+        assert_eq!(error.kind(), &DeserializeError::UnknownFieldType(0x80));
+        assert!(error.is_unsupported_feature());
+        let extended = [0xF1, 0x02, 0x42, 0x00];
+        let error = from_bytes::<serde::de::IgnoredAny>(&extended).unwrap_err();
+        assert!(error.is_unsupported_feature() && !error.is_type_mismatch());
+    }
+
+    #[test]
+    fn test_error_offset() {
+        let data = crate::to_bytes(&(1u32, "x")).unwrap();
+        assert_eq!(data[4..], [0x61, b'x']);
+        let error = from_bytes::<(u32, u32)>(&data).unwrap_err();
+        assert_eq!(error.offset(), Some(4));
+        assert_eq!(error.lead_byte(), Some(0x61));
+        assert!(error.is_type_mismatch());
+        assert!(error.to_string().ends_with("at [1], byte 4 (lead byte 0x61)"));
+
+        // The innermost field is reported, and the end of the input has no lead byte
+        let data = crate::to_bytes(&vec![vec![1u32], vec![2, 300]]).unwrap();
+        let error = from_bytes::<Vec<Vec<u8>>>(&data).unwrap_err();
+        assert_eq!(error.offset(), Some(data.len() - 3));
+        assert_eq!(error.lead_byte(), Some(0x22));
+        let error = from_bytes::<u32>(&[]).unwrap_err();
+        assert_eq!((error.offset(), error.lead_byte()), (Some(0), None));
+    }
+
+    #[test]
+    fn test_error_path() {
+        use serde::Serialize;
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, Serialize, Deserialize)]
+        struct User<Zip> {
+            name: String,
+            address: BTreeMap<String, Zip>,
+        }
+        let user = |zip: &str| User {
+            name: "a".to_string(),
+            address: BTreeMap::from([("zip".to_string(), zip.to_string())]),
+        };
+        let users = vec![user("1000"), user("2000"), user("3000")];
+        let data = crate::to_bytes(&BTreeMap::from([("users", users)])).unwrap();
+        let error = from_bytes::<BTreeMap<String, Vec<User<u32>>>>(&data).unwrap_err();
+        assert_eq!(error.path().unwrap().to_string(), "users[0].address.zip");
+        assert!(error.is_type_mismatch());
+
+        // Rows of a table and their columns
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Row<Id> {
+            id: Id,
+        }
+        let rows = crate::TableVec(vec![Row { id: 1u32 }, Row { id: 256 }]);
+        let data = crate::to_bytes(&rows).unwrap();
+        let error = from_bytes::<crate::TableVec<Row<u8>>>(&data).unwrap_err();
+        assert_eq!(error.path().unwrap().to_string(), "[1].id");
+        assert!(error.to_string().contains(" at [1].id, byte "));
+
+        // A failing field with more fields after it
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Package<Name> {
+            name: Name,
+            version: String,
+        }
+        let package = Package {
+            name: 2024u32,
+            version: "x".to_string(),
+        };
+        let data = crate::to_bytes(&package).unwrap();
+        let error = from_bytes::<Package<String>>(&data).unwrap_err();
+        assert_eq!(error.path().unwrap().to_string(), "name");
+        assert_eq!(error.offset(), Some(7));
+        assert!(error.is_type_mismatch());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        // Arrays nested 300 deep
+        let mut data = vec![0xA0];
+        for _ in 0..300 {
+            let mut length = Vec::new();
+            crate::int_to_bytes(&(data.len() as u64), &mut length).unwrap();
+            data = [vec![0xA0 | length.len() as u8], length, data].concat();
+        }
+        let error = from_bytes::<serde::de::IgnoredAny>(&data).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &DeserializeError::DepthLimit(crate::DEFAULT_MAX_DEPTH)
+        );
+        let mut deserializer = Deserializer::new(&data).max_depth(301);
+        assert!(serde::de::IgnoredAny::deserialize(&mut deserializer).is_ok());
+
+        use serde::Serialize;
+        let nested = vec![vec![vec![1u8]]];
+        let mut serializer = crate::Serializer::new().max_depth(2);
+        assert!(matches!(
+            nested.serialize(&mut serializer),
+            Err(crate::SerializeError::DepthLimit(2))
+        ));
+        let data = crate::to_bytes(&nested).unwrap();
+        let mut deserializer = Deserializer::new(&data).max_depth(2);
+        assert!(Vec::<Vec<Vec<u8>>>::deserialize(&mut deserializer).is_err());
+        let mut deserializer = Deserializer::new(&data).max_depth(3);
+        assert_eq!(
+            Vec::<Vec<Vec<u8>>>::deserialize(&mut deserializer).unwrap(),
+            nested
+        );
+
+        // Sizing and writing into a slice stop at the default depth too
+        #[derive(Serialize)]
+        struct Nested(Vec<Nested>);
+        let mut deep = Nested(Vec::new());
+        for _ in 0..300 {
+            deep = Nested(vec![deep]);
+        }
+        let limit = crate::DEFAULT_MAX_DEPTH;
+        assert!(matches!(
+            crate::encoded_size(&deep),
+            Err(crate::SerializeError::DepthLimit(depth)) if depth == limit
+        ));
+        assert!(matches!(
+            crate::to_slice(&deep, &mut [0; 1024]),
+            Err(crate::SerializeError::DepthLimit(depth)) if depth == limit
+        ));
+    }
+
+    #[test]
+    fn test_strict_deeply_nested() {
+        // Arrays nested 10,000 deep around `inner`
+        let nested = |mut data: Vec<u8>| {
+            for _ in 0..10_000 {
+                let mut length = Vec::new();
+                crate::int_to_bytes(&(data.len() as u64), &mut length).unwrap();
+                data = [vec![0xA0 | length.len() as u8], length, data].concat();
+            }
+            data
+        };
+        // Checking for canonical form doesn't recurse, so gets to the depth
+        // limit of decoding
+        let error = from_bytes_strict::<serde::de::IgnoredAny>(&nested(vec![0xA0])).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &DeserializeError::DepthLimit(crate::DEFAULT_MAX_DEPTH)
+        );
+
+        // An empty array with a zero length byte
+        let error =
+            from_bytes_strict::<serde::de::IgnoredAny>(&nested(vec![0xA1, 0x00])).unwrap_err();
+        assert!(matches!(error.kind(), DeserializeError::NonCanonical(..)));
+    }
+
+    #[test]
+    fn test_trailing_data() {
+        let mut data = crate::to_bytes(&(1u8, "a")).unwrap();
+        data.push(0x10);
+        let error = from_bytes::<(u8, String)>(&data).unwrap_err();
+        assert_eq!(error.kind(), &DeserializeError::ExtraData);
+        assert_eq!(error.offset(), Some(data.len() - 1));
+        assert!(from_bytes_strict::<(u8, String)>(&data).is_err());
+        let value: (u8, String) = from_bytes_lenient(&data).unwrap();
+        assert_eq!(value, (1, "a".to_string()));
+
+        // Elements of an array left over
+        let data = crate::to_bytes(&(1u8, 2u8, 3u8)).unwrap();
+        let error = from_bytes_lenient::<(u8, u8)>(&data).unwrap_err();
+        assert_eq!(error.kind(), &DeserializeError::ExtraData);
+    }
+
+    #[test]
+    fn test_utf8_policy() {
+        // An array holding "ok" and a string with an invalid second byte
+        let data = vec![0xA1, 0x06, 0x62, b'o', b'k', 0x62, b'a', 0xFF];
+        let decode =
+            |policy| Vec::<String>::deserialize(&mut Deserializer::new(&data).utf8_policy(policy));
+        assert!(decode(Utf8Policy::Strict).is_err());
+        assert_eq!(decode(Utf8Policy::Lossy).unwrap(), ["ok", "a\u{FFFD}"]);
+        // Strings don't take bytes
+        assert!(decode(Utf8Policy::Raw).is_err());
+
+        let mut deserializer = Deserializer::new(&data[5..]).utf8_policy(Utf8Policy::Raw);
+        let name: std::ffi::OsString = crate::os_str::deserialize(&mut deserializer).unwrap();
+        #[cfg(unix)]
+        assert_eq!(
+            std::os::unix::ffi::OsStrExt::as_bytes(name.as_os_str()),
+            b"a\xFF"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_negative_range() {
+        let data = crate::to_bytes(&i64::MIN).unwrap();
+        assert_eq!(from_bytes::<i64>(&data).unwrap(), i64::MIN);
+        assert_eq!(from_bytes::<i128>(&data).unwrap(), i64::MIN as i128);
+
+        // Below i64::MIN, -(u64::MAX + 1)
+        let data = [0x38, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert_eq!(from_bytes::<i128>(&data).unwrap(), -(u64::MAX as i128) - 1);
+        assert!(from_bytes::<i64>(&data).is_err());
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error;
+        use std::io::ErrorKind;
+
+        let err = DeserializeError::from(std::io::Error::from(ErrorKind::WouldBlock));
+        let located = DeserializeError::At {
+            path: crate::Path::default(),
+            offset: 3,
+            lead: None,
+            error: Box::new(err),
+        };
+        let source = located.source().unwrap().downcast_ref::<std::io::Error>();
+        assert_eq!(source.unwrap().kind(), ErrorKind::WouldBlock);
+
+        let boxed: Box<dyn Error> = std::io::Error::from(ErrorKind::WouldBlock).into();
+        assert_eq!(
+            DeserializeError::from(boxed),
+            DeserializeError::Io(ErrorKind::WouldBlock.into())
+        );
+        let bytes = vec![b'a', 0xFF];
+        let utf8 = std::str::from_utf8(&bytes).unwrap_err();
+        let err = DeserializeError::from(crate::FerionError::from(utf8));
+        assert_eq!(err, DeserializeError::Utf8(utf8));
+        assert!(err.source().unwrap().is::<std::str::Utf8Error>());
+    }
+
+    #[test]
+    fn test_count_hint_limits() {
+        use crate::RionField;
+        // A hint needs a container after it, hints in a row fail instead of
+        // recursing once per hint
+        let mut data = [0xF1, 0x01, RionField::COUNT_HINT].repeat(100_000);
+        data.extend([0xA1, 0x02, 0x21, 0x05]);
+        assert!(matches!(
+            from_bytes::<Vec<u8>>(&data).unwrap_err().kind(),
+            DeserializeError::InvalidData(_)
+        ));
+
+        // A hint can't promise more entries than the container has bytes
+        let mut data = vec![0xF1, 0x09, RionField::COUNT_HINT, 0x01, 0, 0, 0, 0, 0, 0, 0];
+        data.extend([0xA1, 0x02, 0x21, 0x05]);
+        assert!(from_bytes::<Vec<u8>>(&data).is_err());
+
+        let data = [0xF1, 0x02, RionField::COUNT_HINT, 0x01, 0xA1, 0x02, 0x21, 0x05];
+        assert_eq!(from_bytes::<Vec<u8>>(&data).unwrap(), vec![5]);
+
+        // A hint must be followed by an array or object
+        let data = [0xF1, 0x02, RionField::COUNT_HINT, 0x01, 0x21, 0x05];
+        assert!(matches!(
+            from_bytes::<u64>(&data).unwrap_err().kind(),
+            DeserializeError::InvalidData(_)
+        ));
+    }
+
+    #[test]
+    fn test_huge_row_count() {
+        // 2^56 rows of one column, but only one row of data
+        let data = [
+            0xB1, 0x0D, 0x28, 0x01, 0, 0, 0, 0, 0, 0, 0, 0xE1, b'a', 0x21, 0x05,
+        ];
+        assert!(from_bytes::<crate::RionField>(&data).is_err());
+    }
@@ -0,0 +1,124 @@
+//! Reading and writing a single value as a file
+//!
+//! [`to_file`] writes to a temporary file next to the target and renames it
+//! over the target once everything is on disk, so readers see either the old
+//! file or the new one and never a partial write.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{from_bytes, to_bytes, DeserializeError, SerializeError};
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_file_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ferion-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scores.rion");
+        let scores = BTreeMap::from([("a".to_string(), 1u32), ("b".to_string(), 2)]);
+        to_file(&path, &scores).unwrap();
+        assert_eq!(from_file::<BTreeMap<String, u32>>(&path).unwrap(), scores);
+
+        // Replaced whole, without leaving the temporary file behind
+        to_file(&path, &vec![true]).unwrap();
+        assert_eq!(from_file::<Vec<bool>>(&path).unwrap(), [true]);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let error = from_file::<Vec<u32>>(&path).unwrap_err();
+        assert!(matches!(error, FileError::Decode(..)));
+        assert!(error.to_string().starts_with(&path.display().to_string()));
+        let missing = dir.join("missing.rion");
+        let error = from_file::<Vec<bool>>(&missing).unwrap_err();
+        assert!(matches!(error, FileError::Io(..)));
+        assert_eq!(error.path(), missing);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+/// Encode `value` into the file at `path`, replacing it atomically
+///
+/// The value is written to a temporary file in the same directory, synced to
+/// disk and renamed over `path`. The temporary file is removed on failure.
+pub fn to_file<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<(), FileError> {
+    let path = path.as_ref();
+    let bytes = to_bytes(value).map_err(|err| FileError::Encode(path.to_path_buf(), err))?;
+    let temp = temp_path(path);
+    let write = || -> io::Result<()> {
+        let mut file = File::create(&temp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    };
+    write().map_err(|err| {
+        let _ = fs::remove_file(&temp);
+        FileError::Io(path.to_path_buf(), err)
+    })
+}
+
+/// Decode the file at `path` as a `T`
+pub fn from_file<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, FileError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|err| FileError::Io(path.to_path_buf(), err))?;
+    from_bytes(&bytes).map_err(|err| FileError::Decode(path.to_path_buf(), err))
+}
+
+// A hidden file next to `path`, unique to this process
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{name}.{}.tmp", std::process::id()))
+}
+
+#[derive(Debug)]
+pub enum FileError {
+    /// The file could not be read, written or renamed into place
+    Io(PathBuf, io::Error),
+    /// The value could not be encoded, nothing was written
+    Encode(PathBuf, SerializeError),
+    /// The file doesn't hold a valid encoding of the target type
+    Decode(PathBuf, DeserializeError),
+}
+
+impl FileError {
+    /// The file being read or written
+    pub fn path(&self) -> &Path {
+        match self {
+            FileError::Io(path, _) | FileError::Encode(path, _) | FileError::Decode(path, _) => {
+                path
+            }
+        }
+    }
+}
+
+impl Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            FileError::Encode(path, err) => {
+                write!(f, "{}: failed to encode value: {err}", path.display())
+            }
+            FileError::Decode(path, err) => write!(f, "{}: {err}", path.display()),
+        }
+    }
+}
+
+impl Error for FileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FileError::Io(_, err) => Some(err),
+            FileError::Encode(_, err) => Some(err),
+            FileError::Decode(_, err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Helpers for sending and receiving RION bodies over HTTP
+//!
+//! [`encode_body`] and [`decode_body`] work with any client, e.g. with reqwest
+//! set [`CONTENT_TYPE`] on the request, send `encode_body(&value)?` and decode
+//! the response with `decode_body(&response.bytes()?[..], limit)`. With the
+//! `ureq` feature [`send`] and [`read_response`] do all of that for ureq.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Read},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{from_bytes, to_bytes, DeserializeError, SerializeError};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_body_round_trip() {
+        let body = encode_body(&("a", 1u8)).unwrap();
+        let value: (String, u8) = decode_body(&body[..], DEFAULT_BODY_LIMIT).unwrap();
+        assert_eq!(value, ("a".to_string(), 1));
+    }
+
+    #[test]
+    fn test_body_limit() {
+        let body = encode_body(&"a long string").unwrap();
+        let result = decode_body::<String>(&body[..], body.len() - 1);
+        assert!(matches!(result, Err(HttpError::TooLarge(limit)) if limit == body.len() - 1));
+        assert!(decode_body::<String>(&body[..], body.len()).is_ok());
+    }
+
+    #[test]
+    fn test_content_type() {
+        assert!(is_rion_content_type("application/rion"));
+        assert!(is_rion_content_type("Application/RION; charset=binary"));
+        assert!(!is_rion_content_type("application/json"));
+        assert!(!is_rion_content_type("application/rionx"));
+    }
+
+    #[cfg(feature = "ureq")]
+    #[test]
+    fn test_ureq_round_trip() {
+        use std::{io::Write, net::TcpListener};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/echo", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            // Headers, then a body of the advertised length
+            let body = loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+                    continue;
+                };
+                let headers = String::from_utf8_lossy(&request[..end]).to_lowercase();
+                assert!(headers.contains("content-type: application/rion"));
+                let len: usize = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length: "))
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                if request.len() >= end + 4 + len {
+                    break request[end + 4..end + 4 + len].to_vec();
+                }
+            };
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+
+        let response = send(ureq::post(&url), &vec![1u32, 2, 3]).unwrap();
+        let echoed: Vec<u32> = read_response(response, DEFAULT_BODY_LIMIT).unwrap();
+        assert_eq!(echoed, [1, 2, 3]);
+        server.join().unwrap();
+    }
+}
+
+/// The media type of RION bodies
+pub const CONTENT_TYPE: &str = "application/rion";
+
+/// Largest body [`read_response`] accepts unless told otherwise, 16 MiB
+pub const DEFAULT_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum HttpError {
+    Io(io::Error),
+    /// The body was longer than the limit, in bytes
+    TooLarge(usize),
+    /// The response was not `application/rion`, holds what it was instead
+    ContentType(String),
+    Serialize(SerializeError),
+    Deserialize(DeserializeError),
+    /// The server answered with a status outside of 2xx, and the body if it
+    /// could be read as text
+    Status(u16, String),
+    #[cfg(feature = "ureq")]
+    Transport(Box<ureq::Transport>),
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpError::Io(err) => write!(f, "IO error reading body: {err}"),
+            HttpError::TooLarge(limit) => write!(f, "body is larger than the {limit} byte limit"),
+            HttpError::ContentType(found) => {
+                write!(f, "expected content type {CONTENT_TYPE}, got {found:?}")
+            }
+            HttpError::Serialize(err) => write!(f, "failed to encode body: {err}"),
+            HttpError::Deserialize(err) => write!(f, "failed to decode body: {err}"),
+            HttpError::Status(status, body) if body.is_empty() => write!(f, "HTTP status {status}"),
+            HttpError::Status(status, body) => write!(f, "HTTP status {status}: {body}"),
+            #[cfg(feature = "ureq")]
+            HttpError::Transport(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for HttpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HttpError::Io(err) => Some(err),
+            HttpError::Serialize(err) => Some(err),
+            HttpError::Deserialize(err) => Some(err),
+            #[cfg(feature = "ureq")]
+            HttpError::Transport(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HttpError {
+    fn from(err: io::Error) -> Self {
+        HttpError::Io(err)
+    }
+}
+
+impl From<SerializeError> for HttpError {
+    fn from(err: SerializeError) -> Self {
+        HttpError::Serialize(err)
+    }
+}
+
+impl From<DeserializeError> for HttpError {
+    fn from(err: DeserializeError) -> Self {
+        HttpError::Deserialize(err)
+    }
+}
+
+/// Whether a `Content-Type` header value is RION, ignoring case and parameters
+pub fn is_rion_content_type(value: &str) -> bool {
+    let essence = value.split(';').next().unwrap_or_default().trim();
+    essence.eq_ignore_ascii_case(CONTENT_TYPE)
+}
+
+pub fn encode_body<T: Serialize>(value: &T) -> Result<Vec<u8>, HttpError> {
+    Ok(to_bytes(value)?)
+}
+
+/// Read a body of at most `limit` bytes and decode it
+pub fn decode_body<T: DeserializeOwned>(body: impl Read, limit: usize) -> Result<T, HttpError> {
+    let mut data = Vec::new();
+    // One byte past the limit tells a body of exactly `limit` bytes apart
+    body.take(limit as u64 + 1).read_to_end(&mut data)?;
+    if data.len() > limit {
+        return Err(HttpError::TooLarge(limit));
+    }
+    Ok(from_bytes(&data)?)
+}
+
+/// Send `body` as RION, asking for a RION response
+///
+/// Statuses outside of 2xx become [`HttpError::Status`].
+#[cfg(feature = "ureq")]
+pub fn send<T: Serialize>(request: ureq::Request, body: &T) -> Result<ureq::Response, HttpError> {
+    let body = encode_body(body)?;
+    let result = request
+        .set("Content-Type", CONTENT_TYPE)
+        .set("Accept", CONTENT_TYPE)
+        .send_bytes(&body);
+    match result {
+        Ok(response) => Ok(response),
+        Err(ureq::Error::Status(status, response)) => Err(HttpError::Status(
+            status,
+            response.into_string().unwrap_or_default(),
+        )),
+        Err(ureq::Error::Transport(err)) => Err(HttpError::Transport(Box::new(err))),
+    }
+}
+
+/// Check the response is RION and decode at most `limit` bytes of it
+#[cfg(feature = "ureq")]
+pub fn read_response<T: DeserializeOwned>(
+    response: ureq::Response,
+    limit: usize,
+) -> Result<T, HttpError> {
+    let content_type = response.header("Content-Type").unwrap_or_default();
+    if !is_rion_content_type(content_type) {
+        return Err(HttpError::ContentType(content_type.to_string()));
+    }
+    decode_body(response.into_reader(), limit)
+}
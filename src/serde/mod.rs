@@ -1,5 +1,37 @@
+#[cfg(feature = "config")]
+pub mod config;
+mod containers;
+pub mod datetime;
 mod de;
+mod file;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod net;
+mod number;
+pub mod os_str;
+pub(crate) mod rows;
 mod ser;
+#[cfg(feature = "cycles")]
+pub mod shared;
+mod stats;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 pub use de::*;
+pub use file::{from_file, to_file, FileError};
+pub use rows::TableVec;
 pub use ser::*;
+pub use stats::Stats;
+
+/// How deep [`Serializer`] and [`Deserializer`] nest arrays, objects and
+/// tables unless told otherwise with their `max_depth`. [`encoded_size`] and
+/// [`to_slice`] always use it
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+// Capacity to reserve for a sequence from its size hint. The hint comes from
+// the input, so cap what it can allocate before any element is read
+pub(crate) fn cautious_capacity<T>(hint: Option<usize>) -> usize {
+    const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+    hint.unwrap_or(0)
+        .min(MAX_PREALLOC_BYTES / std::mem::size_of::<T>().max(1))
+}
@@ -0,0 +1,100 @@
+//! Compact (de)serialization of network addresses
+//!
+//! Use with `#[serde(with = "ferion::net::ip_addr")]` or
+//! `#[serde(with = "ferion::net::socket_addr")]`. Addresses are written as
+//! Bytes fields holding the 4 or 16 address octets (followed by the big endian
+//! port for socket addresses) instead of their textual form. The textual form
+//! is still accepted when decoding.
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use serde::de::{self, SeqAccess, Visitor};
+
+pub mod ip_addr {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &IpAddr, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            IpAddr::V4(ip) => serializer.serialize_bytes(&ip.octets()),
+            IpAddr::V6(ip) => serializer.serialize_bytes(&ip.octets()),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<IpAddr, D::Error> {
+        match deserializer.deserialize_any(RawVisitor)? {
+            Raw::Str(s) => s.parse().map_err(de::Error::custom),
+            Raw::Bytes(bytes) => ip_from_octets(&bytes)
+                .ok_or_else(|| de::Error::invalid_length(bytes.len(), &"4 or 16 bytes")),
+        }
+    }
+}
+
+pub mod socket_addr {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &SocketAddr, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = match value.ip() {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        bytes.extend_from_slice(&value.port().to_be_bytes());
+        serializer.serialize_bytes(&bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SocketAddr, D::Error> {
+        match deserializer.deserialize_any(RawVisitor)? {
+            Raw::Str(s) => s.parse().map_err(de::Error::custom),
+            Raw::Bytes(bytes) => {
+                let invalid = || de::Error::invalid_length(bytes.len(), &"6 or 18 bytes");
+                let (ip, port) = bytes.split_last_chunk::<2>().ok_or_else(invalid)?;
+                let ip = ip_from_octets(ip).ok_or_else(invalid)?;
+                Ok(SocketAddr::new(ip, u16::from_be_bytes(*port)))
+            }
+        }
+    }
+}
+
+fn ip_from_octets(octets: &[u8]) -> Option<IpAddr> {
+    if let Ok(octets) = <[u8; 4]>::try_from(octets) {
+        return Some(Ipv4Addr::from(octets).into());
+    }
+    <[u8; 16]>::try_from(octets)
+        .ok()
+        .map(|octets| Ipv6Addr::from(octets).into())
+}
+
+enum Raw {
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+struct RawVisitor;
+
+impl<'de> Visitor<'de> for RawVisitor {
+    type Value = Raw;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("address bytes or an address string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Raw::Str(v.to_string()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(Raw::Bytes(v.to_vec()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(super::cautious_capacity::<u8>(seq.size_hint()));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        Ok(Raw::Bytes(bytes))
+    }
+}
@@ -0,0 +1,77 @@
+//! Numbers from serde_json's `arbitrary_precision` feature
+//!
+//! With that feature enabled `serde_json::Number` serializes as a struct named
+//! [`TOKEN`] with a single field holding the number as written in the JSON
+//! text. The RION serializer recognizes it and writes the smallest field that
+//! holds the number exactly:
+//!
+//! - integers up to 120 bits become integer fields, the short field length
+//!   allows up to 15 bytes of magnitude,
+//! - other numbers become float fields when an `f64` reproduces every digit,
+//! - anything else is kept as a UTF8 field with the original text, so no
+//!   precision is lost but the value reads back as a string.
+//!
+//! Integer fields wider than 64 bits are handed to visitors as `u128` or
+//! `i128`, which `serde_json::Value` turns back into the exact number when
+//! `arbitrary_precision` is enabled.
+
+use crate::RionField;
+
+/// Struct and field name serde_json uses for arbitrary precision numbers
+pub(crate) const TOKEN: &str = "$serde_json::private::Number";
+
+/// The field holding the number spelled `text` without losing precision
+pub(crate) fn from_str(text: &str) -> RionField<'static> {
+    if let Ok(value) = text.parse::<u64>() {
+        return value.into();
+    }
+    if let Ok(value) = text.parse::<i64>() {
+        return value.into();
+    }
+    if let Some(field) = wide_int(text) {
+        return field;
+    }
+    match text.parse::<f64>() {
+        Ok(value) if value.is_finite() && same_value(text, &format!("{value:e}")) => value.into(),
+        _ => RionField::from_str(text).into_owned(),
+    }
+}
+
+// Integers past 64 bits that still fit in an integer field
+fn wide_int(text: &str) -> Option<RionField<'static>> {
+    // Fails past the 15 bytes a short field holds
+    RionField::try_from(text.parse::<i128>().ok()?).ok()
+}
+
+// Whether two decimal numbers are equal, regardless of how they are written
+fn same_value(a: &str, b: &str) -> bool {
+    match (normalize(a), normalize(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+// A decimal number as its sign, significant digits and the exponent of the
+// last digit
+fn normalize(text: &str) -> Option<(bool, String, i64)> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(at) => (&text[..at], text[at + 1..].parse::<i64>().ok()?),
+        None => (text, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if whole.is_empty() || !(whole.bytes().chain(fraction.bytes())).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits = format!("{whole}{fraction}");
+    let trimmed = digits.trim_end_matches('0');
+    let exponent = exponent - fraction.len() as i64 + (digits.len() - trimmed.len()) as i64;
+    let trimmed = trimmed.trim_start_matches('0');
+    if trimmed.is_empty() {
+        return Some((negative, String::new(), 0));
+    }
+    Some((negative, trimmed.to_string(), exponent))
+}
@@ -0,0 +1,91 @@
+//! Lossless (de)serialization of `OsString` and `PathBuf`
+//!
+//! Use with `#[serde(with = "ferion::os_str")]`. Valid UTF-8 is written as a
+//! UTF8 field, anything else falls back to a Bytes field holding the raw
+//! platform encoding, so non-UTF-8 file names survive the round trip.
+
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserializer, Serializer,
+};
+
+pub fn serialize<S, P>(value: &P, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    P: AsRef<OsStr> + ?Sized,
+{
+    let value = value.as_ref();
+    if let Some(s) = value.to_str() {
+        return serializer.serialize_str(s);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        serializer.serialize_bytes(value.as_bytes())
+    }
+    #[cfg(not(unix))]
+    Err(serde::ser::Error::custom(
+        "non UTF-8 OS strings are only supported on unix",
+    ))
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: From<OsString>,
+{
+    deserializer.deserialize_any(OsStringVisitor).map(T::from)
+}
+
+struct OsStringVisitor;
+
+impl OsStringVisitor {
+    fn from_bytes<E: de::Error>(bytes: Vec<u8>) -> Result<OsString, E> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+            Ok(OsString::from_vec(bytes))
+        }
+        #[cfg(not(unix))]
+        String::from_utf8(bytes)
+            .map(OsString::from)
+            .map_err(E::custom)
+    }
+}
+
+impl<'de> Visitor<'de> for OsStringVisitor {
+    type Value = OsString;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Self::from_bytes(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Self::from_bytes(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(super::cautious_capacity::<u8>(seq.size_hint()));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        Self::from_bytes(bytes)
+    }
+}
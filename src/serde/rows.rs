@@ -0,0 +1,136 @@
+//! Writing a single field of rows as a table
+//!
+//! Use `#[serde(with = "ferion::table")]` on a `Vec` of structs, or wrap it in
+//! [`TableVec`], and the RION serializer writes that field as a Table field
+//! while the rest of the value is written as usual. Every row has to have the
+//! same fields, so rows using `skip_serializing_if` can fail to encode.
+//! Decoding reads a table or an array of rows.
+//!
+//! Other formats see the rows as a sequence.
+
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{from_bytes, to_bytes, NormalRionType, RionField, RionValue};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Reading {
+        sensor: String,
+        value: u32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Batch {
+        source: String,
+        #[serde(with = "crate::table")]
+        readings: Vec<Reading>,
+    }
+
+    fn reading(sensor: &str, value: u32) -> Reading {
+        Reading {
+            sensor: sensor.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_table_field() {
+        let batch = Batch {
+            source: "roof".to_string(),
+            readings: vec![reading("a", 1), reading("b", 2)],
+        };
+        let bytes = to_bytes(&batch).unwrap();
+        let RionValue::Object(fields) = &RionValue::from_slice(&bytes).unwrap() else {
+            panic!("Expected an object");
+        };
+        let RionValue::Table { columns, rows } = &fields[&b"readings"[..]] else {
+            panic!("Expected readings to be a table");
+        };
+        assert_eq!(columns, &[b"sensor"[..].into(), b"value"[..].into()]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(from_bytes::<Batch>(&bytes).unwrap(), batch);
+        assert_eq!(crate::encoded_size(&batch).unwrap(), bytes.len());
+        let mut buf = [0; 64];
+        let len = crate::to_slice(&batch, &mut buf).unwrap();
+        assert_eq!(buf[..len], bytes);
+
+        // No rows is an empty table
+        let empty = Batch {
+            readings: Vec::new(),
+            ..batch
+        };
+        let bytes = to_bytes(&empty).unwrap();
+        assert_eq!(from_bytes::<Batch>(&bytes).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_table_vec() {
+        let rows = TableVec(vec![reading("a", 1)]);
+        let bytes = to_bytes(&rows).unwrap();
+        let (field, _) = RionField::parse(&bytes).unwrap();
+        assert!(field.is_normal_type(NormalRionType::Table));
+        assert_eq!(from_bytes::<TableVec<Reading>>(&bytes).unwrap(), rows);
+        // Arrays of rows decode too
+        let bytes = to_bytes(&rows.0).unwrap();
+        assert_eq!(from_bytes::<TableVec<Reading>>(&bytes).unwrap(), rows);
+        assert!(to_bytes(&TableVec(vec![1u32, 2])).is_err());
+    }
+}
+
+/// Newtype struct name the RION serializer writes as a Table field
+pub(crate) const TOKEN: &str = "$ferion::Table";
+
+pub fn serialize<T: Serialize, S: Serializer>(
+    rows: &[T],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_newtype_struct(TOKEN, rows)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Vec::deserialize(deserializer)
+}
+
+/// A `Vec` of rows written as a table, see [`table`](crate::table)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TableVec<T>(pub Vec<T>);
+
+impl<T> Deref for TableVec<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for TableVec<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+impl<T> From<Vec<T>> for TableVec<T> {
+    fn from(rows: Vec<T>) -> Self {
+        TableVec(rows)
+    }
+}
+
+impl<T: Serialize> Serialize for TableVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for TableVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(TableVec)
+    }
+}
@@ -1,4 +1,10 @@
 mod serializer;
+mod size;
+mod slice;
 #[cfg(test)]
 mod tests;
-pub use serializer::{to_bytes, Serializer};
+pub use serializer::{
+    to_bytes, to_bytes_many, to_bytes_with_stats, to_writer, Batch, SerializeError, Serializer,
+};
+pub use size::encoded_size;
+pub use slice::to_slice;
@@ -1,4 +1,4 @@
-use std::error::Error;
+use std::{error::Error, time::Instant};
 
 use serde::{
     ser::{
@@ -10,12 +10,19 @@ use serde::{
 
 use crate::{
     needed_bytes_usize,
+    serde::{number, rows},
     types::{LeadByte, NormalRionType, RionFieldType, ShortRionType},
-    RionField,
+    FerionError, InvalidKey, RionArray, RionField, RionKey, RionTable, Stats,
 };
 
 pub struct Serializer {
     output: Vec<u8>,
+    stats: Stats,
+    count_hints: bool,
+    numeric_fields: bool,
+    // Containers around the values this serializer writes
+    depth: usize,
+    max_depth: usize,
 }
 
 impl Default for Serializer {
@@ -26,22 +33,77 @@ impl Default for Serializer {
 
 impl Serializer {
     pub fn new() -> Self {
-        Self { output: Vec::new() }
+        Self {
+            output: Vec::new(),
+            stats: Stats::default(),
+            count_hints: false,
+            numeric_fields: false,
+            depth: 0,
+            max_depth: crate::DEFAULT_MAX_DEPTH,
+        }
     }
 
-    pub fn serialize_key(&mut self, key: &[u8]) -> Result<(), SerializeError> {
-        let field = RionField::key(key);
-        field.encode(&mut self.output).unwrap();
+    /// Write the number of entries before every array and object, so the
+    /// deserializer can allocate collections at their final size
+    ///
+    /// The count is an extended field of type [`RionField::COUNT_HINT`] right
+    /// before the container. [`Deserializer`](crate::Deserializer) reads it,
+    /// other decoders have to know to skip it, so only enable this when both
+    /// ends are this crate.
+    pub fn count_hints(mut self, count_hints: bool) -> Self {
+        self.count_hints = count_hints;
+        self
+    }
+
+    /// Key struct fields by their index in the struct instead of their name
+    ///
+    /// Each key is a Key field holding the index as a big-endian integer, so a
+    /// field costs two bytes of key instead of its name. Indices count fields
+    /// skipped with `skip_serializing_if` too, matching what serde's derived
+    /// `Deserialize` expects. Decode with
+    /// [`Deserializer::numeric_fields`](crate::Deserializer::numeric_fields),
+    /// both ends need the same definition of the struct.
+    pub fn numeric_fields(mut self, numeric_fields: bool) -> Self {
+        self.numeric_fields = numeric_fields;
+        self
+    }
+
+    /// Fail with [`SerializeError::DepthLimit`] instead of nesting arrays and
+    /// objects deeper than `max_depth`, [`DEFAULT_MAX_DEPTH`](crate::DEFAULT_MAX_DEPTH)
+    /// by default
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// The encoded output
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.output
+    }
+
+    /// Counters collected by this serializer so far
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    fn write_field(&mut self, field: RionField) -> Result<(), SerializeError> {
+        field.encode(&mut self.output)?;
+        self.stats.fields += 1;
         Ok(())
     }
 
+    pub fn serialize_key(&mut self, key: &[u8]) -> Result<(), SerializeError> {
+        self.write_field(RionKey::new(key)?.into())
+    }
+
     pub fn serialize_entry<T: ?Sized + Serialize>(
         &mut self,
         key: &str,
         value: &T,
     ) -> Result<(), SerializeError> {
-        let mut sized = SizedSerializer::new(self);
+        let mut sized = SizedSerializer::new(self)?;
         sized.serialize_key(key)?;
+        sized.temp.stats.entries += 1;
         value.serialize(&mut sized.temp)?;
         sized.finish(0xC)
     }
@@ -50,17 +112,87 @@ impl Serializer {
 pub struct SizedSerializer<'a> {
     output: &'a mut Serializer,
     temp: Serializer,
+    // Set for serde_json's arbitrary precision numbers, written as a single
+    // field instead of an object
+    number: bool,
+    // Index of the next struct field
+    index: usize,
 }
 
 pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>, SerializeError>
 where
     T: RionSerialize,
 {
+    to_bytes_with_stats(value).map(|(bytes, _)| bytes)
+}
+
+/// Like [`to_bytes`], but also returns the counters collected while encoding
+pub fn to_bytes_with_stats<T>(value: &T) -> Result<(Vec<u8>, Stats), SerializeError>
+where
+    T: RionSerialize,
+{
+    let start = Instant::now();
     let mut serializer = Serializer::new();
     value.serialize(&mut serializer)?;
+    let mut stats = serializer.stats;
+    stats.bytes = serializer.output.len();
+    stats.elapsed = start.elapsed();
+    #[cfg(feature = "tracing")]
+    stats.trace("encode");
+    Ok((serializer.output, stats))
+}
+
+/// How [`to_bytes_many`] lays out a batch of values, and how
+/// [`from_bytes_many`](crate::from_bytes_many) expects to find them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Batch {
+    /// Top-level values one after the other, so batches can be appended to
+    #[default]
+    Concatenated,
+    /// A single array holding the values, readable as a `Vec<T>` anywhere
+    Array,
+}
+
+/// Encode all of `values` into one buffer with one serializer
+///
+/// Cheaper than calling [`to_bytes`] for each value when the values are
+/// small and many.
+pub fn to_bytes_many<T>(values: &[T], batch: Batch) -> Result<Vec<u8>, SerializeError>
+where
+    T: RionSerialize,
+{
+    let mut serializer = Serializer::new();
+    match batch {
+        Batch::Concatenated => {
+            for value in values {
+                value.serialize(&mut serializer)?;
+            }
+        }
+        Batch::Array => {
+            let mut sized = SizedSerializer::new(&mut serializer)?;
+            for value in values {
+                sized.temp.stats.entries += 1;
+                value.serialize(&mut sized.temp)?;
+            }
+            sized.finish(0xA)?;
+        }
+    }
     Ok(serializer.output)
 }
 
+/// Encode `value` into `writer`
+///
+/// Containers need their length before their content, so the value is still
+/// encoded in memory first and written out in one go.
+pub fn to_writer<W, T>(mut writer: W, value: &T) -> Result<(), SerializeError>
+where
+    W: std::io::Write,
+    T: RionSerialize,
+{
+    writer.write_all(&to_bytes(value)?)?;
+    Ok(())
+}
+
 pub trait RionSerialize {
     fn serialize(&self, serializer: &mut Serializer) -> Result<(), SerializeError>;
 }
@@ -86,7 +218,7 @@ macro_rules! impl_rion_serialize_const_array {
             fn serialize(&self, serializer: &mut Serializer) -> Result<(), SerializeError> {
                 // println!("Serializing array of length {}", $len);
                 let bytes = RionField::bytes(self);
-                bytes.encode(&mut serializer.output).unwrap();
+                bytes.encode(&mut serializer.output)?;
                 Ok(())
             }
         }
@@ -101,7 +233,7 @@ impl_rion_serialize_const_array!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 1
 impl RionSerialize for &[u8] {
     fn serialize(&self, serializer: &mut Serializer) -> Result<(), SerializeError> {
         let bytes = RionField::bytes(self);
-        bytes.encode(&mut serializer.output).unwrap();
+        bytes.encode(&mut serializer.output)?;
         Ok(())
     }
 }
@@ -110,7 +242,7 @@ impl RionSerialize for &[u8] {
 impl RionSerialize for Vec<u8> {
     fn serialize(&self, serializer: &mut Serializer) -> Result<(), SerializeError> {
         let bytes = RionField::bytes(self);
-        bytes.encode(&mut serializer.output).unwrap();
+        bytes.encode(&mut serializer.output)?;
         Ok(())
     }
 }
@@ -126,10 +258,26 @@ impl std::fmt::Display for SerializeError {
                 write!(f, "Length overflow: {}", len)
             }
             SerializeError::IoError(err) => write!(f, "IO Error: {}", err),
+            SerializeError::InvalidKey(err) => write!(f, "Invalid key: {}", err),
+            SerializeError::BufferTooSmall(needed) => {
+                write!(f, "Buffer too small, {} bytes needed", needed)
+            }
+            SerializeError::CyclicReference => write!(f, "Cyclic reference"),
+            SerializeError::DepthLimit(max) => {
+                write!(f, "Containers nested deeper than the limit of {}", max)
+            }
+        }
+    }
+}
+impl Error for SerializeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SerializeError::IoError(err) => Some(err),
+            SerializeError::InvalidKey(err) => Some(err),
+            _ => None,
         }
     }
 }
-impl Error for SerializeError {}
 impl serde::ser::Error for SerializeError {
     fn custom<T>(msg: T) -> Self
     where
@@ -140,7 +288,21 @@ impl serde::ser::Error for SerializeError {
 }
 impl From<Box<dyn Error>> for SerializeError {
     fn from(err: Box<dyn Error>) -> Self {
-        SerializeError::Custom(err.to_string())
+        FerionError::from(err).into()
+    }
+}
+impl From<FerionError> for SerializeError {
+    fn from(err: FerionError) -> Self {
+        match err {
+            FerionError::InvalidKey(err) => SerializeError::InvalidKey(err),
+            FerionError::Io(err) => SerializeError::IoError(err),
+            err => SerializeError::Custom(err.to_string()),
+        }
+    }
+}
+impl From<InvalidKey> for SerializeError {
+    fn from(err: InvalidKey) -> Self {
+        SerializeError::InvalidKey(err)
     }
 }
 impl From<std::io::Error> for SerializeError {
@@ -150,11 +312,22 @@ impl From<std::io::Error> for SerializeError {
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum SerializeError {
     Custom(String),
     InvalidType(RionFieldType),
     LengthOverflow(usize),
     IoError(std::io::Error),
+    InvalidKey(InvalidKey),
+    /// The output buffer of [`to_slice`](super::to_slice) can't hold the
+    /// value, which needs this many bytes
+    BufferTooSmall(usize),
+    /// A shared pointer written through the `ferion::shared` adapters contains
+    /// itself
+    CyclicReference,
+    /// Containers nest deeper than [`Serializer::max_depth`] allows, holds
+    /// the limit
+    DepthLimit(usize),
 }
 
 impl<'a> serde::Serializer for &'a mut Serializer {
@@ -169,9 +342,7 @@ impl<'a> serde::Serializer for &'a mut Serializer {
     type SerializeStructVariant = SizedSerializer<'a>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        let field = RionField::bool(v);
-        field.encode(&mut self.output).unwrap();
-        Ok(())
+        self.write_field(RionField::bool(v))
     }
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::from(v))
@@ -184,9 +355,11 @@ impl<'a> serde::Serializer for &'a mut Serializer {
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        let field = RionField::int64(v);
-        field.encode(&mut self.output).unwrap();
-        Ok(())
+        self.write_field(RionField::int64(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.write_field(RionField::try_from(v)?)
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
@@ -202,21 +375,19 @@ impl<'a> serde::Serializer for &'a mut Serializer {
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        let field = RionField::uint64(v);
-        field.encode(&mut self.output).unwrap();
-        Ok(())
+        self.write_field(RionField::uint64(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.write_field(RionField::try_from(v)?)
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        let field = RionField::f32(v);
-        field.encode(&mut self.output).unwrap();
-        Ok(())
+        self.write_field(RionField::f32(v))
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        let field = RionField::f64(v);
-        field.encode(&mut self.output).unwrap();
-        Ok(())
+        self.write_field(RionField::f64(v))
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
@@ -224,15 +395,11 @@ impl<'a> serde::Serializer for &'a mut Serializer {
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        let field = RionField::from_str(v);
-        field.encode(&mut self.output).unwrap();
-        Ok(())
+        self.write_field(RionField::from_str(v))
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        let field = RionField::bytes(v);
-        field.encode(&mut self.output).unwrap();
-        Ok(())
+        self.write_field(RionField::bytes(v))
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
@@ -247,11 +414,16 @@ impl<'a> serde::Serializer for &'a mut Serializer {
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        self.output.push(0x00); // Null Bytes field
+        self.output.push(0x10); // Null Tiny field, not tied to any data type
+        self.stats.fields += 1;
         Ok(())
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        #[cfg(feature = "cycles")]
+        if _name == crate::serde::shared::TOKEN {
+            return Err(SerializeError::CyclicReference);
+        }
         self.serialize_unit()
     }
 
@@ -266,12 +438,18 @@ impl<'a> serde::Serializer for &'a mut Serializer {
 
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        if name == crate::datetime::TOKEN {
+            return self.write_field(datetime_field(value)?);
+        }
+        if name == rows::TOKEN {
+            return self.write_field(table_field(value)?);
+        }
         value.serialize(self)
     }
 
@@ -296,7 +474,7 @@ impl<'a> serde::Serializer for &'a mut Serializer {
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Ok(SizedSerializer::new(self))
+        SizedSerializer::new(self)
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
@@ -319,21 +497,23 @@ impl<'a> serde::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        let mut sized = SizedSerializer::new(self);
+        let mut sized = SizedSerializer::new(self)?;
         sized.serialize_key(variant)?;
         Ok(sized)
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        Ok(SizedSerializer::new(self))
+        SizedSerializer::new(self)
     }
 
     fn serialize_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        self.serialize_map(Some(len))
+        let mut sized = self.serialize_map(Some(len))?;
+        sized.number = name == number::TOKEN;
+        Ok(sized)
     }
 
     // Todo this is not correct, does not handle key
@@ -356,6 +536,7 @@ impl<'a> SerializeTuple for SizedSerializer<'a> {
     where
         T: ?Sized + serde::Serialize,
     {
+        self.temp.stats.entries += 1;
         value.serialize(&mut self.temp)
     }
     fn end(self) -> Result<Self::Ok, Self::Error> {
@@ -372,6 +553,7 @@ impl<'a> SerializeSeq for SizedSerializer<'a> {
     where
         T: ?Sized + serde::Serialize,
     {
+        self.temp.stats.entries += 1;
         value.serialize(&mut self.temp)
     }
 
@@ -392,31 +574,14 @@ impl SerializeMap for SizedSerializer<'_> {
         // key.serialize(&mut self.temp)
         let initial_len = self.temp.output.len();
         key.serialize(&mut self.temp)?;
-        assert!(self.temp.output.len() > initial_len);
-        let lead = self.temp.output[initial_len]; // Guaranteed to have at least one byte written
-        let lead_byte = LeadByte::try_from(lead)?;
-        // If the first byte is not a Key field, throw an error
-        let ft = lead_byte.field_type();
-        let target = &mut self.temp.output[initial_len];
-        match ft {
-            ft if ft.is_key() => {}
-            RionFieldType::Normal(NormalRionType::UTF8) => {
-                *target &= 0x0F;
-                *target |= NormalRionType::Key.to_byte() << 4;
-            }
-            RionFieldType::Short(ShortRionType::UTF8) => {
-                *target &= 0x0F;
-                *target |= ShortRionType::Key.to_byte() << 4;
-            }
-            _ => return Err(SerializeError::InvalidType(ft)),
-        }
-        Ok(())
+        mark_key(&mut self.temp.output[initial_len..])
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        self.temp.stats.entries += 1;
         value.serialize(&mut self.temp)
     }
 
@@ -426,12 +591,110 @@ impl SerializeMap for SizedSerializer<'_> {
     }
 }
 
+// The UTCDateTime field written by the `datetime` adapters, which pass its
+// data as bytes
+pub(super) fn datetime_field<T: ?Sized + Serialize>(
+    value: &T,
+) -> Result<RionField<'static>, SerializeError> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+    let (field, _) = RionField::parse(&serializer.output)?;
+    if !field.is_normal_type(NormalRionType::Bytes) {
+        return Err(SerializeError::InvalidType(field.field_type()));
+    }
+    Ok(crate::datetime::from_data(field.as_bytes())?)
+}
+
+// The Table field for a sequence of structs written through `rows::TOKEN`,
+// the rows themselves are written without count hints or numeric fields
+pub(super) fn table_field<T: ?Sized + Serialize>(
+    value: &T,
+) -> Result<RionField<'static>, SerializeError> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+    let table = RionTable::from_array(&RionArray::from_slice(&serializer.output)?)?;
+    let encoded = table.encode();
+    let (field, _) = RionField::parse(&encoded)?;
+    Ok(field.into_owned())
+}
+
+// Turn the string field just written for a map key into a Key field
+pub(super) fn mark_key(field: &mut [u8]) -> Result<(), SerializeError> {
+    assert!(!field.is_empty());
+    let lead = field[0]; // Guaranteed to have at least one byte written
+    let lead_byte = LeadByte::try_from(lead)?;
+    // If the first byte is not a Key field, throw an error
+    let ft = lead_byte.field_type();
+    let target = &mut field[0];
+    match ft {
+        ft if ft.is_key() => {}
+        RionFieldType::Normal(NormalRionType::UTF8) => {
+            *target &= 0x0F;
+            *target |= NormalRionType::Key.to_byte() << 4;
+        }
+        RionFieldType::Short(ShortRionType::UTF8) => {
+            *target &= 0x0F;
+            *target |= ShortRionType::Key.to_byte() << 4;
+        }
+        _ => return Err(SerializeError::InvalidType(ft)),
+    }
+    let (key, _) = RionField::parse(field)?;
+    RionKey::new(key.as_bytes())?;
+    Ok(())
+}
+
+// The field for an arbitrary precision number, whose text serde_json passes
+// as a string
+pub(super) fn number_field<T: ?Sized + Serialize>(
+    value: &T,
+) -> Result<RionField<'static>, SerializeError> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+    let (field, _) = RionField::parse(&serializer.output)?;
+    let text = field
+        .as_str()
+        .ok_or(SerializeError::InvalidType(field.field_type()))?;
+    Ok(number::from_str(text))
+}
+
+// The encoded length of a map key, checked the same way as when serializing
+pub(super) fn key_len<T: ?Sized + Serialize>(key: &T) -> Result<usize, SerializeError> {
+    let mut serializer = Serializer::new();
+    let mut sized = SizedSerializer::new(&mut serializer)?;
+    SerializeMap::serialize_key(&mut sized, key)?;
+    Ok(sized.temp.output.len())
+}
+
 impl<'a> SizedSerializer<'a> {
-    fn new(output: &'a mut Serializer) -> Self {
-        Self {
+    fn new(output: &'a mut Serializer) -> Result<Self, SerializeError> {
+        if output.depth >= output.max_depth {
+            return Err(SerializeError::DepthLimit(output.max_depth));
+        }
+        let temp = Serializer {
+            depth: output.depth + 1,
+            ..Serializer::new()
+                .count_hints(output.count_hints)
+                .numeric_fields(output.numeric_fields)
+                .max_depth(output.max_depth)
+        };
+        Ok(Self {
             output,
-            temp: Serializer::new(),
+            temp,
+            number: false,
+            index: 0,
+        })
+    }
+
+    // The key of the next struct field, its name or its index
+    fn serialize_field_key(&mut self, key: &'static str) -> Result<(), SerializeError> {
+        let index = self.index;
+        self.index += 1;
+        if !self.temp.numeric_fields {
+            return SerializeMap::serialize_key(self, key);
         }
+        let bytes = (index as u64).to_be_bytes();
+        let zeros = bytes.iter().take_while(|&&b| b == 0).count().min(7);
+        self.temp.serialize_key(&bytes[zeros..])
     }
 
     fn finish(self, type_byte: u8) -> Result<(), SerializeError> {
@@ -440,6 +703,18 @@ impl<'a> SizedSerializer<'a> {
         if length_length > 15 {
             return Err(SerializeError::LengthOverflow(length_length)); // TODO handle error
         }
+        if self.output.count_hints && matches!(type_byte, 0xA | 0xC) {
+            let fields = crate::count_fields(&self.temp.output)?;
+            // Objects hold a key and a value per entry
+            let entries = if type_byte == 0xC { fields / 2 } else { fields };
+            let mut count = Vec::new();
+            crate::int_to_bytes(&(entries as u64), &mut count)?;
+            RionField::Extended {
+                type_id: RionField::COUNT_HINT,
+                data: count.into(),
+            }
+            .encode(&mut self.output.output)?;
+        }
         self.output
             .output
             .push(type_byte << 4 | length_length as u8);
@@ -448,6 +723,9 @@ impl<'a> SizedSerializer<'a> {
         crate::int_to_bytes(&ll, &mut self.output.output)?;
         assert_eq!(self.output.output.len() - orig, length_length);
         self.output.output.extend(self.temp.output);
+        self.output.stats.fields += 1;
+        self.output.stats.containers += 1;
+        self.output.stats.merge(self.temp.stats);
         Ok(())
     }
 }
@@ -460,6 +738,7 @@ impl SerializeTupleStruct for SizedSerializer<'_> {
     where
         T: ?Sized + serde::Serialize,
     {
+        self.temp.stats.entries += 1;
         value.serialize(&mut self.temp)
     }
 
@@ -476,13 +755,25 @@ impl SerializeStruct for SizedSerializer<'_> {
     where
         T: ?Sized + serde::Serialize,
     {
+        if self.number {
+            return self.output.write_field(number_field(value)?);
+        }
         // let key = RionField::key(key.as_bytes());
         // key.encode(&mut self.temp.output).unwrap();
-        self.serialize_key(key)?;
+        self.serialize_field_key(key)?;
+        self.temp.stats.entries += 1;
         value.serialize(&mut self.temp)
     }
 
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
+        self.index += 1;
+        Ok(())
+    }
+
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        if self.number {
+            return Ok(());
+        }
         self.finish(0xC)
     }
 }
@@ -495,10 +786,16 @@ impl SerializeStructVariant for SizedSerializer<'_> {
     where
         T: ?Sized + serde::Serialize,
     {
-        self.serialize_key(key)?;
+        self.serialize_field_key(key)?;
+        self.temp.stats.entries += 1;
         value.serialize(&mut self.temp)
     }
 
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
+        self.index += 1;
+        Ok(())
+    }
+
     fn end(self) -> Result<Self::Ok, Self::Error> {
         self.finish(0xC)
     }
@@ -512,6 +809,7 @@ impl SerializeTupleVariant for SizedSerializer<'_> {
     where
         T: ?Sized + serde::Serialize,
     {
+        self.temp.stats.entries += 1;
         value.serialize(&mut self.temp)
     }
 
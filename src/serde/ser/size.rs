@@ -0,0 +1,400 @@
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize,
+};
+
+use super::{
+    serializer::{datetime_field, key_len, number_field, table_field},
+    SerializeError,
+};
+use crate::{field::InlineNumber, needed_bytes_usize, RionField, RionKey};
+
+/// The exact number of bytes [`to_bytes`](super::to_bytes) would produce for
+/// `value`, computed without encoding it
+///
+/// Useful to preallocate buffers or to enforce a size limit up front. Values
+/// that fail to serialize fail here with the same error.
+pub fn encoded_size<T>(value: &T) -> Result<usize, SerializeError>
+where
+    T: ?Sized + Serialize,
+{
+    let mut counter = SizeCounter::default();
+    value.serialize(&mut counter)?;
+    Ok(counter.len)
+}
+
+// Mirrors `Serializer`, adding up field sizes instead of writing them
+#[derive(Default)]
+struct SizeCounter {
+    len: usize,
+    depth: usize,
+}
+
+impl SizeCounter {
+    fn count_field(&mut self, field: RionField) -> Result<(), SerializeError> {
+        self.len += field.needed_bytes();
+        Ok(())
+    }
+
+    fn count_key(&mut self, key: &str) -> Result<(), SerializeError> {
+        self.count_field(RionKey::try_from(key)?.into())
+    }
+}
+
+struct SizedCounter<'a> {
+    output: &'a mut SizeCounter,
+    temp: SizeCounter,
+    number: bool,
+}
+
+impl<'a> SizedCounter<'a> {
+    fn new(output: &'a mut SizeCounter) -> Result<Self, SerializeError> {
+        if output.depth >= crate::DEFAULT_MAX_DEPTH {
+            return Err(SerializeError::DepthLimit(crate::DEFAULT_MAX_DEPTH));
+        }
+        let temp = SizeCounter {
+            len: 0,
+            depth: output.depth + 1,
+        };
+        Ok(Self {
+            output,
+            temp,
+            number: false,
+        })
+    }
+
+    fn finish(self) -> Result<(), SerializeError> {
+        let content_len = self.temp.len;
+        let length_length = needed_bytes_usize(content_len);
+        if length_length > 15 {
+            return Err(SerializeError::LengthOverflow(length_length));
+        }
+        self.output.len += 1 + length_length + content_len;
+        Ok(())
+    }
+}
+
+impl<'a> serde::Serializer for &'a mut SizeCounter {
+    type Ok = ();
+    type Error = SerializeError;
+    type SerializeSeq = SizedCounter<'a>;
+    type SerializeTuple = SizedCounter<'a>;
+    type SerializeTupleStruct = SizedCounter<'a>;
+    type SerializeTupleVariant = SizedCounter<'a>;
+    type SerializeMap = SizedCounter<'a>;
+    type SerializeStruct = SizedCounter<'a>;
+    type SerializeStructVariant = SizedCounter<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.count_field(RionField::bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.count_field(InlineNumber::signed(v.into()).field()?)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.count_field(InlineNumber::signed(v).field()?)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.count_field(InlineNumber::unsigned(v.into()).field()?)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.count_field(InlineNumber::unsigned(v).field()?)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.count_field(InlineNumber::f32(v).field()?)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.count_field(InlineNumber::f64(v).field()?)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.count_field(RionField::from_str(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.count_field(RionField::bytes(v))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.len += 1;
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        #[cfg(feature = "cycles")]
+        if _name == crate::serde::shared::TOKEN {
+            return Err(SerializeError::CyclicReference);
+        }
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == crate::datetime::TOKEN {
+            return self.count_field(datetime_field(value)?);
+        }
+        if name == crate::serde::rows::TOKEN {
+            return self.count_field(table_field(value)?);
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut sized = SizedCounter::new(self)?;
+        sized.temp.count_key(variant)?;
+        value.serialize(&mut sized.temp)?;
+        sized.finish()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        SizedCounter::new(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let mut sized = SizedCounter::new(self)?;
+        sized.temp.count_key(variant)?;
+        Ok(sized)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        SizedCounter::new(self)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let mut sized = self.serialize_map(Some(len))?;
+        sized.number = name == crate::serde::number::TOKEN;
+        Ok(sized)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+}
+
+impl SerializeSeq for SizedCounter<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut self.temp)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl SerializeTuple for SizedCounter<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut self.temp)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl SerializeTupleStruct for SizedCounter<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut self.temp)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl SerializeTupleVariant for SizedCounter<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut self.temp)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl SerializeMap for SizedCounter<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.temp.len += key_len(key)?;
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut self.temp)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl SerializeStruct for SizedCounter<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.number {
+            return self.output.count_field(number_field(value)?);
+        }
+        self.temp.count_key(key)?;
+        value.serialize(&mut self.temp)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        if self.number {
+            return Ok(());
+        }
+        self.finish()
+    }
+}
+
+impl SerializeStructVariant for SizedCounter<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.temp.count_key(key)?;
+        value.serialize(&mut self.temp)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
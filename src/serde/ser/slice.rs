@@ -0,0 +1,449 @@
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize,
+};
+
+use super::{
+    serializer::{datetime_field, mark_key, number_field, table_field},
+    size::encoded_size,
+    SerializeError,
+};
+use crate::{field::InlineNumber, needed_bytes_usize, types::LeadByte, RionField};
+
+/// Encode `value` into `buf`, returning the number of bytes written
+///
+/// Nothing is allocated for the output: containers reserve room for a short
+/// header and move their content over once its length is known. When
+/// the value doesn't fit this fails with [`SerializeError::BufferTooSmall`]
+/// holding the size it needs, and the content of `buf` is unspecified.
+///
+/// Scalars, strings, bytes and containers are written without allocating at
+/// all, so values made of fixed-capacity types like arrays or
+/// `heapless::Vec` encode without touching the heap. Date times, arbitrary
+/// precision numbers and tables are still built on the heap first.
+pub fn to_slice<T>(value: &T, buf: &mut [u8]) -> Result<usize, SerializeError>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = SliceSerializer {
+        buf,
+        pos: 0,
+        depth: 0,
+    };
+    match value.serialize(&mut serializer) {
+        Ok(()) => Ok(serializer.pos),
+        Err(SerializeError::BufferTooSmall(_)) => {
+            Err(SerializeError::BufferTooSmall(encoded_size(value)?))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// Mirrors `Serializer`, writing into a fixed buffer. Running out of room is
+// reported as `BufferTooSmall(0)`, `to_slice` fills in the needed size
+struct SliceSerializer<'b> {
+    buf: &'b mut [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl SliceSerializer<'_> {
+    fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    fn write_field(&mut self, field: RionField) -> Result<(), SerializeError> {
+        let len = field.needed_bytes();
+        if len > self.remaining() {
+            return Err(SerializeError::BufferTooSmall(0));
+        }
+        field.encode(&mut &mut self.buf[self.pos..])?;
+        self.pos += len;
+        Ok(())
+    }
+}
+
+struct SizedSlice<'a, 'b> {
+    output: &'a mut SliceSerializer<'b>,
+    start: usize,
+    // Bytes kept free for the header, enough for a length that fits in one
+    // byte. Longer content is moved further along when finished
+    reserved: usize,
+    number: bool,
+}
+
+impl<'a, 'b> SizedSlice<'a, 'b> {
+    fn new(output: &'a mut SliceSerializer<'b>) -> Result<Self, SerializeError> {
+        if output.depth >= crate::DEFAULT_MAX_DEPTH {
+            return Err(SerializeError::DepthLimit(crate::DEFAULT_MAX_DEPTH));
+        }
+        let remaining = output.remaining();
+        if remaining == 0 {
+            return Err(SerializeError::BufferTooSmall(0));
+        }
+        let start = output.pos;
+        let reserved = remaining.min(2);
+        output.pos += reserved;
+        output.depth += 1;
+        Ok(Self {
+            output,
+            start,
+            reserved,
+            number: false,
+        })
+    }
+
+    fn finish(self, type_byte: u8) -> Result<(), SerializeError> {
+        let content = self.start + self.reserved..self.output.pos;
+        let total_len = content.len();
+        let length_length = needed_bytes_usize(total_len);
+        let header = 1 + length_length;
+        if self.start + header + total_len > self.output.buf.len() {
+            return Err(SerializeError::BufferTooSmall(0));
+        }
+        let buf = &mut self.output.buf;
+        buf.copy_within(content, self.start + header);
+        buf[self.start] = type_byte << 4 | length_length as u8;
+        let length = (total_len as u64).to_be_bytes();
+        buf[self.start + 1..self.start + header].copy_from_slice(&length[8 - length_length..]);
+        self.output.pos = self.start + header + total_len;
+        self.output.depth -= 1;
+        Ok(())
+    }
+
+    fn serialize_key(&mut self, key: &str) -> Result<(), SerializeError> {
+        SerializeMap::serialize_key(self, key)
+    }
+}
+
+impl<'a, 'b> serde::Serializer for &'a mut SliceSerializer<'b> {
+    type Ok = ();
+    type Error = SerializeError;
+    type SerializeSeq = SizedSlice<'a, 'b>;
+    type SerializeTuple = SizedSlice<'a, 'b>;
+    type SerializeTupleStruct = SizedSlice<'a, 'b>;
+    type SerializeTupleVariant = SizedSlice<'a, 'b>;
+    type SerializeMap = SizedSlice<'a, 'b>;
+    type SerializeStruct = SizedSlice<'a, 'b>;
+    type SerializeStructVariant = SizedSlice<'a, 'b>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.write_field(RionField::bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.write_field(InlineNumber::signed(v.into()).field()?)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.write_field(InlineNumber::signed(v).field()?)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.write_field(InlineNumber::unsigned(v.into()).field()?)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.write_field(InlineNumber::unsigned(v).field()?)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.write_field(InlineNumber::f32(v).field()?)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.write_field(InlineNumber::f64(v).field()?)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.write_field(RionField::from_str(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.write_field(RionField::bytes(v))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.write_field(RionField::Tiny(LeadByte::NULL))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        #[cfg(feature = "cycles")]
+        if _name == crate::serde::shared::TOKEN {
+            return Err(SerializeError::CyclicReference);
+        }
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == crate::datetime::TOKEN {
+            return self.write_field(datetime_field(value)?);
+        }
+        if name == crate::serde::rows::TOKEN {
+            return self.write_field(table_field(value)?);
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut sized = SizedSlice::new(self)?;
+        sized.serialize_key(variant)?;
+        value.serialize(&mut *sized.output)?;
+        sized.finish(0xC)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        SizedSlice::new(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let mut sized = SizedSlice::new(self)?;
+        sized.serialize_key(variant)?;
+        Ok(sized)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        SizedSlice::new(self)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        if name == crate::serde::number::TOKEN {
+            // Written as a single field, no header to reserve
+            return Ok(SizedSlice {
+                start: self.pos,
+                output: self,
+                reserved: 0,
+                number: true,
+            });
+        }
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+}
+
+impl SerializeSeq for SizedSlice<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self.output)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(0xA)
+    }
+}
+
+impl SerializeTuple for SizedSlice<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self.output)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(0xA)
+    }
+}
+
+impl SerializeTupleStruct for SizedSlice<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self.output)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(0xA)
+    }
+}
+
+impl SerializeTupleVariant for SizedSlice<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self.output)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(0xA)
+    }
+}
+
+impl SerializeMap for SizedSlice<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let start = self.output.pos;
+        key.serialize(&mut *self.output)?;
+        mark_key(&mut self.output.buf[start..self.output.pos])
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self.output)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(0xC)
+    }
+}
+
+impl SerializeStruct for SizedSlice<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.number {
+            return self.output.write_field(number_field(value)?);
+        }
+        self.serialize_key(key)?;
+        value.serialize(&mut *self.output)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        if self.number {
+            return Ok(());
+        }
+        self.finish(0xC)
+    }
+}
+
+impl SerializeStructVariant for SizedSlice<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.serialize_key(key)?;
+        value.serialize(&mut *self.output)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(0xC)
+    }
+}
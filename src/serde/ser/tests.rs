@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use super::to_bytes;
-use crate::RionObject;
+use crate::{RionField, RionObject};
 
 #[test]
 fn test_serialize_bool() {
@@ -66,6 +66,16 @@ fn test_nested_object_serialization() {
     }
 }
 
+#[test]
+fn test_serialize_invalid_key() {
+    let mut obj = HashMap::new();
+    obj.insert("", 1u8);
+    assert!(matches!(
+        to_bytes(&obj),
+        Err(super::SerializeError::InvalidKey(crate::InvalidKey::Empty))
+    ));
+}
+
 #[test]
 fn test_serialize_empty_object() {
     let obj: std::collections::HashMap<String, String> = std::collections::HashMap::new();
@@ -140,6 +150,25 @@ fn test_serialize_negative_integer() {
     assert_eq!(serialized, vec![0x31, 0x29]);
 }
 
+#[test]
+fn test_serialize_128_bit_integers() {
+    let below = i64::MIN as i128 - 1;
+    let bytes = to_bytes(&below).unwrap();
+    assert_eq!(bytes, [0x38, 0x80, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(crate::from_bytes::<i128>(&bytes).unwrap(), below);
+    assert_eq!(super::encoded_size(&below).unwrap(), bytes.len());
+
+    let big = 1u128 << 100;
+    let bytes = to_bytes(&big).unwrap();
+    assert_eq!(bytes.len(), 14);
+    assert_eq!(crate::from_bytes::<u128>(&bytes).unwrap(), big);
+    assert_eq!(to_bytes(&7u128).unwrap(), to_bytes(&7u8).unwrap());
+
+    // 16 bytes is past what a short field can hold
+    assert!(to_bytes(&u128::MAX).is_err());
+    assert!(to_bytes(&i128::MIN).is_err());
+}
+
 #[test]
 fn test_serialize_nested_objects() {
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -187,3 +216,479 @@ fn test_serialize_deeply_nested() {
     assert_eq!(decoded, nest);
     // println!("{:?}", result);
 }
+
+#[test]
+fn test_serialize_stats() {
+    #[derive(Serialize)]
+    struct Counted {
+        id: u8,
+        tags: Vec<u8>,
+    }
+    let value = Counted {
+        id: 1,
+        tags: vec![2, 3],
+    };
+    let (bytes, stats) = super::to_bytes_with_stats(&value).unwrap();
+    assert_eq!(stats.bytes, bytes.len());
+    assert_eq!(stats.containers, 2);
+    // Two object entries plus two array elements
+    assert_eq!(stats.entries, 4);
+    // Object, two keys, id, array and its two elements
+    assert_eq!(stats.fields, 7);
+}
+
+#[test]
+fn test_serialize_unit() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Marker;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Holder {
+        marker: Marker,
+        units: Vec<()>,
+        after: u8,
+    }
+
+    assert_eq!(to_bytes(&()).unwrap(), vec![0x10]);
+    assert_eq!(to_bytes(&Marker).unwrap(), vec![0x10]);
+
+    let value = Holder {
+        marker: Marker,
+        units: vec![(), ()],
+        after: 7,
+    };
+    let bytes = to_bytes(&value).unwrap();
+    assert_eq!(crate::from_bytes::<Holder>(&bytes).unwrap(), value);
+}
+
+#[test]
+fn test_os_str_adapter() {
+    use std::{ffi::OsString, path::PathBuf};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct FileMeta {
+        #[serde(with = "crate::os_str")]
+        path: PathBuf,
+        #[serde(with = "crate::os_str")]
+        name: OsString,
+    }
+
+    let value = FileMeta {
+        path: PathBuf::from("/tmp/a"),
+        name: OsString::from("b"),
+    };
+    let bytes = to_bytes(&value).unwrap();
+    let object = RionObject::from_slice(&bytes).unwrap();
+    assert_eq!(object.fields[b"path".as_slice()].as_str(), Some("/tmp/a"));
+    assert_eq!(crate::from_bytes::<FileMeta>(&bytes).unwrap(), value);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        let value = FileMeta {
+            path: PathBuf::from(OsString::from_vec(vec![b'/', 0xFF, 0xFE])),
+            name: OsString::from_vec(vec![0x80]),
+        };
+        let bytes = to_bytes(&value).unwrap();
+        assert_eq!(crate::from_bytes::<FileMeta>(&bytes).unwrap(), value);
+    }
+}
+
+#[test]
+fn test_net_adapters() {
+    use std::net::{IpAddr, SocketAddr};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Peer {
+        #[serde(with = "crate::net::ip_addr")]
+        ip: IpAddr,
+        #[serde(with = "crate::net::socket_addr")]
+        addr: SocketAddr,
+    }
+
+    let value = Peer {
+        ip: "10.0.0.1".parse().unwrap(),
+        addr: "[::1]:8080".parse().unwrap(),
+    };
+    let bytes = to_bytes(&value).unwrap();
+    let object = RionObject::from_slice(&bytes).unwrap();
+    assert_eq!(object.fields[b"ip".as_slice()].as_bytes(), &[10, 0, 0, 1]);
+    assert_eq!(object.fields[b"addr".as_slice()].as_bytes().len(), 18);
+    assert_eq!(crate::from_bytes::<Peer>(&bytes).unwrap(), value);
+
+    // Textual addresses are still accepted
+    let mut text = RionObject::new();
+    text.add_field("ip", "10.0.0.1");
+    text.add_field("addr", "[::1]:8080");
+    assert_eq!(crate::from_bytes::<Peer>(&text.encode()).unwrap(), value);
+}
+
+#[test]
+fn test_serialize_containers() {
+    let mut object = RionObject::new();
+    object.add_field("name", "Alice");
+    object.add_field("age", 30u64);
+    let bytes = to_bytes(&object).unwrap();
+    assert_eq!(RionObject::from_slice(&bytes).unwrap(), object);
+    // Typed nulls come out as the untyped null
+    object.add_field("nick", None::<&str>);
+
+    let mut array = crate::RionArray::new();
+    array.extend([1u64, 2, 3]);
+    array.add_element(RionField::from(vec![RionField::from("x")]));
+    assert_eq!(to_bytes(&array).unwrap(), array.encode());
+
+    // Embedded in another struct
+    #[derive(Serialize)]
+    struct Wrapper<'a> {
+        inner: RionObject<'a>,
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Inner {
+        name: String,
+        age: u32,
+        nick: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct Decoded {
+        inner: Inner,
+    }
+    let bytes = to_bytes(&Wrapper { inner: object }).unwrap();
+    let decoded: Decoded = crate::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        decoded.inner,
+        Inner {
+            name: "Alice".to_string(),
+            age: 30,
+            nick: None,
+        }
+    );
+}
+
+#[test]
+fn test_serialize_table_rows() {
+    let data = crate::RionValue::Table {
+        columns: vec![b"id"[..].into()],
+        rows: vec![vec![1u64.into()], vec![2u64.into()]],
+    }
+    .encode_to_vec()
+    .unwrap();
+    let table = crate::RionTable::from_slice(&data).unwrap();
+    let rows: Vec<HashMap<String, u64>> = crate::from_bytes(&to_bytes(&table).unwrap()).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1]["id"], 2);
+}
+
+#[test]
+fn test_to_writer() {
+    let mut out = Vec::new();
+    super::to_writer(&mut out, &("a", 1u8)).unwrap();
+    assert_eq!(out, to_bytes(&("a", 1u8)).unwrap());
+}
+
+#[test]
+fn test_encoded_size() {
+    #[derive(Serialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Pair(u8, i32),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Serialize)]
+    struct Document {
+        title: String,
+        tags: Vec<&'static str>,
+        shapes: Vec<Shape>,
+        meta: HashMap<String, Option<i64>>,
+        blob: Vec<u8>,
+    }
+
+    let document = Document {
+        title: "a title longer than fifteen bytes".to_string(),
+        tags: vec!["x"; 300],
+        shapes: vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Pair(1, -2),
+            Shape::Rect { w: 3, h: 4 },
+        ],
+        meta: HashMap::from([("a".to_string(), Some(-7)), ("b".to_string(), None)]),
+        blob: vec![0; 70_000],
+    };
+    let size = super::encoded_size(&document).unwrap();
+    assert_eq!(size, to_bytes(&document).unwrap().len());
+    assert_eq!(super::encoded_size(&'é').unwrap(), 3);
+
+    let bad_key = HashMap::from([("", 1u8)]);
+    assert!(super::encoded_size(&bad_key).is_err());
+    assert!(super::encoded_size(&HashMap::from([(1u8, 1u8)])).is_err());
+}
+
+#[test]
+#[cfg(feature = "chrono")]
+fn test_naive_datetime_adapters() {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Event {
+        #[serde(with = "crate::datetime::naive_date")]
+        day: NaiveDate,
+        #[serde(with = "crate::datetime::naive_date_time")]
+        at: NaiveDateTime,
+    }
+
+    let day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+    let at = day.and_hms_milli_opt(13, 45, 10, 500).unwrap();
+    let bytes = to_bytes(&Event { day, at }).unwrap();
+    assert_eq!(
+        super::encoded_size(&Event { day, at }).unwrap(),
+        bytes.len()
+    );
+
+    let object = RionObject::from_slice(&bytes).unwrap();
+    assert_eq!(object.get("day"), Some(&RionField::try_from(day).unwrap()));
+    assert_eq!(object.get("day").unwrap().as_bytes().len(), 4);
+    let decoded: Event = crate::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.day, day);
+    assert_eq!(decoded.at, day.and_hms_opt(13, 45, 10).unwrap());
+
+    // chrono's own string forms still decode
+    let strings = HashMap::from([
+        ("day", day.to_string()),
+        ("at", at.format("%FT%T%.f").to_string()),
+    ]);
+    let decoded: Event = crate::from_bytes(&to_bytes(&strings).unwrap()).unwrap();
+    assert_eq!(decoded, Event { day, at });
+}
+
+#[test]
+#[cfg(feature = "chrono")]
+fn test_rion_date_time() {
+    use crate::{Precision, RionDateTime};
+
+    // Precision survives, even past what the components need
+    let at = RionDateTime::from_components(2024, 2, 29, 13, 45, 0, 0)
+        .unwrap()
+        .with_precision(Precision::Second);
+    let bytes = to_bytes(&HashMap::from([("at", at)])).unwrap();
+    let object = RionObject::from_slice(&bytes).unwrap();
+    assert_eq!(
+        object.get("at"),
+        Some(&at.to_field(Default::default()).unwrap())
+    );
+    let decoded: HashMap<String, RionDateTime> = crate::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded["at"], at);
+    assert_eq!(decoded["at"].precision(), Precision::Second);
+
+    // RFC 3339 strings still decode, other ones don't
+    let decoded: RionDateTime =
+        crate::from_bytes(&to_bytes(&"2024-02-29T13:45:00Z").unwrap()).unwrap();
+    assert_eq!(decoded.datetime(), at.datetime());
+    assert!(crate::from_bytes::<RionDateTime>(&to_bytes(&"2024-02-29").unwrap()).is_err());
+}
+
+#[test]
+fn test_arbitrary_precision_numbers() {
+    use serde::ser::SerializeStruct;
+
+    // How serde_json::Number serializes with `arbitrary_precision` enabled
+    struct JsonNumber(&'static str);
+    impl Serialize for JsonNumber {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut s = serializer.serialize_struct("$serde_json::private::Number", 1)?;
+            s.serialize_field("$serde_json::private::Number", self.0)?;
+            s.end()
+        }
+    }
+
+    let encode = |text| {
+        let bytes = to_bytes(&JsonNumber(text)).unwrap();
+        assert_eq!(super::encoded_size(&JsonNumber(text)).unwrap(), bytes.len());
+        bytes
+    };
+    assert_eq!(encode("42"), to_bytes(&42u64).unwrap());
+    assert_eq!(encode("-42"), to_bytes(&-42i64).unwrap());
+    assert_eq!(encode("0.1"), to_bytes(&0.1f64).unwrap());
+    assert_eq!(encode("1.50e2"), to_bytes(&150.0f64).unwrap());
+
+    let big = u64::MAX as u128 * 1000;
+    let bytes = encode("18446744073709551615000");
+    assert_eq!(crate::from_bytes::<u128>(&bytes).unwrap(), big);
+    let bytes = encode("-18446744073709551615000");
+    assert_eq!(crate::from_bytes::<i128>(&bytes).unwrap(), -(big as i128));
+    assert_eq!(crate::from_bytes::<u128>(&encode("7")).unwrap(), 7);
+
+    // Digits an f64 can't hold are kept as text
+    let precise = "3.14159265358979323846264338327950288";
+    assert_eq!(encode(precise), to_bytes(&precise).unwrap());
+    let huge = "1329227995784915872903807060280344576";
+    assert_eq!(encode(huge), to_bytes(&huge).unwrap());
+    assert_eq!(encode("1e400"), to_bytes(&"1e400").unwrap());
+
+    let wrapped = HashMap::from([("n", JsonNumber("0.25"))]);
+    let bytes = to_bytes(&wrapped).unwrap();
+    let object = RionObject::from_slice(&bytes).unwrap();
+    assert_eq!(object.get("n"), Some(&RionField::f64(0.25)));
+}
+
+#[test]
+fn test_to_slice() {
+    #[derive(Serialize)]
+    struct Reading<'a> {
+        sensor: &'a str,
+        values: Vec<f64>,
+        flags: HashMap<&'a str, bool>,
+        note: Option<String>,
+    }
+
+    let reading = Reading {
+        sensor: "a sensor with a long name",
+        values: (0..40).map(f64::from).collect(),
+        flags: HashMap::from([("ok", true)]),
+        note: None,
+    };
+    let expected = to_bytes(&reading).unwrap();
+    let mut buf = [0; 1024];
+    let len = super::to_slice(&reading, &mut buf).unwrap();
+    assert_eq!(&buf[..len], expected);
+
+    // Exactly enough room, then one byte short
+    let mut exact = vec![0; expected.len()];
+    assert_eq!(
+        super::to_slice(&reading, &mut exact).unwrap(),
+        expected.len()
+    );
+    assert_eq!(exact, expected);
+    let mut short = vec![0; expected.len() - 1];
+    assert!(matches!(
+        super::to_slice(&reading, &mut short),
+        Err(super::SerializeError::BufferTooSmall(needed)) if needed == expected.len()
+    ));
+
+    // Nested containers whose headers need more than one length byte
+    fn fits_exactly<T: Serialize>(value: &T) {
+        let expected = to_bytes(value).unwrap();
+        let mut buf = vec![0; super::encoded_size(value).unwrap()];
+        assert_eq!(super::to_slice(value, &mut buf).unwrap(), expected.len());
+        assert_eq!(buf, expected);
+    }
+    let nested = vec![vec!["a".repeat(250)]];
+    assert_eq!(super::encoded_size(&nested).unwrap(), 256);
+    fits_exactly(&nested);
+    fits_exactly(&HashMap::from([(
+        "rows",
+        vec![vec!["b".repeat(300); 3]; 2],
+    )]));
+
+    let empty: Vec<u8> = Vec::new();
+    let mut one = [0; 1];
+    assert_eq!(super::to_slice(&empty, &mut one).unwrap(), 1);
+    assert_eq!(one[..], to_bytes(&empty).unwrap()[..]);
+    assert!(super::to_slice(&HashMap::from([("", 1u8)]), &mut buf).is_err());
+}
+
+// Counts the allocations made on each thread, so tests can check a path
+// allocates nothing
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        std::alloc::System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+#[test]
+fn test_fixed_capacity_round_trip() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Telemetry<'a> {
+        id: u32,
+        sensor: &'a str,
+        name: heapless::String<16>,
+        samples: heapless::Vec<i16, 8>,
+        raw: [u8; 4],
+        temperature: Option<f32>,
+    }
+
+    let telemetry = Telemetry {
+        id: 7,
+        sensor: "probe",
+        name: heapless::String::try_from("engine bay").unwrap(),
+        samples: heapless::Vec::from_slice(&[1, -2, 300]).unwrap(),
+        raw: [1, 2, 3, 4],
+        temperature: Some(21.5),
+    };
+    let mut buf = [0; 128];
+    let allocations = ALLOCATIONS.with(|count| count.get());
+    let len = super::to_slice(&telemetry, &mut buf).unwrap();
+    let decoded: Telemetry = crate::from_bytes(&buf[..len]).unwrap();
+    let size = super::encoded_size(&telemetry).unwrap();
+    assert_eq!(ALLOCATIONS.with(|count| count.get()), allocations);
+    assert_eq!(decoded, telemetry);
+    assert_eq!(size, len);
+    assert_eq!(&buf[..len], to_bytes(&telemetry).unwrap());
+}
+
+#[test]
+fn test_batches() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        id: u32,
+        name: String,
+    }
+
+    let records = (0..100)
+        .map(|id| Record {
+            id,
+            name: format!("record {id}"),
+        })
+        .collect::<Vec<_>>();
+    let concatenated = super::to_bytes_many(&records, super::Batch::Concatenated).unwrap();
+    let expected = records
+        .iter()
+        .flat_map(|record| to_bytes(record).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(concatenated, expected);
+    let decoded: Vec<Record> =
+        crate::from_bytes_many(&concatenated, super::Batch::Concatenated).unwrap();
+    assert_eq!(decoded, records);
+
+    let array = super::to_bytes_many(&records, super::Batch::Array).unwrap();
+    assert_eq!(array, to_bytes(&records).unwrap());
+    let decoded: Vec<Record> = crate::from_bytes_many(&array, super::Batch::Array).unwrap();
+    assert_eq!(decoded, records);
+
+    // An array is a single value, not a batch of arrays
+    assert!(crate::from_bytes_many::<Record>(&array, super::Batch::Concatenated).is_err());
+    assert!(crate::from_bytes_many::<Record>(&concatenated, super::Batch::Array).is_err());
+}
+
+#[test]
+fn test_io_error() {
+    use super::SerializeError;
+    use std::io::{Error, ErrorKind};
+
+    // Failed writes stay IO errors, also when boxed on the way
+    let is_write_zero = |err: SerializeError| match err {
+        SerializeError::IoError(err) => err.kind() == ErrorKind::WriteZero,
+        _ => false,
+    };
+    let err = crate::FerionError::from(Error::new(ErrorKind::WriteZero, "full"));
+    assert!(is_write_zero(err.into()));
+    let err = crate::FerionError::from(Error::new(ErrorKind::WriteZero, "full"));
+    let boxed: Box<dyn std::error::Error> = err.into();
+    assert!(is_write_zero(boxed.into()));
+}
@@ -0,0 +1,234 @@
+//! Shared pointers that may form cycles
+//!
+//! serde writes an `Rc` or `Arc` as the value behind it, so a graph that
+//! points back at itself through a `RefCell` or `Mutex` recurses until the
+//! stack overflows. Use `#[serde(with = "ferion::shared")]` on such a field,
+//! or `ferion::shared::option` for an `Option` of one, and serializing fails
+//! with [`CyclicReference`](crate::SerializeError::CyclicReference) when the
+//! pointer is reached again while its own value is still being written.
+//!
+//! A pointer reached twice along different paths is not a cycle, the value is
+//! written both times. Decoding builds a fresh pointer for every value.
+//!
+//! Pointers are tracked per thread, so this works with any serde format. The
+//! adapters need the `cycles` feature.
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{from_bytes, to_bytes, SerializeError};
+
+    #[derive(Serialize, Deserialize)]
+    struct Node {
+        name: String,
+        #[serde(with = "super::option")]
+        next: Option<Rc<RefCell<Node>>>,
+    }
+
+    fn node(name: &str, next: Option<Rc<RefCell<Node>>>) -> Rc<RefCell<Node>> {
+        Rc::new(RefCell::new(Node {
+            name: name.to_string(),
+            next,
+        }))
+    }
+
+    #[test]
+    fn test_cyclic_reference() {
+        let last = node("c", None);
+        let first = node("a", Some(node("b", Some(last.clone()))));
+        let bytes = to_bytes(&*first.borrow()).unwrap();
+        let decoded: Node = from_bytes(&bytes).unwrap();
+        let second = decoded.next.unwrap();
+        assert_eq!(second.borrow().name, "b");
+        assert!(second
+            .borrow()
+            .next
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .next
+            .is_none());
+
+        last.borrow_mut().next = Some(first.clone());
+        let error = to_bytes(&*first.borrow()).unwrap_err();
+        assert!(matches!(error, SerializeError::CyclicReference));
+        let error = crate::encoded_size(&*first.borrow()).unwrap_err();
+        assert!(matches!(error, SerializeError::CyclicReference));
+        let error = crate::to_slice(&*first.borrow(), &mut [0; 64]).unwrap_err();
+        assert!(matches!(error, SerializeError::CyclicReference));
+        // A message that happens to match is still a custom error
+        let custom = <SerializeError as serde::ser::Error>::custom("cyclic reference");
+        assert!(matches!(custom, SerializeError::Custom(_)));
+        assert!(serde_json::to_vec(&*first.borrow()).is_err());
+        // Nothing is left tracked after the error
+        last.borrow_mut().next = None;
+        assert!(to_bytes(&*first.borrow()).is_ok());
+    }
+
+    #[test]
+    fn test_shared_twice() {
+        #[derive(Serialize, Deserialize)]
+        struct Pair {
+            #[serde(with = "super")]
+            left: Arc<Mutex<u32>>,
+            #[serde(with = "super")]
+            right: Arc<Mutex<u32>>,
+        }
+        let value = Arc::new(Mutex::new(7));
+        let pair = Pair {
+            left: value.clone(),
+            right: value,
+        };
+        let decoded: Pair = from_bytes(&to_bytes(&pair).unwrap()).unwrap();
+        assert_eq!(*decoded.left.lock().unwrap(), 7);
+        // Decoding doesn't restore the sharing
+        assert!(!Arc::ptr_eq(&decoded.left, &decoded.right));
+    }
+}
+
+/// Name of the unit struct the adapters write when a pointer is reached again,
+/// the RION serializers fail on it with
+/// [`SerializeError::CyclicReference`](crate::SerializeError::CyclicReference)
+pub(crate) const TOKEN: &str = "$ferion::CyclicReference";
+
+/// A shared pointer the adapters can write
+pub trait Shared {
+    type Target;
+
+    fn new(value: Self::Target) -> Self;
+
+    /// The address of the value, the same for every clone of the pointer
+    fn address(&self) -> *const ();
+
+    /// Call `f` with the value, locking it if needed
+    fn with<R>(&self, f: impl FnOnce(&Self::Target) -> R) -> R;
+}
+
+impl<T> Shared for Rc<RefCell<T>> {
+    type Target = T;
+
+    fn new(value: T) -> Self {
+        Rc::new(RefCell::new(value))
+    }
+
+    fn address(&self) -> *const () {
+        Rc::as_ptr(self).cast()
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.borrow())
+    }
+}
+
+// A poisoned lock still holds a value worth writing
+impl<T> Shared for Arc<Mutex<T>> {
+    type Target = T;
+
+    fn new(value: T) -> Self {
+        Arc::new(Mutex::new(value))
+    }
+
+    fn address(&self) -> *const () {
+        Arc::as_ptr(self).cast()
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl<T> Shared for Arc<RwLock<T>> {
+    type Target = T;
+
+    fn new(value: T) -> Self {
+        Arc::new(RwLock::new(value))
+    }
+
+    fn address(&self) -> *const () {
+        Arc::as_ptr(self).cast()
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.read().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+thread_local! {
+    // Pointers whose values are being written on this thread, outermost first
+    static ACTIVE: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+// Pops the pointer once its value is written, also when writing fails
+struct Active;
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        ACTIVE.with_borrow_mut(|active| active.pop());
+    }
+}
+
+pub fn serialize<P, S>(value: &P, serializer: S) -> Result<S::Ok, S::Error>
+where
+    P: Shared<Target: Serialize>,
+    S: Serializer,
+{
+    let address = value.address();
+    if ACTIVE.with_borrow(|active| active.contains(&address)) {
+        // Other formats write the unit struct, the error below still stops them
+        serializer.serialize_unit_struct(TOKEN)?;
+        return Err(ser::Error::custom(
+            "cyclic reference: a shared value contains itself",
+        ));
+    }
+    ACTIVE.with_borrow_mut(|active| active.push(address));
+    let _active = Active;
+    value.with(|value| value.serialize(serializer))
+}
+
+pub fn deserialize<'de, P, D>(deserializer: D) -> Result<P, D::Error>
+where
+    P: Shared<Target: Deserialize<'de>>,
+    D: Deserializer<'de>,
+{
+    P::Target::deserialize(deserializer).map(P::new)
+}
+
+/// The adapter for an `Option` of a shared pointer
+pub mod option {
+    use super::*;
+
+    pub fn serialize<P, S>(value: &Option<P>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        P: Shared<Target: Serialize>,
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serializer.serialize_some(&Tracked(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, P, D>(deserializer: D) -> Result<Option<P>, D::Error>
+    where
+        P: Shared<Target: Deserialize<'de>>,
+        D: Deserializer<'de>,
+    {
+        Option::<P::Target>::deserialize(deserializer).map(|value| value.map(P::new))
+    }
+
+    struct Tracked<'a, P>(&'a P);
+
+    impl<P: Shared<Target: Serialize>> Serialize for Tracked<'_, P> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::serialize(self.0, serializer)
+        }
+    }
+}
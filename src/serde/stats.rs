@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+/// Counters collected over a single top-level encode or decode call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of fields encoded or decoded, including containers
+    pub fields: usize,
+    /// Number of Array and Object fields
+    pub containers: usize,
+    /// Number of elements and key/value pairs inside containers
+    pub entries: usize,
+    /// Bytes produced (encode) or consumed (decode)
+    pub bytes: usize,
+    /// Wall time spent in the top-level call
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    // Fold the counters of a nested (de)serializer into this one
+    pub(crate) fn merge(&mut self, other: Stats) {
+        self.fields += other.fields;
+        self.containers += other.containers;
+        self.entries += other.entries;
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn trace(&self, op: &'static str) {
+        tracing::debug!(
+            target: "ferion",
+            op,
+            fields = self.fields,
+            containers = self.containers,
+            entries = self.entries,
+            bytes = self.bytes,
+            elapsed_us = self.elapsed.as_micros() as u64,
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Helpers for exchanging RION messages over WebSockets
+//!
+//! Values travel as binary messages. [`encode_message`] and [`decode_message`]
+//! work on tungstenite's [`Message`], which tokio-tungstenite re-exports, so
+//! they fit both the blocking and the async clients. [`send`] and [`read`] do
+//! the whole exchange on a blocking [`WebSocket`].
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{Read, Write},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tungstenite::{protocol::CloseFrame, Message, WebSocket};
+
+use super::{from_bytes, to_bytes, DeserializeError, SerializeError};
+
+#[cfg(test)]
+mod test {
+    use std::net::{TcpListener, TcpStream};
+
+    use tungstenite::protocol::Role;
+
+    use super::*;
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = encode_message(&("a", 1u8)).unwrap();
+        assert!(message.is_binary());
+        let value: Option<(String, u8)> = decode_message(&message, DEFAULT_MESSAGE_LIMIT).unwrap();
+        assert_eq!(value, Some(("a".to_string(), 1)));
+
+        let ping = Message::Ping(vec![1].into());
+        assert!(decode_message::<u8>(&ping, DEFAULT_MESSAGE_LIMIT)
+            .unwrap()
+            .is_none());
+        let text = Message::text("hi");
+        assert!(matches!(
+            decode_message::<u8>(&text, DEFAULT_MESSAGE_LIMIT),
+            Err(WebSocketError::Text(text)) if text == "hi"
+        ));
+        assert!(matches!(
+            decode_message::<u8>(&Message::Close(None), DEFAULT_MESSAGE_LIMIT),
+            Err(WebSocketError::Closed(None))
+        ));
+    }
+
+    #[test]
+    fn test_message_limit() {
+        let message = encode_message(&"a long string").unwrap();
+        let len = message.len();
+        let result = decode_message::<String>(&message, len - 1);
+        assert!(matches!(result, Err(WebSocketError::TooLarge(limit)) if limit == len - 1));
+        assert!(decode_message::<String>(&message, len).is_ok());
+    }
+
+    #[test]
+    fn test_socket_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let mut client = WebSocket::from_raw_socket(client, Role::Client, None);
+        let mut server = WebSocket::from_raw_socket(server, Role::Server, None);
+
+        client.send(Message::Ping(vec![].into())).unwrap();
+        send(&mut client, &vec![1u32, 2, 3]).unwrap();
+        let received: Vec<u32> = read(&mut server, DEFAULT_MESSAGE_LIMIT).unwrap();
+        assert_eq!(received, [1, 2, 3]);
+
+        server.close(None).unwrap();
+        server.flush().unwrap();
+        let result = read::<_, Vec<u32>>(&mut client, DEFAULT_MESSAGE_LIMIT);
+        assert!(matches!(result, Err(WebSocketError::Closed(None))));
+    }
+}
+
+/// Largest message [`read`] accepts unless told otherwise, 16 MiB
+pub const DEFAULT_MESSAGE_LIMIT: usize = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum WebSocketError {
+    /// The message was longer than the limit, in bytes
+    TooLarge(usize),
+    /// A text message arrived where a binary one was expected, holds its text
+    Text(String),
+    /// The peer closed the connection, with its reason if it gave one
+    Closed(Option<CloseFrame>),
+    Serialize(SerializeError),
+    Deserialize(DeserializeError),
+    Transport(Box<tungstenite::Error>),
+}
+
+impl Display for WebSocketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebSocketError::TooLarge(limit) => {
+                write!(f, "message is larger than the {limit} byte limit")
+            }
+            WebSocketError::Text(_) => write!(f, "expected a binary message, got text"),
+            WebSocketError::Closed(Some(frame)) => write!(f, "connection closed: {frame}"),
+            WebSocketError::Closed(None) => write!(f, "connection closed"),
+            WebSocketError::Serialize(err) => write!(f, "failed to encode message: {err}"),
+            WebSocketError::Deserialize(err) => write!(f, "failed to decode message: {err}"),
+            WebSocketError::Transport(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for WebSocketError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WebSocketError::Serialize(err) => Some(err),
+            WebSocketError::Deserialize(err) => Some(err),
+            WebSocketError::Transport(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SerializeError> for WebSocketError {
+    fn from(err: SerializeError) -> Self {
+        WebSocketError::Serialize(err)
+    }
+}
+
+impl From<DeserializeError> for WebSocketError {
+    fn from(err: DeserializeError) -> Self {
+        WebSocketError::Deserialize(err)
+    }
+}
+
+impl From<tungstenite::Error> for WebSocketError {
+    fn from(err: tungstenite::Error) -> Self {
+        match err {
+            tungstenite::Error::ConnectionClosed => WebSocketError::Closed(None),
+            err => WebSocketError::Transport(Box::new(err)),
+        }
+    }
+}
+
+/// `value` as a binary message
+pub fn encode_message<T: Serialize>(value: &T) -> Result<Message, WebSocketError> {
+    Ok(Message::binary(to_bytes(value)?))
+}
+
+/// Decode a binary message of at most `limit` bytes
+///
+/// Pings, pongs and raw frames carry no value and give `None`, close and text
+/// messages are errors.
+pub fn decode_message<T: DeserializeOwned>(
+    message: &Message,
+    limit: usize,
+) -> Result<Option<T>, WebSocketError> {
+    match message {
+        Message::Binary(data) if data.len() > limit => Err(WebSocketError::TooLarge(limit)),
+        Message::Binary(data) => Ok(Some(from_bytes(data)?)),
+        Message::Text(text) => Err(WebSocketError::Text(text.to_string())),
+        Message::Close(frame) => Err(WebSocketError::Closed(frame.clone())),
+        Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => Ok(None),
+    }
+}
+
+/// Send `value` as a binary message and flush it
+pub fn send<S: Read + Write, T: Serialize>(
+    socket: &mut WebSocket<S>,
+    value: &T,
+) -> Result<(), WebSocketError> {
+    socket.send(encode_message(value)?)?;
+    Ok(())
+}
+
+/// Wait for the next binary message and decode at most `limit` bytes of it
+///
+/// Pings are answered by the socket while waiting.
+pub fn read<S: Read + Write, T: DeserializeOwned>(
+    socket: &mut WebSocket<S>,
+    limit: usize,
+) -> Result<T, WebSocketError> {
+    loop {
+        if let Some(value) = decode_message(&socket.read()?, limit)? {
+            return Ok(value);
+        }
+    }
+}
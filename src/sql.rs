@@ -0,0 +1,280 @@
+//! Storing RION documents in SQL databases
+//!
+//! [`RionValue`] and [`RionObject`] are written to and read from BLOB columns
+//! with rusqlite (`rusqlite` feature) or sqlx (`sqlx` feature). The
+//! `from_*_row` helpers turn a whole query row into an object keyed by column
+//! name, with SQL values mapped to the matching RION field.
+
+#[cfg(feature = "rusqlite")]
+pub use self::rusqlite_impl::from_rusqlite_row;
+#[cfg(feature = "sqlx")]
+pub use self::sqlx_impl::from_sqlx_row;
+
+use std::borrow::Cow;
+
+use crate::{types::LeadByte, RionField, RionObject};
+
+// A row of named SQL values as an object, NULLs become null fields
+fn row_object<'a>(
+    columns: impl IntoIterator<Item = (&'a str, Option<RionField<'static>>)>,
+) -> RionObject<'static> {
+    let mut object = RionObject::new();
+    for (name, field) in columns {
+        let field = field.unwrap_or(RionField::Tiny(LeadByte::NULL));
+        object
+            .fields
+            .insert(Cow::Owned(name.as_bytes().to_vec()), field);
+    }
+    object.recompute_len();
+    object
+}
+
+#[cfg(feature = "rusqlite")]
+mod rusqlite_impl {
+    use rusqlite::{
+        types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
+        Row, ToSql,
+    };
+
+    use super::*;
+    use crate::RionValue;
+
+    fn to_sql(encoded: crate::Result<Vec<u8>>) -> rusqlite::Result<ToSqlOutput<'static>> {
+        let encoded =
+            encoded.map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.to_string().into()))?;
+        Ok(ToSqlOutput::from(encoded))
+    }
+
+    impl ToSql for RionValue {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            to_sql(self.encode_to_vec())
+        }
+    }
+
+    impl FromSql for RionValue {
+        fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+            RionValue::from_slice(value.as_blob()?)
+                .map_err(|e| FromSqlError::Other(e.to_string().into()))
+        }
+    }
+
+    impl ToSql for RionObject<'_> {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            to_sql(Ok(self.encode()))
+        }
+    }
+
+    impl FromSql for RionObject<'static> {
+        fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+            RionObject::from_slice(value.as_blob()?)
+                .map(RionObject::into_owned)
+                .map_err(|e| FromSqlError::Other(e.to_string().into()))
+        }
+    }
+
+    /// Map every column of a query row to a field, for use with
+    /// [`query_map`](rusqlite::Statement::query_map)
+    pub fn from_rusqlite_row(row: &Row) -> rusqlite::Result<RionObject<'static>> {
+        let statement = row.as_ref();
+        let mut columns = Vec::with_capacity(statement.column_count());
+        for i in 0..statement.column_count() {
+            let field = match row.get_ref(i)? {
+                ValueRef::Null => None,
+                ValueRef::Integer(n) => Some(RionField::from(n)),
+                ValueRef::Real(n) => Some(RionField::from(n)),
+                ValueRef::Text(text) => {
+                    let text = std::str::from_utf8(text).map_err(rusqlite::Error::Utf8Error)?;
+                    Some(RionField::from(text).into_owned())
+                }
+                ValueRef::Blob(bytes) => Some(RionField::bytes(bytes).into_owned()),
+            };
+            columns.push((statement.column_name(i)?, field));
+        }
+        Ok(row_object(columns))
+    }
+}
+
+#[cfg(feature = "sqlx")]
+mod sqlx_impl {
+    use sqlx::{
+        encode::IsNull,
+        error::BoxDynError,
+        sqlite::{SqliteRow, SqliteValueRef},
+        Column, Database, Decode, Encode, Row, Type, TypeInfo, ValueRef,
+    };
+
+    use super::*;
+    use crate::RionValue;
+
+    // Both types are stored as the BLOB encoding of the sqlx byte types, so
+    // they work with every database those support
+    impl<DB: Database> Type<DB> for RionValue
+    where
+        Vec<u8>: Type<DB>,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <Vec<u8> as Type<DB>>::type_info()
+        }
+
+        fn compatible(ty: &DB::TypeInfo) -> bool {
+            <Vec<u8> as Type<DB>>::compatible(ty)
+        }
+    }
+
+    impl<'q, DB: Database> Encode<'q, DB> for RionValue
+    where
+        Vec<u8>: Encode<'q, DB>,
+    {
+        fn encode_by_ref(
+            &self,
+            buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+        ) -> Result<IsNull, BoxDynError> {
+            let encoded = self.encode_to_vec().map_err(|e| e.to_string())?;
+            encoded.encode(buf)
+        }
+    }
+
+    impl<'r, DB: Database> Decode<'r, DB> for RionValue
+    where
+        &'r [u8]: Decode<'r, DB>,
+    {
+        fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+            Ok(RionValue::from_slice(<&[u8]>::decode(value)?).map_err(|e| e.to_string())?)
+        }
+    }
+
+    impl<DB: Database> Type<DB> for RionObject<'_>
+    where
+        Vec<u8>: Type<DB>,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <Vec<u8> as Type<DB>>::type_info()
+        }
+
+        fn compatible(ty: &DB::TypeInfo) -> bool {
+            <Vec<u8> as Type<DB>>::compatible(ty)
+        }
+    }
+
+    impl<'q, DB: Database> Encode<'q, DB> for RionObject<'_>
+    where
+        Vec<u8>: Encode<'q, DB>,
+    {
+        fn encode_by_ref(
+            &self,
+            buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+        ) -> Result<IsNull, BoxDynError> {
+            self.encode().encode(buf)
+        }
+    }
+
+    impl<'r, DB: Database> Decode<'r, DB> for RionObject<'static>
+    where
+        &'r [u8]: Decode<'r, DB>,
+    {
+        fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+            let object =
+                RionObject::from_slice(<&[u8]>::decode(value)?).map_err(|e| e.to_string())?;
+            Ok(object.into_owned())
+        }
+    }
+
+    /// Map every column of a SQLite row to a field
+    pub fn from_sqlx_row(row: &SqliteRow) -> Result<RionObject<'static>, sqlx::Error> {
+        let mut columns = Vec::with_capacity(row.len());
+        for column in row.columns() {
+            let value = row.try_get_raw(column.ordinal())?;
+            let field = sqlite_field(value).map_err(|source| sqlx::Error::ColumnDecode {
+                index: column.name().to_string(),
+                source,
+            })?;
+            columns.push((column.name(), field));
+        }
+        Ok(row_object(columns))
+    }
+
+    fn sqlite_field(value: SqliteValueRef) -> Result<Option<RionField<'static>>, BoxDynError> {
+        if value.is_null() {
+            return Ok(None);
+        }
+        let type_name = value.type_info().name().to_string();
+        let field = match type_name.as_str() {
+            "INTEGER" => RionField::from(<i64 as Decode<sqlx::Sqlite>>::decode(value)?),
+            "REAL" => RionField::from(<f64 as Decode<sqlx::Sqlite>>::decode(value)?),
+            "TEXT" => RionField::from(<&str as Decode<sqlx::Sqlite>>::decode(value)?).into_owned(),
+            _ => RionField::bytes(<&[u8] as Decode<sqlx::Sqlite>>::decode(value)?).into_owned(),
+        };
+        Ok(Some(field))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RionValue;
+
+    #[cfg(feature = "rusqlite")]
+    #[test]
+    fn test_blob_round_trip() {
+        let db = rusqlite::Connection::open_in_memory().unwrap();
+        db.execute("CREATE TABLE docs (id INTEGER, doc BLOB, meta BLOB)", [])
+            .unwrap();
+        let value = RionValue::Array(vec!["a".into(), RionValue::PosInt(1)]);
+        let mut meta = RionObject::new();
+        meta.add_field("source", "test");
+        db.execute(
+            "INSERT INTO docs VALUES (1, ?1, ?2)",
+            rusqlite::params![value, meta],
+        )
+        .unwrap();
+
+        let (doc, stored): (RionValue, RionObject) = db
+            .query_row("SELECT doc, meta FROM docs", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(doc, value);
+        assert_eq!(stored, meta);
+
+        let bad = db.query_row("SELECT id FROM docs", [], |row| row.get::<_, RionValue>(0));
+        assert!(bad.is_err());
+    }
+
+    #[cfg(feature = "rusqlite")]
+    #[test]
+    fn test_row_to_object() {
+        let db = rusqlite::Connection::open_in_memory().unwrap();
+        let object = db
+            .query_row(
+                "SELECT 1 AS id, 'Alice' AS name, 2.5 AS score, NULL AS nick, x'0102' AS raw",
+                [],
+                from_rusqlite_row,
+            )
+            .unwrap();
+        assert_eq!(object.get("id"), Some(&RionField::from(1i64)));
+        assert_eq!(object.get("name"), Some(&RionField::from("Alice")));
+        assert_eq!(object.get("score"), Some(&RionField::from(2.5f64)));
+        assert!(object.get("nick").unwrap().is_null());
+        assert_eq!(object.get("raw"), Some(&RionField::bytes(&[1, 2])));
+    }
+
+    #[cfg(feature = "sqlx")]
+    #[test]
+    fn test_sqlx_round_trip() {
+        use sqlx::{Connection, Row, SqliteConnection};
+
+        futures_executor::block_on(async {
+            let mut db = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+            let value = RionValue::Array(vec!["a".into(), RionValue::PosInt(1)]);
+            let row = sqlx::query("SELECT ?1 AS doc, 'Alice' AS name, NULL AS nick")
+                .bind(&value)
+                .fetch_one(&mut db)
+                .await
+                .unwrap();
+            assert_eq!(row.get::<RionValue, _>("doc"), value);
+            let object = from_sqlx_row(&row).unwrap();
+            assert_eq!(object.get("name"), Some(&RionField::from("Alice")));
+            assert!(object.get("nick").unwrap().is_null());
+        });
+    }
+}
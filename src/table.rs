@@ -1,8 +1,16 @@
+//! RION tables, and the `#[serde(with = "ferion::table")]` adapter writing a
+//! field of rows as one
+
 use std::borrow::Cow;
 
+#[cfg(feature = "serde")]
+pub use crate::serde::rows::{deserialize, serialize};
 use crate::{
-    types::{NormalRionType, RionFieldType},
-    Result, RionField,
+    error::Result,
+    field::NormalField,
+    needed_bytes_usize,
+    types::{NormalRionType, RionFieldType, ShortRionType},
+    FerionError, FromRawField, RionArray, RionField, RionKey, RionObject,
 };
 
 #[cfg(test)]
@@ -25,6 +33,150 @@ mod test {
         data
     }
 
+    #[test]
+    fn test_table_encode_round_trip() {
+        let data = create_test_table_data();
+        let table = RionTable::from_slice(&data).unwrap();
+        assert_eq!(table.row_count(), 2);
+        let encoded = table.encode();
+        assert_eq!(RionTable::from_slice(&encoded).unwrap(), table);
+    }
+
+    #[test]
+    fn test_table_append_rows() {
+        let original = create_test_table_data();
+        let mut table = RionTable::from_slice(&original).unwrap();
+        let mut data = table.encode();
+        let cells = [RionField::from(3u64), RionField::from("C")];
+        RionTable::append_rows(&mut data, &cells).unwrap();
+        table.rows.extend(cells.iter().cloned());
+        assert_eq!(RionTable::from_slice(&data).unwrap(), table);
+        assert_eq!(data, table.encode());
+
+        // Past 255 bytes the length needs a second byte
+        let cells: Vec<RionField> = (0..100u64)
+            .flat_map(|i| [RionField::from(i), RionField::from("row")])
+            .collect();
+        RionTable::append_rows(&mut data, &cells).unwrap();
+        table.rows.extend(cells);
+        assert_eq!(data[0], 0xB2);
+        assert_eq!(RionTable::from_slice(&data).unwrap(), table);
+        assert_eq!(data, table.encode());
+
+        assert!(RionTable::append_rows(&mut data, &[RionField::from(1u64)]).is_err());
+        assert!(RionTable::append_rows(&mut vec![0x12], &[]).is_err());
+    }
+
+    #[test]
+    fn test_table_column_as() {
+        let data = create_test_table_data();
+        let mut table = RionTable::from_slice(&data).unwrap();
+        assert_eq!(table.column_as::<i64>("id").unwrap(), [1, 2]);
+        assert_eq!(table.column_as::<String>("name").unwrap(), ["A", "B"]);
+        assert_eq!(table.column_as::<&str>("name").unwrap(), ["A", "B"]);
+        let ids = table.column_iter_as::<u64>("id").unwrap();
+        assert_eq!(ids.map(Result::unwrap).collect::<Vec<_>>(), [1, 2]);
+        assert!(table.column_as::<f64>("missing").is_err());
+
+        table.rows[3] = RionField::from(1.5);
+        let error = table.column_as::<String>("name").unwrap_err();
+        assert!(error.to_string().contains("row 1"), "{error}");
+    }
+
+    #[test]
+    fn test_table_column_major() {
+        let data = create_test_table_data();
+        let table = RionTable::from_slice(&data).unwrap();
+        let encoded = table.encode_with_layout(TableLayout::ColumnMajor);
+        assert_eq!(
+            &encoded[data.len() - 8..],
+            &[0x21, 0x01, 0x21, 0x02, 0x61, b'A', 0x61, b'B']
+        );
+        let decoded =
+            RionTable::from_slice_with_layout(&encoded, TableLayout::ColumnMajor).unwrap();
+        assert_eq!(decoded, table);
+    }
+
+    #[test]
+    fn test_table_dictionary() {
+        let statuses = ["open", "closed", "open", "pending", "open", "closed"];
+        let value = crate::RionValue::Table {
+            columns: vec![b"id"[..].into(), b"status"[..].into()],
+            rows: statuses
+                .iter()
+                .cycle()
+                .take(60)
+                .enumerate()
+                .map(|(i, status)| vec![(i as u64).into(), (*status).into()])
+                .chain([vec![9u64.into(), crate::RionValue::Null]])
+                .collect(),
+        };
+        let data = value.encode_to_vec().unwrap();
+        let table = RionTable::from_slice(&data).unwrap();
+
+        let encoded = table.encode_with_dictionary(TableLayout::RowMajor, 8);
+        assert!(encoded.len() < data.len());
+        assert_eq!(RionTable::from_slice(&encoded).unwrap(), table);
+        assert_eq!(crate::RionValue::from_slice(&encoded).unwrap(), value);
+
+        let encoded = table.encode_with_dictionary(TableLayout::ColumnMajor, 8);
+        let decoded =
+            RionTable::from_slice_with_layout(&encoded, TableLayout::ColumnMajor).unwrap();
+        assert_eq!(decoded, table);
+
+        // Too many different values, or too few rows to be worth it
+        assert_eq!(table.encode_with_dictionary(TableLayout::RowMajor, 2), data);
+        let data = create_test_table_data();
+        let table = RionTable::from_slice(&data).unwrap();
+        assert_eq!(
+            table.encode_with_dictionary(TableLayout::RowMajor, 8),
+            table.encode()
+        );
+    }
+
+    #[test]
+    fn test_table_to_objects() {
+        let data = create_test_table_data();
+        let table = RionTable::from_slice(&data).unwrap();
+        let objects = table.to_objects();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[1].get("id"), Some(&RionField::from(2u64)));
+        assert_eq!(objects[1].get("name"), Some(&RionField::from("B")));
+        assert_eq!(objects[0].encoded_len(), objects[0].encode().len());
+        assert_eq!(table.objects().count(), 2);
+        assert_eq!(table.row(1).unwrap(), &table.rows[2..]);
+        assert!(table.row(2).is_none());
+    }
+
+    #[test]
+    fn test_table_from_objects() {
+        let data = create_test_table_data();
+        let table = RionTable::from_slice(&data).unwrap();
+        let objects = table.to_objects();
+        assert_eq!(RionTable::from_objects(&objects).unwrap(), table);
+
+        let mut array = RionArray::new();
+        array.extend(objects.iter().cloned());
+        assert_eq!(RionTable::from_array(&array).unwrap(), table);
+
+        let mut odd = RionObject::new();
+        odd.add_field("id", 3u64);
+        odd.add_field("email", "c@example.com");
+        let mut objects = objects;
+        objects.push(odd);
+        let err = RionTable::from_objects(&objects).unwrap_err();
+        assert_eq!(err.row, 2);
+        assert_eq!(err.missing, [b"name".to_vec()]);
+        assert_eq!(err.unexpected, [b"email".to_vec()]);
+        assert_eq!(
+            err.to_string(),
+            "Object 2 has different keys than the first, missing [name], unexpected [email]"
+        );
+
+        array.add_element(1u64);
+        assert!(RionTable::from_array(&array).is_err());
+    }
+
     #[test]
     fn test_table_from_slice() {
         let data = create_test_table_data();
@@ -53,8 +205,7 @@ mod test {
         data.extend_from_slice(&[0x00, 0x00]); // Add extra data
 
         let result = RionTable::from_slice(&data);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().to_string(), "Extra data after table");
+        assert!(matches!(result, Err(FerionError::TrailingData(2))));
     }
 
     #[test]
@@ -63,11 +214,13 @@ mod test {
         data[0] = 0xA0; // Change lead byte to Array instead of Table
 
         let result = RionTable::parse(&data);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err().to_string(),
-            "Expected a RION table, found Normal(Array)"
-        );
+        assert!(matches!(
+            result,
+            Err(FerionError::UnexpectedType {
+                found: RionFieldType::Normal(NormalRionType::Array),
+                ..
+            })
+        ));
     }
 
     #[test]
@@ -76,11 +229,13 @@ mod test {
         data[2] = 0x50; // Change row count to UTF8 instead of Int64Positive
 
         let result = RionTable::parse(&data);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err().to_string(),
-            "Expected a short field, found Normal(NormalField { field_type: UTF8, data: [] })"
-        );
+        assert!(matches!(
+            result,
+            Err(FerionError::UnexpectedType {
+                found: RionFieldType::Normal(NormalRionType::UTF8),
+                ..
+            })
+        ));
     }
 
     #[test]
@@ -130,6 +285,30 @@ mod test {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_table_malformed_errors() {
+        let mut data = create_test_table_data();
+        data[3] = 100;
+        assert!(matches!(
+            RionTable::parse(&data),
+            Err(FerionError::RowCount(100))
+        ));
+        // Rows without column names before them
+        assert!(matches!(
+            RionTable::parse(&[0xB1, 0x02, 0x21, 0x02]),
+            Err(FerionError::Truncated(1))
+        ));
+
+        let mut data = RionTable::from_slice(&create_test_table_data())
+            .unwrap()
+            .encode();
+        data.push(0x21);
+        assert!(matches!(
+            RionTable::append_rows(&mut data, &[]),
+            Err(FerionError::TrailingData(1))
+        ));
+    }
+
     #[test]
     fn test_table_column_data_types() {
         let data = vec![
@@ -191,7 +370,21 @@ mod test {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The order the cells of a table are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableLayout {
+    /// All cells of the first row, then the second row, and so on
+    #[default]
+    RowMajor,
+    /// All cells of the first column, then the second column, and so on
+    ///
+    /// Keeps values of the same type together, which compresses far better for
+    /// numeric data. Nothing in the encoding marks the layout, so the reader has
+    /// to decode with the same layout it was written with.
+    ColumnMajor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct RionTable<'a> {
     pub column_names: Vec<Cow<'a, [u8]>>,
     pub rows: Vec<RionField<'a>>, // TODO Make better type
@@ -201,55 +394,425 @@ impl<'a> RionTable<'a> {
     pub fn from_slice(data: &'a [u8]) -> Result<Self> {
         let (table, rest) = Self::parse(data)?;
         if !rest.is_empty() {
-            return Err("Extra data after table".into());
+            return Err(FerionError::TrailingData(rest.len()));
         }
         Ok(table)
     }
 
-    fn parse(data: &'a [u8]) -> Result<(Self, &[u8])> {
-        if data.is_empty() {
-            return Err("Data is empty".into());
+    /// Decode a table whose cells were written in `layout` order, the cells
+    /// are always returned row-major
+    pub fn from_slice_with_layout(data: &'a [u8], layout: TableLayout) -> Result<Self> {
+        let (mut table, dictionaries, rest) = Self::parse_raw(data)?;
+        if !rest.is_empty() {
+            return Err(FerionError::TrailingData(rest.len()));
+        }
+        if layout == TableLayout::ColumnMajor {
+            let rows = table.row_count();
+            table.rows = transpose(table.rows, table.column_names.len(), rows);
+        }
+        table.expand(dictionaries)?;
+        Ok(table)
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows
+            .len()
+            .checked_div(self.column_names.len())
+            .unwrap_or(0)
+    }
+
+    /// The cells of row `index`, in column order
+    pub fn row(&self, index: usize) -> Option<&[RionField<'a>]> {
+        let columns = self.column_names.len();
+        if index >= self.row_count() {
+            return None;
+        }
+        Some(&self.rows[index * columns..(index + 1) * columns])
+    }
+
+    /// The cells of column `name` read as `T`, from the first row to the last
+    ///
+    /// `T` is anything [`FromRawField`] reads, e.g. `i64`, `f64`, `String` or
+    /// [`RionDateTime`](crate::RionDateTime). Fails if there is no such column
+    /// or a cell can't be read as a `T`, nulls included.
+    pub fn column_as<T: FromRawField<'a>>(&self, name: &str) -> Result<Vec<T>> {
+        self.column_iter_as(name)?.collect()
+    }
+
+    /// Like [`column_as`](Self::column_as), reading each cell when it's reached
+    pub fn column_iter_as<T: FromRawField<'a>>(
+        &self,
+        name: &str,
+    ) -> Result<impl Iterator<Item = Result<T>> + use<'_, 'a, T>> {
+        let Some(index) = self
+            .column_names
+            .iter()
+            .position(|column| **column == *name.as_bytes())
+        else {
+            return Err(FerionError::invalid(format!("No column named {name:?}")));
+        };
+        let name = name.to_string();
+        let cells = self.rows.iter().skip(index);
+        Ok(cells
+            .step_by(self.column_names.len())
+            .enumerate()
+            .map(move |(row, cell)| {
+                T::from_raw_field(cell.clone())
+                    .map_err(|e| FerionError::invalid(format!("Column {name:?} at row {row}: {e}")))
+            }))
+    }
+
+    /// Each row as an object keyed by column name
+    pub fn objects(&self) -> impl Iterator<Item = RionObject<'a>> + '_ {
+        (0..self.row_count()).map(|index| {
+            let mut object = RionObject::new();
+            for (name, cell) in self.column_names.iter().zip(self.row(index).unwrap()) {
+                object.fields.insert(name.clone(), cell.clone());
+            }
+            object.recompute_len();
+            object
+        })
+    }
+
+    pub fn to_objects(&self) -> Vec<RionObject<'a>> {
+        self.objects().collect()
+    }
+
+    /// A table with a column per key and a row per object, columns are sorted
+    /// by name
+    ///
+    /// Every object has to have the same keys as the first, the error names the
+    /// first object that doesn't and the keys it is missing or adds.
+    pub fn from_objects(objects: &[RionObject<'a>]) -> std::result::Result<Self, KeyMismatch> {
+        let Some(first) = objects.first() else {
+            return Ok(RionTable::default());
+        };
+        let mut column_names = first.fields.keys().cloned().collect::<Vec<_>>();
+        column_names.sort_unstable();
+        let mut rows = Vec::with_capacity(objects.len() * column_names.len());
+        for (row, object) in objects.iter().enumerate() {
+            let unexpected = object
+                .keys()
+                .filter(|key| !first.contains_key(key))
+                .map(<[u8]>::to_vec)
+                .collect::<Vec<_>>();
+            let missing = column_names
+                .iter()
+                .filter(|name| !object.contains_key(name))
+                .map(|name| name.to_vec())
+                .collect::<Vec<_>>();
+            if !missing.is_empty() || !unexpected.is_empty() {
+                return Err(KeyMismatch {
+                    row,
+                    missing,
+                    unexpected,
+                });
+            }
+            rows.extend(column_names.iter().map(|name| object.fields[name].clone()));
+        }
+        Ok(RionTable { column_names, rows })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        self.encode_with_layout(TableLayout::RowMajor)
+    }
+
+    /// Like [`from_objects`](Self::from_objects) for an array whose elements
+    /// are all objects, a [`KeyMismatch`] is returned boxed
+    pub fn from_array(array: &RionArray) -> Result<RionTable<'static>> {
+        let mut encoded = Vec::new();
+        let mut objects = Vec::with_capacity(array.len());
+        for (i, element) in array.elements.iter().enumerate() {
+            if !element.is_normal_type(NormalRionType::Object) {
+                return Err(FerionError::invalid(format!(
+                    "Element {i} is not an object: {element:?}"
+                )));
+            }
+            encoded.clear();
+            element.encode(&mut encoded)?;
+            let object = RionObject::from_slice(&encoded)?;
+            let mut owned = RionObject::new();
+            for (key, field) in object.fields {
+                owned
+                    .fields
+                    .insert(Cow::Owned(key.into_owned()), field.into_owned());
+            }
+            objects.push(owned);
+        }
+        Ok(RionTable::from_objects(&objects)?)
+    }
+
+    pub fn encode_with_layout(&self, layout: TableLayout) -> Vec<u8> {
+        let cells = &self.rows[..self.row_count() * self.column_names.len()];
+        self.encode_cells(layout, cells, &[])
+    }
+
+    /// Encode with string columns of at most `max_entries` different values
+    /// stored as a dictionary plus an index per row
+    ///
+    /// The dictionaries are written as an object of column name to array of
+    /// strings right after the row count, [`from_slice`](Self::from_slice) and
+    /// [`RionValue`](crate::RionValue) expand the indices back into strings.
+    /// Columns holding anything but strings and nulls, or that wouldn't repeat
+    /// a value, are written as usual.
+    pub fn encode_with_dictionary(&self, layout: TableLayout, max_entries: usize) -> Vec<u8> {
+        let columns = self.column_names.len();
+        let mut cells = self.rows[..self.row_count() * columns].to_vec();
+        let mut dictionaries = Vec::new();
+        for (i, name) in self.column_names.iter().enumerate() {
+            let column = || cells.iter().skip(i).step_by(columns);
+            let is_string = |cell: &&RionField| {
+                cell.is_short_type(ShortRionType::UTF8) || cell.is_normal_type(NormalRionType::UTF8)
+            };
+            let values = column().filter(|cell| !cell.is_null()).count();
+            if values == 0
+                || !column()
+                    .filter(|cell| !cell.is_null())
+                    .all(|cell| is_string(&cell))
+            {
+                continue;
+            }
+            let mut entries: Vec<RionField> = Vec::new();
+            for cell in column().filter(|cell| !cell.is_null()) {
+                if !entries.contains(cell) {
+                    entries.push(cell.clone());
+                }
+                if entries.len() > max_entries {
+                    break;
+                }
+            }
+            if entries.len() > max_entries || entries.len() == values {
+                continue;
+            }
+            let indices = column()
+                .map(|cell| match cell.is_null() {
+                    true => cell.clone(),
+                    false => {
+                        let index = entries.iter().position(|entry| entry == cell).unwrap();
+                        RionField::from(index as u64)
+                    }
+                })
+                .collect::<Vec<_>>();
+            let entries = RionField::from(entries);
+            // Only worth it if the indices and dictionary are smaller
+            let before = column().map(RionField::needed_bytes).sum::<usize>();
+            let after = indices.iter().map(RionField::needed_bytes).sum::<usize>()
+                + RionField::key(name).needed_bytes()
+                + entries.needed_bytes();
+            if after >= before {
+                continue;
+            }
+            for (cell, index) in cells.iter_mut().skip(i).step_by(columns).zip(indices) {
+                *cell = index;
+            }
+            RionField::key(name).encode(&mut dictionaries).unwrap();
+            entries.encode(&mut dictionaries).unwrap();
+        }
+        let mut encoded_dictionaries = Vec::new();
+        if !dictionaries.is_empty() {
+            RionField::Normal(NormalField {
+                field_type: NormalRionType::Object,
+                data: dictionaries.into(),
+            })
+            .encode(&mut encoded_dictionaries)
+            .unwrap();
+        }
+        self.encode_cells(layout, &cells, &encoded_dictionaries)
+    }
+
+    // `header` is written between the row count and the column names, the
+    // stats and dictionaries if there are any
+    pub(crate) fn encode_cells(
+        &self,
+        layout: TableLayout,
+        cells: &[RionField],
+        header: &[u8],
+    ) -> Vec<u8> {
+        let row_count = self.row_count();
+        let mut content = Vec::new();
+        RionField::from(row_count as u64)
+            .encode(&mut content)
+            .unwrap();
+        content.extend_from_slice(header);
+        for name in &self.column_names {
+            RionField::key(name).encode(&mut content).unwrap();
+        }
+        match layout {
+            TableLayout::RowMajor => {
+                for cell in cells {
+                    cell.encode(&mut content).unwrap();
+                }
+            }
+            TableLayout::ColumnMajor => {
+                for cell in transpose(cells.to_vec(), row_count, self.column_names.len()) {
+                    cell.encode(&mut content).unwrap();
+                }
+            }
+        }
+        let length_length = needed_bytes_usize(content.len()).max(1);
+        let mut encoded = Vec::with_capacity(1 + length_length + content.len());
+        encoded.push((RionFieldType::TABLE << 4) | length_length as u8);
+        encoded.extend_from_slice(&content.len().to_be_bytes()[8 - length_length..]);
+        encoded.extend(content);
+        encoded
+    }
+
+    /// Append rows to a table already encoded in `encoded`, without decoding
+    /// or re-encoding the rows it holds
+    ///
+    /// `cells` are the new rows one after the other. The row count and the
+    /// table length are rewritten in place, growing the header when the new
+    /// length needs more bytes. The table has to be row-major and fill the
+    /// whole buffer, dictionary encoded tables and tables with stats are
+    /// refused.
+    pub fn append_rows(encoded: &mut Vec<u8>, cells: &[RionField]) -> Result<()> {
+        let (lead, length, content) = crate::get_normal_header(encoded)?;
+        let RionFieldType::Normal(NormalRionType::Table) = lead.field_type() else {
+            return Err(FerionError::unexpected("a table", lead.field_type()));
+        };
+        if content.len() > length {
+            return Err(FerionError::TrailingData(content.len() - length));
+        }
+        let header_len = encoded.len() - length;
+        let (count, after_count) = RionField::parse(content)?;
+        let Some(row_count) = count.as_u64() else {
+            return Err(FerionError::unexpected("a row count", count.field_type()));
+        };
+        let count_len = content.len() - after_count.len();
+        if crate::skip_table_stats(after_count)?.len() < after_count.len() {
+            return Err(FerionError::invalid("Can't append to a table with stats"));
+        }
+        if after_count
+            .first()
+            .is_some_and(|&lead| lead >> 4 == RionFieldType::OBJECT && lead & 0x0F != 0)
+        {
+            return Err(FerionError::invalid(
+                "Can't append to a dictionary encoded table",
+            ));
         }
+        let mut columns = 0;
+        let mut rest = after_count;
+        while let Ok((field, next)) = RionField::parse(rest) {
+            if !field.is_key() {
+                break;
+            }
+            columns += 1;
+            rest = next;
+        }
+        if columns == 0 {
+            return Err(FerionError::invalid(
+                "Can't append rows to a table without columns",
+            ));
+        }
+        if !cells.len().is_multiple_of(columns) {
+            return Err(FerionError::invalid(format!(
+                "{} cells don't fill rows of {columns}",
+                cells.len()
+            )));
+        }
+
+        let mut count = Vec::new();
+        RionField::from(row_count + (cells.len() / columns) as u64).encode(&mut count)?;
+        let mut rows = Vec::new();
+        for cell in cells {
+            cell.encode(&mut rows)?;
+        }
+        let new_length = length - count_len + count.len() + rows.len();
+        let length_length = needed_bytes_usize(new_length).max(1);
+        let mut header = Vec::with_capacity(1 + length_length + count.len());
+        header.push((RionFieldType::TABLE << 4) | length_length as u8);
+        header.extend_from_slice(&new_length.to_be_bytes()[8 - length_length..]);
+        header.extend(count);
+        encoded.splice(..header_len + count_len, header);
+        encoded.extend(rows);
+        Ok(())
+    }
+
+    fn parse(data: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        let (mut table, dictionaries, rest) = Self::parse_raw(data)?;
+        table.expand(dictionaries)?;
+        Ok((table, rest))
+    }
+
+    // Replace the indices in dictionary encoded columns with their strings,
+    // the cells have to be in row-major order
+    fn expand(&mut self, dictionaries: Vec<(Cow<'a, [u8]>, Vec<RionField<'a>>)>) -> Result<()> {
+        let columns = self.column_names.len();
+        for (name, entries) in dictionaries {
+            let Some(i) = self.column_names.iter().position(|column| *column == name) else {
+                return Err(FerionError::invalid(format!(
+                    "Dictionary for unknown column {name:x?}"
+                )));
+            };
+            for cell in self.rows.iter_mut().skip(i).step_by(columns) {
+                if cell.is_null() {
+                    continue;
+                }
+                let index = cell
+                    .as_u64()
+                    .and_then(|index| entries.get(index as usize))
+                    .ok_or_else(|| {
+                        FerionError::invalid(format!("Invalid dictionary index {cell:?}"))
+                    })?;
+                *cell = index.clone();
+            }
+        }
+        Ok(())
+    }
+
+    // The table with dictionary encoded columns still holding indices
+    #[allow(clippy::type_complexity)]
+    fn parse_raw(
+        data: &'a [u8],
+    ) -> Result<(Self, Vec<(Cow<'a, [u8]>, Vec<RionField<'a>>)>, &'a [u8])> {
         let (lead, length, rest) = crate::get_normal_header(data)?;
         let RionFieldType::Normal(NormalRionType::Table) = lead.field_type() else {
-            return Err(format!("Expected a RION table, found {:?}", lead.field_type()).into());
+            return Err(FerionError::unexpected("a table", lead.field_type()));
         };
         // First field is Int64Positive = m = number of rows
-        let (field, mut rest) = RionField::parse(rest)?;
-        let RionField::Short(short) = field else {
-            return Err(format!("Expected a short field, found {:?}", field).into());
-        };
-        let Some(m) = short.as_pos_int() else {
-            return Err(format!("Expected a positive integer, found {:?}", short).into());
+        let (field, rest) = RionField::parse(rest)?;
+        let Some(m) = field.as_u64() else {
+            return Err(FerionError::unexpected("a row count", field.field_type()));
         };
+        let mut rest = crate::skip_table_stats(rest)?;
+        let mut dictionaries = Vec::new();
+        if rest
+            .first()
+            .is_some_and(|&lead| lead >> 4 == RionFieldType::OBJECT && lead & 0x0F != 0)
+        {
+            (dictionaries, rest) = parse_dictionaries(rest)?;
+        }
         let mut column_names = Vec::new();
         // Next n Key/KeyShorts = Column names
         let first_object = loop {
-            let Ok((field, new_rest)) = RionField::parse(rest) else {
-                if m != 0 {
-                    return Err("Not enough column names".into());
+            let (field, new_rest) = match RionField::parse(rest) {
+                Ok(parsed) => parsed,
+                // Rows need column names before them
+                Err(err) if m != 0 => return Err(err),
+                Err(_) => {
+                    return Ok((
+                        RionTable {
+                            column_names,
+                            rows: Vec::new(),
+                        },
+                        dictionaries,
+                        rest,
+                    ))
                 }
-                return Ok((
-                    RionTable {
-                        column_names,
-                        rows: Vec::new(),
-                    },
-                    rest,
-                ));
             };
             rest = new_rest;
             if !field.is_key() {
                 break field;
             }
-            column_names.push(field.to_data().unwrap());
+            column_names.push(RionKey::from_field(field)?.into_inner());
         };
-        println!("first_object: {:?}", first_object);
         if column_names.is_empty() || m == 0 {
             return Ok((
                 RionTable {
                     column_names,
                     rows: Vec::new(),
                 },
+                dictionaries,
                 rest,
             ));
         }
@@ -257,11 +820,7 @@ impl<'a> RionTable<'a> {
         // next m * n fields = data
         let data_len = m * column_names.len() as u64;
         if data_len > length as u64 {
-            return Err(format!(
-                "Not enough data for rows, expected {}, found {}",
-                data_len, length
-            )
-            .into());
+            return Err(FerionError::RowCount(m));
         }
         let mut rows = Vec::with_capacity((data_len) as usize);
         rows.push(first_object);
@@ -271,6 +830,80 @@ impl<'a> RionTable<'a> {
             rows.push(field);
         }
 
-        Ok((RionTable { column_names, rows }, rest))
+        Ok((RionTable { column_names, rows }, dictionaries, rest))
+    }
+}
+
+// The column name and entries of each dictionary in the object at the start
+// of `data`
+#[allow(clippy::type_complexity)]
+fn parse_dictionaries(data: &[u8]) -> Result<(Vec<(Cow<'_, [u8]>, Vec<RionField<'_>>)>, &[u8])> {
+    let (_, data_len, rest) = crate::get_normal_header(data)?;
+    let (mut content, rest) = rest.split_at(data_len);
+    let mut dictionaries = Vec::new();
+    while !content.is_empty() {
+        let (key, next) = RionField::parse(content)?;
+        let key = RionKey::from_field(key)?;
+        let (lead, entries_len, next) = crate::get_normal_header(next)?;
+        let RionFieldType::Normal(NormalRionType::Array) = lead.field_type() else {
+            return Err(FerionError::invalid(
+                "Expected an array of dictionary entries",
+            ));
+        };
+        let (mut entries_data, next) = next.split_at(entries_len);
+        let mut entries = Vec::new();
+        while !entries_data.is_empty() {
+            let (entry, after) = RionField::parse(entries_data)?;
+            entries.push(entry);
+            entries_data = after;
+        }
+        dictionaries.push((key.into_inner(), entries));
+        content = next;
+    }
+    Ok((dictionaries, rest))
+}
+
+/// Why a list of objects couldn't become a table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMismatch {
+    /// Index of the first object whose keys differ from the first object's
+    pub row: usize,
+    /// Keys of the first object this one lacks
+    pub missing: Vec<Vec<u8>>,
+    /// Keys of this object the first one lacks
+    pub unexpected: Vec<Vec<u8>>,
+}
+
+impl std::fmt::Display for KeyMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = |keys: &[Vec<u8>]| {
+            keys.iter()
+                .map(|key| String::from_utf8_lossy(key).into_owned())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        write!(f, "Object {} has different keys than the first", self.row)?;
+        if !self.missing.is_empty() {
+            write!(f, ", missing [{}]", keys(&self.missing))?;
+        }
+        if !self.unexpected.is_empty() {
+            write!(f, ", unexpected [{}]", keys(&self.unexpected))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for KeyMismatch {}
+
+// Turn a `rows` by `columns` grid of cells stored row by row into one stored
+// column by column, or back by swapping the dimensions
+fn transpose<T>(cells: Vec<T>, rows: usize, columns: usize) -> Vec<T> {
+    let mut cells = cells.into_iter().map(Some).collect::<Vec<_>>();
+    let mut out = Vec::with_capacity(cells.len());
+    for column in 0..columns {
+        for row in 0..rows {
+            out.extend(cells[row * columns + column].take());
+        }
     }
+    out
 }
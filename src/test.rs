@@ -1,4 +1,5 @@
 use super::*;
+#[cfg(feature = "chrono")]
 use chrono::Utc;
 
 mod rion_field {
@@ -13,6 +14,108 @@ mod rion_field {
         assert!(!field.is_null());
     }
 
+    #[test]
+    fn test_const_lead_bytes() {
+        use types::{NormalRionType, RionFieldType, ShortRionType};
+
+        const NAME: LeadByte = LeadByte::short(ShortRionType::Key, 4);
+        const OBJECT: LeadByte = LeadByte::normal(NormalRionType::Object, 1);
+        const INVALID: Option<LeadByte> = LeadByte::new(0x80);
+        const IS_KEY: bool = NAME.field_type().is_key();
+        assert_eq!(NAME.byte(), 0xE4);
+        assert_eq!(OBJECT.length(), 1);
+        assert!(INVALID.is_none() && IS_KEY);
+        assert_eq!(LeadByte::TRUE.as_bool(), Some(true));
+        assert!(LeadByte::NULL.is_null());
+        assert_eq!(
+            RionFieldType::from_byte(0xA2),
+            Some(RionFieldType::Normal(NormalRionType::Array))
+        );
+    }
+
+    #[test]
+    fn test_try_new_fields() {
+        use field::{NormalField, ShortField};
+        use types::{NormalRionType, ShortRionType};
+
+        assert!(ShortField::try_new(ShortRionType::UTF8, &[b'a'; 15]).is_ok());
+        // 256 bytes used to wrap around to a length of 0
+        assert!(ShortField::try_new(ShortRionType::UTF8, &[b'a'; 256]).is_err());
+        assert!(NormalField::try_new(NormalRionType::Bytes, &[0; 256]).is_ok());
+    }
+
+    #[test]
+    fn test_try_from_str() {
+        let long = "a".repeat(300);
+        for value in ["", "short", long.as_str()] {
+            let field = RionField::try_from_str(value).unwrap();
+            assert_eq!(field, RionField::from(value));
+            assert_eq!(String::try_from(field).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_extended_round_trip() {
+        // Type 0x42 with three bytes of data, inside an object
+        let extended = [0xF1, 0x04, 0x42, 1, 2, 3];
+        let field = RionField::from_slice(&extended).unwrap();
+        assert_eq!(
+            field,
+            RionField::Extended {
+                type_id: 0x42,
+                data: vec![1, 2, 3].into()
+            }
+        );
+        assert_eq!(field.needed_bytes(), extended.len());
+        let mut encoded = Vec::new();
+        field.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, extended);
+
+        let mut data = vec![0xC1, 0x0A, 0xE3, b'e', b'x', b't'];
+        data.extend_from_slice(&extended);
+        let object = RionObject::from_slice(&data).unwrap();
+        assert_eq!(object.get("ext"), Some(&field));
+        assert_eq!(object.encode(), data);
+        assert!(RionField::from_slice(&[0xF1, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_lead_byte_lookup() {
+        for byte in 0..=u8::MAX {
+            let Some(field_type) = RionFieldType::from_byte(byte) else {
+                assert!(LeadByte::new(byte).is_none());
+                continue;
+            };
+            let lead = LeadByte::new(byte).unwrap();
+            assert_eq!(lead.field_type(), field_type);
+            let tiny = matches!(field_type, RionFieldType::Tiny(_));
+            assert_eq!(lead.length(), if tiny { 0 } else { byte & 0x0F });
+        }
+        assert!(LeadByte::NULL.is_null() && !LeadByte::TRUE.is_null());
+        assert_eq!(LeadByte::FALSE.as_bool(), Some(false));
+        assert_eq!(LeadByte::new(0x21).unwrap().as_bool(), None);
+    }
+
+    #[test]
+    fn test_field_ref() {
+        let mut data = Vec::new();
+        RionField::from("hello").encode(&mut data).unwrap();
+        RionField::from(7u64).encode(&mut data).unwrap();
+
+        let (text, rest) = RionFieldRef::parse(&data).unwrap();
+        // Borrowed from `data`, not from `text`
+        let borrowed: &str = text.as_str().unwrap();
+        assert_eq!(borrowed, "hello");
+        assert_eq!(text.to_field(), RionField::from("hello"));
+        assert_eq!(RionField::from("hello").as_field_ref(), text);
+
+        let number = RionFieldRef::from_slice(rest).unwrap();
+        let owned: RionFieldBuf = number.to_buf();
+        assert_eq!(owned, RionField::from(7u64));
+        assert_eq!(owned.as_field_ref(), number);
+        assert!(RionFieldRef::from_slice(&data).is_err());
+    }
+
     #[test]
     fn test_empty_utf8_field() {
         let field = RionField::from("");
@@ -62,11 +165,12 @@ mod rion_field {
         let field_false = RionField::from(false);
         assert!(matches!(field_true, RionField::Tiny(_)));
         assert!(matches!(field_false, RionField::Tiny(_)));
-        assert_eq!(field_true.as_bytes(), &[]);
-        assert_eq!(field_false.as_bytes(), &[]);
+        assert!(field_true.as_bytes().is_empty());
+        assert!(field_false.as_bytes().is_empty());
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_from_f32() {
         let field = RionField::from(3.14f32);
         assert!(matches!(field, RionField::Short(_)));
@@ -74,6 +178,7 @@ mod rion_field {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_from_f64() {
         let field = RionField::from(3.14159265359f64);
         assert!(matches!(field, RionField::Short(_)));
@@ -81,9 +186,104 @@ mod rion_field {
     }
 
     #[test]
+    fn test_scalar_accessors() {
+        assert_eq!(RionField::from(-5i64).as_i64(), Some(-5));
+        assert_eq!(RionField::from(5u64).as_i64(), Some(5));
+        assert_eq!(RionField::from(u64::MAX).as_i64(), None);
+        assert_eq!(RionField::from(u64::MAX).as_u64(), Some(u64::MAX));
+        assert_eq!(RionField::from(-5i64).as_u64(), None);
+        assert_eq!(RionField::from(1.5f32).as_f64(), Some(1.5));
+        assert_eq!(RionField::from(1.5f64).as_f64(), Some(1.5));
+        assert_eq!(RionField::from("1.5").as_f64(), None);
+        assert_eq!(RionField::from(true).as_bool(), Some(true));
+        assert_eq!(RionField::from(1u64).as_bool(), None);
+    }
+
+    #[test]
+    fn test_from_rust_values() {
+        let data = [1u8, 2, 3];
+        let field = RionField::from(&data[..]);
+        assert_eq!(field, RionField::bytes(&data));
+        assert_eq!(RionField::from(data.to_vec()), field);
+
+        let array = RionField::from(vec![RionField::from(1u64), RionField::from("a")]);
+        let mut encoded = Vec::new();
+        array.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, [0xA1, 0x04, 0x21, 0x01, 0x61, b'a']);
+
+        let mut map = std::collections::HashMap::new();
+        map.insert("b", 2u64);
+        map.insert("a", 1u64);
+        let mut encoded = Vec::new();
+        RionField::from(map).encode(&mut encoded).unwrap();
+        assert_eq!(
+            encoded,
+            [0xC1, 0x08, 0xE1, b'a', 0x21, 0x01, 0xE1, b'b', 0x21, 0x02]
+        );
+
+        assert_eq!(RionField::from(Some(5u64)), RionField::from(5u64));
+        let null = RionField::from(None::<u64>);
+        assert!(null.is_null());
+        assert_eq!(null.field_type(), RionField::from(5u64).field_type());
+        let mut encoded = Vec::new();
+        RionField::from(None::<bool>).encode(&mut encoded).unwrap();
+        assert_eq!(encoded, [0x10]);
+        assert!(RionField::from(None::<Vec<u8>>).is_null());
+    }
+
+    #[test]
+    fn test_into_bytes() {
+        let owned = vec![1, 2, 3];
+        let ptr = owned.as_ptr();
+        assert_eq!(RionField::bytes(&[]).into_bytes(), Some(Vec::new()));
+
+        let field = RionField::from(String::from_utf8(owned).unwrap());
+        let bytes = field.into_bytes().unwrap();
+        // The owned buffer is moved out, not copied
+        assert_eq!(bytes.as_ptr(), ptr);
+
+        let data = [0x01, 0x02, 0xAB, 0xCD];
+        let bytes: Vec<u8> = RionField::from_slice(&data).unwrap().try_into().unwrap();
+        assert_eq!(bytes, [0xAB, 0xCD]);
+        assert!(Vec::<u8>::try_from(RionField::from(1u64)).is_err());
+    }
+
+    #[test]
+    fn test_field_ordering() {
+        let a = RionField::from(-2i64);
+        let b = RionField::from(1u64);
+        let c = RionField::from(1.5f32);
+        let d = RionField::from(100u64);
+        assert!(a < b && b < c && c < d);
+        // Same value, different encodings
+        assert_ne!(RionField::from(1u64), RionField::from(1.0f64));
+        assert_ne!(
+            RionField::from(1u64).cmp(&RionField::from(1.0f64)),
+            std::cmp::Ordering::Equal
+        );
+        assert!(RionField::from(false) < RionField::from(true));
+        assert!(RionField::from(true) < RionField::from(0u64));
+        assert!(RionField::from("a") < RionField::from("ab"));
+        assert!(RionField::from(9u64) < RionField::from("0"));
+    }
+
+    #[test]
+    fn test_trimmed_floats() {
+        let field = RionField::from(1.5f64);
+        assert_eq!(field.as_bytes(), &[0x3F, 0xF8, 0x00, 0x00, 0x00]);
+        assert_eq!(f64::try_from(field).unwrap(), 1.5);
+        let field = RionField::from(0.0f32);
+        assert!(!field.is_null());
+        assert_eq!(f32::try_from(field).unwrap(), 0.0);
+        let field = RionField::from(-0.0f64);
+        assert_eq!(f64::try_from(field).unwrap().to_bits(), (-0.0f64).to_bits());
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
     fn test_from_datetime() {
         let now = Utc::now();
-        let field = RionField::from(now);
+        let field = RionField::try_from(now).unwrap();
         println!("{:?}", field);
         assert!(matches!(field, RionField::Short(_)));
         // assert_eq!(field.as_bytes().len(), 11);
@@ -103,6 +303,17 @@ mod rion_field {
         let field = RionField::from_slice(&[0x50]).unwrap();
         assert!(field.is_null());
     }
+
+    #[test]
+    fn test_zero_int_is_not_null() {
+        assert!(!RionField::from(0u64).is_null());
+        assert!(!RionField::from(0i64).is_null());
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            crate::from_bytes::<u64>(&crate::to_bytes(&0u64).unwrap()).unwrap(),
+            0
+        );
+    }
 }
 
 mod rion_object {
@@ -122,6 +333,79 @@ mod rion_object {
         assert!(obj.fields.contains_key("key".as_bytes()));
     }
 
+    #[test]
+    fn test_insert_key() {
+        let mut obj = RionObject::new();
+        let key = crate::RionKey::try_from("key").unwrap();
+        assert_eq!(obj.insert(key, "value"), None);
+        assert_eq!(obj.get("key"), Some(&RionField::from("value")));
+        assert_eq!(obj.encoded_len(), obj.encode().len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_empty_key() {
+        RionObject::new().add_field("", 1i64);
+    }
+
+    #[test]
+    fn test_parse_null_key() {
+        assert!(RionObject::from_slice(&[0xC1, 0x02, 0xE0, 0x12]).is_err());
+    }
+
+    #[test]
+    fn test_add_field_replaces() {
+        let mut obj = RionObject::new();
+        assert_eq!(obj.add_field("key", "value"), None);
+        assert_eq!(obj.add_field("other", 1i64), None);
+        assert_eq!(obj.encoded_len(), obj.encode().len());
+        assert_eq!(obj.add_field("key", "v"), Some(RionField::from("value")));
+        assert_eq!(obj.len(), 2);
+        assert_eq!(obj.encoded_len(), obj.encode().len());
+
+        let long = "x".repeat(300);
+        obj.add_field("key", long.as_str());
+        assert_eq!(obj.encoded_len(), obj.encode().len());
+
+        obj.fields.clear();
+        assert_eq!(obj.recompute_len(), obj.encode().len());
+        let decoded = RionObject::from_slice(&[0xC1, 0x04, 0xE1, b'a', 0x21, 0x01]).unwrap();
+        assert_eq!(decoded.encoded_len(), 6);
+    }
+
+    #[test]
+    fn test_containers_as_map_keys() {
+        let mut a = RionObject::new();
+        a.add_field("x", 1u64);
+        a.add_field("y", 2u64);
+        let mut b = RionObject::new();
+        b.add_field("y", 2u64);
+        b.add_field("x", 1u64);
+        let set = std::collections::HashSet::from([a.clone(), b]);
+        assert_eq!(set.len(), 1);
+
+        let mut array = RionArray::new();
+        array.add_element("x");
+        let set = std::collections::HashSet::from([array.clone(), array]);
+        assert_eq!(set.len(), 1);
+        assert_eq!(RionTable::default().row_count(), 0);
+    }
+
+    #[test]
+    fn test_object_iteration() {
+        let mut obj = RionObject::new();
+        obj.add_field("b", 2i64);
+        obj.add_field("a", 1i64);
+        obj.add_field("c", 3i64);
+        assert_eq!(obj.len(), 3);
+        assert!(obj.contains_key("a") && !obj.contains_key("d"));
+        assert_eq!(obj.get("b"), Some(&RionField::from(2i64)));
+        assert_eq!(obj.keys().count(), 3);
+        assert_eq!(obj.values().count(), 3);
+        let keys = obj.iter_sorted().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys, [b"a", b"b", b"c"]);
+    }
+
     #[test]
     fn test_decode_object() {
         let data = vec![
@@ -170,4 +454,147 @@ mod rion_object {
 
         assert_eq!(outer_obj, decoded_obj);
     }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let mut content = Vec::new();
+        for (key, value) in [("a", 1u64), ("b", 2), ("a", 3)] {
+            RionField::key_str(key).encode(&mut content).unwrap();
+            RionField::from(value).encode(&mut content).unwrap();
+        }
+        let encoded = [vec![0xC1, content.len() as u8], content].concat();
+        let parse = |duplicates| RionObject::from_slice_with_duplicates(&encoded, duplicates);
+
+        assert!(matches!(
+            parse(DuplicateKeys::Reject),
+            Err(FerionError::DuplicateKey(key)) if key == b"a"
+        ));
+        let first = parse(DuplicateKeys::FirstWins).unwrap();
+        assert_eq!(first.get("a"), Some(&RionField::from(1u64)));
+        assert_eq!(first.encoded_len(), first.encode().len());
+        let last = RionObject::from_slice(&encoded).unwrap();
+        assert_eq!(last.get("a"), Some(&RionField::from(3u64)));
+        assert_eq!(last.len(), 2);
+
+        let all = parse(DuplicateKeys::Multimap).unwrap();
+        let a = vec![RionField::from(1u64), RionField::from(3u64)];
+        assert_eq!(all.get("a"), Some(&RionField::from(a)));
+        let b = vec![RionField::from(2u64)];
+        assert_eq!(all.get("b"), Some(&RionField::from(b)));
+        assert_eq!(all.encoded_len(), all.encode().len());
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod rion_datetime {
+    use super::*;
+    use chrono::{DateTime, TimeZone};
+
+    fn round_trip(dt: DateTime<Utc>) -> DateTime<Utc> {
+        let mut encoded = Vec::new();
+        RionField::try_from(dt)
+            .unwrap()
+            .encode(&mut encoded)
+            .unwrap();
+        RionField::from_slice(&encoded).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn test_datetime_round_trip() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        assert_eq!(round_trip(date), date);
+        let millis = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+            + chrono::Duration::milliseconds(250);
+        assert_eq!(round_trip(millis), millis);
+        let nanos = Utc.with_ymd_and_hms(1999, 12, 31, 23, 59, 59).unwrap()
+            + chrono::Duration::nanoseconds(123_456_789);
+        assert_eq!(round_trip(nanos), nanos);
+    }
+
+    #[test]
+    fn test_naive_round_trip() {
+        use chrono::{NaiveDate, NaiveDateTime};
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        assert_eq!(
+            NaiveDate::try_from(RionField::try_from(day).unwrap()).unwrap(),
+            day
+        );
+        let at = day.and_hms_nano_opt(8, 30, 0, 1).unwrap();
+        let decoded = NaiveDateTime::try_from(RionField::try_from(at).unwrap()).unwrap();
+        assert_eq!(decoded, day.and_hms_opt(8, 30, 0).unwrap());
+        assert!(NaiveDate::try_from(RionField::try_from(at).unwrap()).is_err());
+    }
+}
+
+mod ferion_error {
+    use super::*;
+    use crate::field::ShortField;
+
+    #[test]
+    fn test_error_variants() {
+        assert!(matches!(
+            RionField::parse(&[]),
+            Err(FerionError::Truncated(1))
+        ));
+        assert!(matches!(
+            RionField::parse(&[0x51, 0x05, b'a']),
+            Err(FerionError::Truncated(4))
+        ));
+        assert!(matches!(
+            RionField::parse(&[0x80]),
+            Err(FerionError::InvalidLeadByte(0x80))
+        ));
+        assert!(matches!(
+            RionField::from_slice(&[0x21, 0x01, 0x00]),
+            Err(FerionError::TrailingData(1))
+        ));
+        assert!(matches!(
+            String::try_from(RionField::Short(ShortField::new(
+                ShortRionType::UTF8,
+                &[0xFF]
+            ))),
+            Err(FerionError::InvalidUtf8(_))
+        ));
+        assert!(matches!(
+            u8::try_from(RionField::from(300u64)),
+            Err(FerionError::OutOfRange("u8"))
+        ));
+        let error = bool::try_from(RionField::from(1u64)).unwrap_err();
+        assert!(matches!(
+            error,
+            FerionError::UnexpectedType {
+                expected: "a boolean",
+                found: RionFieldType::Short(ShortRionType::Int64Positive),
+            }
+        ));
+        // Integers too long to read fail instead of panicking
+        let (long, _) = RionField::parse(&[0x29, 1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        assert!(matches!(
+            i64::try_from(long),
+            Err(FerionError::LengthOverflow(9))
+        ));
+        // Errors boxed by callers keep their variant when converted back
+        let boxed: Box<dyn std::error::Error> = RionObject::from_slice(&[0xC1]).unwrap_err().into();
+        assert!(FerionError::from(boxed).is_truncated());
+    }
+
+    #[test]
+    fn test_negative_range() {
+        let min = RionField::from(i64::MIN);
+        assert_eq!(min.as_bytes(), i64::MAX.to_be_bytes());
+        assert_eq!(i64::try_from(min.clone()).unwrap(), i64::MIN);
+        assert_eq!(i128::try_from(min).unwrap(), i64::MIN as i128);
+
+        // The smallest value 8 bytes hold, -(u64::MAX + 1)
+        let (below, _) =
+            RionField::parse(&[0x38, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]).unwrap();
+        let value = -(u64::MAX as i128) - 1;
+        assert_eq!(below.as_i64(), None);
+        assert_eq!(below.as_i128(), Some(value));
+        assert!(matches!(
+            i64::try_from(below.clone()),
+            Err(FerionError::NegativeOutOfRange(v)) if v == value
+        ));
+        assert_eq!(i128::try_from(below).unwrap(), value);
+    }
 }
@@ -0,0 +1,323 @@
+//! Assertions for tests comparing encoded RION
+//!
+//! [`assert_rion_eq!`](crate::assert_rion_eq) decodes both sides and compares
+//! them as values, so two encodings of the same document are equal. When they
+//! differ the panic lists each difference with its path instead of printing
+//! two walls of hex:
+//!
+//! ```text
+//! assertion `left == right` failed: RION values differ
+//!   users[1].name: "Al" != "Alice"
+//!   users[2]: only on the right: {..2 fields}
+//! ```
+
+use std::{borrow::Cow, fmt::Write};
+
+use crate::{Result, RionValue};
+
+/// Compare two encoded buffers, or values, as RION values
+///
+/// Either side can be bytes holding an encoded value or a [`RionValue`].
+/// Takes an optional message like [`assert_eq!`].
+///
+/// ```
+/// use ferion::{assert_rion_eq, RionValue};
+///
+/// let encoded = RionValue::from("hi").encode_to_vec().unwrap();
+/// assert_rion_eq!(encoded, RionValue::from("hi"));
+/// ```
+#[macro_export]
+macro_rules! assert_rion_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_eq(&$left, &$right, None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::testing::assert_eq(&$left, &$right, Some(format_args!($($arg)+)))
+    };
+}
+
+/// Anything [`assert_rion_eq!`](crate::assert_rion_eq) can compare
+pub trait Operand {
+    fn to_value(&self) -> Result<Cow<'_, RionValue>>;
+}
+
+impl Operand for [u8] {
+    fn to_value(&self) -> Result<Cow<'_, RionValue>> {
+        Ok(Cow::Owned(RionValue::from_slice(self)?))
+    }
+}
+
+impl Operand for Vec<u8> {
+    fn to_value(&self) -> Result<Cow<'_, RionValue>> {
+        self.as_slice().to_value()
+    }
+}
+
+impl<const N: usize> Operand for [u8; N] {
+    fn to_value(&self) -> Result<Cow<'_, RionValue>> {
+        self.as_slice().to_value()
+    }
+}
+
+impl Operand for RionValue {
+    fn to_value(&self) -> Result<Cow<'_, RionValue>> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl<T: Operand + ?Sized> Operand for &T {
+    fn to_value(&self) -> Result<Cow<'_, RionValue>> {
+        (**self).to_value()
+    }
+}
+
+/// The function behind [`assert_rion_eq!`](crate::assert_rion_eq)
+#[track_caller]
+pub fn assert_eq<L: Operand + ?Sized, R: Operand + ?Sized>(
+    left: &L,
+    right: &R,
+    message: Option<std::fmt::Arguments>,
+) {
+    let left = decoded("left", left.to_value());
+    let right = decoded("right", right.to_value());
+    let differences = diff(&left, &right);
+    if differences.is_empty() {
+        return;
+    }
+    let mut report = String::from("assertion `left == right` failed: ");
+    match message {
+        Some(message) => write!(report, "{message}").unwrap(),
+        None => report.push_str("RION values differ"),
+    }
+    for difference in differences.iter().take(MAX_SHOWN) {
+        write!(report, "\n  {difference}").unwrap();
+    }
+    if differences.len() > MAX_SHOWN {
+        write!(report, "\n  ...and {} more", differences.len() - MAX_SHOWN).unwrap();
+    }
+    panic!("{report}");
+}
+
+#[track_caller]
+fn decoded<'a>(side: &str, operand: Result<Cow<'a, RionValue>>) -> Cow<'a, RionValue> {
+    match operand {
+        Ok(value) => value,
+        Err(e) => panic!("assertion `left == right` failed: {side} is not valid RION: {e}"),
+    }
+}
+
+// Differences listed before the rest are summarized
+const MAX_SHOWN: usize = 20;
+
+/// One line per difference between `left` and `right`, each starting with the
+/// path to it, empty when they are equal
+pub fn diff(left: &RionValue, right: &RionValue) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff_at(&mut String::new(), left, right, &mut differences);
+    differences
+}
+
+fn diff_at(path: &mut String, left: &RionValue, right: &RionValue, out: &mut Vec<String>) {
+    let len = path.len();
+    match (left, right) {
+        (RionValue::Object(left), RionValue::Object(right)) => {
+            let mut keys = left.keys().chain(right.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                push_key(path, key);
+                match (left.get(key), right.get(key)) {
+                    (Some(l), Some(r)) => diff_at(path, l, r, out),
+                    (Some(l), None) => report(out, path, format!("only on the left: {}", show(l))),
+                    (None, Some(r)) => report(out, path, format!("only on the right: {}", show(r))),
+                    (None, None) => unreachable!("Keys come from either side"),
+                }
+                path.truncate(len);
+            }
+        }
+        (RionValue::Array(left), RionValue::Array(right)) => {
+            diff_elements(path, left, right, out, diff_at)
+        }
+        (
+            RionValue::Table { columns, rows },
+            RionValue::Table {
+                columns: right_columns,
+                rows: right_rows,
+            },
+        ) if columns == right_columns => {
+            diff_elements(path, rows, right_rows, out, |path, l, r, out| {
+                let len = path.len();
+                for ((column, l), r) in columns.iter().zip(l).zip(r) {
+                    push_key(path, column);
+                    diff_at(path, l, r, out);
+                    path.truncate(len);
+                }
+            })
+        }
+        (RionValue::Float(l), RionValue::Float(r)) if l.to_bits() == r.to_bits() => {}
+        (left, right) if left == right => {}
+        (left, right) => report(out, path, format!("{} != {}", show(left), show(right))),
+    }
+}
+
+fn diff_elements<T>(
+    path: &mut String,
+    left: &[T],
+    right: &[T],
+    out: &mut Vec<String>,
+    element: impl Fn(&mut String, &T, &T, &mut Vec<String>),
+) where
+    T: ShowElement,
+{
+    let len = path.len();
+    for i in 0..left.len().max(right.len()) {
+        write!(path, "[{i}]").unwrap();
+        match (left.get(i), right.get(i)) {
+            (Some(l), Some(r)) => element(path, l, r, out),
+            (Some(l), None) => report(out, path, format!("only on the left: {}", l.show())),
+            (None, Some(r)) => report(out, path, format!("only on the right: {}", r.show())),
+            (None, None) => unreachable!("Index is below one of the lengths"),
+        }
+        path.truncate(len);
+    }
+}
+
+fn report(out: &mut Vec<String>, path: &str, difference: String) {
+    match path {
+        "" => out.push(format!("(root): {difference}")),
+        path => out.push(format!("{path}: {difference}")),
+    }
+}
+
+// Keys are written like the paths `RionValue::query` takes, quoted when they
+// aren't plain identifiers
+fn push_key(path: &mut String, key: &[u8]) {
+    match std::str::from_utf8(key) {
+        Ok(key)
+            if !key.is_empty()
+                && key.chars().all(|c| c.is_alphanumeric() || c == '_')
+                && !key.starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(key);
+        }
+        Ok(key) => write!(path, "[{key:?}]").unwrap(),
+        Err(_) => write!(path, "[h'{}']", hex(key)).unwrap(),
+    }
+}
+
+trait ShowElement {
+    fn show(&self) -> String;
+}
+
+impl ShowElement for RionValue {
+    fn show(&self) -> String {
+        show(self)
+    }
+}
+
+// A table row
+impl ShowElement for Vec<RionValue> {
+    fn show(&self) -> String {
+        format!("[..{} cells]", self.len())
+    }
+}
+
+// Leaves in full, containers by their size
+fn show(value: &RionValue) -> String {
+    match value {
+        RionValue::Null => "null".to_string(),
+        RionValue::Bool(b) => b.to_string(),
+        RionValue::PosInt(n) => n.to_string(),
+        RionValue::NegInt(n) => n.to_string(),
+        RionValue::Float(n) => format!("{n:?}"),
+        RionValue::String(s) => format!("{s:?}"),
+        RionValue::Bytes(bytes) => format!("h'{}'", hex(bytes)),
+        RionValue::DateTime(dt) => format!("dt'{}'", dt.to_rfc3339()),
+        RionValue::Extended { type_id, data } => format!("ext{type_id}'{}'", hex(data)),
+        RionValue::Array(elements) => format!("[..{} elements]", elements.len()),
+        RionValue::Object(fields) => format!("{{..{} fields}}", fields.len()),
+        RionValue::Table { columns, rows } => {
+            let columns = columns.iter().map(|column| {
+                let mut name = String::new();
+                push_key(&mut name, column);
+                name
+            });
+            format!(
+                "table [{}] [..{} rows]",
+                columns.collect::<Vec<_>>().join(", "),
+                rows.len()
+            )
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn user(name: &str, age: u64) -> RionValue {
+        RionValue::Object(
+            [
+                (b"name"[..].into(), name.into()),
+                (b"age"[..].into(), RionValue::PosInt(age.into())),
+            ]
+            .into(),
+        )
+    }
+
+    #[test]
+    fn test_diff() {
+        let left = RionValue::Object(
+            [
+                (
+                    b"users"[..].into(),
+                    RionValue::Array(vec![user("Bo", 3), user("Al", 4)]),
+                ),
+                (b"odd.key"[..].into(), RionValue::Bytes(vec![1, 2])),
+            ]
+            .into(),
+        );
+        let right = RionValue::Object(
+            [(
+                b"users"[..].into(),
+                RionValue::Array(vec![user("Bo", 3), user("Alice", 4), user("Cy", 5)]),
+            )]
+            .into(),
+        );
+        assert_eq!(
+            diff(&left, &right),
+            [
+                "[\"odd.key\"]: only on the left: h'0102'",
+                "users[1].name: \"Al\" != \"Alice\"",
+                "users[2]: only on the right: {..2 fields}",
+            ]
+        );
+        assert!(diff(&left, &left).is_empty());
+        assert_eq!(
+            diff(&RionValue::Float(f64::NAN), &RionValue::PosInt(1)),
+            ["(root): NaN != 1"]
+        );
+    }
+
+    #[test]
+    fn test_assert_rion_eq() {
+        let value = user("Al", 4);
+        let encoded = value.encode_to_vec().unwrap();
+        crate::assert_rion_eq!(encoded, value);
+        crate::assert_rion_eq!(&encoded[..], encoded.clone(), "same bytes");
+    }
+
+    #[test]
+    #[should_panic(expected = "age: 4 != 5")]
+    fn test_assert_rion_eq_fails() {
+        let encoded = user("Al", 4).encode_to_vec().unwrap();
+        crate::assert_rion_eq!(encoded, user("Al", 5));
+    }
+}
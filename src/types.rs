@@ -1,32 +1,84 @@
-use std::error::Error;
+use crate::FerionError;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct LeadByte(pub(crate) u8); // (field type, length)
 
+// The field type of each high nibble, decoded once at compile time. The lead
+// byte in the Tiny entry is a placeholder, `field_type` puts in the real one
+const FIELD_TYPES: [Option<RionFieldType>; 16] = {
+    let mut types = [None; 16];
+    let mut nibble = 0;
+    while nibble < 16 {
+        types[nibble] = RionFieldType::from_byte((nibble as u8) << 4);
+        nibble += 1;
+    }
+    types
+};
+
 impl LeadByte {
-    pub fn from_type(field_type: RionFieldType, length: u8) -> Self {
+    /// The null Tiny field, not tied to any data type
+    pub const NULL: LeadByte = LeadByte(0x10);
+    pub const FALSE: LeadByte = LeadByte(0x11);
+    pub const TRUE: LeadByte = LeadByte(0x12);
+
+    /// `byte` as a lead byte, `None` if it has no valid field type
+    pub const fn new(byte: u8) -> Option<Self> {
+        match FIELD_TYPES[(byte >> 4) as usize] {
+            Some(_) => Some(LeadByte(byte)),
+            None => None,
+        }
+    }
+
+    pub const fn from_type(field_type: RionFieldType, length: u8) -> Self {
+        LeadByte(field_type.to_byte() << 4 | length)
+    }
+
+    /// The lead byte of a short field holding `length` bytes
+    ///
+    /// # Panics
+    ///
+    /// If `length` is over 15, at compile time in const contexts
+    pub const fn short(field_type: ShortRionType, length: u8) -> Self {
+        assert!(length <= 15, "Short fields hold at most 15 bytes");
         LeadByte(field_type.to_byte() << 4 | length)
     }
 
-    pub fn field_type(self) -> RionFieldType {
-        RionFieldType::try_from(self.0).unwrap()
+    /// The lead byte of a normal field whose length takes `length_length` bytes
+    ///
+    /// # Panics
+    ///
+    /// If `length_length` is over 15, at compile time in const contexts
+    pub const fn normal(field_type: NormalRionType, length_length: u8) -> Self {
+        assert!(length_length <= 15, "Lengths take at most 15 bytes");
+        LeadByte(field_type.to_byte() << 4 | length_length)
     }
 
-    pub fn length(self) -> u8 {
-        match self.field_type() {
-            RionFieldType::Tiny(_) => 0,
-            _ => self.0 & 0x0F,
+    pub const fn field_type(self) -> RionFieldType {
+        match FIELD_TYPES[(self.0 >> 4) as usize] {
+            Some(RionFieldType::Tiny(_)) => RionFieldType::Tiny(self),
+            Some(field_type) => field_type,
+            None => panic!("Lead byte with an invalid field type"),
         }
     }
 
-    pub fn is_null(self) -> bool {
-        match self.field_type() {
-            RionFieldType::Tiny(lead) => lead.byte() & 0x0F == 0,
-            _ => self.length() == 0,
+    const fn is_tiny(self) -> bool {
+        self.0 >> 4 == RionFieldType::TINY
+    }
+
+    pub const fn length(self) -> u8 {
+        match self.is_tiny() {
+            true => 0,
+            false => self.0 & 0x0F,
         }
     }
 
-    pub fn is_short(self) -> bool {
+    /// Tiny fields are null when their value is 0, other fields when they
+    /// have no length
+    pub const fn is_null(self) -> bool {
+        self.0 & 0x0F == 0
+    }
+
+    pub const fn is_short(self) -> bool {
         self.length() < 15
     }
 
@@ -34,19 +86,18 @@ impl LeadByte {
         self.0
     }
 
-    pub fn as_bool(self) -> Option<bool> {
-        match self.field_type() {
-            RionFieldType::Tiny(lead) if lead.byte() & 0x0F != 0 => Some(lead.byte() & 0x0F == 2),
+    pub const fn as_bool(self) -> Option<bool> {
+        match self.0 & 0x0F {
+            value if self.is_tiny() && value != 0 => Some(value == 2),
             _ => None,
         }
     }
 }
 
 impl TryFrom<u8> for LeadByte {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
-        RionFieldType::try_from(value)?;
-        Ok(LeadByte(value))
+        LeadByte::new(value).ok_or(FerionError::InvalidLeadByte(value))
     }
 }
 
@@ -93,19 +144,26 @@ impl ShortRionType {
     }
 }
 
-impl TryFrom<u8> for ShortRionType {
-    type Error = Box<dyn Error>;
-    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
-        let out = match (value & 0xF0) >> 4 {
+impl ShortRionType {
+    /// The short type in the high bits of `value`, if any
+    pub const fn from_byte(value: u8) -> Option<Self> {
+        Some(match (value & 0xF0) >> 4 {
             0x2 => ShortRionType::Int64Positive,
             0x3 => ShortRionType::Int64Negative,
             0x4 => ShortRionType::Float,
             0x6 => ShortRionType::UTF8,
             0x7 => ShortRionType::UTCDateTime,
             0xE => ShortRionType::Key,
-            _ => return Err(format!("Invalid short field type: {value:#X}").into()),
-        };
-        Ok(out)
+            _ => return None,
+        })
+    }
+}
+
+impl TryFrom<u8> for ShortRionType {
+    type Error = FerionError;
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        Self::from_byte(value)
+            .ok_or_else(|| FerionError::invalid(format!("Invalid short field type: {value:#X}")))
     }
 }
 
@@ -120,22 +178,27 @@ pub enum NormalRionType {
 }
 
 impl TryFrom<u8> for NormalRionType {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
-        let out = match (value & 0xF0) >> 4 {
+        Self::from_byte(value)
+            .ok_or_else(|| FerionError::invalid(format!("Invalid normal field type: {value:#X}")))
+    }
+}
+
+impl NormalRionType {
+    /// The normal type in the high bits of `value`, if any
+    pub const fn from_byte(value: u8) -> Option<Self> {
+        Some(match (value & 0xF0) >> 4 {
             0x0 => NormalRionType::Bytes,
             0x5 => NormalRionType::UTF8,
             0xA => NormalRionType::Array,
             0xB => NormalRionType::Table,
             0xC => NormalRionType::Object,
             0xD => NormalRionType::Key,
-            _ => return Err(format!("Invalid normal field type: {value:#X}").into()),
-        };
-        Ok(out)
+            _ => return None,
+        })
     }
-}
 
-impl NormalRionType {
     pub const fn to_byte(self) -> u8 {
         match self {
             NormalRionType::Bytes => RionFieldType::BYTES,
@@ -161,6 +224,7 @@ impl RionFieldType {
     pub const INT64_NEGATIVE: u8 = 0x3;
     pub const UTF8_SHORT: u8 = 0x6;
     pub const KEY_SHORT: u8 = 0xE;
+    pub const TINY: u8 = 0x1;
 
     pub const fn to_byte(self) -> u8 {
         match self {
@@ -171,7 +235,23 @@ impl RionFieldType {
         }
     }
 
-    pub fn is_key(&self) -> bool {
+    /// The type of a field with lead byte `value`, if valid
+    pub const fn from_byte(value: u8) -> Option<Self> {
+        match value >> 4 {
+            0xF => Some(RionFieldType::Extended),
+            0x1 => Some(RionFieldType::Tiny(LeadByte(value))),
+            0x0 | 0x5 | 0xA..=0xD => match NormalRionType::from_byte(value) {
+                Some(normal) => Some(RionFieldType::Normal(normal)),
+                None => None,
+            },
+            _ => match ShortRionType::from_byte(value) {
+                Some(short) => Some(RionFieldType::Short(short)),
+                None => None,
+            },
+        }
+    }
+
+    pub const fn is_key(&self) -> bool {
         matches!(
             self,
             RionFieldType::Short(ShortRionType::Key) | RionFieldType::Normal(NormalRionType::Key)
@@ -180,17 +260,8 @@ impl RionFieldType {
 }
 
 impl TryFrom<u8> for RionFieldType {
-    type Error = Box<dyn Error>;
+    type Error = FerionError;
     fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
-        let type_bits = value & 0xF0;
-        match type_bits >> 4 {
-            0xF => Ok(RionFieldType::Extended),
-            0x1 => Ok(RionFieldType::Tiny(LeadByte(value))),
-            0x0 | 0x5 | 0xA..=0xD => {
-                Ok(RionFieldType::Normal(NormalRionType::try_from(type_bits)?))
-            }
-            0x0..=0x7 | 0xE => Ok(RionFieldType::Short(ShortRionType::try_from(type_bits)?)),
-            _ => Err(format!("Invalid field type: {value:#X}").into()),
-        }
+        Self::from_byte(value).ok_or(FerionError::InvalidLeadByte(value))
     }
 }
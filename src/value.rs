@@ -0,0 +1,1032 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+
+use crate::{
+    field::NormalField,
+    get_header, get_normal_header, needed_bytes_usize,
+    types::{LeadByte, NormalRionType, RionFieldType, ShortRionType},
+    FerionError, Interner, Result, RionDateTime, RionField, RionKey, RionObject,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "chrono")]
+    use chrono::TimeZone;
+
+    fn sample() -> RionValue {
+        let mut user = BTreeMap::new();
+        user.insert(b"name"[..].into(), "Alice".into());
+        user.insert(b"age"[..].into(), RionValue::PosInt(30));
+        user.insert(b"balance"[..].into(), RionValue::NegInt(-12));
+        user.insert(b"score"[..].into(), RionValue::Float(1.5));
+        user.insert(b"active"[..].into(), RionValue::Bool(true));
+        user.insert(b"avatar"[..].into(), RionValue::Bytes(vec![1, 2, 3]));
+        user.insert(b"nickname"[..].into(), RionValue::Null);
+        user.insert(
+            b"tags"[..].into(),
+            RionValue::Array(vec!["a".into(), "b".into()]),
+        );
+        user.insert(b"empty"[..].into(), RionValue::Array(Vec::new()));
+        #[cfg(feature = "chrono")]
+        user.insert(
+            b"joined"[..].into(),
+            Utc.with_ymd_and_hms(2024, 2, 29, 12, 0, 0).unwrap().into(),
+        );
+        RionValue::Object(user)
+    }
+
+    #[test]
+    fn test_encode_decode_value() {
+        let value = sample();
+        let encoded = value.encode_to_vec().unwrap();
+        assert_eq!(RionValue::from_slice(&encoded).unwrap(), value);
+    }
+
+    #[test]
+    fn test_encode_decode_table() {
+        let value = RionValue::Table {
+            columns: vec![b"id"[..].into(), b"name"[..].into()],
+            rows: vec![
+                vec![RionValue::PosInt(1), "A".into()],
+                vec![RionValue::PosInt(2), RionValue::Null],
+            ],
+        };
+        let encoded = value.encode_to_vec().unwrap();
+        assert_eq!(RionValue::from_slice(&encoded).unwrap(), value);
+    }
+
+    #[test]
+    fn test_decode_object_bytes() {
+        let mut obj = crate::RionObject::new();
+        obj.add_field("name", "Alice");
+        obj.add_field("age", 30i64);
+        let value = RionValue::from_slice(&obj.encode()).unwrap();
+        assert_eq!(value.get("name").and_then(RionValue::as_str), Some("Alice"));
+        assert_eq!(value.get("age"), Some(&RionValue::PosInt(30)));
+    }
+
+    #[test]
+    fn test_null_and_empty_containers_differ() {
+        assert_eq!(RionValue::from_slice(&[0xA0]).unwrap(), RionValue::Null);
+        assert_eq!(
+            RionValue::from_slice(&[0xA1, 0x00]).unwrap(),
+            RionValue::Array(Vec::new())
+        );
+
+        // Parsing and converting a field agree on which scalars are null
+        for data in [&[0x51, 0x00][..], &[0x01, 0x00], &[0x60], &[0x50]] {
+            let (field, _) = RionField::parse(data).unwrap();
+            assert_eq!(
+                RionValue::from_slice(data).unwrap(),
+                RionValue::try_from(field).unwrap()
+            );
+        }
+        assert_eq!(
+            RionValue::from_slice(&[0x51, 0x00]).unwrap(),
+            RionValue::Null
+        );
+        assert_eq!(
+            RionValue::from_slice(&[0x01, 0x00]).unwrap(),
+            RionValue::Null
+        );
+    }
+
+    #[test]
+    fn test_map_conversions() {
+        let mut obj = RionObject::new();
+        obj.add_field("name", "Alice");
+        obj.add_field("age", 30i64);
+        let map: HashMap<String, RionValue> = obj.clone().try_into().unwrap();
+        assert_eq!(map["name"], RionValue::from("Alice"));
+        assert_eq!(map["age"], RionValue::PosInt(30));
+
+        let back: RionObject = map.clone().try_into().unwrap();
+        assert_eq!(back, obj);
+        assert_eq!(back.encoded_len(), back.encode().len());
+        let value = RionValue::from(map);
+        let sorted: BTreeMap<String, RionValue> = value.try_into().unwrap();
+        assert_eq!(sorted.keys().collect::<Vec<_>>(), ["age", "name"]);
+
+        let mut obj = RionObject::new();
+        obj.add_field_bytes(&[0xFF], true);
+        assert!(HashMap::<String, RionValue>::try_from(obj).is_err());
+    }
+
+    #[test]
+    fn test_nested_field_conversion() {
+        let value = sample();
+        let field = RionField::try_from(&value).unwrap();
+        assert_eq!(RionValue::try_from(field).unwrap(), value);
+    }
+
+    #[test]
+    fn test_interned_decode() {
+        let rows = (0..3)
+            .map(|i| {
+                let mut row = BTreeMap::new();
+                row.insert(b"id"[..].into(), RionValue::PosInt(i));
+                row.insert(b"level"[..].into(), "info".into());
+                RionValue::Object(row)
+            })
+            .collect();
+        let encoded = RionValue::Array(rows).encode_to_vec().unwrap();
+        let mut interner = Interner::new();
+        let value = RionValue::from_slice_interned(&encoded, &mut interner).unwrap();
+        assert_eq!(value, RionValue::from_slice(&encoded).unwrap());
+
+        let RionValue::Array(rows) = &value else {
+            panic!("Expected an array");
+        };
+        let (RionValue::Object(first), RionValue::Object(last)) = (&rows[0], &rows[2]) else {
+            panic!("Expected objects");
+        };
+        let (RionValue::String(a), RionValue::String(b)) =
+            (&first[&b"level"[..]], &last[&b"level"[..]])
+        else {
+            panic!("Expected strings");
+        };
+        assert!(Arc::ptr_eq(a, b));
+        assert!(Arc::ptr_eq(
+            first.keys().next().unwrap(),
+            last.keys().next().unwrap()
+        ));
+        assert_eq!(interner.len(), 3);
+    }
+
+    #[test]
+    fn test_deeply_nested_value() {
+        // Arrays and objects with key "a", alternating, nested 100,000 deep.
+        // Built back to front, each header goes in front of its content
+        let mut reversed = vec![0x10];
+        for depth in 0..100_000 {
+            let field_type = if depth % 2 == 0 {
+                reversed.extend([b'a', 0xE1]);
+                NormalRionType::Object
+            } else {
+                NormalRionType::Array
+            };
+            let length = reversed.len();
+            let length_length = needed_bytes_usize(length);
+            reversed.extend(length.to_be_bytes()[8 - length_length..].iter().rev());
+            reversed.push(field_type.to_byte() << 4 | length_length as u8);
+        }
+        let data: Vec<u8> = reversed.into_iter().rev().collect();
+        let value = RionValue::from_slice(&data).unwrap();
+        assert_eq!(value.encode_to_vec().unwrap(), data);
+
+        let mut depth = 0;
+        let mut inner = &value;
+        loop {
+            inner = match inner {
+                RionValue::Array(elements) => &elements[0],
+                RionValue::Object(fields) => &fields[&b"a"[..]],
+                inner => break assert!(inner.is_null()),
+            };
+            depth += 1;
+        }
+        assert_eq!(depth, 100_000);
+    }
+
+    #[test]
+    fn test_count_hints_skipped() {
+        // {"a": [1]} with a count hint before each container
+        let data = [
+            0xF1, 0x02, 0x01, 0x01, 0xC1, 0x0A, 0xE1, b'a', 0xF1, 0x02, 0x01, 0x01, 0xA1, 0x02,
+            0x21, 0x01,
+        ];
+        let value = RionValue::from_slice(&data).unwrap();
+        assert_eq!(
+            value.get("a"),
+            Some(&RionValue::Array(vec![RionValue::PosInt(1)]))
+        );
+        let object = RionObject::from_slice(&data).unwrap();
+        assert_eq!(object.encoded_len(), object.encode().len());
+        assert_eq!(RionValue::from(HashMap::try_from(object).unwrap()), value);
+
+        // A hint must be followed by the array or object it counts
+        let chained = [
+            0xF1, 0x02, 0x01, 0x01, 0xF1, 0x02, 0x01, 0x01, 0xA1, 0x02, 0x21, 0x01,
+        ];
+        let before_int = [0xF1, 0x02, 0x01, 0x01, 0x21, 0x01];
+        for data in [&chained[..], &before_int, &data[..4]] {
+            assert!(RionValue::from_slice(data).is_err());
+            assert!(crate::RionArray::from_slice(data).is_err());
+        }
+    }
+
+    #[test]
+    fn test_extended_round_trip() {
+        // An extended field of type 0x7F, as an object value
+        let data = [0xC1, 0x07, 0xE1, b'a', 0xF1, 0x03, 0x7F, 0xAB, 0xCD];
+        let value = RionValue::from_slice(&data).unwrap();
+        assert_eq!(
+            value.get("a"),
+            Some(&RionValue::Extended {
+                type_id: 0x7F,
+                data: vec![0xAB, 0xCD]
+            })
+        );
+        assert_eq!(value.encode_to_vec().unwrap(), data);
+        let field = RionField::try_from(&value).unwrap();
+        assert_eq!(RionValue::try_from(field).unwrap(), value);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_wide_integers() {
+        let below = i64::MIN as i128 - 1;
+        let value = RionValue::from_slice(&crate::to_bytes(&below).unwrap()).unwrap();
+        assert_eq!(value, RionValue::NegInt(below));
+        let big = 1u128 << 100;
+        let bytes = crate::to_bytes(&big).unwrap();
+        let value = RionValue::from_slice(&bytes).unwrap();
+        assert_eq!(value, RionValue::PosInt(big));
+        assert_eq!(value.encode_to_vec().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_truncated_value() {
+        assert!(RionValue::from_slice(&[0xC1, 0x05, 0xE1, b'a']).is_err());
+    }
+
+    #[test]
+    fn test_table_row_count_bounded() {
+        // No columns and a row count of 2^56 - 1
+        let data = [
+            0xB1, 0x09, 0x28, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ];
+        assert!(RionValue::from_slice(&data).is_err());
+        // One column "a" and two rows, but only one cell
+        let data = [0xB1, 0x05, 0x21, 0x02, 0xE1, b'a', 0x10];
+        assert!(RionValue::from_slice(&data).is_err());
+    }
+}
+
+/// An owned, fully decoded RION value
+///
+/// Object keys are kept as raw bytes since RION does not require them to be
+/// UTF-8, and are stored sorted like [`RionObject::encode`](crate::RionObject::encode)
+/// writes them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RionValue {
+    Null,
+    Bool(bool),
+    /// Integers past 64 bits are kept whole, the fields hold up to 120 bits
+    PosInt(u128),
+    NegInt(i128),
+    Float(f64),
+    String(Arc<str>),
+    Bytes(Vec<u8>),
+    DateTime(RionDateTime),
+    Array(Vec<RionValue>),
+    Object(BTreeMap<Arc<[u8]>, RionValue>),
+    Table {
+        columns: Vec<Arc<[u8]>>,
+        rows: Vec<Vec<RionValue>>,
+    },
+    /// An extended field of a type this crate doesn't decode, kept as is so
+    /// it encodes back to the same bytes
+    Extended {
+        type_id: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl RionValue {
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        Self::finish(Self::parse(data)?)
+    }
+
+    /// Like [`RionValue::from_slice`], sharing repeated strings and keys
+    /// through `interner`
+    pub fn from_slice_interned(data: &[u8], interner: &mut Interner) -> Result<Self> {
+        Self::finish(Self::parse_interned(data, interner)?)
+    }
+
+    fn finish((value, rest): (Self, &[u8])) -> Result<Self> {
+        if !rest.is_empty() {
+            return Err(FerionError::invalid("Extra data after value"));
+        }
+        Ok(value)
+    }
+
+    /// Parse a single value, returning it and the remaining data
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8])> {
+        Self::parse_with(data, None)
+    }
+
+    pub fn parse_interned<'a>(data: &'a [u8], interner: &mut Interner) -> Result<(Self, &'a [u8])> {
+        Self::parse_with(data, Some(interner))
+    }
+
+    fn parse_with<'a>(
+        data: &'a [u8],
+        mut interner: Option<&mut Interner>,
+    ) -> Result<(Self, &'a [u8])> {
+        let mut stack = Vec::new();
+        let (value, rest) = Self::parse_next(data, &mut stack, interner.as_deref_mut())?;
+        Ok((Self::parse_nested(stack, value, interner)?, rest))
+    }
+
+    // Parse the rest of the containers on `stack`, kept there instead of on the
+    // call stack so values nested any number of levels deep can be parsed.
+    // `value` is the last value parsed, `None` if it was a container
+    fn parse_nested(
+        mut stack: Vec<Frame>,
+        mut value: Option<Self>,
+        mut interner: Option<&mut Interner>,
+    ) -> Result<Self> {
+        while let Some(frame) = stack.last_mut() {
+            if let Some(value) = value.take() {
+                frame.push(value)?;
+            }
+            if frame.wants_value(interner.as_deref_mut())? {
+                let index = stack.len() - 1;
+                let content = stack[index].content;
+                let (next, content) =
+                    Self::parse_next(content, &mut stack, interner.as_deref_mut())?;
+                stack[index].content = content;
+                value = next;
+            } else {
+                let frame = stack.pop().expect("the loop checked the stack isn't empty");
+                value = Some(frame.finish()?);
+            }
+        }
+        Ok(value.expect("the outermost container was finished"))
+    }
+
+    // The value at the start of `data`, or `None` for a container, which is
+    // pushed onto `stack` for `parse_nested` to fill in
+    fn parse_next<'a>(
+        data: &'a [u8],
+        stack: &mut Vec<Frame<'a>>,
+        interner: Option<&mut Interner>,
+    ) -> Result<(Option<Self>, &'a [u8])> {
+        let data = crate::skip_count_hint(data)?;
+        let (lead, _, _) = get_header(data)?;
+        if lead.is_null() {
+            let (_, rest) = RionField::parse(data)?;
+            return Ok((Some(RionValue::Null), rest));
+        }
+        match lead.field_type() {
+            RionFieldType::Normal(normal) => {
+                let (_, data_len, rest) = get_normal_header(data)?;
+                let (content, rest) = rest.split_at(data_len);
+                if let NormalRionType::Array | NormalRionType::Object | NormalRionType::Table =
+                    normal
+                {
+                    stack.push(Frame::open(normal, content)?);
+                    return Ok((None, rest));
+                }
+                // Zero length strings and bytes are null, as in `try_from`
+                if content.is_empty() {
+                    return Ok((Some(RionValue::Null), rest));
+                }
+                Ok((Some(Self::parse_normal(normal, content, interner)?), rest))
+            }
+            _ => {
+                let (field, rest) = RionField::parse(data)?;
+                Ok((Some(Self::from_scalar(field, interner)?), rest))
+            }
+        }
+    }
+
+    fn from_scalar(field: RionField, interner: Option<&mut Interner>) -> Result<Self> {
+        let value = match field.field_type() {
+            RionFieldType::Tiny(lead) => RionValue::Bool(
+                lead.as_bool()
+                    .ok_or_else(|| FerionError::invalid("Invalid tiny field"))?,
+            ),
+            // Either integer type, up to the 120 bits a short field holds
+            RionFieldType::Short(ShortRionType::Int64Positive | ShortRionType::Int64Negative) => {
+                i128::try_from(field)?.into()
+            }
+            RionFieldType::Short(ShortRionType::Float) if field.as_bytes().len() <= 4 => {
+                RionValue::Float(f32::try_from(field)? as f64)
+            }
+            RionFieldType::Short(ShortRionType::Float) => RionValue::Float(field.try_into()?),
+            RionFieldType::Short(ShortRionType::UTF8) => {
+                RionValue::String(intern_str(std::str::from_utf8(field.as_bytes())?, interner))
+            }
+            RionFieldType::Short(ShortRionType::UTCDateTime) => {
+                RionValue::DateTime(field.try_into()?)
+            }
+            RionFieldType::Extended if field.is_wide_date_time() => {
+                RionValue::DateTime(field.try_into()?)
+            }
+            RionFieldType::Extended => {
+                let RionField::Extended { type_id, data } = field else {
+                    unreachable!("the field type is extended")
+                };
+                RionValue::Extended {
+                    type_id,
+                    data: data.into_owned(),
+                }
+            }
+            field_type => {
+                return Err(FerionError::invalid(format!(
+                    "Unexpected field {field_type:?}"
+                )))
+            }
+        };
+        Ok(value)
+    }
+
+    fn parse_normal(
+        normal: NormalRionType,
+        content: &[u8],
+        interner: Option<&mut Interner>,
+    ) -> Result<Self> {
+        let value = match normal {
+            NormalRionType::Bytes => RionValue::Bytes(content.to_vec()),
+            NormalRionType::UTF8 => {
+                RionValue::String(intern_str(std::str::from_utf8(content)?, interner))
+            }
+            NormalRionType::Key => return Err(FerionError::invalid("Unexpected key field")),
+            container => {
+                Self::parse_nested(vec![Frame::open(container, content)?], None, interner)?
+            }
+        };
+        Ok(value)
+    }
+
+    fn parse_key<'a>(
+        data: &'a [u8],
+        interner: Option<&mut Interner>,
+    ) -> Result<(Arc<[u8]>, &'a [u8])> {
+        let (key, rest) = RionField::parse(data)?;
+        let key = RionKey::from_field(key)?;
+        let key = match interner {
+            Some(interner) => interner.intern_key(key.as_bytes()),
+            None => key.as_bytes().into(),
+        };
+        Ok((key, rest))
+    }
+
+    pub fn encode(&self, w: &mut impl std::io::Write) -> Result<()> {
+        // Containers still being written are kept on `stack` instead of the
+        // call stack, so values nested any number of levels deep can be encoded
+        let mut stack: Vec<EncodeFrame> = Vec::new();
+        let mut next = Some(self);
+        loop {
+            if let Some(value) = next {
+                match EncodeFrame::open(value)? {
+                    Some(frame) => stack.push(frame),
+                    None => {
+                        let mut out: &mut dyn std::io::Write = match stack.last_mut() {
+                            Some(frame) => &mut frame.content,
+                            None => &mut *w,
+                        };
+                        value.encode_scalar(&mut out)?;
+                    }
+                }
+            }
+            let Some(frame) = stack.last_mut() else {
+                return Ok(());
+            };
+            next = frame.next_child()?;
+            if next.is_none() {
+                let frame = stack.pop().expect("the frame was just looked at");
+                let mut out: &mut dyn std::io::Write = match stack.last_mut() {
+                    Some(parent) => &mut parent.content,
+                    None => &mut *w,
+                };
+                write_container(&mut out, frame.field_type, &frame.content)?;
+            }
+        }
+    }
+
+    // Everything but the containers, which `encode` handles
+    fn encode_scalar(&self, w: &mut impl std::io::Write) -> Result<()> {
+        match self {
+            // Null Tiny field, like the serializer writes for unit
+            RionValue::Null => w.write_all(&[0x10])?,
+            RionValue::Bool(value) => RionField::from(*value).encode(w)?,
+            RionValue::PosInt(value) => RionField::try_from(*value)?.encode(w)?,
+            RionValue::NegInt(value) => RionField::try_from(*value)?.encode(w)?,
+            RionValue::Float(value) => RionField::from(*value).encode(w)?,
+            RionValue::String(value) => RionField::from(&**value).encode(w)?,
+            RionValue::Bytes(value) => RionField::bytes(value).encode(w)?,
+            RionValue::DateTime(value) => RionField::try_from(*value)?.encode(w)?,
+            RionValue::Extended { type_id, data } => RionField::Extended {
+                type_id: *type_id,
+                data: data.into(),
+            }
+            .encode(w)?,
+            RionValue::Array(_) | RionValue::Object(_) | RionValue::Table { .. } => {
+                unreachable!("containers are encoded by RionValue::encode")
+            }
+        }
+        Ok(())
+    }
+
+    pub fn encode_to_vec(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        self.encode(&mut out)?;
+        Ok(out)
+    }
+
+    // Move the values held by a container onto `stack`, leaving it empty
+    fn take_children(&mut self, stack: &mut Vec<RionValue>) {
+        match self {
+            RionValue::Array(elements) => stack.append(elements),
+            RionValue::Object(fields) => stack.extend(std::mem::take(fields).into_values()),
+            RionValue::Table { rows, .. } => {
+                stack.extend(std::mem::take(rows).into_iter().flatten())
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, RionValue::Null)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RionValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Look up a field of an Object value
+    pub fn get(&self, key: &str) -> Option<&RionValue> {
+        match self {
+            RionValue::Object(fields) => fields.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut RionValue> {
+        match self {
+            RionValue::Object(fields) => fields.get_mut(key.as_bytes()),
+            _ => None,
+        }
+    }
+}
+
+// Dropping nested containers would take a stack frame per level, their
+// children are moved onto a heap stack and dropped from there instead
+impl Drop for RionValue {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_children(&mut stack);
+        while let Some(mut value) = stack.pop() {
+            value.take_children(&mut stack);
+        }
+    }
+}
+
+fn intern_str(value: &str, interner: Option<&mut Interner>) -> Arc<str> {
+    match interner {
+        Some(interner) => interner.intern_str(value),
+        None => value.into(),
+    }
+}
+
+// A container `RionValue::parse_with` is in the middle of, with the part of its
+// content that is left to parse
+struct Frame<'a> {
+    content: &'a [u8],
+    container: Container,
+}
+
+enum Container {
+    Array(Vec<RionValue>),
+    Object {
+        fields: BTreeMap<Arc<[u8]>, RionValue>,
+        // Key of the value being parsed
+        key: Option<Arc<[u8]>>,
+    },
+    Table(Box<TableFrame>),
+}
+
+struct TableFrame {
+    row_count: u64,
+    // Set until the dictionaries written before the columns are parsed, see
+    // RionTable::encode_with_dictionary
+    awaiting_dictionaries: bool,
+    dictionaries: BTreeMap<Arc<[u8]>, Vec<RionValue>>,
+    columns: Option<Vec<Arc<[u8]>>>,
+    rows: Vec<Vec<RionValue>>,
+    row: Vec<RionValue>,
+}
+
+impl<'a> Frame<'a> {
+    fn open(normal: NormalRionType, content: &'a [u8]) -> Result<Self> {
+        let (content, container) = match normal {
+            NormalRionType::Array => (content, Container::Array(Vec::new())),
+            NormalRionType::Object => (
+                content,
+                Container::Object {
+                    fields: BTreeMap::new(),
+                    key: None,
+                },
+            ),
+            NormalRionType::Table => {
+                let (count, rest) = RionField::parse(content)?;
+                let rest = crate::skip_table_stats(rest)?;
+                let awaiting_dictionaries = rest
+                    .first()
+                    .is_some_and(|&lead| lead >> 4 == RionFieldType::OBJECT && lead & 0x0F != 0);
+                let table = TableFrame {
+                    row_count: count.try_into()?,
+                    awaiting_dictionaries,
+                    dictionaries: BTreeMap::new(),
+                    columns: None,
+                    rows: Vec::new(),
+                    row: Vec::new(),
+                };
+                (rest, Container::Table(Box::new(table)))
+            }
+            _ => unreachable!("only containers are opened"),
+        };
+        Ok(Frame { content, container })
+    }
+
+    // Whether the next value in `content` belongs to this container, reading
+    // any keys in front of it
+    fn wants_value(&mut self, interner: Option<&mut Interner>) -> Result<bool> {
+        match &mut self.container {
+            Container::Array(_) => Ok(!self.content.is_empty()),
+            Container::Object { key, .. } => {
+                if self.content.is_empty() {
+                    return Ok(false);
+                }
+                let (next, rest) = RionValue::parse_key(self.content, interner)?;
+                *key = Some(next);
+                self.content = rest;
+                Ok(true)
+            }
+            Container::Table(table) => {
+                if table.awaiting_dictionaries {
+                    return Ok(true);
+                }
+                let columns = match &mut table.columns {
+                    Some(columns) => columns,
+                    None => {
+                        let mut interner = interner;
+                        let mut columns = Vec::new();
+                        while self.content.first().is_some_and(|&lead| {
+                            RionFieldType::try_from(lead).is_ok_and(|t| t.is_key())
+                        }) {
+                            let (key, rest) =
+                                RionValue::parse_key(self.content, interner.as_deref_mut())?;
+                            columns.push(key);
+                            self.content = rest;
+                        }
+                        // Every cell takes at least one byte, so the rows
+                        // can't outnumber what's left of the content
+                        if table.row_count > 0
+                            && (columns.is_empty()
+                                || table.row_count > (self.content.len() / columns.len()) as u64)
+                        {
+                            return Err(FerionError::RowCount(table.row_count));
+                        }
+                        table.columns.insert(columns)
+                    }
+                };
+                while table.row.len() == columns.len()
+                    && (table.rows.len() as u64) < table.row_count
+                {
+                    table.rows.push(std::mem::take(&mut table.row));
+                }
+                Ok((table.rows.len() as u64) < table.row_count)
+            }
+        }
+    }
+
+    fn push(&mut self, mut value: RionValue) -> Result<()> {
+        match &mut self.container {
+            Container::Array(elements) => elements.push(value),
+            Container::Object { fields, key } => {
+                let key = key.take().expect("wants_value read the key");
+                fields.insert(key, value);
+            }
+            Container::Table(table) if table.awaiting_dictionaries => {
+                table.awaiting_dictionaries = false;
+                let RionValue::Object(fields) = &mut value else {
+                    unreachable!("an object lead byte parses to an object")
+                };
+                for (name, mut entries) in std::mem::take(fields) {
+                    let RionValue::Array(entries) = &mut entries else {
+                        return Err(FerionError::invalid(
+                            "Expected an array of dictionary entries",
+                        ));
+                    };
+                    table.dictionaries.insert(name, std::mem::take(entries));
+                }
+            }
+            Container::Table(table) => {
+                let columns = table
+                    .columns
+                    .as_ref()
+                    .expect("wants_value read the columns");
+                let mut cell = value;
+                if let (Some(entries), RionValue::PosInt(index)) =
+                    (table.dictionaries.get(&columns[table.row.len()]), &cell)
+                {
+                    cell = entries
+                        .get(*index as usize)
+                        .ok_or_else(|| {
+                            FerionError::invalid(format!("Invalid dictionary index {index}"))
+                        })?
+                        .clone();
+                }
+                table.row.push(cell);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<RionValue> {
+        Ok(match self.container {
+            Container::Array(elements) => RionValue::Array(elements),
+            Container::Object { fields, .. } => RionValue::Object(fields),
+            Container::Table(table) => {
+                if !self.content.is_empty() {
+                    return Err(FerionError::invalid("Extra data after table rows"));
+                }
+                RionValue::Table {
+                    columns: table.columns.unwrap_or_default(),
+                    rows: table.rows,
+                }
+            }
+        })
+    }
+}
+
+// A container `RionValue::encode` is in the middle of, with its content
+// encoded so far
+struct EncodeFrame<'a> {
+    field_type: NormalRionType,
+    content: Vec<u8>,
+    children: Children<'a>,
+}
+
+enum Children<'a> {
+    Array(std::slice::Iter<'a, RionValue>),
+    Object(std::collections::btree_map::Iter<'a, Arc<[u8]>, RionValue>),
+    Table(std::iter::Flatten<std::slice::Iter<'a, Vec<RionValue>>>),
+}
+
+impl<'a> EncodeFrame<'a> {
+    // The frame for a container, `None` for any other value
+    fn open(value: &'a RionValue) -> Result<Option<Self>> {
+        let mut content = Vec::new();
+        let (field_type, children) = match value {
+            RionValue::Array(elements) => (NormalRionType::Array, Children::Array(elements.iter())),
+            RionValue::Object(fields) => (NormalRionType::Object, Children::Object(fields.iter())),
+            RionValue::Table { columns, rows } => {
+                RionField::from(rows.len() as u64).encode(&mut content)?;
+                for column in columns {
+                    RionField::key(column).encode(&mut content)?;
+                }
+                if let Some(row) = rows.iter().find(|row| row.len() != columns.len()) {
+                    return Err(FerionError::invalid(format!(
+                        "Table row has {} cells, expected {}",
+                        row.len(),
+                        columns.len()
+                    )));
+                }
+                (
+                    NormalRionType::Table,
+                    Children::Table(rows.iter().flatten()),
+                )
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(EncodeFrame {
+            field_type,
+            content,
+            children,
+        }))
+    }
+
+    // The next value to encode into this container, writing its key first
+    fn next_child(&mut self) -> Result<Option<&'a RionValue>> {
+        Ok(match &mut self.children {
+            Children::Array(elements) => elements.next(),
+            Children::Object(fields) => match fields.next() {
+                Some((key, value)) => {
+                    RionField::key(key).encode(&mut self.content)?;
+                    Some(value)
+                }
+                None => None,
+            },
+            Children::Table(cells) => cells.next(),
+        })
+    }
+}
+
+// Containers always get at least one length byte, a zero length lead byte is null
+fn write_container(
+    w: &mut impl std::io::Write,
+    field_type: NormalRionType,
+    content: &[u8],
+) -> Result<()> {
+    let length_length = needed_bytes_usize(content.len()).max(1);
+    if length_length > 15 {
+        return Err(FerionError::invalid(
+            "Data length too large for normal field",
+        ));
+    }
+    w.write_all(&[field_type.to_byte() << 4 | length_length as u8])?;
+    w.write_all(&content.len().to_be_bytes()[8 - length_length..])?;
+    w.write_all(content)?;
+    Ok(())
+}
+
+impl From<bool> for RionValue {
+    fn from(value: bool) -> Self {
+        RionValue::Bool(value)
+    }
+}
+
+impl From<u64> for RionValue {
+    fn from(value: u64) -> Self {
+        RionValue::PosInt(value.into())
+    }
+}
+
+impl From<i64> for RionValue {
+    fn from(value: i64) -> Self {
+        i128::from(value).into()
+    }
+}
+
+impl From<u128> for RionValue {
+    fn from(value: u128) -> Self {
+        RionValue::PosInt(value)
+    }
+}
+
+impl From<i128> for RionValue {
+    fn from(value: i128) -> Self {
+        if value < 0 {
+            RionValue::NegInt(value)
+        } else {
+            RionValue::PosInt(value as u128)
+        }
+    }
+}
+
+impl From<f64> for RionValue {
+    fn from(value: f64) -> Self {
+        RionValue::Float(value)
+    }
+}
+
+impl From<&str> for RionValue {
+    fn from(value: &str) -> Self {
+        RionValue::String(value.into())
+    }
+}
+
+impl From<String> for RionValue {
+    fn from(value: String) -> Self {
+        RionValue::String(value.into())
+    }
+}
+
+impl From<Arc<str>> for RionValue {
+    fn from(value: Arc<str>) -> Self {
+        RionValue::String(value)
+    }
+}
+
+impl From<Vec<u8>> for RionValue {
+    fn from(value: Vec<u8>) -> Self {
+        RionValue::Bytes(value)
+    }
+}
+
+impl From<RionDateTime> for RionValue {
+    fn from(value: RionDateTime) -> Self {
+        RionValue::DateTime(value)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for RionValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        RionValue::DateTime(value.into())
+    }
+}
+
+impl From<Vec<RionValue>> for RionValue {
+    fn from(value: Vec<RionValue>) -> Self {
+        RionValue::Array(value)
+    }
+}
+
+impl TryFrom<RionField<'_>> for RionValue {
+    type Error = FerionError;
+    fn try_from(field: RionField<'_>) -> Result<Self> {
+        if field.is_null() {
+            return Ok(RionValue::Null);
+        }
+        match field.field_type() {
+            RionFieldType::Normal(normal) => Self::parse_normal(normal, field.as_bytes(), None),
+            _ => Self::from_scalar(field, None),
+        }
+    }
+}
+
+impl TryFrom<&RionValue> for RionField<'static> {
+    type Error = FerionError;
+    fn try_from(value: &RionValue) -> Result<Self> {
+        let field_type = match value {
+            RionValue::Null => return Ok(RionField::Tiny(LeadByte::NULL)),
+            RionValue::Bool(value) => return Ok((*value).into()),
+            RionValue::PosInt(value) => return (*value).try_into(),
+            RionValue::NegInt(value) => return (*value).try_into(),
+            RionValue::Float(value) => return Ok((*value).into()),
+            RionValue::String(value) => return Ok(value.to_string().into()),
+            RionValue::DateTime(value) => return (*value).try_into(),
+            RionValue::Extended { type_id, data } => {
+                return Ok(RionField::Extended {
+                    type_id: *type_id,
+                    data: data.clone().into(),
+                })
+            }
+            RionValue::Bytes(_) => NormalRionType::Bytes,
+            RionValue::Array(_) => NormalRionType::Array,
+            RionValue::Object(_) => NormalRionType::Object,
+            RionValue::Table { .. } => NormalRionType::Table,
+        };
+        // Reuse the container encoding, keeping only the content
+        let encoded = value.encode_to_vec()?;
+        let (_, data_len, content) = get_normal_header(&encoded)?;
+        Ok(RionField::Normal(NormalField {
+            field_type,
+            data: content[..data_len].to_vec().into(),
+        }))
+    }
+}
+
+pub(crate) fn utf8_key(key: &[u8]) -> Result<String> {
+    String::from_utf8(key.to_vec())
+        .map_err(|_| FerionError::invalid(format!("Key {key:x?} is not valid UTF-8")))
+}
+
+macro_rules! impl_map_conversions {
+    ($($map:ident),*) => {$(
+        /// Errors on keys that are not UTF-8, or fields that fail to decode
+        impl TryFrom<RionObject<'_>> for $map<String, RionValue> {
+            type Error = FerionError;
+            fn try_from(object: RionObject<'_>) -> Result<Self> {
+                object
+                    .fields
+                    .into_iter()
+                    .map(|(key, field)| Ok((utf8_key(&key)?, field.try_into()?)))
+                    .collect()
+            }
+        }
+
+        impl TryFrom<$map<String, RionValue>> for RionObject<'static> {
+            type Error = FerionError;
+            fn try_from(map: $map<String, RionValue>) -> Result<Self> {
+                let mut object = RionObject::new();
+                for (key, value) in map {
+                    let field = RionField::try_from(&value)?;
+                    object.fields.insert(key.into_bytes().into(), field);
+                }
+                object.recompute_len();
+                Ok(object)
+            }
+        }
+
+        /// Errors if the value is not an Object or has keys that are not UTF-8
+        impl TryFrom<RionValue> for $map<String, RionValue> {
+            type Error = FerionError;
+            fn try_from(mut value: RionValue) -> Result<Self> {
+                let RionValue::Object(fields) = &mut value else {
+                    return Err(FerionError::invalid(format!("Expected an object, found {value:?}")));
+                };
+                std::mem::take(fields)
+                    .into_iter()
+                    .map(|(key, value)| Ok((utf8_key(&key)?, value)))
+                    .collect()
+            }
+        }
+
+        impl From<$map<String, RionValue>> for RionValue {
+            fn from(map: $map<String, RionValue>) -> Self {
+                RionValue::Object(
+                    map.into_iter()
+                        .map(|(key, value)| (key.into_bytes().into(), value))
+                        .collect(),
+                )
+            }
+        }
+    )*};
+}
+
+impl_map_conversions!(HashMap, BTreeMap);
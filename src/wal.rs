@@ -0,0 +1,417 @@
+//! A write-ahead log of encoded values
+//!
+//! Records are appended to numbered segment files in a directory, each
+//! segment growing to at most [`WalOptions::segment_size`] bytes before the
+//! next one is started. A record is a big endian CRC-32 of the value followed
+//! by the encoded value itself, which carries its own length (see
+//! [`frame`](crate::frame)). After a crash the last record of the last segment
+//! may be cut short or hold garbage; [`WalWriter::open`] and [`recover`] find
+//! it by its length or checksum and truncate the segment back to the last
+//! whole record.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::frame::read_frame;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{temp_dir::TempDir, RionValue};
+
+    fn record(i: u64) -> Vec<u8> {
+        RionValue::Array(vec![i.into(), format!("event {i}").into()])
+            .encode_to_vec()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    }
+
+    #[test]
+    fn test_write_read_segments() {
+        let dir = TempDir::new("wal-segments");
+        let options = WalOptions {
+            segment_size: 64,
+            ..Default::default()
+        };
+        let mut writer = WalWriter::open(&dir.0, options).unwrap();
+        for i in 0..20 {
+            writer.append(&record(i)).unwrap();
+        }
+        writer.sync().unwrap();
+        assert!(segments(&dir.0).unwrap().len() > 1);
+
+        let records = WalReader::open(&dir.0, options.max_record)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records, (0..20).map(record).collect::<Vec<_>>());
+        assert!(writer.append(&[0x21]).is_err());
+    }
+
+    #[test]
+    fn test_recover_torn_record() {
+        let dir = TempDir::new("wal-torn");
+        let options = WalOptions::default();
+        let mut writer = WalWriter::open(&dir.0, options).unwrap();
+        for i in 0..3 {
+            writer.append(&record(i)).unwrap();
+        }
+        drop(writer);
+
+        // Half of a fourth record, as if the process died mid write
+        let (_, last) = segments(&dir.0).unwrap().pop().unwrap();
+        let whole = fs::metadata(&last).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&last).unwrap();
+        let torn = record(3);
+        file.write_all(&crc32(&torn).to_be_bytes()).unwrap();
+        file.write_all(&torn[..torn.len() / 2]).unwrap();
+        drop(file);
+
+        let mut reader = WalReader::open(&dir.0, options.max_record).unwrap();
+        assert_eq!(reader.by_ref().count(), 3);
+        assert!(reader.torn());
+
+        let recovery = recover(&dir.0, options.max_record).unwrap();
+        assert_eq!(recovery.records, 3);
+        assert_eq!(recovery.truncated_bytes, 4 + torn.len() as u64 / 2);
+        assert_eq!(fs::metadata(&last).unwrap().len(), whole);
+
+        // Appending after recovery picks up where the whole records end
+        let mut writer = WalWriter::open(&dir.0, options).unwrap();
+        writer.append(&record(3)).unwrap();
+        let records = WalReader::open(&dir.0, options.max_record)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records, (0..4).map(record).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_recover_bad_checksum() {
+        let dir = TempDir::new("wal-checksum");
+        let options = WalOptions::default();
+        let mut writer = WalWriter::open(&dir.0, options).unwrap();
+        writer.append(&record(0)).unwrap();
+        writer.append(&record(1)).unwrap();
+        drop(writer);
+
+        let (_, last) = segments(&dir.0).unwrap().pop().unwrap();
+        let mut data = fs::read(&last).unwrap();
+        *data.last_mut().unwrap() ^= 0xFF;
+        fs::write(&last, data).unwrap();
+
+        let recovery = recover(&dir.0, options.max_record).unwrap();
+        assert_eq!(recovery.records, 1);
+        assert_eq!(recovery.truncated_bytes, 4 + record(1).len() as u64);
+    }
+}
+
+/// When appended records are flushed to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncMode {
+    /// fsync after every record, nothing acknowledged is ever lost
+    #[default]
+    Always,
+    /// Leave it to the OS and explicit [`WalWriter::sync`] calls
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalOptions {
+    /// Bytes after which a new segment is started, a record is never split so
+    /// one larger than this gets a segment to itself
+    pub segment_size: u64,
+    pub sync: SyncMode,
+    /// Largest encoded value accepted, anything claiming to be longer is
+    /// treated as garbage when reading
+    pub max_record: usize,
+}
+
+impl Default for WalOptions {
+    fn default() -> Self {
+        WalOptions {
+            segment_size: 64 * 1024 * 1024,
+            sync: SyncMode::Always,
+            max_record: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// What [`recover`] found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Recovery {
+    /// Whole records across all segments
+    pub records: u64,
+    /// Bytes of the torn final record that were cut off
+    pub truncated_bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct WalWriter {
+    dir: PathBuf,
+    options: WalOptions,
+    file: File,
+    segment: u64,
+    len: u64,
+}
+
+impl WalWriter {
+    /// Open the log in `dir`, creating it if needed and truncating a torn
+    /// final record left by a crash
+    pub fn open(dir: impl AsRef<Path>, options: WalOptions) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        recover(&dir, options.max_record)?;
+        let segment = segments(&dir)?.last().map_or(0, |(segment, _)| *segment);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(segment_path(&dir, segment))?;
+        let len = file.metadata()?.len();
+        Ok(WalWriter {
+            dir,
+            options,
+            file,
+            segment,
+            len,
+        })
+    }
+
+    /// Append one encoded value
+    pub fn append(&mut self, record: &[u8]) -> io::Result<()> {
+        // The record has to be exactly one value to be read back
+        match read_frame(&mut &record[..], self.options.max_record) {
+            Ok(Some(frame)) if frame.len() == record.len() => {}
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "A record must be a single encoded value",
+                ))
+            }
+        }
+        let size = 4 + record.len() as u64;
+        if self.len > 0 && self.len + size > self.options.segment_size {
+            self.roll()?;
+        }
+        let mut buf = Vec::with_capacity(size as usize);
+        buf.extend_from_slice(&crc32(record).to_be_bytes());
+        buf.extend_from_slice(record);
+        self.file.write_all(&buf)?;
+        self.len += size;
+        if self.options.sync == SyncMode::Always {
+            self.file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Flush everything appended so far to disk
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
+    fn roll(&mut self) -> io::Result<()> {
+        self.file.sync_data()?;
+        self.segment += 1;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(segment_path(&self.dir, self.segment))?;
+        self.len = 0;
+        // Make the new segment's directory entry durable too
+        if self.options.sync == SyncMode::Always {
+            File::open(&self.dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the records of every segment in order
+///
+/// A torn record at the end of the last segment ends the iteration, see
+/// [`torn`](Self::torn). One anywhere else is corruption and an error.
+#[derive(Debug)]
+pub struct WalReader {
+    segments: std::vec::IntoIter<(u64, PathBuf)>,
+    current: Option<BufReader<File>>,
+    max_record: usize,
+    torn: bool,
+}
+
+impl WalReader {
+    pub fn open(dir: impl AsRef<Path>, max_record: usize) -> io::Result<Self> {
+        Ok(WalReader {
+            segments: segments(dir.as_ref())?.into_iter(),
+            current: None,
+            max_record,
+            torn: false,
+        })
+    }
+
+    /// Whether the log ended in a torn record
+    pub fn torn(&self) -> bool {
+        self.torn
+    }
+}
+
+impl Iterator for WalReader {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.torn {
+                return None;
+            }
+            let reader = match &mut self.current {
+                Some(reader) => reader,
+                None => {
+                    let (_, path) = self.segments.next()?;
+                    match File::open(path) {
+                        Ok(file) => self.current.insert(BufReader::new(file)),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+            };
+            match read_record(reader, self.max_record) {
+                Ok(Next::Record(record)) => return Some(Ok(record)),
+                Ok(Next::End) => self.current = None,
+                Ok(Next::Torn) if self.segments.len() == 0 => self.torn = true,
+                Ok(Next::Torn) => {
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Corrupt record before the last segment",
+                    )))
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Check every segment and cut a torn final record off the last one
+pub fn recover(dir: impl AsRef<Path>, max_record: usize) -> io::Result<Recovery> {
+    let segments = segments(dir.as_ref())?;
+    let mut recovery = Recovery::default();
+    for (i, (_, path)) in segments.iter().enumerate() {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut reader = BufReader::new(&mut file);
+        let mut valid = 0;
+        loop {
+            match read_record(&mut reader, max_record)? {
+                Next::Record(record) => {
+                    recovery.records += 1;
+                    valid += 4 + record.len() as u64;
+                }
+                Next::End => break,
+                Next::Torn if i + 1 == segments.len() => {
+                    let len = reader.seek(SeekFrom::End(0))?;
+                    drop(reader);
+                    file.set_len(valid)?;
+                    file.sync_all()?;
+                    recovery.truncated_bytes = len - valid;
+                    break;
+                }
+                Next::Torn => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Corrupt record in {}", path.display()),
+                    ))
+                }
+            }
+        }
+    }
+    Ok(recovery)
+}
+
+enum Next {
+    Record(Vec<u8>),
+    End,
+    Torn,
+}
+
+fn read_record(reader: &mut impl Read, max_record: usize) -> io::Result<Next> {
+    let mut checksum = [0; 4];
+    let mut read = 0;
+    while read < checksum.len() {
+        match reader.read(&mut checksum[read..]) {
+            Ok(0) if read == 0 => return Ok(Next::End),
+            Ok(0) => return Ok(Next::Torn),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let record = match read_frame(reader, max_record) {
+        Ok(Some(record)) => record,
+        Ok(None) => return Ok(Next::Torn),
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData
+            ) =>
+        {
+            return Ok(Next::Torn)
+        }
+        Err(e) => return Err(e),
+    };
+    if crc32(&record) != u32::from_be_bytes(checksum) {
+        return Ok(Next::Torn);
+    }
+    Ok(Next::Record(record))
+}
+
+// Segment files in `dir` by number
+fn segments(dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "wal") {
+            if let Some(segment) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+            {
+                segments.push((segment, path));
+            }
+        }
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+fn segment_path(dir: &Path, segment: u64) -> PathBuf {
+    dir.join(format!("{segment:020}.wal"))
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+// CRC-32 (IEEE), as used by zlib and gzip
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &b| {
+        CRC_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}